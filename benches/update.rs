@@ -0,0 +1,205 @@
+//! Time per update of `Window` (`cargo bench`).
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use hampel::Window;
+
+fn bench<const N: usize>(count: usize) {
+    let mut filter = Window::<f64, N>::new(0.0, 3.0);
+
+    // Pseudo-random inputs (xorshift) with 2% outliers
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut inputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let x = (state >> 11) as f64 / (1u64 << 53) as f64;
+        inputs.push(if state.is_multiple_of(50) { x + 10.0 } else { x });
+    }
+
+    let start = Instant::now();
+    for &x in &inputs {
+        black_box(filter.update(black_box(x)));
+    }
+    let elapsed = start.elapsed();
+    println!("WINDOW_SIZE = {:4}: {:10.1} ns/update", N, elapsed.as_nanos() as f64 / count as f64);
+}
+
+/// 256サンプルのブロックを`update`のループと`process`で処理する時間を比較する
+fn bench_block<const N: usize>(blocks: usize) {
+    let mut a = Window::<f32, N>::new(0.0, 3.0);
+    let mut b = Window::<f32, N>::new(0.0, 3.0);
+
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut inputs = vec![[0.0f32; 256]; blocks];
+    for x in inputs.iter_mut().flatten() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let v = (state >> 40) as f32 / (1u32 << 24) as f32;
+        *x = if state.is_multiple_of(50) { v + 10.0 } else { v };
+    }
+    let mut looped = inputs.clone();
+
+    let start = Instant::now();
+    for block in &mut looped {
+        for x in black_box(block).iter_mut() {
+            *x = a.update(*x);
+        }
+    }
+    let scalar = start.elapsed();
+    let start = Instant::now();
+    for block in &mut inputs {
+        b.process(black_box(block));
+    }
+    let block = start.elapsed();
+    assert!(looped == inputs);
+
+    let count = (blocks * 256) as f64;
+    println!(
+        "WINDOW_SIZE = {:4}, blocks of 256: {:10.1} ns/update (process), {:10.1} ns/update (update)",
+        N,
+        block.as_nanos() as f64 / count,
+        scalar.as_nanos() as f64 / count,
+    );
+}
+
+/// 外れ値が10%の入力を`Replacement::LinearExtrapolation`で処理する時間
+fn bench_extrapolation<const N: usize>(count: usize) {
+    use hampel::Replacement;
+
+    let mut filter = Window::<f64, N>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut inputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let x = (state >> 11) as f64 / (1u64 << 53) as f64;
+        inputs.push(if state.is_multiple_of(10) { x + 10.0 } else { x });
+    }
+
+    let start = Instant::now();
+    for &x in &inputs {
+        black_box(filter.update(black_box(x)));
+    }
+    let elapsed = start.elapsed();
+    println!("WINDOW_SIZE = {:4}, 10% outliers extrapolated: {:10.1} ns/update", N, elapsed.as_nanos() as f64 / count as f64);
+}
+
+/// 1回ごとの`update`の時間の分布（`constant-time`フィーチャの有無で比べる）
+fn bench_spread<const N: usize>(count: usize) {
+    let mut filter = Window::<f32, N>::new(0.0, 3.0);
+
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut times = Vec::with_capacity(count);
+    for _ in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let v = (state >> 40) as f32 / (1u32 << 24) as f32;
+        let x = if state.is_multiple_of(50) { v + 10.0 } else { v };
+        let start = Instant::now();
+        black_box(filter.update(black_box(x)));
+        times.push(start.elapsed().as_nanos());
+    }
+    times.sort_unstable();
+    let q = |p: f64| times[((count - 1) as f64 * p) as usize];
+    println!(
+        "WINDOW_SIZE = {:4}, single updates: {:6} ns (min), {:6} ns (median), {:6} ns (99%), {:6} ns (99.99%)",
+        N, times[0], q(0.5), q(0.99), q(0.9999),
+    );
+}
+
+/// `CenteredWindow`の更新時間（5, 7, 9個の窓は選択ネットワークで中央値を求める）
+fn bench_centered<const N: usize>(count: usize) {
+    use hampel::CenteredWindow;
+
+    let mut filter = CenteredWindow::<f64, N>::new(3.0);
+
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut inputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let x = (state >> 11) as f64 / (1u64 << 53) as f64;
+        inputs.push(if state.is_multiple_of(50) { x + 10.0 } else { x });
+    }
+
+    let start = Instant::now();
+    for &x in &inputs {
+        black_box(filter.update(black_box(x)));
+    }
+    let elapsed = start.elapsed();
+    println!("WINDOW_SIZE = {:4}, centered: {:10.1} ns/update", N, elapsed.as_nanos() as f64 / count as f64);
+}
+
+/// 64チャンネルのf32を`SimdBank`と`Window`で処理する時間を比較する
+#[cfg(feature = "simd")]
+fn bench_simd<const N: usize>(count: usize) {
+    use hampel::{Replacement, SimdBank};
+
+    let mut bank = SimdBank::<N, 64>::new(0.0, 3.0);
+    let mut windows: [_; 64] = core::array::from_fn(|_| Window::<f32, N>::with_replacement(0.0, 3.0, Replacement::Median));
+
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut inputs = Vec::with_capacity(count);
+    for _ in 0..count {
+        inputs.push(core::array::from_fn::<f32, 64, _>(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let x = (state >> 40) as f32 / (1u32 << 24) as f32;
+            if state.is_multiple_of(50) { x + 10.0 } else { x }
+        }));
+    }
+
+    let start = Instant::now();
+    for &x in &inputs {
+        black_box(bank.update(black_box(x)));
+    }
+    let simd = start.elapsed();
+    let start = Instant::now();
+    for &x in &inputs {
+        for (w, x) in windows.iter_mut().zip(x) {
+            black_box(w.update(black_box(x)));
+        }
+    }
+    let scalar = start.elapsed();
+    println!(
+        "WINDOW_SIZE = {:4}, 64 channels: {:10.1} ns/update (SimdBank), {:10.1} ns/update (Window)",
+        N,
+        simd.as_nanos() as f64 / count as f64,
+        scalar.as_nanos() as f64 / count as f64,
+    );
+}
+
+fn main() {
+    bench::<5>(2_000_000);
+    bench::<7>(2_000_000);
+    bench::<9>(2_000_000);
+    bench::<11>(2_000_000);
+    bench::<51>(1_000_000);
+    bench::<101>(500_000);
+    bench::<301>(200_000);
+    bench_block::<9>(4_000);
+    bench_extrapolation::<31>(1_000_000);
+    bench_spread::<11>(1_000_000);
+    bench_spread::<31>(1_000_000);
+    bench_centered::<5>(2_000_000);
+    bench_centered::<7>(2_000_000);
+    bench_centered::<9>(2_000_000);
+    bench_centered::<11>(2_000_000);
+
+    #[cfg(feature = "simd")]
+    {
+        bench_simd::<5>(200_000);
+        bench_simd::<7>(200_000);
+        bench_simd::<9>(200_000);
+        bench_simd::<11>(200_000);
+    }
+}
@@ -0,0 +1,166 @@
+#ifndef HAMPEL_H
+#define HAMPEL_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#include <stdint.h>
+#include <stddef.h>
+#define HAMPEL_ALIGNED(n) __attribute__((aligned(n)))
+
+
+
+
+
+/**
+ * The operation succeeded.
+ */
+#define HAMPEL_OK 0
+
+/**
+ * The state pointer is null.
+ */
+#define HAMPEL_ERR_NULL -1
+
+/**
+ * `n_sigma` is not a positive finite number.
+ */
+#define HAMPEL_ERR_INVALID_N_SIGMA -2
+
+/**
+ * The state has not been initialized by `init`.
+ */
+#define HAMPEL_ERR_UNINITIALIZED -3
+
+/**
+ * Size of [`HampelF32W5`] in bytes.
+ */
+#define HAMPEL_F32_W5_SIZE 720
+
+/**
+ * Size of [`HampelF32W11`] in bytes.
+ */
+#define HAMPEL_F32_W11_SIZE 784
+
+/**
+ * Size of [`HampelF32W31`] in bytes.
+ */
+#define HAMPEL_F32_W31_SIZE 1024
+
+/**
+ * Alignment of the state structs in bytes.
+ */
+#define HAMPEL_STATE_ALIGN 16
+
+/**
+ * State of `hampel_f32_w5_*` (`Window<f32, 5>`).
+ */
+typedef struct HAMPEL_ALIGNED(16) HampelF32W5 {
+  uint8_t storage[704];
+  float init_val;
+  uint32_t magic;
+} HampelF32W5;
+
+/**
+ * State of `hampel_f32_w11_*` (`Window<f32, 11>`).
+ */
+typedef struct HAMPEL_ALIGNED(16) HampelF32W11 {
+  uint8_t storage[768];
+  float init_val;
+  uint32_t magic;
+} HampelF32W11;
+
+/**
+ * State of `hampel_f32_w31_*` (`Window<f32, 31>`).
+ */
+typedef struct HAMPEL_ALIGNED(16) HampelF32W31 {
+  uint8_t storage[1008];
+  float init_val;
+  uint32_t magic;
+} HampelF32W31;
+
+/**
+ * Initialize `state` with the window filled with `init_val` (see [`Window::new`]).
+ *
+ * Returns [`HAMPEL_OK`], [`HAMPEL_ERR_NULL`] or [`HAMPEL_ERR_INVALID_N_SIGMA`].
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to writable memory for a `HampelF32W5` (it may be uninitialized).
+ */
+int32_t hampel_f32_w5_init(struct HampelF32W5 *state, float init_val, float n_sigma);
+
+/**
+ * Update the window, and return the filtered value (NaN if `state` is null or not initialized).
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to a `HampelF32W5` that is not accessed concurrently.
+ */
+float hampel_f32_w5_update(struct HampelF32W5 *state, float x);
+
+/**
+ * Restore the state right after `init`.
+ *
+ * Returns [`HAMPEL_OK`], [`HAMPEL_ERR_NULL`] or [`HAMPEL_ERR_UNINITIALIZED`].
+ *
+ * # Safety
+ *
+ * Same as [`hampel_f32_w5_update`].
+ */
+int32_t hampel_f32_w5_reset(struct HampelF32W5 *state);
+
+/**
+ * Same as [`hampel_f32_w5_init`], for `HampelF32W11`.
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to writable memory for a `HampelF32W11` (it may be uninitialized).
+ */
+int32_t hampel_f32_w11_init(struct HampelF32W11 *state, float init_val, float n_sigma);
+
+/**
+ * Same as [`hampel_f32_w5_update`], for `HampelF32W11`.
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to a `HampelF32W11` that is not accessed concurrently.
+ */
+float hampel_f32_w11_update(struct HampelF32W11 *state, float x);
+
+/**
+ * Same as [`hampel_f32_w5_reset`], for `HampelF32W11`.
+ *
+ * # Safety
+ *
+ * Same as [`hampel_f32_w11_update`].
+ */
+int32_t hampel_f32_w11_reset(struct HampelF32W11 *state);
+
+/**
+ * Same as [`hampel_f32_w5_init`], for `HampelF32W31`.
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to writable memory for a `HampelF32W31` (it may be uninitialized).
+ */
+int32_t hampel_f32_w31_init(struct HampelF32W31 *state, float init_val, float n_sigma);
+
+/**
+ * Same as [`hampel_f32_w5_update`], for `HampelF32W31`.
+ *
+ * # Safety
+ *
+ * `state` must be null, or point to a `HampelF32W31` that is not accessed concurrently.
+ */
+float hampel_f32_w31_update(struct HampelF32W31 *state, float x);
+
+/**
+ * Same as [`hampel_f32_w5_reset`], for `HampelF32W31`.
+ *
+ * # Safety
+ *
+ * Same as [`hampel_f32_w31_update`].
+ */
+int32_t hampel_f32_w31_reset(struct HampelF32W31 *state);
+
+#endif  /* HAMPEL_H */
@@ -0,0 +1,102 @@
+//! Adaptation of the threshold to the recent outlier rate.
+
+use num_traits::float::FloatCore;
+
+use crate::precision;
+
+/// Configuration of the adaptive threshold (see [`WindowBuilder::adaptive`](crate::WindowBuilder::adaptive)).
+///
+/// After each decision, the fraction of outliers over the last [`HORIZON`](Self::HORIZON) decisions is
+/// compared with `target_rate`. If it is higher, the effective `n_sigma` is increased by `gain_up`
+/// (up to `max_n_sigma`); if it is lower, it is decreased by `gain_down` (down to the nominal `n_sigma`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdaptiveThreshold<T> {
+    /// Target fraction of outliers, in `[0, 1]`.
+    pub target_rate: T,
+    /// Increase of the effective `n_sigma` per update while the rate is above the target.
+    pub gain_up: T,
+    /// Decrease of the effective `n_sigma` per update while the rate is below the target.
+    pub gain_down: T,
+    /// Upper bound of the effective `n_sigma`.
+    pub max_n_sigma: T,
+}
+
+impl<T: FloatCore> AdaptiveThreshold<T> {
+    /// Number of the latest decisions used to compute the outlier rate.
+    pub const HORIZON: u32 = u128::BITS;
+
+    /// Adaptation to `target_rate`, with the gains of `0.01` per update.
+    pub fn new(target_rate: T, max_n_sigma: T) -> Self {
+        let gain = precision::narrow(0.01);
+        Self { target_rate, gain_up: gain, gain_down: gain, max_n_sigma }
+    }
+
+    pub(crate) fn is_valid(&self, n_sigma: T) -> bool {
+        let valid = |x: T| !x.is_nan() && x >= T::zero();
+        valid(self.target_rate) && self.target_rate <= T::one()
+            && valid(self.gain_up) && valid(self.gain_down)
+            && self.max_n_sigma >= n_sigma
+    }
+}
+
+/// 適応のための状態
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Adaptation<T> {
+    pub(crate) config: AdaptiveThreshold<T>,
+    pub(crate) offset: T,  // 公称のn_sigmaに加える値
+    recent: u128,  // 直近の判定結果（bit 0: 最新）
+    count: u32,  // 判定の回数（HORIZONで飽和）
+    counts: Option<(T, T)>,  // recentの1の数とcountをTで数えたもの（HORIZON以下の整数なので誤差なく表せる，復元直後はNone）
+}
+
+impl<T: FloatCore> Adaptation<T> {
+    pub(crate) fn new(config: AdaptiveThreshold<T>) -> Self {
+        Self { config, offset: T::zero(), recent: 0, count: 0, counts: Some((T::zero(), T::zero())) }
+    }
+
+    /// 判定結果を記録してoffsetを更新する（`n_sigma`: 公称値のうち大きい方）
+    pub(crate) fn record(&mut self, is_outlier: bool, n_sigma: T) {
+        let (mut ones, mut count) = self.counts.unwrap_or_else(|| self.recount());
+        if self.recent >> (u128::BITS - 1) == 1 {
+            ones = ones - T::one();  // 押し出される判定結果
+        }
+        if is_outlier {
+            ones = ones + T::one();
+        }
+        if self.count < AdaptiveThreshold::<T>::HORIZON {
+            count = count + T::one();
+        }
+        self.recent = (self.recent << 1) | is_outlier as u128;
+        self.count = (self.count + 1).min(AdaptiveThreshold::<T>::HORIZON);
+        self.counts = Some((ones, count));
+
+        let rate = ones / count;
+        let c = &self.config;
+        if rate > c.target_rate {
+            self.offset = (self.offset + c.gain_up).min(c.max_n_sigma - n_sigma);
+        } else if rate < c.target_rate {
+            self.offset = (self.offset - c.gain_down).max(T::zero());
+        }
+    }
+
+    /// `recent`の1の数と`count`をTで数え直す
+    fn recount(&self) -> (T, T) {
+        let ones = (0..u128::BITS).filter(|&i| (self.recent >> i) & 1 == 1).fold(T::zero(), |s, _| s + T::one());
+        let count = (0..self.count).fold(T::zero(), |s, _| s + T::one());
+        (ones, count)
+    }
+
+    /// 設定以外の状態（offset, recent, count）
+    #[cfg(feature = "serde")]
+    pub(crate) fn parts(&self) -> (T, u128, u32) {
+        (self.offset, self.recent, self.count)
+    }
+
+    /// `parts`で取り出した状態から作る（`count`はHORIZONで飽和させ，`counts`は次の`record`で数え直す）
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(config: AdaptiveThreshold<T>, (offset, recent, count): (T, u128, u32)) -> Self {
+        Self { config, offset, recent, count: count.min(AdaptiveThreshold::<T>::HORIZON), counts: None }
+    }
+}
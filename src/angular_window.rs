@@ -0,0 +1,306 @@
+//! Hampel filter for circular data such as angles and headings.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{filter, iter::Samples, offline, stats, Replacement};
+
+/// Hampel filter for angles, whose values wrap around every `period` (e.g. `360.0` for degrees, `2π` for radians).
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The samples are compared on the circle: the median of the window is the circular median (the value minimizing
+/// the sum of the angular distances to the samples, searched among the medians of the window unwrapped around
+/// each sample), and the deviations are the wrapped differences in `(-period / 2, period / 2]`. A legitimate crossing of the wrap is not an outlier, unlike with [`Window`](crate::Window):
+///
+/// ```
+/// use hampel::{AngularWindow, Replacement, Window};
+///
+/// // Compass heading turning through ±180°, with a little noise
+/// let noise = |i: usize| ((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) as f64 / (1u64 << 24) as f64 * 0.2 - 0.1;
+/// let heading = |i: usize| {
+///     let h = 150.0 + 0.5 * i as f64 + noise(i);
+///     if h > 180.0 { h - 360.0 } else { h }
+/// };
+///
+/// let mut angular = AngularWindow::<f64, 9>::new(heading(0), 3.0, 360.0, Replacement::Median);
+/// let mut linear = Window::<f64, 9>::new(heading(0), 3.0);
+/// let mut naive = 0;
+/// for i in 0..120 {
+///     let x = heading(i);
+///     let y = angular.update(x);
+///     let flagged = linear.update_detailed(x).is_outlier;
+///     if i >= 9 {
+///         // Once the initial window is flushed out
+///         assert_eq!(y, x);
+///         assert!(!angular.is_last_outlier());
+///         naive += flagged as usize;
+///     }
+/// }
+/// // The plain window sees the jump from 180° to -180° as outliers
+/// assert!(naive > 0);
+/// ```
+///
+/// The outliers are replaced as [`Replacement`] specifies, with the result wrapped into `(-period / 2, period / 2]`.
+/// The extrapolations fit the previous samples unwrapped into a continuous sequence (excluding the samples
+/// flagged as outliers by the previous updates, as [`Window`](crate::Window) does), so that a spike of 90°
+/// right at the wrap is replaced on the correct side:
+///
+/// ```
+/// use hampel::{AngularWindow, Replacement};
+///
+/// // Degrees wrapped into (-180, 180]
+/// let wrap = |h: f64| h - 360.0 * (h / 360.0 - 0.5).ceil();
+/// let heading = |i: usize| wrap(170.0 + 0.25 * i as f64 + 0.01 * (i % 3) as f64);
+///
+/// for replacement in [Replacement::Median, Replacement::LinearExtrapolation] {
+///     let mut filter = AngularWindow::<f64, 9>::new(heading(0), 3.0, 360.0, replacement);
+///     for i in 0..80 {
+///         // Spikes of 90° at 179.99° and at -179.75°
+///         let spike = if i == 40 || i == 41 { 90.0 } else { 0.0 };
+///         let y = filter.update(wrap(heading(i) + spike));
+///         if i < 9 {
+///             continue;
+///         }
+///         assert_eq!(filter.is_last_outlier(), spike > 0.0);
+///         if spike > 0.0 {
+///             // Within (-180, 180], and close to the heading across the wrap
+///             assert!(y > -180.0 && y <= 180.0);
+///             let tolerance = if replacement == Replacement::Median { 1.1 } else { 0.05 };
+///             assert!(wrap(y - heading(i)).abs() < tolerance, "{}: {}", i, y);
+///         }
+///     }
+///
+///     // Non-finite samples are replaced from the whole window, and are not stored in it
+///     let y = filter.update(f64::NAN);
+///     assert!(filter.is_last_outlier());
+///     assert!(wrap(y - heading(80)).abs() < 1.5);
+///     assert!(filter.iter().all(|h| h.is_finite()));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AngularWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],  // (-period / 2, period / 2]に折り返したサンプル
+    work: [T; WINDOW_SIZE],  // 中央値の計算と外挿の作業領域
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) period: T,
+    coef: T,  // 閾値判定に使う係数
+    pub(crate) replacement: Replacement,
+    flagged: u128,  // 直近のサンプルを外れ値と判定したか（ビット0が最新）
+    median: T,  // 直近の更新で求めた円周上の中央値
+    last: T,  // 直前の出力値
+    pub(crate) last_outlier: bool,  // 直前のサンプルを外れ値と判定したか
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> AngularWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    /// * `period`: Period of the values (`360.0` for degrees, `2π` for radians).
+    /// * `replacement`: How the outliers are replaced.
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::AngularWindow::<f32, 2>::new(0.0, 3.0, 360.0, hampel::Replacement::Median);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number, or `period` is not positive and finite (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(init_val: T, n_sigma: T, period: T, replacement: Replacement) -> Self {
+        let () = Self::VALID_SIZE;
+        filter::assert_n_sigma(n_sigma);
+        assert!(period > T::zero() && period.is_finite(), "period must be a positive finite number");
+
+        let init_val = wrap(init_val, period);
+        Self {
+            window: [init_val; WINDOW_SIZE],
+            work: [init_val; WINDOW_SIZE],
+            oldest: 0,
+            period,
+            coef: offline::coefficient(n_sigma),
+            replacement,
+            flagged: 0,
+            median: init_val,
+            last: init_val,
+            last_outlier: false,
+        }
+    }
+
+    /// Update the window, and return the filtered value.
+    ///
+    /// A sample that is not an outlier is returned as is, without wrapping.
+    pub fn update(&mut self, x: T) -> T {
+        if !x.is_finite() {
+            // 窓には入れず，今の窓の全サンプルから置き換える値を求める
+            let (median, mad) = self.estimate();
+            let value = self.replace(WINDOW_SIZE, x, median, mad);
+            return self.output(value, true);
+        }
+
+        self.window[self.oldest] = wrap(x, self.period);
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        let (median, mad) = self.estimate();
+        let deviation = difference(x, median, self.period);
+        let is_outlier = deviation.abs() > self.coef * mad;
+        let value = if is_outlier { self.replace(WINDOW_SIZE - 1, x, median, mad) } else { x };
+        self.flagged = (self.flagged << 1) | is_outlier as u128;
+        self.output(value, is_outlier)
+    }
+
+    /// 出力値と判定を記録する
+    fn output(&mut self, value: T, is_outlier: bool) -> T {
+        self.last = value;
+        self.last_outlier = is_outlier;
+        value
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier.
+    pub fn is_last_outlier(&self) -> bool {
+        self.last_outlier
+    }
+
+    /// Circular median of the window used by the last update, in `(-period / 2, period / 2]`.
+    pub fn median(&self) -> T {
+        self.median
+    }
+
+    /// Period of the values.
+    pub fn period(&self) -> T {
+        self.period
+    }
+
+    /// How the outliers are replaced.
+    pub fn replacement(&self) -> Replacement {
+        self.replacement
+    }
+
+    /// Fill the window with `init_val`, as right after construction.
+    pub fn reset(&mut self, init_val: T) {
+        let init_val = wrap(init_val, self.period);
+        self.window = [init_val; WINDOW_SIZE];
+        self.oldest = 0;
+        self.flagged = 0;
+        self.median = init_val;
+        self.last = init_val;
+        self.last_outlier = false;
+    }
+
+    /// Iterator over the samples in the window in chronological order (oldest first),
+    /// wrapped into `(-period / 2, period / 2]`.
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.oldest)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// 窓の円周上の中央値と，中央値からの折り返した偏差の絶対値の中央値
+    ///
+    /// 各サンプルの周りに窓を展開した列の中央値を候補として，角度の距離の和が最小のものを選ぶ
+    /// （O(WINDOW_SIZE²)。半周に収まる窓では候補はすべて同じで，普通の中央値と一致する）
+    fn estimate(&mut self) -> (T, T) {
+        let period = self.period;
+        let mut best = (T::infinity(), self.window[0]);
+        for (j, &c) in self.window.iter().enumerate() {
+            if self.window[..j].contains(&c) {
+                continue;  // 同じ値の候補は同じ結果になる
+            }
+            for (w, &x) in self.work.iter_mut().zip(&self.window) {
+                *w = unwrap(x, c, period);
+            }
+            let m = stats::median(&mut self.work);
+            let cost = self.window.iter().fold(T::zero(), |s, &x| s + difference(x, m, period).abs());
+            if cost < best.0 {
+                best = (cost, m);
+            }
+        }
+        let median = wrap(best.1, period);
+        for (w, &x) in self.work.iter_mut().zip(&self.window) {
+            *w = difference(x, median, period).abs();
+        }
+        self.median = median;
+        (median, stats::median(&mut self.work))
+    }
+
+    /// 外れ値`x`を置き換える値（`n`: `x`より前の，判定に使うサンプル数）
+    fn replace(&mut self, n: usize, x: T, median: T, mad: T) -> T {
+        let period = self.period;
+        let value = match self.replacement {
+            Replacement::LinearExtrapolation | Replacement::QuadraticExtrapolation | Replacement::TheilSenExtrapolation => {
+                self.extrapolation(n).unwrap_or(median)
+            },
+            Replacement::HoldLast => self.last,
+            Replacement::PassThrough => return x,
+            Replacement::Clamp if x.is_finite() => {
+                let band = self.coef * mad;
+                if difference(x, median, period) > T::zero() { median + band } else { median - band }
+            },
+            _ => median,
+        };
+        wrap(value, period)
+    }
+
+    /// 前の`n`個のサンプルのうち外れ値と判定しなかったものを連続するように展開して多項式を当てはめ，
+    /// 次の点に外挿した値（`Window`と同じく，最初の使えるサンプルとの差を当てはめる）
+    fn extrapolation(&mut self, n: usize) -> Option<T> {
+        // i番目のサンプルの判定はflaggedのビットn - 1 - iに記録されている（flaggedはまだ今回の分をシフトしていない）
+        let clean = |i: usize| n - 1 - i >= u128::BITS as usize || (self.flagged >> (n - 1 - i)) & 1 == 0;
+        let mut prev: Option<(T, T)> = None;  // 直前の使えるサンプルと，その展開した値
+        let mut offset = T::zero();
+        for (i, &x) in Samples::new(&self.window, self.oldest).take(n).enumerate() {
+            if !clean(i) {
+                self.work[i] = T::nan();
+                continue;
+            }
+            let u = match prev {
+                Some((p, u)) => u + difference(x, p, self.period),
+                None => {
+                    offset = x;
+                    T::zero()
+                },
+            };
+            self.work[i] = u;
+            prev = Some((x, u));
+        }
+        let work = &self.work;
+        let y = |i: usize| if clean(i) { Some(work[i]) } else { None };
+        let gap = |_| T::one();
+        filter::fit(self.replacement, n, &gap, y).map(|f| f.at(filter::next_x(n, &gap)) + offset)
+    }
+}
+
+/// `x - c`を(-period / 2, period / 2]に折り返した差
+fn difference<T: FloatCore>(x: T, c: T, period: T) -> T {
+    let half = period / cast::<f32, T>(2.0).unwrap();
+    let d = x - c;
+    let d = d - period * (d / period - cast::<f32, T>(0.5).unwrap()).ceil();
+    // 丸め誤差で範囲を外れた場合
+    if d <= -half {
+        d + period
+    } else if d > half {
+        d - period
+    } else {
+        d
+    }
+}
+
+/// `c`との差が(-period / 2, period / 2]になるように`x`に周期の整数倍を足した値（範囲内なら`x`のまま）
+fn unwrap<T: FloatCore>(x: T, c: T, period: T) -> T {
+    let d = difference(x, c, period);
+    if d == x - c { x } else { c + d }
+}
+
+/// `x`を(-period / 2, period / 2]に折り返した値
+fn wrap<T: FloatCore>(x: T, period: T) -> T {
+    unwrap(x, T::zero(), period)
+}
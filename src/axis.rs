@@ -0,0 +1,124 @@
+//! Offline filter along an axis of `ndarray` arrays (`ndarray` feature).
+
+use alloc::vec;
+
+use ndarray::{Array2, ArrayView2, ArrayViewMut2, Axis};
+use num_traits::float::FloatCore;
+
+use crate::{filter_slice_into, offline, EdgeMode};
+
+/// Centered Hampel filter applied in place to each lane of `data` along `axis`
+/// (e.g. `Axis(1)` to filter each row of a channels × samples array).
+///
+/// Each lane is filtered as [`filter_slice`](crate::filter_slice) does,
+/// including the windows shrunk to the available samples at both ends of the lane.
+/// The lanes are copied to a buffer, so that non-contiguous views (e.g. strided slices or
+/// transposed arrays) are also supported:
+///
+/// ```
+/// use hampel::{filter_axis, filter_slice, EdgeMode};
+/// use ndarray::{s, Array2, Axis};
+///
+/// // 3 channels × 200 samples with spikes, including at both ends
+/// let data = Array2::from_shape_fn((3, 200), |(c, i)| {
+///     let spike = if (i + 7 * c) % 23 == 0 { 5.0 } else { 0.0 };
+///     (i as f64 * 0.1 + c as f64).sin() + spike
+/// });
+/// let rows = |data: &Array2<f64>, half_window| {
+///     let mut expected = data.clone();
+///     for mut row in expected.rows_mut() {
+///         filter_slice(row.as_slice_mut().unwrap(), half_window, 3.0, EdgeMode::Shrink);
+///     }
+///     expected
+/// };
+///
+/// let mut a = data.clone();
+/// filter_axis(&mut a.view_mut(), Axis(1), 4, 3.0);
+/// assert_eq!(a, rows(&data, 4));
+///
+/// // The same samples in a transposed (samples × channels) view
+/// let mut b = data.clone();
+/// filter_axis(&mut b.view_mut().reversed_axes(), Axis(0), 4, 3.0);
+/// assert_eq!(b, rows(&data, 4));
+///
+/// // Every other sample of a strided view
+/// let mut c = data.clone();
+/// filter_axis(&mut c.slice_mut(s![.., ..;2]), Axis(1), 2, 3.0);
+/// let expected = rows(&data.slice(s![.., ..;2]).to_owned(), 2);
+/// assert_eq!(c.slice(s![.., ..;2]), expected);
+/// assert_eq!(c.slice(s![.., 1..;2]), data.slice(s![.., 1..;2]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `half_window` > [`MAX_HALF_WINDOW`](crate::MAX_HALF_WINDOW).
+pub fn filter_axis<T: FloatCore>(data: &mut ArrayViewMut2<T>, axis: Axis, half_window: usize, n_sigma: T) {
+    let len = data.len_of(axis);
+    let (mut src, mut dst) = (vec![T::zero(); len], vec![T::zero(); len]);
+    for mut lane in data.lanes_mut(axis) {
+        for (s, x) in src.iter_mut().zip(lane.iter()) {
+            *s = *x;
+        }
+        filter_slice_into(&src, &mut dst, half_window, n_sigma, EdgeMode::Shrink);
+        for (x, y) in lane.iter_mut().zip(&dst) {
+            *x = *y;
+        }
+    }
+}
+
+/// Same as [`filter_axis`], but the result is returned as a new array and `data` is left unchanged.
+///
+/// # Panics
+///
+/// Same as [`filter_axis`].
+pub fn filtered_axis<T: FloatCore>(data: ArrayView2<T>, axis: Axis, half_window: usize, n_sigma: T) -> Array2<T> {
+    let mut filtered = data.to_owned();
+    filter_axis(&mut filtered.view_mut(), axis, half_window, n_sigma);
+    filtered
+}
+
+/// Same as [`filtered_axis`], but also returns the mask of the elements replaced as outliers.
+///
+/// ```
+/// use hampel::filtered_axis_with_mask;
+/// use ndarray::{array, Axis};
+///
+/// let data = array![
+///     [1.0, 1.1, 0.9, 1.0, 8.0, 1.1, 0.9, 1.0, 1.2, -5.0],
+///     [2.0, 2.1, 1.9, 2.0, 2.1, 1.9, 2.0, 2.1, 1.9, 2.0],
+/// ];
+/// let (filtered, mask) = filtered_axis_with_mask(data.view(), Axis(1), 2, 3.0);
+/// assert_eq!(filtered.row(0), array![1.0, 1.1, 0.9, 1.0, 1.0, 1.1, 0.9, 1.0, 1.2, 1.0]);
+/// assert_eq!(filtered.row(1), data.row(1));
+/// assert_eq!(mask.row(0), array![false, false, false, false, true, false, false, false, false, true]);
+/// assert!(mask.row(1).iter().all(|m| !m));
+/// ```
+///
+/// # Panics
+///
+/// Same as [`filter_axis`].
+pub fn filtered_axis_with_mask<T: FloatCore>(
+    data: ArrayView2<T>,
+    axis: Axis,
+    half_window: usize,
+    n_sigma: T,
+) -> (Array2<T>, Array2<bool>) {
+    let len = data.len_of(axis);
+    let (mut src, mut dst, mut flags) = (vec![T::zero(); len], vec![T::zero(); len], vec![false; len]);
+    let mut filtered = Array2::from_elem(data.raw_dim(), T::zero());
+    let mut mask = Array2::from_elem(data.raw_dim(), false);
+    let lanes = data.lanes(axis).into_iter().zip(filtered.lanes_mut(axis)).zip(mask.lanes_mut(axis));
+    for ((lane, mut out), mut replaced) in lanes {
+        for (s, x) in src.iter_mut().zip(lane.iter()) {
+            *s = *x;
+        }
+        offline::filter_slice_flagged(&src, &mut dst, &mut flags, half_window, n_sigma);
+        for (x, y) in out.iter_mut().zip(&dst) {
+            *x = *y;
+        }
+        for (m, f) in replaced.iter_mut().zip(&flags) {
+            *m = *f;
+        }
+    }
+    (filtered, mask)
+}
@@ -0,0 +1,217 @@
+//! Multi-channel filter bank.
+
+use num_traits::float::FloatCore;
+
+use crate::{StrideError, Window};
+
+/// How the outlier decisions of the channels of a [`WindowBank`] are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Decision {
+    /// Each channel is filtered independently (same as separate [`Window`]s).
+    #[default]
+    Independent,
+    /// If any channel is determined to be an outlier, the whole vector is replaced.
+    Any,
+    /// Only if all channels are determined to be outliers, the whole vector is replaced.
+    All,
+    /// The [`Sample::score`](crate::Sample::score)s of the channels (the deviations from the medians normalized by the MADs)
+    /// are combined into a single distance, and the whole vector is replaced if it exceeds the joint threshold
+    /// (see [`WindowBank::set_joint_threshold`]). The decisions of each channel are not used.
+    Joint(Distance),
+}
+
+/// How the scores of the channels are combined by [`Decision::Joint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Distance {
+    /// `score_1² + score_2² + ... > threshold²`, i.e. the Euclidean distance in units of the estimated standard deviations.
+    /// It is never smaller than the score of any channel.
+    #[default]
+    SumOfSquares,
+    /// `max(score_1, score_2, ...) > threshold`.
+    MaxAbs,
+}
+
+/// Bank of Hampel filters for multi-channel samples.
+///
+/// With [`Decision::Any`], [`Decision::All`] or [`Decision::Joint`], a jointly flagged sample is replaced
+/// by the vector of the medians of each channel.
+///
+/// ```
+/// use hampel::{Decision, WindowBank};
+///
+/// let mut bank = WindowBank::<f64, 5, 3>::new(0.0, 3.0, Decision::Any);
+/// for x in [[0.1, 0.2, 0.0], [-0.1, 0.0, 0.2], [0.2, -0.2, 0.1], [0.0, 0.1, -0.1]] {
+///     bank.update(x);
+/// }
+/// // A spike on a single channel replaces the whole vector.
+/// assert_eq!(bank.update([0.15, 9.0, 0.05]), [0.1, 0.1, 0.05]);
+/// ```
+///
+/// A spike of a position (x, y, z) may be split across the axes, so that no axis exceeds its own threshold,
+/// while the jump in 3-D is clearly an outlier. [`Decision::Joint`] tests the distance combined from all axes:
+///
+/// ```
+/// use hampel::{Decision, Distance, WindowBank};
+///
+/// // Same noise on each axis, of the MAD about 0.1
+/// let noise = [0.0, 0.1, -0.1, 0.2, -0.2, 0.05, -0.05, 0.15];
+/// let filters = || {
+///     let mut any = WindowBank::<f64, 9, 3>::new(0.0, 3.0, Decision::Any);
+///     let mut joint = WindowBank::<f64, 9, 3>::new(0.0, 3.0, Decision::Joint(Distance::SumOfSquares));
+///     let mut max_abs = WindowBank::<f64, 9, 3>::new(0.0, 3.0, Decision::Joint(Distance::MaxAbs));
+///     max_abs.set_joint_threshold(2.0);
+///     for v in noise {
+///         for bank in [&mut any, &mut joint, &mut max_abs] {
+///             bank.update([v, -v, v]);
+///         }
+///     }
+///     (any, joint, max_abs)
+/// };
+///
+/// // Diagonal spike of about 2.4 estimated standard deviations on each axis (4.1 in 3-D)
+/// let spike = [0.4, -0.4, 0.4];
+/// let (mut any, mut joint, mut max_abs) = filters();
+/// assert_eq!(any.update(spike), spike);
+/// assert_eq!(joint.update(spike), [0.05, -0.05, 0.05]);
+/// assert_eq!(max_abs.update(spike), [0.05, -0.05, 0.05]);
+///
+/// // Spike on a single axis, caught by all of them
+/// let spike = [0.05, 0.9, 0.0];
+/// let (mut any, mut joint, mut max_abs) = filters();
+/// assert_eq!(any.update(spike), [0.05, 0.0, 0.0]);
+/// assert_eq!(joint.update(spike), [0.05, 0.0, 0.0]);
+/// assert_eq!(max_abs.update(spike), [0.05, 0.0, 0.0]);
+///
+/// // Noise within the threshold in 3-D is kept
+/// let x = [0.2, 0.15, -0.1];
+/// let (_, mut joint, _) = filters();
+/// assert_eq!(joint.update(x), x);
+/// ```
+#[derive(Clone)]
+pub struct WindowBank<T: FloatCore, const WINDOW_SIZE: usize, const CHANNELS: usize> {
+    pub(crate) windows: [Window<T, WINDOW_SIZE>; CHANNELS],
+    pub(crate) decision: Decision,
+    pub(crate) joint_threshold: T,  // Decision::Jointの距離の閾値
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, const CHANNELS: usize> WindowBank<T, WINDOW_SIZE, CHANNELS> {
+    /// * `init_val`: Initialization value of the windows.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`]).
+    /// * `decision`: How the decisions of the channels are combined.
+    pub fn new(init_val: T, n_sigma: T, decision: Decision) -> Self {
+        Self::from_window(Window::new(init_val, n_sigma), decision)
+    }
+
+    /// Bank whose channels are all copies of `window`.
+    ///
+    /// The joint threshold of [`Decision::Joint`] is the `n_sigma` of `window` (for asymmetric thresholds, that of the upper side).
+    pub fn from_window(window: Window<T, WINDOW_SIZE>, decision: Decision) -> Self {
+        Self {
+            joint_threshold: window.effective_n_sigma(),
+            windows: core::array::from_fn(|_| window.clone()),
+            decision,
+        }
+    }
+
+    /// Update the windows of all channels.
+    ///
+    /// ```
+    /// use hampel::{Decision, Replacement, Window, WindowBank};
+    ///
+    /// let window = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+    /// let mut independent = WindowBank::<f64, 5, 3>::from_window(window, Decision::Independent);
+    /// let mut all = WindowBank::<f64, 5, 3>::new(0.0, 3.0, Decision::All);
+    /// for x in [[0.1, 0.2, 0.0], [-0.1, 0.0, 0.2], [0.2, -0.2, 0.1], [0.0, 0.1, -0.1]] {
+    ///     independent.update(x);
+    ///     all.update(x);
+    /// }
+    /// // Single-channel spike
+    /// assert_eq!(independent.update([0.15, 9.0, 0.05]), [0.15, 0.1, 0.05]);
+    /// assert_eq!(all.update([0.15, 9.0, 0.05]), [0.15, 9.0, 0.05]);
+    /// // Correlated spike across channels
+    /// assert_eq!(all.update([8.0, 9.0, 7.0]), [0.15, 0.1, 0.1]);
+    /// ```
+    pub fn update(&mut self, x: [T; CHANNELS]) -> [T; CHANNELS] {
+        let samples: [_; CHANNELS] = core::array::from_fn(|i| self.windows[i].update_detailed(x[i]));
+
+        let joint = match self.decision {
+            Decision::Independent => return samples.map(|s| s.value),
+            Decision::Any => samples.iter().any(|s| s.is_outlier),
+            Decision::All => samples.iter().all(|s| s.is_outlier),
+            Decision::Joint(Distance::SumOfSquares) => {
+                let d = samples.iter().fold(T::zero(), |d, s| d + s.score * s.score);
+                d > self.joint_threshold * self.joint_threshold
+            },
+            Decision::Joint(Distance::MaxAbs) => samples.iter().any(|s| s.score > self.joint_threshold),
+        };
+        if joint {
+            samples.map(|s| s.median)
+        } else {
+            x
+        }
+    }
+
+    /// Same as [`update`](Self::update) applied to each frame of `CHANNELS` samples of the interleaved buffer `buf`
+    /// (e.g. `xyzxyz...`), in place.
+    ///
+    /// Returns an error without updating the windows if the length of `buf` is not a multiple of `CHANNELS`.
+    ///
+    /// ```
+    /// use hampel::{Decision, StrideError, WindowBank};
+    ///
+    /// let input: Vec<f64> = (0..900).map(|i| (i as f64 * 0.02).cos() + if i % 41 == 7 { 6.0 } else { 0.0 }).collect();
+    /// let mut reference = WindowBank::<f64, 7, 3>::new(0.0, 3.0, Decision::Any);
+    /// let mut expected = input.clone();
+    /// for frame in expected.chunks_exact_mut(3) {
+    ///     frame.copy_from_slice(&reference.update([frame[0], frame[1], frame[2]]));
+    /// }
+    ///
+    /// let mut bank = WindowBank::<f64, 7, 3>::new(0.0, 3.0, Decision::Any);
+    /// let mut buf = input.clone();
+    /// bank.process_interleaved(&mut buf).unwrap();
+    /// assert_eq!(buf, expected);
+    ///
+    /// assert_eq!(bank.process_interleaved(&mut buf[..8]), Err(StrideError::PartialFrame { len: 8, channels: 3 }));
+    /// assert!(bank == reference);
+    /// ```
+    pub fn process_interleaved(&mut self, buf: &mut [T]) -> Result<(), StrideError> {
+        if CHANNELS == 0 || !buf.len().is_multiple_of(CHANNELS) {
+            return Err(StrideError::PartialFrame { len: buf.len(), channels: CHANNELS });
+        }
+        for frame in buf.chunks_exact_mut(CHANNELS) {
+            let y = self.update(core::array::from_fn(|i| frame[i]));
+            frame.copy_from_slice(&y);
+        }
+        Ok(())
+    }
+
+    /// Windows of each channel.
+    pub fn windows(&self) -> &[Window<T, WINDOW_SIZE>; CHANNELS] {
+        &self.windows
+    }
+
+    /// Set the threshold of the distance combined by [`Decision::Joint`], in units of the estimated standard deviations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is NaN or negative.
+    pub fn set_joint_threshold(&mut self, threshold: T) {
+        assert!(threshold >= T::zero(), "joint threshold must be a non-negative number");
+        self.joint_threshold = threshold;
+    }
+
+    /// Threshold of the distance combined by [`Decision::Joint`].
+    pub fn joint_threshold(&self) -> T {
+        self.joint_threshold
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, const CHANNELS: usize> PartialEq for WindowBank<T, WINDOW_SIZE, CHANNELS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.windows == other.windows && self.decision == other.decision && self.joint_threshold == other.joint_threshold
+    }
+}
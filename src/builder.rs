@@ -0,0 +1,480 @@
+//! Builder of [`Window`].
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{adaptive::Adaptation, filter::{Core, DEFAULT_LABEL}, network::Selection, precision, AdaptiveThreshold, Center, ConfigError, NanPolicy, OutlierStats, Reference, ReseedPolicy, Replacement, ScaleEstimator, StorePolicy, ThresholdMode, Window, ZeroMad};
+
+/// Builder of [`Window`].
+///
+/// Options that are not specified take the following default values:
+///
+/// * `init_value`: `0.0`
+/// * `n_sigma`: `3.0` (for both sides of the median)
+/// * `scale`: the consistency constant of the `scale_estimator` (`1.4826` for the MAD, see [`Window::with_scale`])
+/// * `scale_estimator`: `ScaleEstimator::Mad`
+/// * `center`: `Center::Median`
+/// * `reference`: `Reference::Median`
+/// * `mad_floor`: `0.0`
+/// * `zero_mad`: `ZeroMad::FlagEverything`
+/// * `abs_threshold`: none (only the relative threshold is used)
+/// * `threshold_mode`: `ThresholdMode::Either`
+/// * `output_range`: none (any value is possible)
+/// * `max_slew`: none (the output is not rate-limited)
+/// * `difference_test`: none (only the values are tested)
+/// * `suspect`: none (no sample is classified as suspect)
+/// * `max_consecutive_replacements`: `0` (unlimited)
+/// * `reseed_policy`: `ReseedPolicy::Run`
+/// * `adaptive`: none (fixed threshold)
+/// * `forgetting_factor`: none (all samples in the window have the same weight)
+/// * `replacement`: `Replacement::default()`
+/// * `store_policy`: `StorePolicy::Raw`
+/// * `nan_policy`: `NanPolicy::Propagate`
+/// * `fill_missing`: `false`
+/// * `max_missing`: `WINDOW_SIZE / 2`
+/// * `warm_up`: `false`
+/// * `undoable`: `false`
+/// * `label`: `"hampel"`
+///
+/// ```
+/// use hampel::{Replacement, Window, WindowBuilder};
+///
+/// let mut filter: Window<f64, 5> = WindowBuilder::new()
+///     .init_value(0.0)
+///     .n_sigma(3.0)
+///     .replacement(Replacement::Median)
+///     .build();
+/// filter.update(1.0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct WindowBuilder<T: FloatCore, const WINDOW_SIZE: usize> {
+    init_value: Option<T>,
+    n_sigma_low: Option<T>,
+    n_sigma_high: Option<T>,
+    scale: Option<T>,
+    scale_estimator: ScaleEstimator,
+    center: Center,
+    reference: Reference,
+    mad_floor: Option<T>,
+    zero_mad: ZeroMad<T>,
+    abs_threshold: Option<T>,
+    threshold_mode: ThresholdMode,
+    output_range: Option<(T, T)>,
+    max_slew: Option<T>,
+    difference_test: Option<(T, ThresholdMode)>,
+    max_consecutive_replacements: usize,
+    reseed_policy: ReseedPolicy,
+    confirmation: Option<(usize, usize)>,
+    hysteresis: Option<T>,
+    suspect: Option<(T, bool)>,
+    adaptive: Option<AdaptiveThreshold<T>>,
+    forgetting_factor: Option<T>,
+    replacement: Option<Replacement>,
+    soft_replacement: Option<T>,
+    store_policy: StorePolicy,
+    nan_policy: NanPolicy,
+    fill_missing: bool,
+    max_missing: Option<usize>,
+    warm_up: bool,
+    undoable: bool,
+    #[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(dead_code))]
+    label: &'static str,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> Default for WindowBuilder<T, WINDOW_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> WindowBuilder<T, WINDOW_SIZE> {
+    /// Builder with all options left at their default values.
+    pub const fn new() -> Self {
+        Self {
+            init_value: None,
+            n_sigma_low: None,
+            n_sigma_high: None,
+            scale: None,
+            scale_estimator: ScaleEstimator::Mad,
+            center: Center::Median,
+            reference: Reference::Median,
+            mad_floor: None,
+            zero_mad: ZeroMad::FlagEverything,
+            abs_threshold: None,
+            threshold_mode: ThresholdMode::Either,
+            output_range: None,
+            max_slew: None,
+            difference_test: None,
+            max_consecutive_replacements: 0,
+            reseed_policy: ReseedPolicy::Run,
+            confirmation: None,
+            hysteresis: None,
+            suspect: None,
+            adaptive: None,
+            forgetting_factor: None,
+            replacement: None,
+            soft_replacement: None,
+            store_policy: StorePolicy::Raw,
+            nan_policy: NanPolicy::Propagate,
+            fill_missing: false,
+            max_missing: None,
+            warm_up: false,
+            undoable: false,
+            label: DEFAULT_LABEL,
+        }
+    }
+
+    /// Initialization value of window.
+    pub const fn init_value(mut self, init_value: T) -> Self {
+        self.init_value = Some(init_value);
+        self
+    }
+
+    /// Threshold for determining an outlier (see [`Window::new`]).
+    ///
+    /// [`try_build`](Self::try_build) returns [`ConfigError::InvalidNSigma`] unless it is a positive finite number.
+    pub const fn n_sigma(mut self, n_sigma: T) -> Self {
+        self.n_sigma_low = Some(n_sigma);
+        self.n_sigma_high = Some(n_sigma);
+        self
+    }
+
+    /// Separate thresholds for deviations below and above the median (see [`Window::asymmetric`]).
+    pub const fn n_sigma_asymmetric(mut self, n_sigma_low: T, n_sigma_high: T) -> Self {
+        self.n_sigma_low = Some(n_sigma_low);
+        self.n_sigma_high = Some(n_sigma_high);
+        self
+    }
+
+    /// Consistency constant of the MAD (see [`Window::with_scale`]).
+    ///
+    /// [`try_build`](Self::try_build) returns [`ConfigError::InvalidScale`] unless it is a positive finite number.
+    pub const fn scale(mut self, scale: T) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Estimator of the spread of the window (see [`ScaleEstimator`]).
+    ///
+    /// Unless the `scale` is specified, the consistency constant of the estimator is used.
+    pub const fn scale_estimator(mut self, scale_estimator: ScaleEstimator) -> Self {
+        self.scale_estimator = scale_estimator;
+        self
+    }
+
+    /// Estimator of the center of the window (see [`Center`]).
+    pub const fn center(mut self, center: Center) -> Self {
+        self.center = center;
+        self
+    }
+
+    /// Reference from which the deviations are measured (see [`Reference`]).
+    pub const fn reference(mut self, reference: Reference) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    /// Lower bound of the scale estimate used for the decision (see [`Window::set_mad_floor`]).
+    pub const fn mad_floor(mut self, mad_floor: T) -> Self {
+        self.mad_floor = Some(mad_floor);
+        self
+    }
+
+    /// Decision when the scale estimate is exactly zero (see [`ZeroMad`]).
+    pub const fn zero_mad(mut self, zero_mad: ZeroMad<T>) -> Self {
+        self.zero_mad = zero_mad;
+        self
+    }
+
+    /// Absolute threshold of the deviation from the median, `|x - median| > abs_threshold`.
+    ///
+    /// It is combined with the relative threshold (`n_sigma`) according to the `threshold_mode`.
+    /// Which criterion tripped is reported by [`Sample::relative`](crate::Sample::relative) and [`Sample::absolute`](crate::Sample::absolute).
+    pub const fn abs_threshold(mut self, abs_threshold: T) -> Self {
+        self.abs_threshold = Some(abs_threshold);
+        self
+    }
+
+    /// How the relative and absolute thresholds are combined (only used with `abs_threshold`).
+    pub const fn threshold_mode(mut self, threshold_mode: ThresholdMode) -> Self {
+        self.threshold_mode = threshold_mode;
+        self
+    }
+
+    /// Physical range `[lo, hi]` of the signal (e.g. the measurement range of the sensor).
+    ///
+    /// An input outside the range is always an outlier (see [`Sample::out_of_range`](crate::Sample::out_of_range)),
+    /// and the replacement values are clamped into the range.
+    pub const fn output_range(mut self, lo: T, hi: T) -> Self {
+        self.output_range = Some((lo, hi));
+        self
+    }
+
+    /// Limit the change of the output to `delta` per sample (e.g. for an actuator command).
+    ///
+    /// Each returned value is clamped to within `±delta` of the previously returned value, after the outlier decision,
+    /// while the window stores the values as without the limit.
+    pub const fn max_slew(mut self, delta: T) -> Self {
+        self.max_slew = Some(delta);
+        self
+    }
+
+    /// Also test the first difference of the signal, for glitches that are small compared with
+    /// the spread of the window but arrive as implausible jumps between consecutive samples.
+    ///
+    /// The jump from the newest sample not flagged as an outlier is compared with the median and the MAD
+    /// of the differences between consecutive samples in the window, and the result is combined with
+    /// the test on the values according to `mode` (see [`Sample::difference`](crate::Sample::difference)).
+    /// [`try_build`](Self::try_build) returns [`ConfigError::InvalidNSigma`] unless `n_sigma` is a positive finite number.
+    pub const fn difference_test(mut self, n_sigma: T, mode: ThresholdMode) -> Self {
+        self.difference_test = Some((n_sigma, mode));
+        self
+    }
+
+    /// Maximum number of consecutive replacements (`0`: unlimited).
+    ///
+    /// Once `max` samples in a row have been replaced, the next sample that would be an outlier
+    /// is accepted as is, as a genuine level shift, and the window is rebuilt (see [`reseed_policy`](Self::reseed_policy)).
+    pub const fn max_consecutive_replacements(mut self, max: usize) -> Self {
+        self.max_consecutive_replacements = max;
+        self
+    }
+
+    /// How the window is rebuilt when [`max_consecutive_replacements`](Self::max_consecutive_replacements) is reached
+    /// (marked by [`Sample::reseeded`](crate::Sample::reseeded)).
+    pub const fn reseed_policy(mut self, reseed_policy: ReseedPolicy) -> Self {
+        self.reseed_policy = reseed_policy;
+        self
+    }
+
+    /// Replace a sample only if at least `m` of the last `k` samples (including it) tripped the tests
+    /// (`1 <= m <= k <= 8`); the other ones that tripped are passed through (see [`Sample::tripped`](crate::Sample::tripped)).
+    pub const fn confirmation(mut self, m: usize, k: usize) -> Self {
+        self.confirmation = Some((m, k));
+        self
+    }
+
+    /// Hysteresis of the relative threshold: after an outlier, the following samples are also
+    /// determined to be outliers until one falls back inside the tighter band of `n_sigma_exit` (<= `n_sigma`).
+    ///
+    /// Whether the filter is in an outlier run can be read by [`Window::in_outlier_run`].
+    pub const fn hysteresis(mut self, n_sigma_exit: T) -> Self {
+        self.hysteresis = Some(n_sigma_exit);
+        self
+    }
+
+    /// Classify the samples that are not outliers, but deviate by more than `n_sigma_suspect` (<= `n_sigma`),
+    /// as [`Class::Suspect`](crate::Class::Suspect) (see [`Sample::class`](crate::Sample::class)).
+    ///
+    /// If `replace` is `true`, the suspect samples are also replaced, but they are not outliers otherwise.
+    pub const fn suspect(mut self, n_sigma_suspect: T, replace: bool) -> Self {
+        self.suspect = Some((n_sigma_suspect, replace));
+        self
+    }
+
+    /// Adapt the threshold to the recent outlier rate (see [`AdaptiveThreshold`]).
+    ///
+    /// The current value can be read by [`Window::effective_n_sigma`].
+    pub const fn adaptive(mut self, adaptive: AdaptiveThreshold<T>) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Weight the samples in the window by their age: a sample `a` updates old has the weight `λ^a`,
+    /// and the median and the MAD are replaced by their weighted versions.
+    ///
+    /// `λ` must be in `(0, 1]` (`1` is the unweighted filter), and only `ScaleEstimator::Mad` is supported.
+    pub const fn forgetting_factor(mut self, lambda: T) -> Self {
+        self.forgetting_factor = Some(lambda);
+        self
+    }
+
+    /// Value returned in place of an outlier.
+    pub const fn replacement(mut self, replacement: Replacement) -> Self {
+        self.replacement = Some(replacement);
+        self
+    }
+
+    /// Soft replacement: instead of switching to the replacement value at the threshold,
+    /// the outliers of the relative test are pulled smoothly toward the edge of the acceptance band.
+    ///
+    /// The output is continuous and non-decreasing in `x`, and never leaves the band widened by `rolloff` times
+    /// its half-width (`rolloff = 0` is the same as `Replacement::Clamp`).
+    pub const fn soft_replacement(mut self, rolloff: T) -> Self {
+        self.soft_replacement = Some(rolloff);
+        self
+    }
+
+    /// What is stored in the window for an outlier (see [`StorePolicy`]).
+    pub const fn store_policy(mut self, store_policy: StorePolicy) -> Self {
+        self.store_policy = store_policy;
+        self
+    }
+
+    /// Handling of non-finite input values (see [`NanPolicy`]).
+    pub const fn nan_policy(mut self, nan_policy: NanPolicy) -> Self {
+        self.nan_policy = nan_policy;
+        self
+    }
+
+    /// If `true`, the estimate returned by [`Window::update_missing`] is stored in the window in place of
+    /// the missing sample, so that the window keeps moving with the samples that should have been received.
+    ///
+    /// The stored estimates are not used for the extrapolation of the following samples.
+    /// If `false`, the window is left unchanged.
+    pub const fn fill_missing(mut self, fill_missing: bool) -> Self {
+        self.fill_missing = fill_missing;
+        self
+    }
+
+    /// Number of consecutive missing samples up to which the estimates of [`Window::update_missing`]
+    /// are considered trustworthy. The estimates after more missing samples are marked by [`Sample::stale`](crate::Sample::stale).
+    pub const fn max_missing(mut self, max_missing: usize) -> Self {
+        self.max_missing = Some(max_missing);
+        self
+    }
+
+    /// If `true`, the median and MAD are computed only over the samples actually received
+    /// until the window is filled, instead of over a window pre-filled with `init_value`.
+    ///
+    /// While fewer than three samples have been received, every sample is passed through as is.
+    pub const fn warm_up(mut self, warm_up: bool) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+
+    /// If `true`, the state before each update is kept, so that the last update can be undone
+    /// by [`Window::undo_last_update`].
+    pub const fn undoable(mut self, undoable: bool) -> Self {
+        self.undoable = undoable;
+        self
+    }
+
+    /// Name of the filter in the records of the replacements (e.g. the channel), with the `log` or `defmt` feature.
+    ///
+    /// Each replaced outlier is recorded at the debug level (target: `hampel`),
+    /// as "`<label>: sample <samples_seen>: replaced <raw> by <value> (median <median>, MAD <mad>)`".
+    pub const fn label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Build the window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid (see [`try_build`](Self::try_build)).
+    pub fn build(self) -> Window<T, WINDOW_SIZE> {
+        match self.try_build() {
+            Ok(window) => window,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Build the window, or return an error if the configuration is invalid (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn try_build(self) -> Result<Window<T, WINDOW_SIZE>, ConfigError> {
+        let () = Window::<T, WINDOW_SIZE>::VALID_SIZE;
+
+        let (init_val, mut core) = self.build_core(WINDOW_SIZE)?;
+        let mut window = [init_val; WINDOW_SIZE];
+        let mut sorted = window;
+        core.reset(&mut window, &mut Selection(&mut sorted), init_val);
+        Ok(Window { window, sorted, gaps: [T::one(); WINDOW_SIZE], time: None, core, undo: None, handler: () })
+    }
+
+    /// 長さ`len`のwindowの初期値とバッファ以外の状態を作る（`WINDOW_SIZE`は使わない）
+    pub(crate) fn build_core(self, len: usize) -> Result<(T, Core<T>), ConfigError> {
+        let init_val = self.init_value.unwrap_or_else(T::zero);
+        let default_n_sigma = cast::<f32, T>(3.0).ok_or(ConfigError::Cast)?;
+        let n_sigma_low = self.n_sigma_low.unwrap_or(default_n_sigma);
+        let n_sigma_high = self.n_sigma_high.unwrap_or(default_n_sigma);
+        // 検証はCore::validateでまとめて行う（u8に収まらない値はそれより先に除く）
+        let confirmation = match self.confirmation {
+            Some((m, k)) => match (u8::try_from(m), u8::try_from(k)) {
+                (Ok(m), Ok(k)) => Some((m, k)),
+                _ => return Err(ConfigError::InvalidConfirmation),
+            },
+            None => None,
+        };
+        let difference = match self.difference_test {
+            Some((n_sigma, mode)) => Some((precision::mul(cast::<f32, T>(1.4826).ok_or(ConfigError::Cast)?, n_sigma), mode)),
+            None => None,
+        };
+        let scale = match self.scale {
+            Some(scale) => scale,
+            None => {
+                let c = match self.scale_estimator {
+                    ScaleEstimator::Mad => 1.4826,  // 1.4826は正規分布にするための係数
+                    ScaleEstimator::Sn => 1.1926,
+                    ScaleEstimator::Iqr => 1.0,  // n_sigmaをそのままTukeyのkとして使う
+                };
+                cast::<f32, T>(c).ok_or(ConfigError::Cast)?
+            }
+        };
+        let suspect = self.suspect.map(|(s, replace)| (precision::mul(scale, s), replace));
+
+        let core = Core {
+            oldest: 0,
+            coef_low: precision::mul(scale, n_sigma_low),
+            coef_high: precision::mul(scale, n_sigma_high),
+            scale_const: scale,
+            n_sigma_low,
+            n_sigma_high,
+            adaptation: self.adaptive.map(Adaptation::new),
+            estimator: self.scale_estimator,
+            center: self.center,
+            reference: self.reference,
+            center_len: 0,
+            mad_floor: self.mad_floor.unwrap_or_else(T::zero),
+            zero_mad: self.zero_mad,
+            abs_threshold: self.abs_threshold,
+            threshold_mode: self.threshold_mode,
+            output_range: self.output_range,
+            max_slew: self.max_slew,
+            difference,
+            replacement: self.replacement.unwrap_or_default(),
+            soft: self.soft_replacement,
+            store: self.store_policy,
+            nan_policy: self.nan_policy,
+            warm_up: self.warm_up,
+            undoable: self.undoable,
+            seen: 0,
+            last: init_val,
+            last_valid: init_val,
+            stats: OutlierStats::default(),
+            max_run: self.max_consecutive_replacements,
+            run: 0,
+            reseed: self.reseed_policy,
+            hysteresis: self.hysteresis.map(|exit| precision::mul(scale, exit)),
+            suspect,
+            in_run: false,
+            confirmation,
+            votes: 0,
+            fill_missing: self.fill_missing,
+            max_missing: self.max_missing.unwrap_or(len / 2),
+            missing: 0,
+            missing_gap: None,
+            flagged: 0,
+            forgetting: self.forgetting_factor,
+            median: init_val,
+            scale: T::zero(),
+            bounds: (init_val, init_val),
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            label: self.label,
+            nan_count: 0,
+            sorted_dirty: false,
+            line: None,
+            quantiles: None,
+        };
+        core.validate()?;
+        Ok((init_val, core))
+    }
+}
+
+/// 既定の設定で長さ`len`のwindowの`Core`を作る（実行時にサイズが決まるwindow用）
+pub(crate) fn default_core<T: FloatCore>(len: usize, init_val: T, n_sigma: T) -> Result<Core<T>, ConfigError> {
+    let (_, core) = WindowBuilder::<T, 0>::new()
+        .init_value(init_val)
+        .n_sigma(n_sigma)
+        .build_core(len)?;
+    Ok(core)
+}
@@ -0,0 +1,81 @@
+//! Threshold derived from a recording without outliers (`alloc` feature).
+
+use alloc::vec::Vec;
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::Window;
+
+/// Result of [`calibrate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CalibrationResult<T> {
+    /// Smallest threshold with which at most `target_fpr` of the scored samples are determined to be outliers.
+    pub n_sigma: T,
+    /// Largest score seen (infinite if a sample differs from the median of a window whose MAD is zero).
+    pub max_score: T,
+    /// Smallest MAD of the windows (the quietest stretch of the recording), as a floor for [`WindowBuilder::mad_floor`](crate::WindowBuilder::mad_floor).
+    /// It does not change the decisions on the recording itself.
+    pub mad_floor: T,
+    /// Number of scored samples (the samples after the first `WINDOW_SIZE - 1`, except those with a NaN score).
+    pub samples: usize,
+}
+
+/// Threshold `n_sigma` for a [`Window`] of `WINDOW_SIZE` that would determine at most a fraction `target_fpr`
+/// (false positive rate) of the samples of `clean` to be outliers, where `clean` is a recording
+/// trusted to be free of outliers.
+///
+/// The recording is streamed through a [`Window`] with the default configuration, which stores the raw samples,
+/// and the [`Sample::score`](crate::Sample::score) of every sample is recorded, starting from the first full window
+/// (the first `WINDOW_SIZE - 1` samples only fill it). The threshold is the `1 - target_fpr` quantile of the scores:
+/// the score such that at most `floor(target_fpr * samples)` scores exceed it.
+///
+/// Calibrated on Gaussian noise, the threshold is close to the quantile of the normal distribution
+/// (2.576 for a false positive rate of 1%), as the score is the deviation in units of the estimated standard deviation:
+///
+/// ```
+/// use hampel::calibrate;
+///
+/// // Noise in [-0.5, 0.5)
+/// let clean: Vec<f64> = (0..2000).map(|i| ((i * 7919) % 101) as f64 / 101.0 - 0.5).collect();
+///
+/// let result = calibrate::<f64, 31>(&clean, 0.01);
+/// assert_eq!(result.samples, 2000 - 30);
+/// assert!(result.max_score >= result.n_sigma);
+/// assert!(result.mad_floor > 0.0);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `target_fpr` is not in `[0, 1]`, or `clean` has fewer than `WINDOW_SIZE` samples.
+pub fn calibrate<T: FloatCore, const WINDOW_SIZE: usize>(clean: &[T], target_fpr: T) -> CalibrationResult<T> {
+    assert!(target_fpr >= T::zero() && target_fpr <= T::one(), "target_fpr must be in [0, 1]");
+    assert!(clean.len() >= WINDOW_SIZE, "clean must have at least WINDOW_SIZE samples");
+
+    let mut window = Window::<T, WINDOW_SIZE>::builder().init_value(clean[0]).build();
+    let mut scores = Vec::with_capacity(clean.len() + 1 - WINDOW_SIZE);
+    let mut mad_floor = T::infinity();
+    for (i, &x) in clean.iter().enumerate() {
+        let s = window.update_detailed(x);
+        if i + 1 < WINDOW_SIZE || s.score.is_nan() {
+            continue;
+        }
+        scores.push(s.score);
+        mad_floor = mad_floor.min(s.mad);
+    }
+    if scores.is_empty() {
+        return CalibrationResult { n_sigma: T::infinity(), max_score: T::zero(), mad_floor: T::zero(), samples: 0 };
+    }
+
+    scores.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = scores.len();
+    // 閾値を超えてよいサンプル数（target_fpr * nの切り捨て）
+    let allowed = (target_fpr * cast::<usize, T>(n).unwrap()).floor().to_usize().unwrap_or(n).min(n);
+    CalibrationResult {
+        n_sigma: scores[(n - 1).saturating_sub(allowed)],
+        max_score: scores[n - 1],
+        mad_floor,
+        samples: n,
+    }
+}
@@ -0,0 +1,192 @@
+//! Streaming version of the offline (centered) Hampel filter.
+
+use core::iter::FusedIterator;
+
+use num_traits::float::FloatCore;
+
+use crate::{filter, iter::Samples, offline};
+
+/// Centered Hampel filter on a stream, with a delay of `WINDOW_SIZE / 2` samples.
+///
+/// * `WINDOW_SIZE` >= 3 (odd)
+///
+/// Unlike [`Window`](crate::Window), which judges the newest sample against the window of the past samples,
+/// each sample is judged against the median and the MAD of the window centered on it
+/// (`WINDOW_SIZE / 2` samples on each side), as [`filter_slice`](crate::filter_slice) does.
+/// Therefore [`update`](Self::update) returns `None` for the first `WINDOW_SIZE / 2` calls,
+/// and thereafter the filtered value of the sample received `WINDOW_SIZE / 2` calls before.
+/// At the end of the stream, [`flush`](Self::flush) returns the remaining samples.
+///
+/// As with [`EdgeMode::Shrink`](crate::EdgeMode::Shrink), the window shrinks to the available samples
+/// at the start and the end of the stream, so that the output is exactly the same as `filter_slice`
+/// with `half_window = WINDOW_SIZE / 2` and `EdgeMode::Shrink`:
+///
+/// ```
+/// use hampel::{filter_slice, CenteredWindow, EdgeMode};
+///
+/// fn check<const N: usize>(trace: &[f64]) {
+///     let mut filter = CenteredWindow::<f64, N>::new(3.0);
+///     let mut output = Vec::new();
+///     for (i, &x) in trace.iter().enumerate() {
+///         match filter.update(x) {
+///             Some(y) => output.push(y),
+///             None => assert!(i < N / 2),
+///         }
+///     }
+///     output.extend(filter.flush());
+///
+///     let mut expected = trace.to_vec();
+///     filter_slice(&mut expected, N / 2, 3.0, EdgeMode::Shrink);
+///     assert_eq!(output, expected);
+/// }
+///
+/// // Recorded trace with spikes, including at both ends
+/// let trace = [
+///     9.0, 1.02, 0.98, 1.01, 1.05, 0.97, 1.03, 6.5, 1.00, 0.99, 1.04, 1.01, 0.96, -3.2, 1.02,
+///     1.00, 1.06, 0.95, 1.03, 1.01, 2.10, 2.05, 2.12, 1.98, 2.03, 7.40, 2.01, 2.07, 1.99, -4.0,
+/// ];
+/// check::<3>(&trace);
+/// check::<5>(&trace);
+/// check::<9>(&trace);
+/// check::<101>(&trace);
+/// check::<5>(&trace[..1]);
+/// ```
+#[derive(Clone)]
+pub struct CenteredWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],  // 受け取ったサンプルのリングバッファ
+    work: [T; WINDOW_SIZE],  // 中央値計算用の作業領域
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) len: usize,  // windowに格納したサンプル数（WINDOW_SIZE以下）
+    pub(crate) pending: usize,  // まだ出力していない最新のサンプル数
+    pub(crate) coef: T,  // 閾値判定に使う係数
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> CenteredWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上の奇数であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3 && WINDOW_SIZE % 2 == 1, "WINDOW_SIZE must be odd and at least 3");
+
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// Outliers are replaced by the median of the window centered on them.
+    ///
+    /// An even `WINDOW_SIZE` or `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::CenteredWindow::<f32, 4>::new(3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (an even `WINDOW_SIZE` or `WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(n_sigma: T) -> Self {
+        let () = Self::VALID_SIZE;
+        filter::assert_n_sigma(n_sigma);
+
+        Self {
+            window: [T::zero(); WINDOW_SIZE],
+            work: [T::zero(); WINDOW_SIZE],
+            oldest: 0,
+            len: 0,
+            pending: 0,
+            coef: offline::coefficient(n_sigma),
+        }
+    }
+
+    /// Add a sample, and return the filtered value of the sample received `WINDOW_SIZE / 2` calls before
+    /// (`None` for the first `WINDOW_SIZE / 2` calls after construction, `reset` or `flush`).
+    ///
+    /// ```
+    /// use hampel::CenteredWindow;
+    ///
+    /// let mut filter = CenteredWindow::<f64, 5>::new(3.0);
+    /// assert_eq!(filter.update(1.0), None);
+    /// assert_eq!(filter.update(1.1), None);
+    /// assert_eq!(filter.update(8.0), Some(1.0));
+    /// assert_eq!(filter.update(0.9), Some(1.1));
+    /// assert_eq!(filter.update(1.2), Some(1.1));  // 8.0 is replaced by the median of [1.0, 1.1, 8.0, 0.9, 1.2]
+    /// assert_eq!(filter.flush().collect::<Vec<_>>(), [0.9, 1.2]);
+    /// ```
+    pub fn update(&mut self, x: T) -> Option<T> {
+        if self.len < WINDOW_SIZE {
+            self.window[self.len] = x;  // 埋まるまではoldest = 0
+            self.len += 1;
+        } else {
+            self.window[self.oldest] = x;
+            self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        }
+        self.pending += 1;
+
+        if self.pending > WINDOW_SIZE / 2 {
+            // 新しい方にWINDOW_SIZE / 2個のサンプルが揃ったので出力する
+            let i = self.len - self.pending;
+            self.pending -= 1;
+            Some(self.filtered(i))
+        } else {
+            None
+        }
+    }
+
+    /// End the stream, and return an iterator over the filtered values of the samples
+    /// that have not been returned by [`update`](Self::update) (at most `WINDOW_SIZE / 2`),
+    /// judged against the windows shrunk to the available samples.
+    ///
+    /// The filter is emptied, so that it can be used for a new stream.
+    pub fn flush(&mut self) -> Flush<T, WINDOW_SIZE> {
+        let filter = self.clone();
+        self.reset();
+        Flush { filter }
+    }
+
+    /// Discard all samples, including those not yet returned.
+    pub fn reset(&mut self) {
+        self.oldest = 0;
+        self.len = 0;
+        self.pending = 0;
+    }
+
+    /// Iterator over the samples in the window, from the oldest to the newest.
+    ///
+    /// The window holds the last `WINDOW_SIZE` samples (fewer at the start of the stream).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window[..self.len], self.oldest)
+    }
+
+    /// 古い方から`i`番目のサンプルを，それを中心とする窓（使えるサンプルに縮める）で判定した出力値
+    fn filtered(&mut self, i: usize) -> T {
+        let begin = i.saturating_sub(WINDOW_SIZE / 2);
+        let work = &mut self.work[..(self.len - begin)];  // 未出力のサンプルはWINDOW_SIZE / 2個以下なので，窓は最新のサンプルまで
+        for (w, x) in work.iter_mut().zip(Samples::new(&self.window[..self.len], self.oldest).skip(begin)) {
+            *w = *x;
+        }
+
+        let x = self.window[(self.oldest + i) % WINDOW_SIZE];
+        offline::judge(x, work, self.coef)
+    }
+}
+
+/// Iterator returned by [`CenteredWindow::flush`].
+#[derive(Clone)]
+pub struct Flush<T: FloatCore, const WINDOW_SIZE: usize> {
+    filter: CenteredWindow<T, WINDOW_SIZE>,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> Iterator for Flush<T, WINDOW_SIZE> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.filter.pending == 0 {
+            return None;
+        }
+        let i = self.filter.len - self.filter.pending;
+        self.filter.pending -= 1;
+        Some(self.filter.filtered(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.filter.pending, Some(self.filter.pending))
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> ExactSizeIterator for Flush<T, WINDOW_SIZE> {}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> FusedIterator for Flush<T, WINDOW_SIZE> {}
@@ -0,0 +1,143 @@
+//! Offline filter over consecutive chunks of a recording (`alloc` feature).
+
+use alloc::{vec, vec::Vec};
+
+use num_traits::float::FloatCore;
+
+use crate::{filter, offline, EdgeMode, MAX_HALF_WINDOW};
+
+/// Centered Hampel filter over a recording given in consecutive chunks, e.g. read from a file that does not fit in memory.
+///
+/// The output is exactly the same as [`filter_slice`](crate::filter_slice) applied to the whole recording at once,
+/// wherever the chunk boundaries are. The filter keeps the last `2 * half_window` samples, so that each sample is
+/// output as soon as the `half_window` samples after it have been pushed, and [`finish`](Self::finish)
+/// outputs the last `half_window` samples, judged at the end of the recording as `edge` specifies.
+///
+/// ```
+/// use hampel::{filter_slice, ChunkedFilter, EdgeMode};
+///
+/// let recording: Vec<f64> = (0..100).map(|i| (i as f64 * 0.3).sin() + if i % 17 == 0 { 10.0 } else { 0.0 }).collect();
+/// let mut expected = recording.clone();
+/// filter_slice(&mut expected, 4, 3.0, EdgeMode::Mirror);
+///
+/// let mut filter = ChunkedFilter::new(4, 3.0, EdgeMode::Mirror);
+/// let mut output = Vec::new();
+/// for chunk in recording.chunks(7) {
+///     filter.push(chunk, &mut output);
+/// }
+/// filter.finish(&mut output);
+/// assert_eq!(output, expected);
+/// ```
+///
+/// Recordings shorter than the window are output by [`finish`](Self::finish):
+///
+/// ```
+/// use hampel::{filter_slice, ChunkedFilter, EdgeMode};
+///
+/// let recording = [1.0, 9.0, 1.1, 0.9];
+/// let mut filter = ChunkedFilter::new(5, 3.0, EdgeMode::Mirror);
+/// let mut output = Vec::new();
+/// filter.push(&recording[..2], &mut output);
+/// filter.push(&recording[2..], &mut output);
+/// assert!(output.is_empty());
+/// filter.finish(&mut output);
+///
+/// let mut expected = recording;
+/// filter_slice(&mut expected, 5, 3.0, EdgeMode::Mirror);
+/// assert_eq!(output, expected);
+/// ```
+#[derive(Clone)]
+pub struct ChunkedFilter<T: FloatCore> {
+    pub(crate) buf: Vec<T>,  // 判定に使う元のサンプル（pushの後に残すのは最大2 * half_window個）
+    pub(crate) work: Vec<T>,  // 窓の作業領域（2 * half_window + 1個）
+    pub(crate) start: usize,  // buf[0]の，記録の先頭からのインデックス
+    pub(crate) next: usize,  // 次に出力するサンプルのインデックス
+    pub(crate) half_window: usize,
+    pub(crate) coef: T,  // 閾値判定に使う係数
+    pub(crate) edge: EdgeMode,
+}
+
+impl<T: FloatCore> ChunkedFilter<T> {
+    /// * `half_window`: Number of samples on each side of the window (see [`filter_slice`](crate::filter_slice)).
+    /// * `n_sigma`: Threshold for determining an outlier.
+    /// * `edge`: How the samples near the start and the end of the recording are handled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `half_window` > [`MAX_HALF_WINDOW`], or `n_sigma` is not a positive finite number.
+    pub fn new(half_window: usize, n_sigma: T, edge: EdgeMode) -> Self {
+        assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+        filter::assert_n_sigma(n_sigma);
+        Self {
+            buf: Vec::with_capacity(2 * half_window),
+            work: vec![T::zero(); 2 * half_window + 1],
+            start: 0,
+            next: 0,
+            half_window,
+            coef: offline::coefficient(n_sigma),
+            edge,
+        }
+    }
+
+    /// Add the next chunk of the recording, and append to `out` the filtered values of the samples
+    /// whose full window has been received.
+    pub fn push(&mut self, chunk: &[T], out: &mut Vec<T>) {
+        self.buf.extend_from_slice(chunk);
+        let received = self.start + self.buf.len();
+        while self.next + self.half_window < received {
+            out.push(self.filtered(self.next, received));
+            self.next += 1;
+        }
+
+        // 次に出力するサンプルの窓より古いサンプルは捨てる
+        let keep = self.next.saturating_sub(self.half_window);
+        self.buf.drain(..(keep - self.start));
+        self.start = keep;
+    }
+
+    /// End the recording, and append to `out` the filtered values of the remaining samples
+    /// (at most `half_window`).
+    ///
+    /// The filter is emptied, so that it can be used for a new recording.
+    pub fn finish(&mut self, out: &mut Vec<T>) {
+        let len = self.start + self.buf.len();
+        while self.next < len {
+            out.push(self.filtered(self.next, len));
+            self.next += 1;
+        }
+        self.reset();
+    }
+
+    /// Discard all samples, including those not yet output.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+        self.next = 0;
+    }
+
+    /// Number of samples on each side of the window.
+    pub fn half_window(&self) -> usize {
+        self.half_window
+    }
+
+    /// How the samples near the start and the end of the recording are handled.
+    pub fn edge(&self) -> EdgeMode {
+        self.edge
+    }
+
+    /// Number of samples pushed but not yet output.
+    pub fn pending(&self) -> usize {
+        self.start + self.buf.len() - self.next
+    }
+
+    /// `i`番目のサンプルを，`end`番目より前のサンプルで判定した出力値
+    ///
+    /// 窓を記録の`[i - half_window, i + half_window]`（端では記録内に縮めた範囲）に切り出して渡すので，
+    /// どのモードでも記録全体に`filter_slice`を適用した場合と同じ窓になる
+    fn filtered(&mut self, i: usize, end: usize) -> T {
+        let lo = i.saturating_sub(self.half_window);
+        let hi = end.min(i + self.half_window + 1);
+        let src = &self.buf[(lo - self.start)..(hi - self.start)];
+        offline::decide(src, i - lo, self.half_window, self.edge, self.coef, &mut self.work).0
+    }
+}
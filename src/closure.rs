@@ -0,0 +1,89 @@
+//! Adapters of the filters to closures, for the APIs taking `impl FnMut` stages.
+
+use num_traits::{float::FloatCore, PrimInt, Signed};
+
+use crate::{
+    CenteredWindow, ComplexWindow, DecimatingWindow, DualWindow, IntWindow, LargeWindow, MadEstimator, MedianWindow,
+    OutlierHandler, QuantileWindow, Sample, SmoothedWindow, Window, WindowBank, WindowMut, ZScoreWindow,
+};
+
+/// 各フィルタに`into_fn`と`as_fn`（`update_detailed`があれば`into_detailed_fn`と`as_detailed_fn`も）を実装する
+macro_rules! impl_closures {
+    ($($(#[$m:meta])* [$($g:tt)*] $ty:ty: $x:ty => $y:ty $(, $d:ty)?;)*) => {$(
+        $(#[$m])*
+        impl<$($g)*> $ty {
+            /// Turn the filter into a closure that forwards to [`update`](Self::update).
+            pub fn into_fn(mut self) -> impl FnMut($x) -> $y {
+                move |x| self.update(x)
+            }
+
+            /// Same as [`into_fn`](Self::into_fn), but the filter is borrowed, so that its state can be inspected afterwards.
+            pub fn as_fn(&mut self) -> impl FnMut($x) -> $y + '_ {
+                move |x| self.update(x)
+            }
+
+            $(
+                /// Turn the filter into a closure that forwards to [`update_detailed`](Self::update_detailed).
+                pub fn into_detailed_fn(mut self) -> impl FnMut($x) -> $d {
+                    move |x| self.update_detailed(x)
+                }
+
+                /// Same as [`into_detailed_fn`](Self::into_detailed_fn), but the filter is borrowed.
+                pub fn as_detailed_fn(&mut self) -> impl FnMut($x) -> $d + '_ {
+                    move |x| self.update_detailed(x)
+                }
+            )?
+        }
+    )*};
+}
+
+impl_closures! {
+    [T: FloatCore, const WINDOW_SIZE: usize, H: OutlierHandler<T>] Window<T, WINDOW_SIZE, H>: T => T, Sample<T>;
+    [T: FloatCore, const WINDOW_SIZE: usize] LargeWindow<T, WINDOW_SIZE>: T => T, Sample<T>;
+    [T: FloatCore, const MEDIAN_WIN: usize, const SCALE_WIN: usize] DualWindow<T, MEDIAN_WIN, SCALE_WIN>: T => T, Sample<T>;
+    [T: FloatCore, const WINDOW_SIZE: usize] SmoothedWindow<T, WINDOW_SIZE>: T => T, (T, Sample<T>);
+    [T: FloatCore, const WINDOW_SIZE: usize] ZScoreWindow<T, WINDOW_SIZE>: T => T;
+    [T: FloatCore, const WINDOW_SIZE: usize] MedianWindow<T, WINDOW_SIZE>: T => T;
+    [T: FloatCore, const WINDOW_SIZE: usize] QuantileWindow<T, WINDOW_SIZE>: T => T;
+    [T: FloatCore, const WINDOW_SIZE: usize] MadEstimator<T, WINDOW_SIZE>: T => T;
+    [T: FloatCore, const WINDOW_SIZE: usize] CenteredWindow<T, WINDOW_SIZE>: T => Option<T>;
+    [T: FloatCore, const WINDOW_SIZE: usize, const FACTOR: usize] DecimatingWindow<T, WINDOW_SIZE, FACTOR>: T => Option<T>;
+    [T: FloatCore, const WINDOW_SIZE: usize, const CHANNELS: usize] WindowBank<T, WINDOW_SIZE, CHANNELS>: [T; CHANNELS] => [T; CHANNELS];
+    [T: FloatCore, const WINDOW_SIZE: usize] ComplexWindow<T, WINDOW_SIZE>: (T, T) => (T, T), ((T, T), Sample<T>);
+    [T: PrimInt + Signed, const WINDOW_SIZE: usize] IntWindow<T, WINDOW_SIZE>: T => T, Sample<T>;
+    #[cfg(feature = "alloc")]
+    [T: FloatCore] crate::DynWindow<T>: T => T, Sample<T>;
+    #[cfg(feature = "fixed")]
+    [F: fixed::traits::Fixed, const WINDOW_SIZE: usize] crate::FixedWindow<F, WINDOW_SIZE>: F => F, Sample<F>;
+    #[cfg(feature = "ordered-float")]
+    [T: FloatCore, const WINDOW_SIZE: usize] crate::NotNanWindow<T, WINDOW_SIZE>: ordered_float::NotNan<T> => ordered_float::NotNan<T>, Sample<T>;
+    #[cfg(feature = "nalgebra")]
+    [T: FloatCore + nalgebra::Scalar, const WINDOW_SIZE: usize, const D: usize] crate::VectorWindow<T, WINDOW_SIZE, D>:
+        nalgebra::SVector<T, D> => nalgebra::SVector<T, D>, crate::VectorSample<T, D>;
+    #[cfg(feature = "simd")]
+    [const WINDOW_SIZE: usize, const CHANNELS: usize] crate::SimdBank<WINDOW_SIZE, CHANNELS>:
+        [f32; CHANNELS] => [f32; CHANNELS], [Sample<f32>; CHANNELS];
+}
+
+// WindowMutは借用したバッファの寿命'aも捕捉するので，個別に実装する
+impl<'a, T: FloatCore> WindowMut<'a, T> {
+    /// Turn the filter into a closure that forwards to [`update`](Self::update).
+    pub fn into_fn(mut self) -> impl FnMut(T) -> T + 'a {
+        move |x| self.update(x)
+    }
+
+    /// Same as [`into_fn`](Self::into_fn), but the filter is borrowed, so that its state can be inspected afterwards.
+    pub fn as_fn(&mut self) -> impl FnMut(T) -> T + use<'_, 'a, T> {
+        move |x| self.update(x)
+    }
+
+    /// Turn the filter into a closure that forwards to [`update_detailed`](Self::update_detailed).
+    pub fn into_detailed_fn(mut self) -> impl FnMut(T) -> Sample<T> + 'a {
+        move |x| self.update_detailed(x)
+    }
+
+    /// Same as [`into_detailed_fn`](Self::into_detailed_fn), but the filter is borrowed.
+    pub fn as_detailed_fn(&mut self) -> impl FnMut(T) -> Sample<T> + use<'_, 'a, T> {
+        move |x| self.update_detailed(x)
+    }
+}
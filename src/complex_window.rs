@@ -0,0 +1,188 @@
+//! Filter for complex (I/Q) samples, with the outliers detected on the magnitude.
+
+use num_traits::float::FloatCore;
+
+use crate::{iter::Samples, precision, Replacement, Sample, Window};
+
+/// How an outlier of a [`ComplexWindow`] is replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ComplexReplacement {
+    /// The outlier rescaled to the median magnitude of the window, keeping its phase.
+    /// A sample of zero magnitude (no phase) is replaced as `MedianSample` instead.
+    #[default]
+    Rescale,
+    /// The sample of the window whose magnitude is the closest to the median magnitude.
+    MedianSample,
+}
+
+/// Hampel filter for complex samples `(re, im)` (e.g. I/Q samples of an SDR),
+/// which detects the outliers on the magnitude `|z|`, and replaces them as complex samples.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The magnitudes are filtered by a [`Window`], so that the decisions are exactly the same as filtering `|z|`.
+/// The window of the complex samples is kept alongside, for [`ComplexReplacement::MedianSample`].
+///
+/// Impulsive gain glitches on a complex sinusoid do not break the continuity of the phase:
+///
+/// ```
+/// use hampel::{ComplexReplacement, ComplexWindow};
+///
+/// let omega = 0.05;
+/// let tone = |i: usize| ((i as f64 * omega).cos(), (i as f64 * omega).sin());
+///
+/// for replacement in [ComplexReplacement::Rescale, ComplexReplacement::MedianSample] {
+///     let mut filter = ComplexWindow::<f64, 9>::new(tone(0), 3.0, replacement);
+///     let mut prev = tone(0);
+///     for i in 1..2000 {
+///         // Amplitude noise of 1%, and bursts of 3 samples with 20x the magnitude
+///         let gain = if i % 100 >= 50 && i % 100 < 53 { 20.0 } else { 1.0 + ((i * 7919) % 13) as f64 * 0.001 };
+///         let (re, im) = tone(i);
+///         let (z, s) = filter.update_detailed((gain * re, gain * im));
+///         if i < 9 {
+///             // The initial window has no noise
+///             prev = z;
+///             continue;
+///         }
+///         assert_eq!(s.is_outlier, gain > 2.0);
+///
+///         let magnitude = (z.0 * z.0 + z.1 * z.1).sqrt();
+///         assert!((magnitude - 1.0).abs() <= 0.013);
+///         // The phase advances by omega per sample, except around the samples taken from the window by `MedianSample`
+///         let step = (z.1 * prev.0 - z.0 * prev.1).atan2(z.0 * prev.0 + z.1 * prev.1);
+///         if replacement == ComplexReplacement::Rescale || !s.is_outlier && i % 100 != 53 {
+///             assert!((step - omega).abs() < 1e-9, "{}: {}", i, step);
+///         } else {
+///             assert!(step.abs() <= 9.0 * omega + 1e-9);
+///         }
+///         prev = z;
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ComplexWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) samples: [(T, T); WINDOW_SIZE],
+    pub(crate) window: Window<T, WINDOW_SIZE>,  // 大きさのwindow（samplesと同じ位置に格納する）
+    pub(crate) oldest: usize,  // samples内の最も古い要素のインデックス
+    pub(crate) replacement: ComplexReplacement,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> ComplexWindow<T, WINDOW_SIZE> {
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier of the magnitude (see [`Window::new`]).
+    /// * `replacement`: How the outliers are replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arguments are invalid for [`Window::new`].
+    pub fn new(init_val: (T, T), n_sigma: T, replacement: ComplexReplacement) -> Self {
+        Self {
+            samples: [init_val; WINDOW_SIZE],
+            window: Window::with_replacement(magnitude(init_val), n_sigma, Replacement::Median),
+            oldest: 0,
+            replacement,
+        }
+    }
+
+    /// Update element in window, and return the filtered sample.
+    pub fn update(&mut self, z: (T, T)) -> (T, T) {
+        self.update_detailed(z).0
+    }
+
+    /// Same as [`update`](Self::update), but the result of the filter of the magnitude is also returned.
+    pub fn update_detailed(&mut self, z: (T, T)) -> ((T, T), Sample<T>) {
+        self.samples[self.oldest] = z;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        let m = magnitude(z);
+        let s = self.window.update_detailed(m);
+        if !s.is_outlier {
+            return (z, s);
+        }
+        let value = match self.replacement {
+            ComplexReplacement::Rescale if m > T::zero() && m.is_finite() => {
+                let k = s.median / m;
+                (z.0 * k, z.1 * k)
+            },
+            _ => self.median_sample(s.median),
+        };
+        (value, s)
+    }
+
+    /// 大きさが`median`に最も近いwindow内のサンプル（同じなら新しいもの）
+    fn median_sample(&self, median: T) -> (T, T) {
+        let mut best = self.samples[(self.oldest + WINDOW_SIZE - 1) % WINDOW_SIZE];
+        let mut distance = T::infinity();
+        for &z in Samples::new(&self.samples, self.oldest).rev() {
+            let d = (magnitude(z) - median).abs();
+            if d < distance {
+                (best, distance) = (z, d);
+            }
+        }
+        best
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    pub fn reset(&mut self, init_val: (T, T)) {
+        self.samples = [init_val; WINDOW_SIZE];
+        self.window.reset(magnitude(init_val));
+        self.oldest = 0;
+    }
+
+    /// How the outliers are replaced.
+    pub fn replacement(&self) -> ComplexReplacement {
+        self.replacement
+    }
+
+    /// Window of the magnitudes.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> PartialEq for ComplexWindow<T, WINDOW_SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.window == other.window
+            && self.replacement == other.replacement
+            && Samples::new(&self.samples, self.oldest).eq(Samples::new(&other.samples, other.oldest))
+    }
+}
+
+/// `z`の大きさ（途中で溢れないように，大きい方の成分で割ってから二乗する）
+fn magnitude<T: FloatCore>(z: (T, T)) -> T {
+    if precision::is_narrow::<T>() {
+        return precision::narrow(magnitude((precision::widen(z.0), precision::widen(z.1))));
+    }
+    let (a, b) = (z.0.abs(), z.1.abs());
+    let (hi, lo) = if a >= b { (a, b) } else { (b, a) };
+    if hi == T::zero() || !hi.is_finite() || lo.is_nan() {
+        return hi + lo;  // 0, ±inf, NaN
+    }
+    let r = lo / hi;
+    hi * sqrt(T::one() + r * r)
+}
+
+/// [1, 4)の`y`の平方根（ニュートン法で，上から単調に収束させる）
+fn sqrt<T: FloatCore>(y: T) -> T {
+    let two = T::one() + T::one();
+    let mut g = (T::one() + y) / two;
+    for _ in 0..64 {
+        let next = (g + y / g) / two;
+        if next >= g {
+            break;
+        }
+        g = next;
+    }
+    g
+}
@@ -0,0 +1,79 @@
+//! Counters of the outlier decisions.
+
+/// Statistics of the outlier decisions made by a filter.
+///
+/// All counters saturate instead of wrapping around.
+///
+/// ```
+/// use hampel::OutlierStats;
+///
+/// let mut stats = OutlierStats::default();
+/// for is_outlier in [true, false, true, true, false, true, true, true, false] {
+///     stats.record(is_outlier);
+/// }
+/// assert_eq!(stats.samples_total, 9);
+/// assert_eq!(stats.outliers_total, 6);
+/// assert_eq!(stats.current_run, 0);
+/// assert_eq!(stats.longest_run, 3);
+/// assert_eq!(stats.recent_rate(), 6.0 / 9.0);
+///
+/// // Counters near the maximum
+/// let mut stats = OutlierStats {
+///     samples_total: u32::MAX - 1,
+///     outliers_total: u32::MAX - 1,
+///     current_run: u32::MAX - 1,
+///     longest_run: u32::MAX - 1,
+///     ..OutlierStats::default()
+/// };
+/// for _ in 0..3 {
+///     stats.record(true);
+/// }
+/// assert_eq!(stats.samples_total, u32::MAX);
+/// assert_eq!(stats.outliers_total, u32::MAX);
+/// assert_eq!(stats.current_run, u32::MAX);
+/// assert_eq!(stats.longest_run, u32::MAX);
+/// assert_eq!(stats.recent_rate(), 3.0 / 64.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutlierStats {
+    /// Number of decisions.
+    pub samples_total: u32,
+    /// Number of samples determined to be outliers.
+    pub outliers_total: u32,
+    /// Number of consecutive outliers up to the latest sample.
+    pub current_run: u32,
+    /// Longest run of consecutive outliers.
+    pub longest_run: u32,
+    /// Decisions of the last [`RECENT_LEN`](Self::RECENT_LEN) samples (bit 0: latest, `1`: outlier).
+    pub recent: u64,
+}
+
+impl OutlierStats {
+    /// Number of the latest decisions used by [`recent_rate`](Self::recent_rate).
+    pub const RECENT_LEN: u32 = u64::BITS;
+
+    /// Record a decision.
+    pub fn record(&mut self, is_outlier: bool) {
+        self.samples_total = self.samples_total.saturating_add(1);
+        self.recent = (self.recent << 1) | is_outlier as u64;
+        if is_outlier {
+            self.outliers_total = self.outliers_total.saturating_add(1);
+            self.current_run = self.current_run.saturating_add(1);
+            self.longest_run = self.longest_run.max(self.current_run);
+        } else {
+            self.current_run = 0;
+        }
+    }
+
+    /// Fraction of outliers in the last [`RECENT_LEN`](Self::RECENT_LEN) decisions
+    /// (or in all decisions if there have been fewer). `0.0` if nothing has been recorded.
+    pub fn recent_rate(&self) -> f32 {
+        let n = self.samples_total.min(Self::RECENT_LEN);
+        if n == 0 {
+            return 0.0;
+        }
+        self.recent.count_ones() as f32 / n as f32
+    }
+}
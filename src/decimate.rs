@@ -0,0 +1,158 @@
+//! Filter at the full rate with the output at a reduced rate.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{precision, stats, Window};
+
+/// How the filtered samples of a decimation period of a [`DecimatingWindow`] are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Aggregation {
+    /// Last filtered value of the period.
+    #[default]
+    Last,
+    /// Mean of the filtered values of the period.
+    Mean,
+    /// Median of the filtered values of the period.
+    Median,
+}
+
+/// Hampel filter that sees every sample, and returns one value per `FACTOR` samples
+/// (e.g. a 1 kHz sensor read by a 100 Hz control loop).
+///
+/// * `FACTOR` >= 1
+///
+/// Each sample is filtered by a [`Window`], and the filtered values of a decimation period are combined
+/// according to the [`Aggregation`], so that a spike between the output instants is replaced before it is aggregated.
+///
+/// ```
+/// use hampel::{Aggregation, DecimatingWindow};
+///
+/// for aggregation in [Aggregation::Last, Aggregation::Mean, Aggregation::Median] {
+///     let mut filter = DecimatingWindow::<f64, 9, 10>::new(0.0, 3.0, aggregation);
+///     // A stream that is not a multiple of the factor: the last 7 samples remain pending
+///     let mut outputs = Vec::new();
+///     for i in 0..1007 {
+///         let x = 1.0 + 0.01 * (i % 3) as f64 + if i % 50 == 23 { 100.0 } else { 0.0 };
+///         if let Some(y) = filter.update(x) {
+///             outputs.push(y);
+///         }
+///     }
+///     assert_eq!(outputs.len(), 100);
+///     assert_eq!(filter.pending(), 7);
+///     // The spikes in the periods do not leak into the aggregates
+///     assert!(outputs[1..].iter().all(|y| (y - 1.01).abs() < 0.015));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DecimatingWindow<T: FloatCore, const WINDOW_SIZE: usize, const FACTOR: usize> {
+    pub(crate) window: Window<T, WINDOW_SIZE>,
+    pub(crate) period: [T; FACTOR],  // 現在の間引き周期の出力値
+    pub(crate) pending: usize,  // periodに格納した出力値の数
+    pub(crate) aggregation: Aggregation,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, const FACTOR: usize> DecimatingWindow<T, WINDOW_SIZE, FACTOR> {
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`]).
+    /// * `aggregation`: How the filtered values of a period are combined.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `FACTOR` is zero, or the arguments are invalid for [`Window::new`].
+    pub fn new(init_val: T, n_sigma: T, aggregation: Aggregation) -> Self {
+        Self::from_window(Window::new(init_val, n_sigma), aggregation)
+    }
+
+    /// Filter with `window` (e.g. configured by [`Window::builder`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `FACTOR` is zero.
+    pub fn from_window(window: Window<T, WINDOW_SIZE>, aggregation: Aggregation) -> Self {
+        assert!(FACTOR >= 1, "FACTOR must be at least 1");
+
+        let init_val = window.latest();
+        Self { window, period: [init_val; FACTOR], pending: 0, aggregation }
+    }
+
+    /// Filter `x`, and return the aggregated value at the end of each period of `FACTOR` samples.
+    pub fn update(&mut self, x: T) -> Option<T> {
+        self.period[self.pending] = self.window.update(x);
+        self.pending += 1;
+        if self.pending < FACTOR {
+            return None;
+        }
+        self.pending = 0;
+        Some(match self.aggregation {
+            Aggregation::Last => self.period[FACTOR - 1],
+            Aggregation::Mean => mean(&self.period),
+            Aggregation::Median => {
+                let mut work = self.period;
+                stats::median(&mut work)
+            },
+        })
+    }
+
+    /// Discard the filtered values of the current period, so that the next value is returned
+    /// after `FACTOR` more samples (e.g. to align the output instants to an external trigger).
+    ///
+    /// The window is not affected.
+    ///
+    /// ```
+    /// use hampel::{Aggregation, DecimatingWindow};
+    ///
+    /// let mut filter = DecimatingWindow::<f64, 5, 4>::new(0.0, 3.0, Aggregation::Last);
+    /// for _ in 0..6 {
+    ///     filter.update(1.0);
+    /// }
+    /// assert_eq!(filter.pending(), 2);
+    ///
+    /// // Trigger
+    /// filter.reset_phase();
+    /// let outputs: Vec<bool> = (0..8).map(|_| filter.update(1.0).is_some()).collect();
+    /// assert_eq!(outputs, [false, false, false, true, false, false, false, true]);
+    /// ```
+    pub fn reset_phase(&mut self) {
+        self.pending = 0;
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`
+    /// (see [`Window::reset`]), and the phase reset.
+    pub fn reset(&mut self, init_val: T) {
+        self.window.reset(init_val);
+        self.reset_phase();
+    }
+
+    /// Number of samples received in the current period (less than `FACTOR`).
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// How the filtered values of a period are combined.
+    pub fn aggregation(&self) -> Aggregation {
+        self.aggregation
+    }
+
+    /// Window that filters each sample.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Decimation factor (`FACTOR`).
+    pub fn factor(&self) -> usize {
+        FACTOR
+    }
+}
+
+/// `values`の平均（精度の低い型ではf32で計算する）
+fn mean<T: FloatCore>(values: &[T]) -> T {
+    if precision::is_narrow::<T>() {
+        let sum = values.iter().fold(0.0, |s, v| s + precision::widen(*v));
+        precision::narrow(sum / values.len() as f32)
+    } else {
+        let sum = values.iter().fold(T::zero(), |s, v| s + *v);
+        sum / cast::<usize, T>(values.len()).unwrap()
+    }
+}
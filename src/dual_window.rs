@@ -0,0 +1,224 @@
+//! Window with separate lengths for the median and the scale estimate.
+
+use num_traits::float::FloatCore;
+
+use crate::{builder::default_core, filter::Core, iter::Samples, OutlierStats, Sample};
+
+/// Window of Hampel filter whose median is taken over the last `MEDIAN_WIN` samples,
+/// and whose MAD is taken over the deviations of the last `SCALE_WIN` samples from that median.
+///
+/// * 3 <= `MEDIAN_WIN` <= `SCALE_WIN`
+///
+/// A short window keeps the median responsive, while the MAD of a few samples is noisy,
+/// which makes the threshold erratic. Here the threshold is estimated from the longer history.
+///
+/// The samples are kept in a ring buffer of `SCALE_WIN` samples with its sorted copy as in [`Window`](crate::Window),
+/// and the median is computed from a copy of the newest `MEDIAN_WIN` samples.
+/// It stores `3 * SCALE_WIN` elements of `T` (the window, its sorted copy and a scratch buffer),
+/// i.e. about `3 * (SCALE_WIN - MEDIAN_WIN) * size_of::<T>()` bytes more than a [`Window`](crate::Window)
+/// of `MEDIAN_WIN` samples (e.g. 672 bytes for `f32`, 7 and 63).
+///
+/// It is configured like [`Window::new`](crate::Window::new), and the results are exactly the same
+/// if `MEDIAN_WIN == SCALE_WIN`:
+///
+/// ```
+/// use hampel::{DualWindow, Window};
+///
+/// let mut a = DualWindow::<f64, 9, 9>::new(0.0, 3.0);
+/// let mut b = Window::<f64, 9>::new(0.0, 3.0);
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+/// }
+/// ```
+///
+/// With a longer scale window, the median is the same as the short window, and the MAD is steadier:
+///
+/// ```
+/// use hampel::{DualWindow, Window};
+///
+/// let mut dual = DualWindow::<f64, 3, 9>::new(0.0, 3.0);
+/// let mut short = Window::<f64, 3>::new(0.0, 3.0);
+/// for x in [0.0, 2.0, 0.0, 2.0, 0.0, 2.0, 1.0, 1.0] {
+///     dual.update(x);
+///     short.update(x);
+/// }
+///
+/// // The short window sees [1, 1, 1] only
+/// let (d, s) = (dual.update_detailed(1.0), short.update_detailed(1.0));
+/// assert_eq!(d.median, s.median);
+/// assert_eq!((d.mad, s.mad), (1.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct DualWindow<T: FloatCore, const MEDIAN_WIN: usize, const SCALE_WIN: usize> {
+    pub(crate) window: [T; SCALE_WIN],
+    pub(crate) sorted: [T; SCALE_WIN],  // windowを昇順に並べたもの
+    pub(crate) work: [T; SCALE_WIN],
+    pub(crate) core: Core<T>,
+}
+
+impl<T: FloatCore, const MEDIAN_WIN: usize, const SCALE_WIN: usize> DualWindow<T, MEDIAN_WIN, SCALE_WIN> {
+    /// `MEDIAN_WIN`が3以上で`SCALE_WIN`以下であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = {
+        assert!(MEDIAN_WIN >= 3, "MEDIAN_WIN must be at least 3");
+        assert!(MEDIAN_WIN <= SCALE_WIN, "MEDIAN_WIN must not be greater than SCALE_WIN");
+    };
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// `MEDIAN_WIN` < 3 and `MEDIAN_WIN` > `SCALE_WIN` are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::DualWindow::<f32, 9, 5>::new(0.0, 3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`MEDIAN_WIN` < 3 and `MEDIAN_WIN` > `SCALE_WIN` are rejected at compile time).
+    pub fn new(init_val: T, n_sigma: T) -> Self {
+        let () = Self::VALID_SIZE;
+
+        let mut core = match default_core(SCALE_WIN, init_val, n_sigma) {
+            Ok(core) => core,
+            Err(e) => panic!("{}", e),
+        };
+        core.center_len = MEDIAN_WIN;
+        let mut filter = Self {
+            window: [init_val; SCALE_WIN],
+            sorted: [init_val; SCALE_WIN],
+            work: [init_val; SCALE_WIN],
+            core,
+        };
+        filter.reset(init_val);
+        filter
+    }
+
+    /// Same as [`new`](Self::new), but until the windows are filled, the statistics are taken only over
+    /// the received samples (as the `warm_up` option of [`WindowBuilder`](crate::WindowBuilder)).
+    ///
+    /// The median is taken over the last `MEDIAN_WIN` received samples as soon as they are available,
+    /// while the MAD is taken over all the received samples until `SCALE_WIN` samples have been received:
+    ///
+    /// ```
+    /// use hampel::{DualWindow, Window};
+    ///
+    /// let mut dual = DualWindow::<f64, 3, 6>::with_warm_up(0.0, 3.0);
+    /// let mut reference = Window::<f64, 3>::builder().warm_up(true).build();
+    ///
+    /// // The same as a window of 3 until 3 samples are received
+    /// for x in [1.0, 2.0, 4.0] {
+    ///     assert_eq!(dual.update_detailed(x), reference.update_detailed(x));
+    /// }
+    ///
+    /// // Median of [2, 4, 8], MAD of the deviations of [1, 2, 4, 8]: |1 - 4|, |2 - 4|, 0, |8 - 4|
+    /// let s = dual.update_detailed(8.0);
+    /// assert_eq!((s.median, s.mad), (4.0, 2.5));
+    /// assert!(!dual.is_warmed_up());
+    ///
+    /// // [1, 2, 4, 8, 16, 32]: median of [8, 16, 32], deviations 15, 14, 12, 8, 0, 16
+    /// dual.update(16.0);
+    /// let s = dual.update_detailed(32.0);
+    /// assert_eq!((s.median, s.mad), (16.0, 13.0));
+    /// assert!(dual.is_warmed_up());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_warm_up(init_val: T, n_sigma: T) -> Self {
+        let mut filter = Self::new(init_val, n_sigma);
+        filter.core.warm_up = true;
+        filter
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    ///
+    /// [`Sample::median`] is the median of the last `MEDIAN_WIN` samples,
+    /// and [`Sample::mad`] is the MAD over the last `SCALE_WIN` samples.
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        self.core.update(&mut self.window, &mut [], &mut self.work, &mut [], &mut self.sorted[..], x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset(&mut self.window, &mut self.sorted[..], init_val);
+    }
+
+    /// See [`Window::seed`](crate::Window::seed).
+    pub fn seed(&mut self, samples: &[T]) {
+        let start = samples.len().saturating_sub(SCALE_WIN);
+        self.seed_from_iter(samples[start..].iter().copied());
+    }
+
+    /// See [`Window::seed_from_iter`](crate::Window::seed_from_iter).
+    pub fn seed_from_iter<I: IntoIterator<Item = T>>(&mut self, samples: I) {
+        self.core.seed_from_iter(&mut self.window, &mut self.work, &mut [], &mut self.sorted[..], samples);
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// Returns `true` if the scale window has been filled with received samples.
+    pub fn is_warmed_up(&self) -> bool {
+        self.core.seen >= SCALE_WIN
+    }
+
+    /// Median of the last `MEDIAN_WIN` samples at the last update (see [`Window::median`](crate::Window::median)).
+    pub fn median(&self) -> T {
+        self.core.median
+    }
+
+    /// MAD of the last `SCALE_WIN` samples at the last update (see [`Window::mad`](crate::Window::mad)).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds).
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// Iterator over the last `SCALE_WIN` samples (see [`Window::iter`](crate::Window::iter)).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> T {
+        self.core.latest(&self.window)
+    }
+
+    /// Length of the scale window (`SCALE_WIN`).
+    pub fn len(&self) -> usize {
+        SCALE_WIN
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Same as [`Window`](crate::Window).
+impl<T: FloatCore, const MEDIAN_WIN: usize, const SCALE_WIN: usize> PartialEq for DualWindow<T, MEDIAN_WIN, SCALE_WIN> {
+    fn eq(&self, other: &Self) -> bool {
+        self.core.eq_with(&self.window, &other.core, &other.window)
+    }
+}
@@ -0,0 +1,184 @@
+//! Heap-allocated window of runtime size.
+
+use alloc::{boxed::Box, vec};
+use num_traits::float::FloatCore;
+
+use crate::{builder::default_core, filter::Core, iter::Samples, ConfigError, OutlierStats, Sample};
+
+/// Window of Hampel filter whose size is determined at runtime, allocated on the heap.
+///
+/// Apart from the size, it behaves exactly like [`Window`](crate::Window).
+///
+/// ```
+/// use hampel::{DynWindow, Window};
+///
+/// let mut a = DynWindow::new(5, 0.0, 3.0).unwrap();
+/// let mut b = Window::<f64, 5>::new(0.0, 3.0);
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+/// }
+///
+/// assert!(DynWindow::new(2, 0.0, 3.0).is_err());
+/// ```
+#[derive(Clone)]
+pub struct DynWindow<T: FloatCore> {
+    pub(crate) window: Box<[T]>,
+    pub(crate) work: Box<[T]>,
+    pub(crate) gaps: Box<[T]>,  // windowの各サンプルと1つ前のサンプルの時刻の差
+    pub(crate) core: Core<T>,
+}
+
+impl<T: FloatCore> DynWindow<T> {
+    /// * `len`: Window size (>= 3).
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    pub fn new(len: usize, init_val: T, n_sigma: T) -> Result<Self, ConfigError> {
+        if len < 3 {
+            return Err(ConfigError::InvalidWindowSize);
+        }
+
+        let core = default_core(len, init_val, n_sigma)?;
+        Ok(Self {
+            window: vec![init_val; len].into_boxed_slice(),
+            work: vec![init_val; len].into_boxed_slice(),
+            gaps: vec![T::one(); len].into_boxed_slice(),
+            core,
+        })
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        self.core.update(&mut self.window, &mut self.gaps, &mut self.work, &mut [], &mut [][..], x)
+    }
+
+    /// See [`Window::update_missing`](crate::Window::update_missing).
+    pub fn update_missing(&mut self) -> T {
+        self.update_missing_detailed().value
+    }
+
+    /// See [`Window::update_missing_detailed`](crate::Window::update_missing_detailed).
+    pub fn update_missing_detailed(&mut self) -> Sample<T> {
+        self.core.update_missing(&mut self.window, &mut self.gaps, &mut self.work, &mut [], &mut [][..])
+    }
+
+    /// See [`Window::update_evicting`](crate::Window::update_evicting).
+    pub fn update_evicting(&mut self, x: T) -> (T, T) {
+        let evicted = self.core.oldest_sample(&self.window);
+        (self.update(x), evicted)
+    }
+
+    /// See [`Window::would_flag`](crate::Window::would_flag).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    pub fn would_flag(&mut self, x: T) -> bool {
+        self.core.would_flag(&self.window, &mut self.work, &mut [], x)
+    }
+
+    /// See [`Window::score`](crate::Window::score).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    pub fn score(&mut self, x: T) -> T {
+        self.core.score_of(&self.window, &mut self.work, &mut [], x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset(&mut self.window, &mut [][..], init_val);
+        self.gaps.fill(T::one());
+    }
+
+    /// See [`Window::seed`](crate::Window::seed).
+    pub fn seed(&mut self, samples: &[T]) {
+        let start = samples.len().saturating_sub(self.window.len());
+        self.seed_from_iter(samples[start..].iter().copied());
+    }
+
+    /// See [`Window::seed_from_iter`](crate::Window::seed_from_iter).
+    pub fn seed_from_iter<I: IntoIterator<Item = T>>(&mut self, samples: I) {
+        let mut seeded = false;
+        let samples = samples.into_iter().inspect(|_| seeded = true);
+        self.core.seed_from_iter(&mut self.window, &mut self.work, &mut [], &mut [][..], samples);
+        if seeded {
+            self.gaps.fill(T::one());
+        }
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::set_mad_floor`](crate::Window::set_mad_floor).
+    pub fn set_mad_floor(&mut self, mad_floor: T) {
+        self.core.mad_floor = mad_floor;
+    }
+
+    /// See [`Window::mad_floor`](crate::Window::mad_floor).
+    pub fn mad_floor(&self) -> T {
+        self.core.mad_floor
+    }
+
+    /// Window size.
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// See [`Window::is_warmed_up`](crate::Window::is_warmed_up).
+    pub fn is_warmed_up(&self) -> bool {
+        self.core.seen >= self.window.len()
+    }
+
+    /// See [`Window::median`](crate::Window::median).
+    pub fn median(&self) -> T {
+        self.core.median
+    }
+
+    /// See [`Window::mad`](crate::Window::mad).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds).
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// See [`Window::iter`](crate::Window::iter).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> T {
+        self.core.latest(&self.window)
+    }
+}
+
+/// Same as [`Window`](crate::Window). Windows of different sizes are not equal.
+impl<T: FloatCore> PartialEq for DynWindow<T> {
+    fn eq(&self, other: &Self) -> bool {
+        let gaps = Samples::new(&self.gaps, self.core.oldest);
+        let other_gaps = Samples::new(&other.gaps, other.core.oldest);
+        self.core.eq_with(&self.window, &other.core, &other.window) && gaps.eq(other_gaps)
+    }
+}
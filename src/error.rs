@@ -0,0 +1,142 @@
+//! Error types.
+
+use core::fmt;
+
+/// Error returned when a filter cannot be constructed from the given configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// A threshold `n_sigma` is not a positive finite number (see [`Window::try_new`](crate::Window::try_new)).
+    InvalidNSigma,
+    /// The consistency constant of the scale is not a positive finite number.
+    InvalidScale,
+    /// The absolute threshold is NaN or negative.
+    InvalidAbsThreshold,
+    /// The trim fraction of the trimmed mean is not in `[0, 0.5)`, the quantile is not in `(0, 1)`,
+    /// or either is used with a scale estimator other than the MAD or with the forgetting factor.
+    InvalidCenter,
+    /// The prediction is used as the reference with a center other than the median,
+    /// a scale estimator other than the MAD, or the forgetting factor.
+    InvalidReference,
+    /// The configuration of the adaptive threshold is out of range.
+    InvalidAdaptiveThreshold,
+    /// The forgetting factor is not in `(0, 1]`, or is used with a scale estimator other than the MAD.
+    InvalidForgettingFactor,
+    /// The exit threshold of the hysteresis is NaN, negative, or greater than `n_sigma`.
+    InvalidHysteresis,
+    /// The suspect threshold is NaN, negative, or greater than `n_sigma`.
+    InvalidSuspect,
+    /// The output range has a NaN bound, or its lower bound is greater than its upper bound.
+    InvalidOutputRange,
+    /// The maximum change of the output per sample is NaN or negative.
+    InvalidMaxSlew,
+    /// The rolloff of the soft replacement is NaN or negative.
+    InvalidRolloff,
+    /// The confirmation does not satisfy `1 <= m <= k <= 8`.
+    InvalidConfirmation,
+    /// The scale of [`ZeroMad::UseFloor`](crate::ZeroMad::UseFloor) is NaN or negative.
+    InvalidZeroMad,
+    /// A constant could not be represented in the element type.
+    Cast,
+    /// The window has fewer than three elements.
+    InvalidWindowSize,
+    /// The scratch buffer or the buffer of the time gaps does not have the same length as the window.
+    ScratchSizeMismatch,
+    /// The position of the oldest sample of a deserialized window is inconsistent with
+    /// the window size or the number of samples seen.
+    InvalidState,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidNSigma => f.write_str("n_sigma must be a positive finite number"),
+            ConfigError::InvalidScale => f.write_str("scale must be a positive finite number"),
+            ConfigError::InvalidAbsThreshold => f.write_str("abs_threshold must be a non-negative number"),
+            ConfigError::InvalidCenter => {
+                f.write_str("center must have a trim fraction in [0, 0.5) or a quantile in (0, 1), and be used with the MAD")
+            }
+            ConfigError::InvalidReference => {
+                f.write_str("prediction reference must be used with the median and the MAD, without the forgetting factor")
+            }
+            ConfigError::InvalidAdaptiveThreshold => {
+                f.write_str("adaptive threshold must have a rate in [0, 1], non-negative gains and a cap not below n_sigma")
+            }
+            ConfigError::InvalidForgettingFactor => {
+                f.write_str("forgetting factor must be in (0, 1] and used with the MAD")
+            }
+            ConfigError::InvalidHysteresis => {
+                f.write_str("hysteresis exit threshold must be in [0, n_sigma]")
+            }
+            ConfigError::InvalidSuspect => f.write_str("suspect threshold must be in [0, n_sigma]"),
+            ConfigError::InvalidOutputRange => f.write_str("output range must satisfy lo <= hi"),
+            ConfigError::InvalidMaxSlew => f.write_str("max_slew must be a non-negative number"),
+            ConfigError::InvalidRolloff => f.write_str("rolloff must be a non-negative number"),
+            ConfigError::InvalidConfirmation => f.write_str("confirmation must satisfy 1 <= m <= k <= 8"),
+            ConfigError::InvalidZeroMad => f.write_str("zero-MAD floor must be a non-negative number"),
+            ConfigError::Cast => f.write_str("constant is not representable in the element type"),
+            ConfigError::InvalidWindowSize => f.write_str("window size must be at least 3"),
+            ConfigError::ScratchSizeMismatch => {
+                f.write_str("scratch and gap buffers must be the same length as the window")
+            }
+            ConfigError::InvalidState => {
+                f.write_str("`oldest` is inconsistent with the window size or `seen`")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
+/// Error returned when the output buffer does not have the same length as the input
+/// (see [`Window::process_into`](crate::Window::process_into)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LengthMismatch {
+    /// Length of the input.
+    pub src: usize,
+    /// Length of the output.
+    pub dst: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dst must be the same length as src ({} != {})", self.dst, self.src)
+    }
+}
+
+/// Error returned when a strided or interleaved buffer cannot be processed
+/// (see [`Window::process_strided`](crate::Window::process_strided)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StrideError {
+    /// `offset` is not less than `stride` (including `stride == 0`).
+    InvalidOffset {
+        /// Index of the first element of the channel.
+        offset: usize,
+        /// Distance between the elements of the channel.
+        stride: usize,
+    },
+    /// The length of the interleaved buffer is not a multiple of the number of channels
+    /// (see [`WindowBank::process_interleaved`](crate::WindowBank::process_interleaved)).
+    PartialFrame {
+        /// Length of the buffer.
+        len: usize,
+        /// Number of channels.
+        channels: usize,
+    },
+}
+
+impl fmt::Display for StrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrideError::InvalidOffset { offset, stride } => {
+                write!(f, "offset must be less than stride ({} >= {})", offset, stride)
+            }
+            StrideError::PartialFrame { len, channels } => {
+                write!(f, "buffer length must be a multiple of the number of channels ({} % {} != 0)", len, channels)
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Implementation of the Hampel filter on samples represented by integers (primitive integers, or the bits of fixed-point numbers).
+//!
+//! Unlike `Core`, no floating-point arithmetic is used, and the statistics and the decision are exact:
+//! the samples are converted to `i128` (`Codec`) in units of a quarter of the LSB, in which the medians of
+//! even numbers of elements are also integers, and the coefficient of the threshold is a rational number.
+//! The element types must be at most 32 bits, so that the products in the decision cannot overflow.
+
+use crate::{stats, Class, OutlierStats, Replacement, Sample, Side};
+
+/// `1.4826`を2^32倍して丸めた値（閾値の係数の分子に使う）
+pub(crate) const GAUSSIAN_SCALE_Q32: i128 = 6_367_718_513;
+
+/// 要素と`i128`の相互変換（固定小数点数の場合はビット列）
+pub(crate) trait Codec<T> {
+    fn widen(x: T) -> i128;
+
+    /// `T`の範囲外は飽和させる
+    fn narrow(v: i128) -> T;
+
+    /// 値1を表す`widen`後の値
+    fn unit() -> i128;
+}
+
+/// LSBの1/4を単位とした値に変換する
+fn quarters<T, C: Codec<T>>(x: T) -> i128 {
+    C::widen(x) * 4
+}
+
+/// LSBの1/4を単位とした値を（切り捨てて）要素に戻す
+fn from_quarters<T, C: Codec<T>>(v: i128) -> T {
+    C::narrow(v.div_euclid(4))
+}
+
+/// LSBの1/4単位で`w0 ± limit`の範囲に収まる最小と最大の要素の値（内側に丸める）
+fn bounds<T, C: Codec<T>>(w0: i128, limit: i128) -> (T, T) {
+    (from_quarters::<T, C>(w0 - limit + 3), from_quarters::<T, C>(w0 + limit))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExactCore<T> {
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) coef_num: i128,  // 閾値判定に使う係数の分子
+    pub(crate) coef_den: i128,  // 閾値判定に使う係数の分母（正）
+    pub(crate) replacement: Replacement,
+    pub(crate) seen: usize,  // windowに格納したサンプル数
+    pub(crate) last: T,  // 前回の出力値
+    pub(crate) median: T,  // 直近の更新で判定に使った中央値
+    pub(crate) scale: T,  // 直近の更新で判定に使った中央絶対偏差
+    pub(crate) bounds: (T, T),  // 直近の更新で判定に合格する最小と最大の値
+    pub(crate) flagged: u128,  // 直近のサンプルが外れ値と判定されたか（ビット0が最新）
+    pub(crate) stats: OutlierStats,
+}
+
+/// `Core`と同じく，リングバッファ上の位置以外の状態を比較する
+impl<T: PartialEq> PartialEq for ExactCore<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coef_num == other.coef_num
+            && self.coef_den == other.coef_den
+            && self.replacement == other.replacement
+            && self.seen == other.seen
+            && self.last == other.last
+            && self.median == other.median
+            && self.scale == other.scale
+            && self.bounds == other.bounds
+            && self.flagged == other.flagged
+            && self.stats == other.stats
+    }
+}
+
+impl<T: Copy + Ord> ExactCore<T> {
+    pub(crate) fn new<C: Codec<T>>(init_val: T, coef_num: i128, coef_den: i128, replacement: Replacement) -> Self {
+        Self {
+            oldest: 0,
+            coef_num,
+            coef_den,
+            replacement,
+            seen: 0,
+            last: init_val,
+            median: init_val,
+            scale: C::narrow(0),
+            bounds: (init_val, init_val),
+            flagged: 0,
+            stats: OutlierStats::default(),
+        }
+    }
+
+    pub(crate) fn update<C: Codec<T>>(&mut self, window: &mut [T], sorted: &mut [T], x: T) -> Sample<T> {
+        let len = window.len();
+
+        let slot = self.oldest;
+        let evicted = window[slot];
+        window[slot] = x;
+        self.oldest = (self.oldest + 1) % len;
+        self.seen = self.seen.saturating_add(1);
+        stats::replace_sorted(sorted, evicted, x);
+
+        // 外れ値かどうか判定（|x - w0| > (coef_num / coef_den) * s0）
+        let (w0, s0) = statistics::<T, C>(sorted);
+        let deviation = (quarters::<T, C>(x) - w0).abs();
+        let is_outlier = deviation * self.coef_den > self.coef_num * s0;
+
+        let median = from_quarters::<T, C>(w0);
+        let value = if is_outlier { self.replace::<C>(window, x, w0, s0) } else { x };
+        self.last = value;
+        self.flagged = (self.flagged << 1) | is_outlier as u128;
+        (self.median, self.scale) = (median, from_quarters::<T, C>(s0));
+        self.bounds = bounds::<T, C>(w0, self.coef_num * s0 / self.coef_den);
+        self.stats.record(is_outlier);
+        let side = match is_outlier {
+            true if quarters::<T, C>(x) < w0 => Some(Side::Below),
+            true => Some(Side::Above),
+            false => None,
+        };
+
+        // score = deviation / (1.4826 * s0)（切り捨て，s0 = 0なら飽和させる）
+        let score = match (s0, deviation) {
+            (_, 0) => C::narrow(0),
+            (0, _) => C::narrow(i128::MAX),
+            _ => C::narrow(deviation * C::unit() * (1 << 32) / (GAUSSIAN_SCALE_Q32 * s0)),
+        };
+
+        Sample { value, is_outlier, median, mad: self.scale, bounds: self.bounds, relative: is_outlier, absolute: false, difference: false, out_of_range: false, reseeded: false, tripped: is_outlier, score, side, stale: false, zero_mad: s0 == 0, class: if is_outlier { Class::Outlier } else { Class::Normal } }
+    }
+
+    /// 最新のサンプル
+    pub(crate) fn latest(&self, window: &[T]) -> T {
+        window[(self.oldest + window.len() - 1) % window.len()]
+    }
+
+    pub(crate) fn reset<C: Codec<T>>(&mut self, window: &mut [T], sorted: &mut [T], init_val: T) {
+        window.fill(init_val);
+        sorted.fill(init_val);
+        self.oldest = 0;
+        self.seen = 0;
+        self.flagged = 0;
+        self.last = init_val;
+        (self.median, self.scale) = (init_val, C::narrow(0));
+        self.bounds = (init_val, init_val);
+    }
+
+    /// 外れ値`x`に対する置換値を返す
+    fn replace<C: Codec<T>>(&self, window: &[T], x: T, w0: i128, s0: i128) -> T {
+        let median = from_quarters::<T, C>(w0);
+        match self.replacement {
+            Replacement::Median => median,
+            // Theil–Sen推定は整数で求めないので，最小二乗法の直線で代用する
+            Replacement::LinearExtrapolation | Replacement::QuadraticExtrapolation | Replacement::TheilSenExtrapolation => {
+                self.extrapolation::<C>(window).unwrap_or(median)
+            },
+            // 差分の判定や欠けたサンプルが無いので，前回の出力値は外れ値でない直近の出力値か，それを保持した値
+            Replacement::HoldLast | Replacement::HoldLastValid => self.last,
+            Replacement::PassThrough => x,
+            Replacement::Clamp => self.clamp::<C>(x, w0, s0).unwrap_or(median),
+        }
+    }
+
+    /// `x`を閾値の`x`側の端（LSBの1/4単位の`w0 ± (coef_num / coef_den) * s0`）に制限した値
+    ///
+    /// 要素の値に切り捨てると判定に合格しなくなる場合は`None`を返す
+    fn clamp<C: Codec<T>>(&self, x: T, w0: i128, s0: i128) -> Option<T> {
+        let limit = self.coef_num * s0 / self.coef_den;
+        // 範囲の内側に丸める
+        let (lower, upper) = bounds::<T, C>(w0, limit);
+        let v = if quarters::<T, C>(x) < w0 { lower } else { upper };
+        let deviation = (quarters::<T, C>(v) - w0).abs();
+        (deviation * self.coef_den <= self.coef_num * s0).then_some(v)
+    }
+
+    /// 最新のサンプルを除く`len - 1`個のサンプルから外挿する（`Core::extrapolation`と同じ多項式を整数で求める）
+    ///
+    /// 以前に外れ値と判定されたサンプル（直近128個まで）は当てはめに使わず，使えるサンプルが2個未満なら`None`を返す
+    fn extrapolation<C: Codec<T>>(&self, window: &[T]) -> Option<T> {
+        let len = window.len();
+        let n = len - 1;
+        let first = self.oldest;  // 最新のサンプルの次が最も古い
+
+        // x座標の偏差の2倍 d = 2i - (n - 1) のモーメント Σd^k と Σd^k y
+        let (mut m, mut b) = ([0i128; 5], [0i128; 3]);
+        for i in 0..n {
+            let ago = n - 1 - i;  // i番目のサンプルはn - i回前の更新で格納した（flaggedはまだ今回の分をシフトしていない）
+            if ago < u128::BITS as usize && (self.flagged >> ago) & 1 == 1 {
+                continue;
+            }
+            let y = C::widen(window[(first + i) % len]);
+            let d = 2 * i as i128 - (n as i128 - 1);
+            let mut dk = 1;
+            for k in 0..5 {
+                m[k] += dk;
+                if k < 3 {
+                    b[k] += dk * y;
+                }
+                dk *= d;
+            }
+        }
+        let d = n as i128 + 1;  // 外挿する点（i = n）
+
+        // 正規方程式をクラメルの公式で解き，多項式のdでの値を (分子, 分母) で求める
+        let (numer, denom) = if self.replacement == Replacement::QuadraticExtrapolation && m[0] >= 3 {
+            let g = [[m[0], m[1], m[2]], [m[1], m[2], m[3]], [m[2], m[3], m[4]]];
+            let minor = |r0: usize, r1: usize, c0: usize, c1: usize| g[r0][c0] * g[r1][c1] - g[r0][c1] * g[r1][c0];
+            // 余因子行列の各行（gは対称なので列と同じ）と(1, d, d²)の内積
+            let adj = [
+                [minor(1, 2, 1, 2), -minor(0, 2, 1, 2), minor(0, 1, 1, 2)],
+                [-minor(1, 2, 0, 2), minor(0, 2, 0, 2), -minor(0, 1, 0, 2)],
+                [minor(1, 2, 0, 1), -minor(0, 2, 0, 1), minor(0, 1, 0, 1)],
+            ];
+            let v = [1, d, d * d];
+            let row = |j: usize| v[0] * adj[0][j] + v[1] * adj[1][j] + v[2] * adj[2][j];
+            let det = g[0][0] * adj[0][0] + g[0][1] * adj[1][0] + g[0][2] * adj[2][0];
+            (row(0) * b[0] + row(1) * b[1] + row(2) * b[2], det)
+        } else if m[0] >= 2 {
+            let det = m[0] * m[2] - m[1] * m[1];
+            ((m[2] * b[0] - m[1] * b[1]) + d * (m[0] * b[1] - m[1] * b[0]), det)
+        } else {
+            return None;
+        };
+
+        // 最も近い整数に丸める（x座標が2種類（2次式は3種類）以上あるのでdenom > 0）
+        Some(C::narrow((2 * numer + denom).div_euclid(2 * denom)))
+    }
+}
+
+/// 昇順に並んだ`sorted`の中央値と中央絶対偏差（LSBの1/4単位）
+fn statistics<T: Copy, C: Codec<T>>(sorted: &[T]) -> (i128, i128) {
+    let n = sorted.len();
+    let mid = n / 2;
+    let get = |i: usize| quarters::<T, C>(sorted[i]);
+
+    let w0 = if n.is_multiple_of(2) { (get(mid - 1) + get(mid)) / 2 } else { get(mid) };
+    // w0より小さい要素の偏差とそれ以外の要素の偏差はそれぞれ昇順に並ぶ
+    let p = sorted.partition_point(|v| quarters::<T, C>(*v) < w0);
+    let kth = |k| stats::kth_of_two(n, p, k, |t| w0 - get(p - 1 - t), |t| get(p + t) - w0);
+    let s0 = if n.is_multiple_of(2) { (kth(mid - 1) + kth(mid)) / 2 } else { kth(mid) };
+
+    (w0, s0)
+}
@@ -0,0 +1,263 @@
+//! C interface for `f32` windows of fixed sizes (`ffi` feature).
+//!
+//! For each window size (5, 11 and 31), a state struct and three functions are exported:
+//!
+//! * `hampel_f32_w11_init(state, init_val, n_sigma)`: Initialize the state (see [`Window::new`]).
+//! * `hampel_f32_w11_update(state, x)`: Update the window, and return the filtered value.
+//! * `hampel_f32_w11_reset(state)`: Restore the state right after `init` (see [`Window::reset`]).
+//!
+//! The state structs are `#[repr(C)]` with a fixed size and alignment (e.g. [`HAMPEL_F32_W11_SIZE`]),
+//! so that C code can allocate them statically. The C declarations are in `include/hampel.h`
+//! (generated by `cbindgen`). The sizes follow the layout of [`Window`], and can change between versions
+//! of the crate, so the C program must be built with the header of the same version. Nothing panics across the boundary: invalid arguments are reported
+//! by the error codes (`HAMPEL_ERR_*`), or by NaN from `update`.
+//!
+//! The symbols are linked into the C program through a `staticlib` crate that depends on this crate
+//! with the `ffi` feature.
+//!
+//! ```
+//! use core::mem::MaybeUninit;
+//! use hampel::ffi::*;
+//! use hampel::Window;
+//!
+//! let mut state = MaybeUninit::<HampelF32W11>::uninit();
+//! let state = state.as_mut_ptr();
+//! let mut reference = Window::<f32, 11>::new(0.0, 3.0);
+//! unsafe {
+//!     assert_eq!(hampel_f32_w11_init(state, 0.0, 3.0), HAMPEL_OK);
+//!     for i in 0..1000 {
+//!         let x = (i as f32 * 0.1).sin() + if i % 13 == 0 { 5.0 } else { 0.0 };
+//!         assert_eq!(hampel_f32_w11_update(state, x), reference.update(x));
+//!     }
+//!     assert_eq!(hampel_f32_w11_reset(state), HAMPEL_OK);
+//!     reference.reset(0.0);
+//!     assert_eq!(hampel_f32_w11_update(state, 1.0), reference.update(1.0));
+//!
+//!     // Invalid arguments
+//!     assert_eq!(hampel_f32_w11_init(state, 0.0, -1.0), HAMPEL_ERR_INVALID_N_SIGMA);
+//!     assert_eq!(hampel_f32_w11_init(core::ptr::null_mut(), 0.0, 3.0), HAMPEL_ERR_NULL);
+//!     assert!(hampel_f32_w11_update(core::ptr::null_mut(), 1.0).is_nan());
+//!     assert_eq!(hampel_f32_w11_reset(core::ptr::null_mut()), HAMPEL_ERR_NULL);
+//! }
+//! ```
+
+use core::mem::{align_of, size_of};
+
+use crate::Window;
+
+/// The operation succeeded.
+pub const HAMPEL_OK: i32 = 0;
+/// The state pointer is null.
+pub const HAMPEL_ERR_NULL: i32 = -1;
+/// `n_sigma` is not a positive finite number.
+pub const HAMPEL_ERR_INVALID_N_SIGMA: i32 = -2;
+/// The state has not been initialized by `init`.
+pub const HAMPEL_ERR_UNINITIALIZED: i32 = -3;
+
+/// Size of [`HampelF32W5`] in bytes.
+pub const HAMPEL_F32_W5_SIZE: usize = 720;
+/// Size of [`HampelF32W11`] in bytes.
+pub const HAMPEL_F32_W11_SIZE: usize = 784;
+/// Size of [`HampelF32W31`] in bytes.
+pub const HAMPEL_F32_W31_SIZE: usize = 1024;
+/// Alignment of the state structs in bytes.
+pub const HAMPEL_STATE_ALIGN: usize = 16;
+
+/// 初期化済みの状態であることを示す値
+const MAGIC: u32 = 0x4841_4d50;
+
+/// State of `hampel_f32_w5_*` (`Window<f32, 5>`).
+#[repr(C, align(16))]
+pub struct HampelF32W5 {
+    storage: [u8; 704],
+    init_val: f32,
+    magic: u32,
+}
+
+/// State of `hampel_f32_w11_*` (`Window<f32, 11>`).
+#[repr(C, align(16))]
+pub struct HampelF32W11 {
+    storage: [u8; 768],
+    init_val: f32,
+    magic: u32,
+}
+
+/// State of `hampel_f32_w31_*` (`Window<f32, 31>`).
+#[repr(C, align(16))]
+pub struct HampelF32W31 {
+    storage: [u8; 1008],
+    init_val: f32,
+    magic: u32,
+}
+
+/// 状態の構造体からWindowの領域などを取り出す
+trait State<const N: usize> {
+    fn parts(&mut self) -> (*mut Window<f32, N>, &mut f32, &mut u32);
+}
+
+macro_rules! impl_state {
+    ($state:ty, $n:literal, $size:expr) => {
+        // Windowが領域に収まり，ヘッダの値と一致することをコンパイル時に確かめる
+        const _: () = assert!(size_of::<Window<f32, $n>>() <= size_of::<[u8; $size - 16]>());
+        const _: () = assert!(align_of::<Window<f32, $n>>() <= HAMPEL_STATE_ALIGN);
+        const _: () = assert!(size_of::<$state>() == $size && align_of::<$state>() == HAMPEL_STATE_ALIGN);
+
+        impl State<$n> for $state {
+            fn parts(&mut self) -> (*mut Window<f32, $n>, &mut f32, &mut u32) {
+                (self.storage.as_mut_ptr().cast(), &mut self.init_val, &mut self.magic)
+            }
+        }
+    };
+}
+
+impl_state!(HampelF32W5, 5, HAMPEL_F32_W5_SIZE);
+impl_state!(HampelF32W11, 11, HAMPEL_F32_W11_SIZE);
+impl_state!(HampelF32W31, 31, HAMPEL_F32_W31_SIZE);
+
+/// # Safety
+///
+/// `state`はnullか，`S`として書き込める領域を指すこと
+unsafe fn init<S: State<N>, const N: usize>(state: *mut S, init_val: f32, n_sigma: f32) -> i32 {
+    let Some(state) = (unsafe { state.as_mut() }) else {
+        return HAMPEL_ERR_NULL;
+    };
+    let window = match Window::<f32, N>::builder().init_value(init_val).n_sigma(n_sigma).try_build() {
+        Ok(window) => window,
+        Err(_) => return HAMPEL_ERR_INVALID_N_SIGMA,
+    };
+    let (ptr, init, magic) = state.parts();
+    unsafe { ptr.write(window) };  // 領域は未初期化かもしれないので，古い値を読まずに書き込む
+    (*init, *magic) = (init_val, MAGIC);
+    HAMPEL_OK
+}
+
+/// # Safety
+///
+/// `state`はnullか，`S`を指すこと（`init`していなければmagicで検出する）
+unsafe fn window<'a, S: State<N>, const N: usize>(state: *mut S) -> Result<(&'a mut Window<f32, N>, f32), i32> {
+    let Some(state) = (unsafe { state.as_mut() }) else {
+        return Err(HAMPEL_ERR_NULL);
+    };
+    let (ptr, init, magic) = state.parts();
+    if *magic != MAGIC {
+        return Err(HAMPEL_ERR_UNINITIALIZED);
+    }
+    Ok((unsafe { &mut *ptr }, *init))
+}
+
+/// # Safety
+///
+/// Same as [`window`].
+unsafe fn update<S: State<N>, const N: usize>(state: *mut S, x: f32) -> f32 {
+    match unsafe { window(state) } {
+        Ok((window, _)) => window.update(x),
+        Err(_) => f32::NAN,
+    }
+}
+
+/// # Safety
+///
+/// Same as [`window`].
+unsafe fn reset<S: State<N>, const N: usize>(state: *mut S) -> i32 {
+    match unsafe { window(state) } {
+        Ok((window, init_val)) => {
+            window.reset(init_val);
+            HAMPEL_OK
+        },
+        Err(e) => e,
+    }
+}
+
+/// Initialize `state` with the window filled with `init_val` (see [`Window::new`]).
+///
+/// Returns [`HAMPEL_OK`], [`HAMPEL_ERR_NULL`] or [`HAMPEL_ERR_INVALID_N_SIGMA`].
+///
+/// # Safety
+///
+/// `state` must be null, or point to writable memory for a `HampelF32W5` (it may be uninitialized).
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w5_init(state: *mut HampelF32W5, init_val: f32, n_sigma: f32) -> i32 {
+    unsafe { init(state, init_val, n_sigma) }
+}
+
+/// Update the window, and return the filtered value (NaN if `state` is null or not initialized).
+///
+/// # Safety
+///
+/// `state` must be null, or point to a `HampelF32W5` that is not accessed concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w5_update(state: *mut HampelF32W5, x: f32) -> f32 {
+    unsafe { update(state, x) }
+}
+
+/// Restore the state right after `init`.
+///
+/// Returns [`HAMPEL_OK`], [`HAMPEL_ERR_NULL`] or [`HAMPEL_ERR_UNINITIALIZED`].
+///
+/// # Safety
+///
+/// Same as [`hampel_f32_w5_update`].
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w5_reset(state: *mut HampelF32W5) -> i32 {
+    unsafe { reset(state) }
+}
+
+/// Same as [`hampel_f32_w5_init`], for `HampelF32W11`.
+///
+/// # Safety
+///
+/// `state` must be null, or point to writable memory for a `HampelF32W11` (it may be uninitialized).
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w11_init(state: *mut HampelF32W11, init_val: f32, n_sigma: f32) -> i32 {
+    unsafe { init(state, init_val, n_sigma) }
+}
+
+/// Same as [`hampel_f32_w5_update`], for `HampelF32W11`.
+///
+/// # Safety
+///
+/// `state` must be null, or point to a `HampelF32W11` that is not accessed concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w11_update(state: *mut HampelF32W11, x: f32) -> f32 {
+    unsafe { update(state, x) }
+}
+
+/// Same as [`hampel_f32_w5_reset`], for `HampelF32W11`.
+///
+/// # Safety
+///
+/// Same as [`hampel_f32_w11_update`].
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w11_reset(state: *mut HampelF32W11) -> i32 {
+    unsafe { reset(state) }
+}
+
+/// Same as [`hampel_f32_w5_init`], for `HampelF32W31`.
+///
+/// # Safety
+///
+/// `state` must be null, or point to writable memory for a `HampelF32W31` (it may be uninitialized).
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w31_init(state: *mut HampelF32W31, init_val: f32, n_sigma: f32) -> i32 {
+    unsafe { init(state, init_val, n_sigma) }
+}
+
+/// Same as [`hampel_f32_w5_update`], for `HampelF32W31`.
+///
+/// # Safety
+///
+/// `state` must be null, or point to a `HampelF32W31` that is not accessed concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w31_update(state: *mut HampelF32W31, x: f32) -> f32 {
+    unsafe { update(state, x) }
+}
+
+/// Same as [`hampel_f32_w5_reset`], for `HampelF32W31`.
+///
+/// # Safety
+///
+/// Same as [`hampel_f32_w31_update`].
+#[no_mangle]
+pub unsafe extern "C" fn hampel_f32_w31_reset(state: *mut HampelF32W31) -> i32 {
+    unsafe { reset(state) }
+}
@@ -0,0 +1,1617 @@
+//! Implementation of the Hampel filter shared by the window types.
+//!
+//! `Core` holds everything except the buffers. The ring buffer (`window`) and scratch
+//! buffers of the same length (`work`, `aux`) are passed in by the window types.
+//! `aux` is only used by the options that need a second scratch buffer (e.g. `ScaleEstimator::Sn`);
+//! window types that cannot be configured with them may pass an empty slice.
+//!
+//! `sorted` is an optional container that holds the window in ascending order (see `order`),
+//! updated incrementally so that the median does not have to be computed by sorting.
+//! If it is empty, the statistics are always computed from a sorted copy of the window.
+//!
+//! `gaps` is an optional buffer of the same length as `window`, which holds for each sample the time elapsed
+//! since the previous one (see `Window::update_at`). It is only used as the x-coordinates of the extrapolation;
+//! if it is empty, the samples are unit-spaced.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{adaptive::Adaptation, iter::Samples, order::Ordered, precision, stats::{self, QuantilePosition}, Center, Class, ConfigError, NanPolicy, OutlierStats, Reference, ReseedPolicy, Replacement, Sample, ScaleEstimator, Side, StorePolicy, ThresholdMode, ZeroMad};
+
+/// 判定に使う統計量（中央値・尺度と，判定区間の基準となる下端・上端）
+struct Estimate<T> {
+    median: T,
+    scale: T,
+    lower: T,
+    upper: T,
+}
+
+/// 等間隔のサンプルすべてに直線を当てはめるときの，サンプル数だけで決まる量（`Core::reset`で求める）
+///
+/// `linear_fit`と同じ順序で計算するので，それを使っても結果はビット単位で等しい
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LineConstants<T> {
+    pub(crate) n: usize,  // サンプル数
+    pub(crate) count: T,  // サンプル数（浮動小数点数）
+    pub(crate) mu_x: T,  // x座標の平均値
+    pub(crate) denom: T,  // x座標の偏差の二乗和
+    pub(crate) last_x: T,  // 最新のサンプルのx座標
+}
+
+impl<T: FloatCore> LineConstants<T> {
+    /// x座標が0, 1, ..., n - 1のとき（精度の低い型ではf32で計算するので使わない）
+    fn new(n: usize) -> Option<Self> {
+        if n < 2 || precision::is_narrow::<T>() {
+            return None;
+        }
+        let xs = || (0..n).scan(T::zero(), |x, i| {
+            if i > 0 {
+                *x = *x + T::one();
+            }
+            Some(*x)
+        });
+        let (count, sum, last_x) = xs().fold((T::zero(), T::zero(), T::zero()), |(c, s, _), x| (c + T::one(), s + x, x));
+        let mu_x = sum / count;
+        let denom = xs().fold(T::zero(), |d, x| d + (x - mu_x) * (x - mu_x));
+        Some(Self { n, count, mu_x, denom, last_x })
+    }
+
+    /// 最新のサンプルの`next_gap`後の値を外挿する（`y(i)`: i番目のサンプル）
+    fn extrapolate(&self, y: impl Fn(usize) -> T, next_gap: T) -> T {
+        let mu_y = (0..self.n).fold(T::zero(), |s, i| s + y(i)) / self.count;
+        let mut x = T::zero();
+        let mut numer = T::zero();
+        for i in 0..self.n {
+            if i > 0 {
+                x = x + T::one();
+            }
+            numer = numer + (x - self.mu_x) * (y(i) - mu_y);
+        }
+        let a = numer / self.denom;
+        let b = mu_y - a * self.mu_x;
+        a * (self.last_x + next_gap) + b
+    }
+}
+
+/// 長さ`n`のwindow全体から分位数を求めるときの位置（`Core::reset`で求める）
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct QuantileConstants<T> {
+    n: usize,  // windowの長さ
+    positions: (QuantilePosition<T>, QuantilePosition<T>),  // `ScaleEstimator::Iqr`では第1・第3四分位数，`Center::Quantile`では両方ともその位置
+}
+
+impl<T: FloatCore> QuantileConstants<T> {
+    /// 分位数を使わない設定では`None`
+    fn new(n: usize, center: Center, estimator: ScaleEstimator) -> Option<Self> {
+        let positions = match (estimator, center) {
+            (ScaleEstimator::Iqr, _) => (QuantilePosition::new(n, 0.25), QuantilePosition::new(n, 0.75)),
+            (ScaleEstimator::Mad, Center::Quantile { p }) => (QuantilePosition::new(n, p), QuantilePosition::new(n, p)),
+            _ => return None,
+        };
+        Some(Self { n, positions })
+    }
+}
+
+/// 置換を記録するときのフィルタの名前の既定値
+pub(crate) const DEFAULT_LABEL: &str = "hampel";
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Core<T> {
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) coef_low: T,  // 閾値判定に使う係数（中央値より小さい側）
+    pub(crate) coef_high: T,  // 閾値判定に使う係数（中央値より大きい側）
+    pub(crate) scale_const: T,
+    pub(crate) n_sigma_low: T,
+    pub(crate) n_sigma_high: T,
+    pub(crate) adaptation: Option<Adaptation<T>>,
+    pub(crate) estimator: ScaleEstimator,
+    pub(crate) center: Center,
+    pub(crate) reference: Reference,
+    pub(crate) center_len: usize,  // 中央値を求める最新のサンプル数（0: window全体，DualWindowで使う）
+    pub(crate) mad_floor: T,  // 尺度の推定値の下限
+    pub(crate) zero_mad: ZeroMad<T>,  // 尺度の推定値が0の場合の判定
+    pub(crate) abs_threshold: Option<T>,  // 中央値からの偏差の絶対的な閾値
+    pub(crate) threshold_mode: ThresholdMode,
+    pub(crate) output_range: Option<(T, T)>,  // 物理的に取り得る値の範囲（範囲外の入力は外れ値，置換値は範囲内に制限する）
+    pub(crate) max_slew: Option<T>,  // 1サンプルあたりの出力の変化量の上限
+    pub(crate) difference: Option<(T, ThresholdMode)>,  // 差分の判定に使う係数と，値の判定との組み合わせ方
+    pub(crate) replacement: Replacement,
+    pub(crate) soft: Option<T>,  // 穏やかな置換の幅（閾値からの距離に対する比）
+    pub(crate) store: StorePolicy,
+    pub(crate) nan_policy: NanPolicy,
+    pub(crate) warm_up: bool,
+    pub(crate) undoable: bool,  // 更新の前の状態を取っておく（Window::undo_last_update）
+    pub(crate) seen: usize,  // windowに格納したサンプル数
+    pub(crate) last: T,  // 前回の出力値
+    pub(crate) last_valid: T,  // 外れ値と判定されなかった直近の出力値
+    pub(crate) stats: OutlierStats,
+    pub(crate) max_run: usize,  // 連続して置換できる最大のサンプル数（0: 無制限）
+    pub(crate) run: usize,  // 連続して置換したサンプル数
+    pub(crate) reseed: ReseedPolicy,  // 置換の連続の上限に達したときのwindowの作り直し方
+    pub(crate) hysteresis: Option<T>,  // 外れ値の連続から抜ける閾値判定に使う係数
+    pub(crate) suspect: Option<(T, bool)>,  // 疑わしいサンプルの閾値判定に使う係数と，置換するかどうか
+    pub(crate) in_run: bool,  // 直前のサンプルを外れ値と判定したか（ヒステリシスの状態）
+    pub(crate) confirmation: Option<(u8, u8)>,  // 直近k個中m個以上が閾値を超えた場合だけ置換する (m, k)
+    pub(crate) votes: u8,  // 確認前の判定結果（ビット0が最新）
+    pub(crate) fill_missing: bool,  // 欠けたサンプルの推定値をwindowに格納するか
+    pub(crate) max_missing: usize,  // 出力を信頼できる連続して欠けたサンプル数の上限
+    pub(crate) missing: usize,  // 連続して欠けたサンプル数
+    pub(crate) missing_gap: Option<T>,  // 最後に格納したサンプルから次のサンプルまでの時刻の差（missing + 1を1ずつ数える，欠けていなければNone）
+    pub(crate) flagged: u128,  // 直近のサンプルが外れ値と判定されたか（ビット0が最新）
+    pub(crate) forgetting: Option<T>,  // 重み付き中央値の忘却係数
+    pub(crate) median: T,  // 直近の更新で判定に使った中央値
+    pub(crate) scale: T,  // 直近の更新で判定に使った尺度の推定値
+    pub(crate) bounds: (T, T),  // 直近の更新で判定に使った許容範囲（下端, 上端）
+    #[cfg(any(feature = "log", feature = "defmt"))]
+    pub(crate) label: &'static str,  // 置換を記録するときにフィルタを区別する名前
+    pub(crate) nan_count: usize,  // window内のNaNの数
+    pub(crate) sorted_dirty: bool,  // sortedが使えない（NaNを含む）
+    pub(crate) line: Option<LineConstants<T>>,  // 最新以外のサンプルに直線を当てはめるときの定数（Noneなら毎回求める）
+    pub(crate) quantiles: Option<QuantileConstants<T>>,  // windowが埋まっているときの分位数の位置（Noneなら毎回求める）
+}
+
+/// 更新で変わる状態の写し（`Core::rollback`で更新前に戻す）
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Checkpoint<T> {
+    oldest: usize,
+    coef_low: T,
+    coef_high: T,
+    adaptation: Option<Adaptation<T>>,
+    seen: usize,
+    last: T,
+    last_valid: T,
+    stats: OutlierStats,
+    run: usize,
+    in_run: bool,
+    votes: u8,
+    missing: usize,
+    flagged: u128,
+    median: T,
+    scale: T,
+    bounds: (T, T),
+    nan_count: usize,
+    sorted_dirty: bool,
+    evicted: T,  // 次に上書きされるスロットの値
+    gap: T,  // 次に上書きされるスロットの時刻の差
+}
+
+/// リングバッファを時系列順（最も古い要素から）に並べたもの
+pub(crate) struct Chronological<'a, T> {
+    pub(crate) window: &'a [T],
+    pub(crate) oldest: usize,
+}
+
+impl<'a, T> Chronological<'a, T> {
+    pub(crate) fn iter(&self) -> Samples<'a, T> {
+        Samples::new(self.window, self.oldest)
+    }
+}
+
+/// Compares the state that determines the output, excluding the position of the ring buffer
+/// and the values derived from the window (the latter are compared with the window by the window types).
+impl<T: PartialEq> PartialEq for Core<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coef_low == other.coef_low
+            && self.coef_high == other.coef_high
+            && self.scale_const == other.scale_const
+            && self.n_sigma_low == other.n_sigma_low
+            && self.n_sigma_high == other.n_sigma_high
+            && self.adaptation == other.adaptation
+            && self.estimator == other.estimator
+            && self.center == other.center
+            && self.reference == other.reference
+            && self.center_len == other.center_len
+            && self.mad_floor == other.mad_floor
+            && self.zero_mad == other.zero_mad
+            && self.abs_threshold == other.abs_threshold
+            && self.threshold_mode == other.threshold_mode
+            && self.output_range == other.output_range
+            && self.max_slew == other.max_slew
+            && self.difference == other.difference
+            && self.replacement == other.replacement
+            && self.soft == other.soft
+            && self.store == other.store
+            && self.nan_policy == other.nan_policy
+            && self.warm_up == other.warm_up
+            && self.undoable == other.undoable
+            && self.seen == other.seen
+            && self.last == other.last
+            && self.last_valid == other.last_valid
+            && self.stats == other.stats
+            && self.max_run == other.max_run
+            && self.run == other.run
+            && self.reseed == other.reseed
+            && self.hysteresis == other.hysteresis
+            && self.suspect == other.suspect
+            && self.in_run == other.in_run
+            && self.confirmation == other.confirmation
+            && self.votes == other.votes
+            && self.fill_missing == other.fill_missing
+            && self.max_missing == other.max_missing
+            && self.missing == other.missing
+            && self.flagged == other.flagged
+            && self.forgetting == other.forgetting
+            && self.median == other.median
+            && self.scale == other.scale
+            && self.bounds == other.bounds
+    }
+}
+
+// n_sigmaを大きくするほど判定が緩くなる（外れ値を見落としやすくなる）
+/// 閾値`n_sigma`が正の有限値であることを確かめる（全てのフィルタの構築で使う）
+pub(crate) fn check_n_sigma<T: FloatCore>(n_sigma: T) -> Result<(), ConfigError> {
+    if n_sigma > T::zero() && n_sigma.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidNSigma)
+    }
+}
+
+/// `check_n_sigma`と同じだが，正の有限値でなければpanicする
+pub(crate) fn assert_n_sigma<T: FloatCore>(n_sigma: T) {
+    if let Err(e) = check_n_sigma(n_sigma) {
+        panic!("{}", e);
+    }
+}
+
+impl<T: FloatCore> Core<T> {
+    /// 既定の設定で`Core`を作る（`const`の文脈で使えるように，係数`coef = scale_const * n_sigma`と0，
+    /// window内のNaNの数は呼び出し側で求める）
+    pub(crate) const fn new_const(init_val: T, n_sigma: T, scale_const: T, coef: T, zero: T, len: usize, nan_count: usize) -> Self {
+        Core {
+            oldest: 0,
+            coef_low: coef,
+            coef_high: coef,
+            scale_const,
+            n_sigma_low: n_sigma,
+            n_sigma_high: n_sigma,
+            adaptation: None,
+            estimator: ScaleEstimator::Mad,
+            center: Center::Median,
+            reference: Reference::Median,
+            center_len: 0,
+            mad_floor: zero,
+            zero_mad: ZeroMad::FlagEverything,
+            abs_threshold: None,
+            threshold_mode: ThresholdMode::Either,
+            output_range: None,
+            max_slew: None,
+            difference: None,
+            replacement: Replacement::DEFAULT,
+            soft: None,
+            store: StorePolicy::Raw,
+            nan_policy: NanPolicy::Propagate,
+            warm_up: false,
+            undoable: false,
+            seen: 0,
+            last: init_val,
+            last_valid: init_val,
+            stats: OutlierStats { samples_total: 0, outliers_total: 0, current_run: 0, longest_run: 0, recent: 0 },
+            max_run: 0,
+            run: 0,
+            reseed: ReseedPolicy::Run,
+            hysteresis: None,
+            suspect: None,
+            in_run: false,
+            confirmation: None,
+            votes: 0,
+            fill_missing: false,
+            max_missing: len / 2,
+            missing: 0,
+            missing_gap: None,
+            flagged: 0,
+            forgetting: None,
+            median: init_val,
+            scale: zero,
+            bounds: (init_val, init_val),
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            label: DEFAULT_LABEL,
+            nan_count,
+            sorted_dirty: nan_count > 0,
+            line: None,
+            quantiles: None,
+        }
+    }
+
+    /// 時系列順のwindowと状態が等しいか（windowの`PartialEq`の実装）
+    pub(crate) fn eq_with(&self, window: &[T], other: &Self, other_window: &[T]) -> bool {
+        let a = Chronological { window, oldest: self.oldest };
+        let b = Chronological { window: other_window, oldest: other.oldest };
+        self == other && a.iter().eq(b.iter())
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        window: &mut [T],
+        gaps: &mut [T],
+        work: &mut [T],
+        aux: &mut [T],
+        sorted: &mut (impl Ordered<T> + ?Sized),
+        x: T,
+    ) -> Sample<T> {
+        let gap = self.pending_gap();
+        self.update_at(window, gaps, work, aux, sorted, gap, x)
+    }
+
+    /// 最後に格納したサンプルから次のサンプルまでの時刻の差（格納しなかった欠けたサンプルの分だけ離れている）
+    pub(crate) fn pending_gap(&self) -> T {
+        match self.missing_gap {
+            Some(gap) if !self.fill_missing => gap,
+            _ => T::one(),
+        }
+    }
+
+    /// `update`と同じだが，`x`と1つ前のサンプルの時刻の差`gap`（非負）を`gaps`に格納する
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn update_at(
+        &mut self,
+        window: &mut [T],
+        gaps: &mut [T],
+        work: &mut [T],
+        aux: &mut [T],
+        sorted: &mut (impl Ordered<T> + ?Sized),
+        gap: T,
+        x: T,
+    ) -> Sample<T> {
+        self.missing = 0;
+        self.missing_gap = None;
+        if self.nan_policy == NanPolicy::TreatAsOutlier && !x.is_finite() {
+            return self.update_non_finite(window, gaps, work, aux, sorted, gap, x);
+        }
+        let len = window.len();
+
+        // Range of `oldest`: [0, len)
+        let slot = self.oldest;
+        let evicted = window[slot];
+        window[slot] = x;
+        if let Some(g) = gaps.get_mut(slot) {
+            *g = gap;
+        }
+        self.oldest = (self.oldest + 1) % len;
+        self.seen = self.seen.saturating_add(1);
+        self.update_sorted(window, sorted, slot, evicted, x);
+
+        let n = self.filled(len);
+        let e = self.estimate_window(window, work, aux, sorted);
+        let e = self.predicted(window, gaps, work, self.oldest + len - n, n - 1, if gaps.is_empty() { T::one() } else { gap }, e);
+        let bounds = self.band(&e);
+
+        // 外れ値かどうか判定（サンプルが少なすぎる間は素通し）
+        let (relative, absolute) = if n >= 3 { self.criteria(x, &e) } else { (false, false) };
+        let score = if n >= 3 { self.score(x, &e) } else { T::zero() };
+        let (difference, expected) = self.jump(window, work, self.oldest + len - n, n - 1, x);
+        // 物理的な範囲外の値は常に外れ値とする（確認や置換の連続の上限にかかわらない）
+        let out_of_range = self.out_of_range(x);
+        let tripped = self.combine_difference(self.combine(relative, absolute), difference) || out_of_range;
+        self.votes = (self.votes << 1) | tripped as u8;
+        let mut is_outlier = out_of_range || (tripped && self.confirmed(self.votes));
+        let reseeded = is_outlier && !out_of_range && self.max_run > 0 && self.run >= self.max_run;
+        if reseeded {
+            // 置換が続きすぎたので真のレベル変化とみなし，xをそのまま受け入れてwindowを作り直す
+            is_outlier = false;
+            match self.reseed {
+                // 連続して置換したサンプルとxで作り直す（置換値を格納している場合はxだけで作り直す）
+                ReseedPolicy::Run => {
+                    let m = if self.store == StorePolicy::Raw { self.run + 1 } else { 1 };
+                    self.reseed_newest(window, work, sorted, m);
+                },
+                ReseedPolicy::Latest => self.refill(window, sorted, x),
+            }
+            gaps.fill(T::one());  // 作り直したwindowは等間隔とみなす
+        }
+        self.run = if is_outlier { self.run + 1 } else { 0 };
+        self.in_run = is_outlier;
+        let replacement = || {
+            if out_of_range {
+                self.clip(self.replace(window, gaps, x, &e, n - 1))
+            } else if !self.combine(relative, absolute) {
+                // 差分だけで外れ値と判定した場合は，直前の外れ値でないサンプルから差分の中央値で外挿する
+                self.clip(expected)
+            } else if relative && self.soft.is_some() {
+                self.clip(self.soften(x, &e))
+            } else {
+                self.clip(self.replace(window, gaps, x, &e, n - 1))
+            }
+        };
+        let value = if cfg!(feature = "constant-time") {
+            // 置換値を常に求めてから選び，外れ値かどうかで処理の流れが分かれないようにする
+            stats::choose(is_outlier, replacement(), x)
+        } else if is_outlier {
+            replacement()
+        } else {
+            x
+        };
+        // 外れ値でなければ，疑わしいサンプルかどうかを固定の閾値で判定する
+        let class = match self.suspect {
+            _ if is_outlier => Class::Outlier,
+            Some((coef, _)) if n >= 3 && self.deviates_by(x, &e, coef, coef) => Class::Suspect,
+            _ => Class::Normal,
+        };
+        let value = match self.suspect {
+            Some((_, true)) if class == Class::Suspect => self.clip(self.replace(window, gaps, x, &e, n - 1)),
+            _ => value,
+        };
+        // 閾値は置換値を求めてから更新する（Clampは判定に使った閾値で制限する）
+        if let Some(a) = self.adaptation.as_mut() {
+            a.record(is_outlier, self.n_sigma_low.max(self.n_sigma_high));
+            self.coef_low = precision::mul(self.scale_const, self.n_sigma_low + a.offset);
+            self.coef_high = precision::mul(self.scale_const, self.n_sigma_high + a.offset);
+        }
+        if is_outlier && self.store == StorePolicy::Replacement {
+            // xを格納したスロット（oldestは既に次に進めてある）を置換値で上書きする
+            window[slot] = value;
+            self.update_sorted(window, sorted, slot, x, value);
+        }
+        let value = self.slew(value);
+        self.last = value;
+        if !is_outlier {
+            self.last_valid = value;
+        }
+        self.flagged = (self.flagged << 1) | is_outlier as u128;
+        self.stats.record(is_outlier);
+        (self.median, self.scale, self.bounds) = (e.median, e.scale, bounds);
+        let side = match is_outlier {
+            true if x < e.median => Some(Side::Below),
+            true => Some(Side::Above),
+            false => None,
+        };
+
+        let zero_mad = n >= 3 && e.scale.max(self.mad_floor) == T::zero();
+
+        Sample { value, is_outlier, median: e.median, mad: e.scale, bounds, relative, absolute, difference, out_of_range, reseeded, tripped, score, side, stale: false, zero_mad, class }
+    }
+
+    /// サンプルが欠けている場合に，`replacement`に応じた推定値を返す（`fill_missing`ならwindowに格納する）
+    pub(crate) fn update_missing(
+        &mut self,
+        window: &mut [T],
+        gaps: &mut [T],
+        work: &mut [T],
+        aux: &mut [T],
+        sorted: &mut (impl Ordered<T> + ?Sized),
+    ) -> Sample<T> {
+        let len = window.len();
+        let n = self.filled(len);
+        let next_gap = self.missing_gap.unwrap_or_else(T::one);
+        self.missing = self.missing.saturating_add(1);
+        if next_gap + T::one() > next_gap {
+            self.missing_gap = Some(next_gap + T::one());
+        } else {
+            self.recount_missing_gap();  // 1を足しても変わらない（f16では2048以上）ので，missingから丸めて求める
+        }
+        let stale = self.missing > self.max_missing;
+        if n == 0 {
+            // 統計量を計算できないので，windowには何も格納しない
+            return Sample {
+                value: self.last, is_outlier: false, median: self.last, mad: T::zero(), bounds: self.band_at(self.last),
+                relative: false, absolute: false, difference: false, out_of_range: false, reseeded: false, tripped: false, score: T::zero(), side: None, stale, zero_mad: false, class: Class::Normal,
+            };
+        }
+
+        // windowのn個のサンプルから，欠けたサンプルの時刻での値を外挿する
+        // （推定値を格納しない場合は，連続して欠けた分だけ先の値になる）
+        let gap = if self.fill_missing { T::one() } else { next_gap };
+        let e = self.estimate_window(window, work, aux, sorted);
+        let e = self.predicted(window, gaps, work, self.oldest + len - n, n, gap, e);
+        let bounds = self.band(&e);
+        let value = match self.replacement {
+            Replacement::Median | Replacement::Clamp => e.median,
+            Replacement::LinearExtrapolation | Replacement::QuadraticExtrapolation | Replacement::TheilSenExtrapolation => {
+                self.extrapolation(window, gaps, self.oldest + len - n, n, gap).unwrap_or(e.median)
+            },
+            Replacement::HoldLast | Replacement::PassThrough => self.last,
+            Replacement::HoldLastValid => self.last_valid,
+        };
+        let value = self.clip(value);
+        if self.fill_missing {
+            let slot = self.oldest;
+            let evicted = window[slot];
+            window[slot] = value;
+            if let Some(g) = gaps.get_mut(slot) {
+                *g = T::one();
+            }
+            self.oldest = (self.oldest + 1) % len;
+            self.seen = self.seen.saturating_add(1);
+            self.update_sorted(window, sorted, slot, evicted, value);
+            self.flagged = (self.flagged << 1) | 1;  // 推定値は外挿に使わない
+        }
+        let value = self.slew(value);
+        self.last = value;
+        (self.median, self.scale, self.bounds) = (e.median, e.scale, bounds);
+
+        Sample {
+            value, is_outlier: false, median: e.median, mad: e.scale, bounds,
+            relative: false, absolute: false, difference: false, out_of_range: false, reseeded: false, tripped: false, score: T::zero(), side: None, stale, zero_mad: false, class: Class::Normal,
+        }
+    }
+
+    /// `NanPolicy::TreatAsOutlier`の場合に非有限値を処理する
+    #[allow(clippy::too_many_arguments)]
+    fn update_non_finite(
+        &mut self,
+        window: &mut [T],
+        gaps: &mut [T],
+        work: &mut [T],
+        aux: &mut [T],
+        sorted: &mut (impl Ordered<T> + ?Sized),
+        gap: T,
+        x: T,
+    ) -> Sample<T> {
+        let len = window.len();
+        let n = self.filled(len);
+        self.stats.record(true);
+        self.in_run = true;
+        self.votes = (self.votes << 1) | 1;
+        if n == 0 {
+            // 統計量を計算できないので，windowには何も格納しない
+            (self.median, self.scale, self.bounds) = (self.last, T::zero(), self.band_at(self.last));
+            return Sample {
+                value: self.last, is_outlier: true, median: self.last, mad: T::zero(), bounds: self.bounds,
+                relative: false, absolute: false, difference: false, out_of_range: self.out_of_range(x), reseeded: false, tripped: true, score: T::infinity(), side: None, stale: false, zero_mad: false, class: Class::Outlier,
+            };
+        }
+
+        let slot = self.oldest;
+        self.oldest = (self.oldest + 1) % len;
+        self.seen = self.seen.saturating_add(1);
+        if let Some(g) = gaps.get_mut(slot) {
+            *g = gap;
+        }
+
+        // 上書きされるスロットを除いた残りの要素で統計量を計算
+        work[..n].copy_from_slice(&window[..n]);
+        self.fill_weights(aux, len, n, slot);
+        let m = if n < len {
+            n  // ウォームアップ中はslot == n
+        } else {
+            work[slot] = work[len - 1];
+            if self.forgetting.is_some() {
+                aux[slot] = aux[len - 1];
+            }
+            len - 1
+        };
+        let e = self.estimate(&mut work[..m], aux);
+        let e = self.predicted(window, gaps, work, slot + len - m, m, if gaps.is_empty() { T::one() } else { gap }, e);
+        let bounds = self.band(&e);
+
+        // 外挿は最新のスロットを参照しないので，置換値を計算してから書き込めば良い
+        let value = self.clip(self.replace(window, gaps, x, &e, m));
+        let evicted = window[slot];
+        window[slot] = if value.is_finite() { value } else { e.median };
+        self.update_sorted(window, sorted, slot, evicted, window[slot]);
+        let value = self.slew(value);
+        self.last = value;
+        self.flagged = (self.flagged << 1) | 1;
+        (self.median, self.scale, self.bounds) = (e.median, e.scale, bounds);
+
+        Sample {
+            value, is_outlier: true, median: e.median, mad: e.scale, bounds,
+            relative: false, absolute: false, difference: false, out_of_range: self.out_of_range(x), reseeded: false, tripped: true, score: T::infinity(), side: None, stale: false, zero_mad: false, class: Class::Outlier,
+        }
+    }
+
+    /// windowの`slot`番目の要素が`evicted`から`inserted`に置き換わったのをsortedに反映する
+    fn update_sorted(&mut self, window: &[T], sorted: &mut (impl Ordered<T> + ?Sized), slot: usize, evicted: T, inserted: T) {
+        if sorted.is_empty() {
+            return;
+        }
+        if evicted.is_nan() {
+            self.nan_count -= 1;
+        }
+        if inserted.is_nan() {
+            self.nan_count += 1;
+        }
+        if self.sorted_dirty {
+            if self.nan_count == 0 {
+                self.rebuild_sorted(window, sorted);
+            }
+        } else if inserted.is_nan() {
+            // NaNは大小比較できないので，window内から無くなるまでsortedは使わない
+            self.sorted_dirty = true;
+        } else {
+            sorted.replace(slot, evicted, inserted);
+        }
+    }
+
+    /// windowからsortedを作り直す
+    pub(crate) fn rebuild_sorted(&mut self, window: &[T], sorted: &mut (impl Ordered<T> + ?Sized)) {
+        if sorted.is_empty() {
+            return;
+        }
+        self.nan_count = window.iter().filter(|v| v.is_nan()).count();
+        self.sorted_dirty = self.nan_count > 0;
+        if !self.sorted_dirty {
+            sorted.rebuild(window);
+        }
+    }
+
+    /// 統計量の計算にsortedを使えるか
+    fn sorted_usable(&self, sorted: &(impl Ordered<T> + ?Sized)) -> bool {
+        !sorted.is_empty() && !self.sorted_dirty && self.forgetting.is_none()
+    }
+
+    /// 現在のwindowの統計量（ウォームアップ中は受け取ったサンプル（window[..n]）のみを使う）
+    fn estimate_window(&self, window: &[T], work: &mut [T], aux: &mut [T], sorted: &(impl Ordered<T> + ?Sized)) -> Estimate<T> {
+        let len = window.len();
+        let n = self.filled(len);
+        if self.center_len > 0 && self.center_len < n {
+            self.estimate_split(window, work, sorted, n)
+        } else if n == len && self.sorted_usable(sorted) {
+            self.estimate_sorted(sorted, work, aux)
+        } else {
+            work[..n].copy_from_slice(&window[..n]);
+            self.fill_weights(aux, len, n, (self.oldest + len - 1) % len);
+            self.estimate(&mut work[..n], aux)
+        }
+    }
+
+    /// 最新の`center_len`個のサンプルの中央値と，window[..n]のそれからの偏差の絶対値の中央値
+    fn estimate_split(&self, window: &[T], work: &mut [T], sorted: &(impl Ordered<T> + ?Sized), n: usize) -> Estimate<T> {
+        let len = window.len();
+        let newest = self.oldest + len - 1;  // ウォームアップ中もoldest == nなので，最新の要素はnewest % len
+        let m = self.center_len;
+        for (k, w) in work[..m].iter_mut().enumerate() {
+            *w = window[(newest - k) % len];
+        }
+        let w0 = stats::median(&mut work[..m]);
+        let s0 = if n == len && self.sorted_usable(sorted) {
+            sorted.mad(w0, work)
+        } else {
+            for (w, v) in work[..n].iter_mut().zip(&window[..n]) {
+                *w = (*v - w0).abs();
+            }
+            stats::median(&mut work[..n])
+        };
+        Estimate { median: w0, scale: s0, lower: w0, upper: w0 }
+    }
+
+    /// 長さ`n`の中での`Center::Quantile { p }`の位置（windowが埋まっていれば求めておいたものを使う）
+    fn quantile_position(&self, n: usize, p: f32) -> QuantilePosition<T> {
+        match self.quantiles {
+            Some(q) if q.n == n => q.positions.0,
+            _ => QuantilePosition::new(n, p),
+        }
+    }
+
+    /// 長さ`n`の中での第1・第3四分位数の位置
+    fn quartile_positions(&self, n: usize) -> (QuantilePosition<T>, QuantilePosition<T>) {
+        match self.quantiles {
+            Some(q) if q.n == n => q.positions,
+            _ => (QuantilePosition::new(n, 0.25), QuantilePosition::new(n, 0.75)),
+        }
+    }
+
+    /// 昇順に並んだ`sorted`から`estimate`と同じ統計量を計算する
+    fn estimate_sorted(&self, sorted: &(impl Ordered<T> + ?Sized), work: &mut [T], aux: &mut [T]) -> Estimate<T> {
+        let w0 = sorted.median();
+        match self.estimator {
+            ScaleEstimator::Mad => {
+                let w0 = match self.center {
+                    Center::Median => w0,
+                    Center::TrimmedMean { trim_fraction } => stats::trimmed_mean_of_sorted(sorted.as_sorted(work), trim_fraction),
+                    Center::Quantile { p } => {
+                        let sorted = sorted.as_sorted(work);
+                        self.quantile_position(sorted.len(), p).of_sorted(sorted)
+                    },
+                };
+                Estimate { median: w0, scale: sorted.mad(w0, work), lower: w0, upper: w0 }
+            },
+            ScaleEstimator::Sn => {
+                Estimate { median: w0, scale: stats::sn(sorted.as_sorted(work), aux), lower: w0, upper: w0 }
+            },
+            ScaleEstimator::Iqr => {
+                let sorted = sorted.as_sorted(work);
+                let (p1, p3) = self.quartile_positions(sorted.len());
+                let (q1, q3) = (p1.of_sorted(sorted), p3.of_sorted(sorted));
+                Estimate { median: w0, scale: q3 - q1, lower: q1, upper: q3 }
+            },
+        }
+    }
+
+    /// 忘却係数を使う場合，`aux[..n]`に`window[..n]`の各要素の重みを書き込む（`newest`: 最新の要素のインデックス）
+    fn fill_weights(&self, aux: &mut [T], len: usize, n: usize, newest: usize) {
+        if let Some(lambda) = self.forgetting {
+            for (i, w) in aux[..n].iter_mut().enumerate() {
+                let age = (newest + len - i) % len;
+                *w = lambda.powi(age as i32);
+            }
+        }
+    }
+
+    /// `work`の中央値と尺度の推定値を返す（`work`の中身は書き換わる）
+    /// 
+    /// 忘却係数を使う場合，`aux`には`work`の各要素の重みが入っている
+    fn estimate(&self, work: &mut [T], aux: &mut [T]) -> Estimate<T> {
+        match self.estimator {
+            ScaleEstimator::Mad if self.forgetting.is_some() => {
+                let (w0, s0) = stats::weighted_statistics(work, &mut aux[..work.len()]);
+                Estimate { median: w0, scale: s0, lower: w0, upper: w0 }
+            },
+            ScaleEstimator::Mad => {
+                let (w0, s0) = match self.center {
+                    Center::Median => stats::statistics(work),
+                    Center::TrimmedMean { trim_fraction } => stats::trimmed_statistics(work, trim_fraction),
+                    Center::Quantile { p } => stats::quantile_statistics(work, self.quantile_position(work.len(), p)),
+                };
+                Estimate { median: w0, scale: s0, lower: w0, upper: w0 }
+            },
+            ScaleEstimator::Sn => {
+                stats::sort(work);
+                let w0 = stats::median_of_sorted(work);
+                Estimate { median: w0, scale: stats::sn(work, aux), lower: w0, upper: w0 }
+            },
+            ScaleEstimator::Iqr => {
+                stats::sort(work);
+                let (p1, p3) = self.quartile_positions(work.len());
+                let (q1, q3) = (p1.of_sorted(work), p3.of_sorted(work));
+                Estimate { median: stats::median_of_sorted(work), scale: q3 - q1, lower: q1, upper: q3 }
+            },
+        }
+    }
+
+    /// 相対的な基準と絶対的な基準のそれぞれで外れ値と判定されたか
+    #[allow(clippy::neg_cmp_op_on_partial_ord)]
+    fn criteria(&self, x: T, e: &Estimate<T>) -> (bool, bool) {
+        let absolute = match self.abs_threshold {
+            Some(t) => !((x - e.median).abs() <= t),
+            None => false,
+        };
+        (self.deviates(x, e), absolute)
+    }
+
+    /// 二つの基準の判定結果を組み合わせる
+    fn combine(&self, relative: bool, absolute: bool) -> bool {
+        match (self.abs_threshold, self.threshold_mode) {
+            (None, _) => relative,
+            (Some(_), ThresholdMode::Either) => relative || absolute,
+            (Some(_), ThresholdMode::Both) => relative && absolute,
+        }
+    }
+
+    /// 差分の判定
+    ///
+    /// windowの`first`番目（リングバッファ上の位置）から`m`個をxの前に並ぶサンプルとして，
+    /// その中で外れ値と判定されなかった最新のサンプル`prev`（k個前）からの差`x - prev`が，
+    /// 隣り合うサンプルの差の中央値・中央絶対偏差のk倍から外れていれば`true`を，`prev`から外挿した値とともに返す
+    /// （差分の判定をしない場合，差が3個未満の場合，`prev`がwindow内に無い場合は`false`）
+    fn jump(&self, window: &[T], work: &mut [T], first: usize, m: usize, x: T) -> (bool, T) {
+        let coef = match self.difference {
+            Some((coef, _)) if m >= 4 => coef,
+            _ => return (false, x),
+        };
+        let len = window.len();
+        // j番目のサンプルの判定はflaggedのビットm - 1 - jに記録されている
+        let clean = |j: usize| m - 1 - j >= u128::BITS as usize || (self.flagged >> (m - 1 - j)) & 1 == 0;
+        let (mut j, mut k) = (m - 1, T::one());
+        while !clean(j) {
+            if j == 0 {
+                return (false, x);  // 外れ値が続いているので，値の判定だけに任せる
+            }
+            (j, k) = (j - 1, k + T::one());
+        }
+        let prev = window[(first + j) % len];
+
+        let work = &mut work[..(m - 1)];
+        for (i, w) in work.iter_mut().enumerate() {
+            *w = window[(first + i + 1) % len] - window[(first + i) % len];
+        }
+        let (md, sd) = stats::statistics(work);
+        let (expected, scale) = (prev + md * k, sd * k);
+        let trips = if x < expected {
+            precision::exceeds(expected, x, coef, scale)
+        } else {
+            precision::exceeds(x, expected, coef, scale)
+        };
+        (trips, expected)
+    }
+
+    /// 値の判定結果と差分の判定結果を組み合わせる
+    fn combine_difference(&self, value: bool, difference: bool) -> bool {
+        match self.difference {
+            None => value,
+            Some((_, ThresholdMode::Either)) => value || difference,
+            Some((_, ThresholdMode::Both)) => value && difference,
+        }
+    }
+
+    /// 判定に使う尺度（下限を適用し，0なら`zero_mad`に従う。全て合格とする場合は無限大）
+    fn scale_of(&self, e: &Estimate<T>) -> T {
+        let scale = e.scale.max(self.mad_floor);
+        if scale != T::zero() {
+            return scale;
+        }
+        match self.zero_mad {
+            ZeroMad::FlagEverything => scale,
+            ZeroMad::PassEverything => T::infinity(),
+            ZeroMad::UseFloor(floor) => floor,
+        }
+    }
+
+    /// `x`が`[lower - coef_low * scale, upper + coef_high * scale]`の外にあれば`true`（NaNは`true`）
+    fn deviates(&self, x: T, e: &Estimate<T>) -> bool {
+        let (coef_low, coef_high) = self.coef();
+        self.deviates_by(x, e, coef_low, coef_high)
+    }
+
+    /// `deviates`と同じだが，係数を指定する
+    fn deviates_by(&self, x: T, e: &Estimate<T>, coef_low: T, coef_high: T) -> bool {
+        let scale = self.scale_of(e);
+        if x < e.lower {
+            precision::exceeds(e.lower, x, coef_low, scale)
+        } else {
+            precision::exceeds(x, e.upper, coef_high, scale)
+        }
+    }
+
+    /// 値の判定（相対と絶対の閾値を組み合わせたもの）に合格する区間の下端と上端
+    fn band(&self, e: &Estimate<T>) -> (T, T) {
+        let scale = self.scale_of(e);
+        let (coef_low, coef_high) = self.coef();
+        let lower = e.lower - precision::mul(coef_low, scale);
+        let upper = e.upper + precision::mul(coef_high, scale);
+        match (self.abs_threshold, self.threshold_mode) {
+            (None, _) => (lower, upper),
+            (Some(t), ThresholdMode::Either) => (lower.max(e.median - t), upper.min(e.median + t)),
+            (Some(t), ThresholdMode::Both) => (lower.min(e.median - t), upper.max(e.median + t)),
+        }
+    }
+
+    /// 尺度が0で，全てのサンプルが`median`の場合の許容範囲
+    fn band_at(&self, median: T) -> (T, T) {
+        self.band(&Estimate { median, scale: T::zero(), lower: median, upper: median })
+    }
+
+    /// 現在の閾値判定に使う係数（外れ値の連続中はヒステリシスの係数で狭める）
+    fn coef(&self) -> (T, T) {
+        match self.hysteresis {
+            Some(exit) if self.in_run => (self.coef_low.min(exit), self.coef_high.min(exit)),
+            _ => (self.coef_low, self.coef_high),
+        }
+    }
+
+    /// `x`が`[lower, upper]`の外にある距離を`scale_const * scale`で割った値（`deviates`と同じ統計量を使う）
+    fn score(&self, x: T, e: &Estimate<T>) -> T {
+        let scale = self.scale_of(e);
+        let d = if x < e.lower {
+            e.lower - x
+        } else if x > e.upper {
+            x - e.upper
+        } else if x.is_nan() {
+            x
+        } else {
+            T::zero()
+        };
+        precision::ratio(d, self.scale_const, scale)
+    }
+
+    /// 外れ値`x`に対する置換値を返す（`n`: 外挿に使う最新以外のサンプル数）
+    fn replace(&self, window: &[T], gaps: &[T], x: T, e: &Estimate<T>, n: usize) -> T {
+        let w0 = e.median;
+        match self.replacement {
+            Replacement::Median => w0,
+            Replacement::Clamp => self.clamp(x, e),
+            Replacement::LinearExtrapolation | Replacement::QuadraticExtrapolation | Replacement::TheilSenExtrapolation => {
+                // 最新のサンプル（外れ値）を除いた直前のn個から，最新のサンプルの時刻での値を外挿する
+                let len = window.len();
+                let newest = (self.oldest + len - 1) % len;
+                let gap = if gaps.is_empty() { T::one() } else { gaps[newest] };
+                self.extrapolation(window, gaps, newest + len - n, n, gap).unwrap_or(w0)
+            },
+            Replacement::HoldLast => self.last,
+            Replacement::HoldLastValid => self.last_valid,
+            Replacement::PassThrough => x,
+        }
+    }
+
+    /// `x`が物理的に取り得る値の範囲外か（NaNは範囲外としない）
+    fn out_of_range(&self, x: T) -> bool {
+        self.output_range.is_some_and(|(lo, hi)| x < lo || x > hi)
+    }
+
+    /// 置換値`v`を物理的に取り得る値の範囲内に制限する（`Replacement::PassThrough`ならそのまま）
+    fn clip(&self, v: T) -> T {
+        match self.output_range {
+            _ if self.replacement == Replacement::PassThrough => v,
+            Some((lo, _)) if v < lo => lo,
+            Some((_, hi)) if v > hi => hi,
+            _ => v,
+        }
+    }
+
+    /// 出力値`v`を前回の出力値から`max_slew`以内に制限する（NaNはそのまま）
+    fn slew(&self, v: T) -> T {
+        match self.max_slew {
+            Some(d) if v < self.last - d => self.last - d,
+            Some(d) if v > self.last + d => self.last + d,
+            _ => v,
+        }
+    }
+
+    /// `x`を判定に合格する範囲の`x`側の端に制限した値（`x`がNaNなら中央値）
+    fn clamp(&self, x: T, e: &Estimate<T>) -> T {
+        let scale = self.scale_of(e);
+        let (mut edge, limit) = if x < e.lower {
+            (e.lower - precision::mul(self.coef().0, scale), self.abs_threshold.map(|t| e.median - t))
+        } else if x > e.upper {
+            (e.upper + precision::mul(self.coef().1, scale), self.abs_threshold.map(|t| e.median + t))
+        } else {
+            return e.median;
+        };
+        // ThresholdMode::Bothなら相対的な閾値の内側は合格なので，絶対的な閾値ではEitherの場合だけ制限する
+        if let (Some(limit), ThresholdMode::Either) = (limit, self.threshold_mode) {
+            edge = if x < e.lower { edge.max(limit) } else { edge.min(limit) };
+        }
+        let accepted = |v: T| {
+            let (relative, absolute) = self.criteria(v, e);
+            !self.combine(relative, absolute)
+        };
+        if accepted(edge) {
+            return edge;
+        }
+        // 丸め誤差で閾値をわずかに超えた場合は中央値の側に寄せる（それでも駄目なら中央値）
+        let two = T::one() + T::one();
+        let step = edge.abs().max((edge - e.median).abs()) * T::epsilon() * two;
+        let nudged = if edge < e.median { edge + step } else { edge - step };
+        if accepted(nudged) { nudged } else { e.median }
+    }
+
+    /// 範囲の端から`x`までの超過`e`を，端から`rolloff * b`以内に滑らかに縮めた値（`b`: 中央値から端までの距離）
+    fn soften(&self, x: T, e: &Estimate<T>) -> T {
+        let edge = self.clamp(x, e);
+        let width = precision::mul(self.soft.unwrap_or_else(T::zero), (edge - e.median).abs());
+        let excess = (x - edge).abs();
+        // width * excess / (width + excess) は excess について単調増加で，widthを超えない
+        let shrunk = if width == T::zero() || excess.is_nan() {
+            T::zero()
+        } else if excess.is_infinite() {
+            width
+        } else {
+            width * (excess / (width + excess))
+        };
+        if x < edge { edge - shrunk } else { edge + shrunk }
+    }
+
+    pub(crate) fn would_flag(&self, window: &[T], work: &mut [T], aux: &mut [T], x: T) -> bool {
+        if (self.nan_policy == NanPolicy::TreatAsOutlier && !x.is_finite()) || self.out_of_range(x) {
+            return true;
+        }
+        let len = window.len();
+
+        let n = if self.filled(len) < len { self.seen + 1 } else { len };
+        work[..n].copy_from_slice(&window[..n]);
+        work[self.oldest] = x;
+        self.fill_weights(aux, len, n, self.oldest);
+        let e = self.estimate(&mut work[..n], aux);
+        if n < 3 {
+            return false;
+        }
+        let e = self.predicted(window, &[], work, self.oldest + len - (n - 1), n - 1, T::one(), e);
+        let (relative, absolute) = self.criteria(x, &e);
+        // xを格納した場合にその前に並ぶn - 1個のサンプル
+        let (difference, _) = self.jump(window, work, self.oldest + len - (n - 1), n - 1, x);
+        self.combine_difference(self.combine(relative, absolute), difference)
+            && self.confirmed((self.votes << 1) | 1)
+            && !(self.max_run > 0 && self.run >= self.max_run)
+    }
+
+    /// 直近の判定`votes`（ビット0が最新）のうち，直近k個中m個以上が外れ値か（確認しない場合は常に`true`）
+    ///
+    /// `1 <= k <= 8`は`validate`で検証するが，範囲外でもパニックしないようにkを0..=8に飽和させる
+    fn confirmed(&self, votes: u8) -> bool {
+        match self.confirmation {
+            Some((m, k)) => {
+                let mask = u8::MAX.checked_shr(8u32.saturating_sub(u32::from(k))).unwrap_or(0);
+                (votes & mask).count_ones() >= u32::from(m)
+            },
+            None => true,
+        }
+    }
+
+    /// `x`を格納した場合の`Sample::score`（`would_flag`と同じく，windowは変更しない）
+    pub(crate) fn score_of(&self, window: &[T], work: &mut [T], aux: &mut [T], x: T) -> T {
+        if self.nan_policy == NanPolicy::TreatAsOutlier && !x.is_finite() {
+            return T::infinity();
+        }
+        let len = window.len();
+
+        let n = if self.filled(len) < len { self.seen + 1 } else { len };
+        work[..n].copy_from_slice(&window[..n]);
+        work[self.oldest] = x;
+        self.fill_weights(aux, len, n, self.oldest);
+        let e = self.estimate(&mut work[..n], aux);
+        let e = self.predicted(window, &[], work, self.oldest + len - (n - 1), n - 1, T::one(), e);
+        if n < 3 { T::zero() } else { self.score(x, &e) }
+    }
+
+    /// 最新のサンプル
+    pub(crate) fn latest(&self, window: &[T]) -> T {
+        window[(self.oldest + window.len() - 1) % window.len()]
+    }
+
+    /// 次に格納するサンプルで上書きされる（最も古い）サンプル
+    pub(crate) fn oldest_sample(&self, window: &[T]) -> T {
+        window[self.oldest]
+    }
+
+    /// 現在の（適応後の）n_sigma
+    pub(crate) fn effective_n_sigma(&self) -> T {
+        match self.adaptation {
+            Some(a) => self.n_sigma_high + a.offset,
+            None => self.n_sigma_high,
+        }
+    }
+
+    pub(crate) fn reset(&mut self, window: &mut [T], sorted: &mut (impl Ordered<T> + ?Sized), init_val: T) {
+        window.fill(init_val);
+        self.rebuild_sorted(window, sorted);
+        self.line = LineConstants::new(window.len() - 1);
+        self.quantiles = QuantileConstants::new(window.len(), self.center, self.estimator);
+        self.oldest = 0;
+        self.seen = 0;
+        self.run = 0;
+        self.in_run = false;
+        self.votes = 0;
+        self.missing = 0;
+        self.missing_gap = None;
+        self.flagged = 0;
+        self.last = init_val;
+        self.last_valid = init_val;
+        (self.median, self.scale, self.bounds) = (init_val, T::zero(), (init_val, init_val));
+        if let Some(a) = self.adaptation.as_mut() {
+            *a = Adaptation::new(a.config);
+            self.coef_low = precision::mul(self.scale_const, self.n_sigma_low);
+            self.coef_high = precision::mul(self.scale_const, self.n_sigma_high);
+        }
+    }
+
+    pub(crate) fn seed_from_iter<I>(
+        &mut self,
+        window: &mut [T],
+        work: &mut [T],
+        aux: &mut [T],
+        sorted: &mut (impl Ordered<T> + ?Sized),
+        samples: I,
+    ) where
+        I: IntoIterator<Item = T>,
+    {
+        let len = window.len();
+
+        // 最新のlen個をリングバッファに保持
+        let mut count = 0usize;
+        for x in samples {
+            work[count % len] = x;
+            count += 1;
+        }
+        if count == 0 {
+            return;
+        }
+        self.run = 0;
+        self.in_run = false;
+        self.votes = 0;
+        self.missing = 0;
+        self.missing_gap = None;
+        self.flagged = 0;
+        self.seed_from_work(window, work, sorted, count);
+        let e = self.estimate_window(window, work, aux, sorted);
+        (self.median, self.scale, self.bounds) = (e.median, e.scale, self.band(&e));
+    }
+
+    /// 最新の`m`個のサンプルだけでwindowを作り直す
+    fn reseed_newest(&mut self, window: &mut [T], work: &mut [T], sorted: &mut (impl Ordered<T> + ?Sized), m: usize) {
+        let len = window.len();
+        if m >= len {
+            return;
+        }
+        let first = self.oldest + len - m;
+        for (i, w) in work[..m].iter_mut().enumerate() {
+            *w = window[(first + i) % len];
+        }
+        self.flagged = 0;  // 置換したサンプルも受け入れる
+        self.seed_from_work(window, work, sorted, m);
+    }
+
+    /// window全体を`x`で埋め直す（windowは満たされたものとする）
+    fn refill(&mut self, window: &mut [T], sorted: &mut (impl Ordered<T> + ?Sized), x: T) {
+        window.fill(x);
+        self.oldest = 0;
+        self.seen = self.seen.max(window.len());
+        self.flagged = 0;
+        (self.last, self.last_valid) = (x, x);
+        self.rebuild_sorted(window, sorted);
+    }
+
+    /// 次の更新の前の状態を写す
+    pub(crate) fn checkpoint(&self, window: &[T], gaps: &[T]) -> Checkpoint<T> {
+        Checkpoint {
+            oldest: self.oldest,
+            coef_low: self.coef_low,
+            coef_high: self.coef_high,
+            adaptation: self.adaptation,
+            seen: self.seen,
+            last: self.last,
+            last_valid: self.last_valid,
+            stats: self.stats,
+            run: self.run,
+            in_run: self.in_run,
+            votes: self.votes,
+            missing: self.missing,
+            flagged: self.flagged,
+            median: self.median,
+            scale: self.scale,
+            bounds: self.bounds,
+            nan_count: self.nan_count,
+            sorted_dirty: self.sorted_dirty,
+            evicted: window[self.oldest],
+            gap: gaps.get(self.oldest).copied().unwrap_or_else(T::one),
+        }
+    }
+
+    /// `checkpoint`で写した状態に戻す（その後の更新は1回だけで，windowを作り直していないこと）
+    pub(crate) fn rollback(&mut self, window: &mut [T], gaps: &mut [T], sorted: &mut (impl Ordered<T> + ?Sized), c: &Checkpoint<T>) {
+        let slot = c.oldest;
+        if self.oldest != slot {
+            // 上書きしたスロットを元の値に戻す（欠けたサンプルを格納しなかった場合はoldestが進んでいない）
+            let current = window[slot];
+            window[slot] = c.evicted;
+            if let Some(g) = gaps.get_mut(slot) {
+                *g = c.gap;
+            }
+            self.update_sorted(window, sorted, slot, current, c.evicted);
+        }
+        self.oldest = c.oldest;
+        self.coef_low = c.coef_low;
+        self.coef_high = c.coef_high;
+        self.adaptation = c.adaptation;
+        self.seen = c.seen;
+        self.last = c.last;
+        self.last_valid = c.last_valid;
+        self.stats = c.stats;
+        self.run = c.run;
+        self.in_run = c.in_run;
+        self.votes = c.votes;
+        self.missing = c.missing;
+        self.recount_missing_gap();
+        self.flagged = c.flagged;
+        self.median = c.median;
+        self.scale = c.scale;
+        self.bounds = c.bounds;
+        self.nan_count = c.nan_count;
+        self.sorted_dirty = c.sorted_dirty;
+    }
+
+    /// `work`にリングバッファとして格納された`count`個のサンプルでwindowを埋める
+    fn seed_from_work(&mut self, window: &mut [T], work: &[T], sorted: &mut (impl Ordered<T> + ?Sized), count: usize) {
+        let len = window.len();
+        if count >= len {
+            // 時系列順に並べ替える
+            for (i, w) in window.iter_mut().enumerate() {
+                *w = work[(count + i) % len];
+            }
+            self.oldest = 0;
+            self.seen = len;
+        } else {
+            // window[..count]に格納し，残りはそれより古いサンプルとして繰り返しで埋める
+            let pad = len - count;
+            window[..count].copy_from_slice(&work[..count]);
+            for t in 0..pad {
+                window[count + t] = work[(t + count - pad % count) % count];
+            }
+            self.oldest = count;
+            self.seen = count;
+        }
+        self.last = window[(self.oldest + len - 1) % len];
+        self.last_valid = self.last;
+        self.rebuild_sorted(window, sorted);
+    }
+
+    /// 復元した`missing`から`missing_gap`を求め直す（1サンプルごとには使わない）
+    pub(crate) fn recount_missing_gap(&mut self) {
+        self.missing_gap = (self.missing > 0).then(|| T::one() + cast::<usize, T>(self.missing).unwrap_or_else(T::infinity));
+    }
+
+    /// 設定を検証する（`WindowBuilder::try_build`とデシリアライズで同じ検証をする）
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let (n_sigma_low, n_sigma_high) = (self.n_sigma_low, self.n_sigma_high);
+        check_n_sigma(n_sigma_low)?;
+        check_n_sigma(n_sigma_high)?;
+        if !(self.scale_const > T::zero() && self.scale_const.is_finite()) {
+            return Err(ConfigError::InvalidScale);
+        }
+        if let Some(lambda) = self.forgetting {
+            if !(lambda > T::zero() && lambda <= T::one()) || self.estimator != ScaleEstimator::Mad {
+                return Err(ConfigError::InvalidForgettingFactor);
+            }
+        }
+        if self.center != Center::Median {
+            let valid = match self.center {
+                Center::Median => true,
+                Center::TrimmedMean { trim_fraction } => (0.0..0.5).contains(&trim_fraction),
+                Center::Quantile { p } => p > 0.0 && p < 1.0,
+            };
+            if !valid || self.estimator != ScaleEstimator::Mad || self.forgetting.is_some() {
+                return Err(ConfigError::InvalidCenter);
+            }
+        }
+        if self.reference == Reference::Prediction
+            && (self.center != Center::Median || self.estimator != ScaleEstimator::Mad || self.forgetting.is_some())
+        {
+            return Err(ConfigError::InvalidReference);
+        }
+        if let Some(a) = self.adaptation {
+            if !a.config.is_valid(n_sigma_low.max(n_sigma_high)) {
+                return Err(ConfigError::InvalidAdaptiveThreshold);
+            }
+        }
+        if let ZeroMad::UseFloor(floor) = self.zero_mad {
+            if floor.is_nan() || floor < T::zero() {
+                return Err(ConfigError::InvalidZeroMad);
+            }
+        }
+        if let Some(t) = self.abs_threshold {
+            if t.is_nan() || t < T::zero() {
+                return Err(ConfigError::InvalidAbsThreshold);
+            }
+        }
+        if let Some((lo, hi)) = self.output_range {
+            if lo.is_nan() || hi.is_nan() || lo > hi {
+                return Err(ConfigError::InvalidOutputRange);
+            }
+        }
+        if let Some(delta) = self.max_slew {
+            if delta.is_nan() || delta < T::zero() {
+                return Err(ConfigError::InvalidMaxSlew);
+            }
+        }
+        if let Some(rolloff) = self.soft {
+            if rolloff.is_nan() || rolloff < T::zero() {
+                return Err(ConfigError::InvalidRolloff);
+            }
+        }
+        if let Some((m, k)) = self.confirmation {
+            if !(1..=k).contains(&m) || k > 8 {
+                return Err(ConfigError::InvalidConfirmation);
+            }
+        }
+        // ヒステリシスと疑わしいサンプルの係数はscale_constを掛けたもの
+        let coef_min = precision::mul(self.scale_const, n_sigma_low.min(n_sigma_high));
+        if let Some(exit) = self.hysteresis {
+            if !(exit >= T::zero() && exit <= coef_min) {
+                return Err(ConfigError::InvalidHysteresis);
+            }
+        }
+        if let Some((coef, _)) = self.difference {
+            check_n_sigma(coef)?;
+        }
+        if let Some((coef, _)) = self.suspect {
+            if !(coef >= T::zero() && coef <= coef_min) {
+                return Err(ConfigError::InvalidSuspect);
+            }
+        }
+        Ok(())
+    }
+
+    /// デシリアライズした状態を検証し，時系列順の`window`をリングバッファの順に戻してsortedを作り直す
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore(&mut self, window: &mut [T], sorted: &mut (impl Ordered<T> + ?Sized)) -> Result<(), ConfigError> {
+        let len = window.len();
+        if len < 3 {
+            return Err(ConfigError::InvalidWindowSize);
+        }
+        if self.oldest >= len || (self.seen < len && self.oldest != self.seen) {
+            return Err(ConfigError::InvalidState);
+        }
+        self.validate()?;
+        window.rotate_right(self.oldest);
+        self.rebuild_sorted(window, sorted);
+        // シリアライズしない状態を求め直す
+        let n_sigma = |n: T| self.adaptation.map_or(n, |a| n + a.offset);
+        self.coef_low = precision::mul(self.scale_const, n_sigma(self.n_sigma_low));
+        self.coef_high = precision::mul(self.scale_const, n_sigma(self.n_sigma_high));
+        self.line = LineConstants::new(len - 1);
+        self.quantiles = QuantileConstants::new(len, self.center, self.estimator);
+        self.recount_missing_gap();
+        Ok(())
+    }
+
+    /// 統計量の計算に使うサンプル数
+    pub(crate) fn filled(&self, len: usize) -> usize {
+        if self.warm_up && self.seen < len {
+            self.seen
+        } else {
+            len
+        }
+    }
+
+    /// windowの`first`番目（リングバッファ上の位置）から`n`個のサンプルに多項式を当てはめ，
+    /// 最後のサンプルの`next_gap`後の値を外挿する
+    ///
+    /// 以前に外れ値と判定されたサンプル（直近128個まで）は当てはめに使わず，
+    /// 使えるサンプル（x座標）が2個未満なら`None`を返す
+    ///
+    /// x座標は最も古いサンプルを0として`gaps`を足し合わせたもの（`gaps`が空なら等間隔で，i番目のサンプルはi）
+    fn extrapolation(&self, window: &[T], gaps: &[T], first: usize, n: usize, next_gap: T) -> Option<T> {
+        let len = window.len();
+        // i番目のサンプルの判定はflaggedのビットn - 1 - iに記録されている（flaggedはまだ今回の分をシフトしていない）
+        let clean = |i: usize| n - 1 - i >= u128::BITS as usize || (self.flagged >> (n - 1 - i)) & 1 == 0;
+        let sample = |i: usize| if clean(i) { Some(window[(first + i) % len]) } else { None };
+        // i - 1番目とi番目のサンプルの時刻の差（i = nは外挿する点）
+        let gap = |i: usize| if i == n { next_gap } else if gaps.is_empty() { T::one() } else { gaps[(first + i) % len] };
+        // 大きな共通のオフセットを持つ信号でも総和の丸め誤差が大きくならないように，最初の使えるサンプルとの差を当てはめる
+        let offset = (0..n).find_map(sample).unwrap_or(T::zero());
+        let y = |i: usize| sample(i).map(|v| v - offset);
+
+        // 等間隔の直線の当てはめでは，x座標は使えるサンプルごとに異なり，最新のサンプルのx座標は求めてある
+        if let Some(c) = self.line.filter(|c| c.n == n && gaps.is_empty() && self.replacement == Replacement::LinearExtrapolation) {
+            let recent = if n >= u128::BITS as usize { self.flagged } else { self.flagged & ((1 << n) - 1) };
+            return match n - recent.count_ones() as usize {
+                // 外れ値と判定したサンプルが無ければ，x座標だけで決まる量も求めてある
+                _ if recent == 0 => Some(c.extrapolate(|i| window[(first + i) % len] - offset, next_gap) + offset),
+                distinct if distinct >= 2 => Some(linear_fit(n, &gap, y).at(c.last_x + next_gap) + offset),
+                _ => None,
+            };
+        }
+
+        // 精度の低い型では総和が溢れたり丸め誤差が大きくなったりするので，f32で計算する
+        if precision::is_narrow::<T>() {
+            let gap = |i| precision::widen(gap(i));
+            let offset = precision::widen(offset);
+            self.fit(n, &gap, |i| sample(i).map(|v| precision::widen(v) - offset)).map(|f| precision::narrow(f.at(next_x(n, &gap)) + offset))
+        } else {
+            self.fit(n, &gap, y).map(|f| f.at(next_x(n, &gap)) + offset)
+        }
+    }
+
+    /// `Reference::Prediction`の場合に，`extrapolation`と同じ多項式で外挿した値と，当てはめたサンプルの残差の
+    /// 絶対値の中央値を判定の基準とする（`work`の中身は書き換わる。当てはめられないサンプルが多い場合と
+    /// `Reference::Median`では`e`のまま）
+    #[allow(clippy::too_many_arguments)]
+    fn predicted(&self, window: &[T], gaps: &[T], work: &mut [T], first: usize, n: usize, next_gap: T, e: Estimate<T>) -> Estimate<T> {
+        if self.reference == Reference::Median {
+            return e;
+        }
+        let len = window.len();
+        let clean = |i: usize| n - 1 - i >= u128::BITS as usize || (self.flagged >> (n - 1 - i)) & 1 == 0;
+        let sample = |i: usize| if clean(i) { Some(window[(first + i) % len]) } else { None };
+        let gap = |i: usize| if i == n { next_gap } else if gaps.is_empty() { T::one() } else { gaps[(first + i) % len] };
+        let offset = (0..n).find_map(sample).unwrap_or(T::zero());  // extrapolationと同じく最初の使えるサンプルとの差を当てはめる
+
+        let mut k = 0;
+        let predicted = if precision::is_narrow::<T>() {
+            let gap = |i| precision::widen(gap(i));
+            let offset = precision::widen(offset);
+            let y = |i| sample(i).map(|v| precision::widen(v) - offset);
+            self.fit(n, &gap, y).map(|f| {
+                for (x, y) in points(n, &gap, &y) {
+                    work[k] = precision::narrow((y - f.at(x)).abs());
+                    k += 1;
+                }
+                precision::narrow(f.at(next_x(n, &gap)) + offset)
+            })
+        } else {
+            let y = |i| sample(i).map(|v| v - offset);
+            self.fit(n, &gap, y).map(|f| {
+                for (x, y) in points(n, &gap, &y) {
+                    work[k] = (y - f.at(x)).abs();
+                    k += 1;
+                }
+                f.at(next_x(n, &gap)) + offset
+            })
+        };
+        // 残差の中央値が当てはめたサンプルの散らばりを表すように，前のサンプルの過半数（3個以上）を使う
+        match predicted {
+            Some(p) if k >= 3 && 2 * k > n => Estimate { median: p, scale: stats::median(&mut work[..k]), lower: p, upper: p },
+            _ => e,
+        }
+    }
+
+    /// `replacement`に応じた多項式を当てはめる
+    fn fit<A: FloatCore>(&self, n: usize, gap: &impl Fn(usize) -> A, y: impl Fn(usize) -> Option<A>) -> Option<Fit<A>> {
+        fit(self.replacement, n, gap, y)
+    }
+}
+
+/// `replacement`に応じた多項式を当てはめる（x座標が3種類未満では2次式が定まらないので直線）
+pub(crate) fn fit<A: FloatCore>(replacement: Replacement, n: usize, gap: &impl Fn(usize) -> A, y: impl Fn(usize) -> Option<A>) -> Option<Fit<A>> {
+    // x座標は単調非減少なので，直前の点より大きければ新しいx座標
+    let mut distinct = 0;
+    let mut prev = None;
+    for (x, _) in points(n, gap, &y) {
+        if prev.is_none_or(|p| x > p) {
+            distinct += 1;
+        }
+        prev = Some(x);
+    }
+    if replacement == Replacement::QuadraticExtrapolation && distinct >= 3 {
+        Some(quadratic_fit(n, gap, y))
+    } else if replacement == Replacement::TheilSenExtrapolation && distinct >= 2 {
+        theil_sen_fit(n, gap, y)
+    } else if distinct >= 2 {
+        Some(linear_fit(n, gap, y))
+    } else {
+        None
+    }
+}
+
+/// 当てはめた多項式
+pub(crate) enum Fit<A> {
+    /// 直線 a x + b
+    Line { a: A, b: A },
+    /// 直交多項式による2次式 mu_y + c1 u + c2 (u² - αu - β)（u = x - mu_x）
+    Quadratic { mu_x: A, mu_y: A, c1: A, c2: A, alpha: A, beta: A },
+}
+
+impl<A: FloatCore> Fit<A> {
+    /// x座標`x`における値
+    pub(crate) fn at(&self, x: A) -> A {
+        match *self {
+            Fit::Line { a, b } => a * x + b,
+            Fit::Quadratic { mu_x, mu_y, c1, c2, alpha, beta } => {
+                let u = x - mu_x;
+                let p = u * u - alpha * u - beta;
+                mu_y + c1 * u + c2 * p
+            }
+        }
+    }
+}
+
+/// n番目（最新のサンプルの次）の点のx座標
+pub(crate) fn next_x<A: FloatCore>(n: usize, gap: &impl Fn(usize) -> A) -> A {
+    (1..=n).fold(A::zero(), |x, i| x + gap(i))
+}
+
+/// `y(i)`が`Some`であるサンプルの座標（x座標は`gap(1..=i)`の和）
+fn points<'a, A: FloatCore + 'a>(
+    n: usize,
+    gap: &'a impl Fn(usize) -> A,
+    y: &'a impl Fn(usize) -> Option<A>,
+) -> impl Iterator<Item = (A, A)> + 'a {
+    (0..n)
+        .scan(A::zero(), move |x, i| {
+            if i > 0 {
+                *x = *x + gap(i);
+            }
+            Some((*x, i))
+        })
+        .filter_map(move |(x, i)| y(i).map(|y| (x, y)))
+}
+
+/// `points`に最小二乗法で当てはめた直線（x座標は2種類以上）
+fn linear_fit<A: FloatCore>(n: usize, gap: &impl Fn(usize) -> A, y: impl Fn(usize) -> Option<A>) -> Fit<A> {
+    // x座標とwindowの平均値（外れ値を除いた平均値）
+    let mut count = A::zero();
+    let mut mu_x = A::zero();
+    let mut mu_y = A::zero();
+    for (x, y) in points(n, gap, &y) {
+        count = count + A::one();
+        mu_x = mu_x + x;
+        mu_y = mu_y + y;
+    }
+    mu_x = mu_x / count;
+    mu_y = mu_y / count;
+
+    let mut numer = A::zero();
+    let mut denom = A::zero();
+    for (x, y) in points(n, gap, &y) {
+        let dev_x = x - mu_x;
+        let dev_y = y - mu_y;
+
+        numer = numer + dev_x * dev_y;
+        denom = denom + dev_x * dev_x;
+    }
+
+    // 最小二乗法で求めた傾きと切片
+    let a = numer / denom;  // x座標は2種類以上あるのでdenom=0となることは無い
+    let b = mu_y - a * mu_x;
+
+    Fit::Line { a, b }
+}
+
+/// Theil–Sen推定に使う最新のサンプル数の上限（傾きの組は`THEIL_SEN_POINTS * (THEIL_SEN_POINTS - 1) / 2`個）
+const THEIL_SEN_POINTS: usize = 32;
+
+/// `points`の最新の`THEIL_SEN_POINTS`個にTheil–Sen推定で当てはめた直線
+///
+/// 傾きは2点を結ぶ傾きの中央値，切片は`y - a x`の中央値とする。x座標の異なる組が無ければ`None`を返す
+#[inline(never)]  // 作業領域が大きいので，呼び出し元のスタックフレームに含めない
+fn theil_sen_fit<A: FloatCore>(n: usize, gap: &impl Fn(usize) -> A, y: impl Fn(usize) -> Option<A>) -> Option<Fit<A>> {
+    const P: usize = THEIL_SEN_POINTS;
+    // 古い点から順に来るので，リングバッファに格納して最新のP個を残す（組の傾きは順序によらない）
+    let (mut xs, mut ys) = ([A::zero(); P], [A::zero(); P]);
+    let mut count = 0;
+    for (x, y) in points(n, gap, &y) {
+        (xs[count % P], ys[count % P]) = (x, y);
+        count += 1;
+    }
+    let m = count.min(P);
+
+    let mut work = [A::zero(); P * (P - 1) / 2];
+    let mut k = 0;
+    for i in 0..m {
+        for j in (i + 1)..m {
+            if xs[i] != xs[j] {
+                work[k] = (ys[j] - ys[i]) / (xs[j] - xs[i]);
+                k += 1;
+            }
+        }
+    }
+    if k == 0 {
+        return None;
+    }
+    let a = stats::median(&mut work[..k]);
+    for (w, (x, y)) in work.iter_mut().zip(xs.iter().zip(&ys)).take(m) {
+        *w = *y - a * *x;
+    }
+    let b = stats::median(&mut work[..m]);
+
+    Some(Fit::Line { a, b })
+}
+
+/// `points`に最小二乗法で当てはめた2次式（x座標は3種類以上）
+///
+/// 直交多項式 1, u, u² - αu - β（u = x - mu_x）で展開すると，正規方程式を解かずに各係数が独立に求まる
+fn quadratic_fit<A: FloatCore>(n: usize, gap: &impl Fn(usize) -> A, y: impl Fn(usize) -> Option<A>) -> Fit<A> {
+    let mut count = A::zero();
+    let mut mu_x = A::zero();
+    let mut mu_y = A::zero();
+    for (x, y) in points(n, gap, &y) {
+        count = count + A::one();
+        mu_x = mu_x + x;
+        mu_y = mu_y + y;
+    }
+    mu_x = mu_x / count;
+    mu_y = mu_y / count;
+
+    // 1とuに直交するようにαとβを決める（x座標が等間隔ならα = 0）
+    let mut m2 = A::zero();
+    let mut m3 = A::zero();
+    for (x, _) in points(n, gap, &y) {
+        let u = x - mu_x;
+        m2 = m2 + u * u;
+        m3 = m3 + u * u * u;
+    }
+    let alpha = m3 / m2;
+    let beta = m2 / count;
+
+    let mut numer1 = A::zero();
+    let mut numer2 = A::zero();
+    let mut denom2 = A::zero();
+    for (x, y) in points(n, gap, &y) {
+        let u = x - mu_x;
+        let p = u * u - alpha * u - beta;
+
+        numer1 = numer1 + u * y;
+        numer2 = numer2 + p * y;
+        denom2 = denom2 + p * p;
+    }
+
+    Fit::Quadratic { mu_x, mu_y, c1: numer1 / m2, c2: numer2 / denom2, alpha, beta }  // x座標は3種類以上あるのでdenom2 > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Core;
+
+    fn with_confirmation(m: u8, k: u8) -> Core<f64> {
+        let mut core = Core::new_const(0.0, 3.0, 1.4826, 1.4826 * 3.0, 0.0, 5, 0);
+        core.confirmation = Some((m, k));
+        core
+    }
+
+    #[test]
+    fn confirmation_in_range() {
+        let core = with_confirmation(2, 3);
+        assert!(!core.confirmed(0b0001));
+        assert!(core.confirmed(0b0011));
+        assert!(core.confirmed(0b0101));
+        assert!(!core.confirmed(0b1001));  // 4個前は数えない
+    }
+
+    #[test]
+    fn confirmation_out_of_range_does_not_panic() {
+        // k = 0: 直近の判定を1つも数えない
+        assert!(!with_confirmation(1, 0).confirmed(u8::MAX));
+        assert!(with_confirmation(0, 0).confirmed(0));
+        // k > 8: 8個すべてを数える
+        assert!(with_confirmation(8, 9).confirmed(u8::MAX));
+        assert!(!with_confirmation(8, u8::MAX).confirmed(0b0111_1111));
+    }
+}
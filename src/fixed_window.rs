@@ -0,0 +1,216 @@
+//! Window for fixed-point numbers of the `fixed` crate (`fixed` feature).
+
+use fixed::traits::Fixed;
+
+use crate::{exact::{Codec, ExactCore, GAUSSIAN_SCALE_Q32}, iter::Samples, ConfigError, OutlierStats, Replacement, Sample};
+
+/// 固定小数点数のビット列をそのまま整数として扱う
+pub(crate) struct FixedCodec;
+
+impl<F: Fixed> Codec<F> for FixedCodec {
+    #[inline]
+    fn widen(x: F) -> i128 {
+        x.to_bits().try_into().ok().unwrap()  // 32ビット以下なので必ず変換できる
+    }
+
+    #[inline]
+    fn narrow(v: i128) -> F {
+        let v = v.clamp(Self::widen(F::MIN), Self::widen(F::MAX));
+        F::from_bits(v.try_into().ok().unwrap())
+    }
+    #[inline]
+    fn unit() -> i128 {
+        1 << F::FRAC_NBITS
+    }
+}
+
+/// Window of Hampel filter for fixed-point numbers of the `fixed` crate (e.g. `I16F16`, `I8F24`),
+/// for targets without an FPU.
+///
+/// * `WINDOW_SIZE` >= 3
+/// * `F`: a fixed-point type of at most 32 bits
+///
+/// No floating-point arithmetic is used. The coefficient `1.4826 * n_sigma` is precomputed
+/// as a fixed-point constant on construction, and the decision `|x - w0| > 1.4826 * n_sigma * s0`
+/// is made with widening (128-bit) integer arithmetic, so that it is exact and cannot overflow
+/// even for full-scale deviations. The medians of even window sizes are also computed without rounding.
+///
+/// The decisions are the same as [`Window`](crate::Window) with the values converted to a float,
+/// except within the rounding error of the coefficient (relative `2^-32`) from the threshold.
+/// The median and the MAD of [`Sample`] are rounded down to the resolution of `F`,
+/// and the extrapolated values are rounded to the nearest (saturating at the range of `F`),
+/// so that they are within one LSB of the float results:
+///
+/// ```
+/// use fixed::types::I16F16;
+/// use hampel::{FixedWindow, Replacement, Window};
+///
+/// let n_sigma = I16F16::from_num(3);
+/// let mut a = FixedWindow::<I16F16, 5>::with_replacement(I16F16::ZERO, n_sigma, Replacement::Median);
+/// let mut b = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+/// let lsb = I16F16::DELTA.to_num::<f64>();
+///
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     let x = I16F16::from_num(x);
+///     let (sa, sb) = (a.update_detailed(x), b.update_detailed(x.to_num()));
+///     assert!((sa.median.to_num::<f64>() - sb.median).abs() < lsb);
+///     assert!((sa.mad.to_num::<f64>() - sb.mad).abs() < lsb);
+///     assert_eq!(sa.is_outlier, sb.is_outlier);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct FixedWindow<F: Fixed, const WINDOW_SIZE: usize> {
+    pub(crate) window: [F; WINDOW_SIZE],
+    pub(crate) sorted: [F; WINDOW_SIZE],  // windowを昇順に並べたもの
+    pub(crate) core: ExactCore<F>,
+}
+
+impl<F: Fixed, const WINDOW_SIZE: usize> FixedWindow<F, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+    /// `F`が32ビット以下であることをコンパイル時に確かめる
+    const VALID_TYPE: () = assert!(F::INT_NBITS + F::FRAC_NBITS <= 32, "fixed-point types of more than 32 bits are not supported");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// Outliers are replaced as specified by `Replacement::default()`.
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::FixedWindow::<fixed::types::I16F16, 2>::new(fixed::types::I16F16::ZERO, fixed::types::I16F16::from_num(3));
+    /// ```
+    ///
+    /// So is a fixed-point type of more than 32 bits:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::FixedWindow::<fixed::types::I32F32, 5>::new(fixed::types::I32F32::ZERO, fixed::types::I32F32::from_num(3));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not positive (`WINDOW_SIZE` < 3 and `F` of more than 32 bits are rejected at compile time).
+    pub fn new(init_val: F, n_sigma: F) -> Self {
+        Self::with_replacement(init_val, n_sigma, Replacement::default())
+    }
+
+    /// Same as [`new`](Self::new), but outliers are replaced as specified by `replacement`.
+    ///
+    /// [`Replacement::LinearExtrapolation`] fits the line in exact integer arithmetic:
+    ///
+    /// ```
+    /// use fixed::types::I8F24;
+    /// use hampel::{FixedWindow, Replacement, Window};
+    ///
+    /// let mut a = FixedWindow::<I8F24, 6>::with_replacement(I8F24::ZERO, I8F24::from_num(2), Replacement::LinearExtrapolation);
+    /// let mut b = Window::<f64, 6>::with_replacement(0.0, 2.0, Replacement::LinearExtrapolation);
+    /// let lsb = I8F24::DELTA.to_num::<f64>();
+    /// let mut detected = 0;
+    /// for i in 0..5000 {
+    ///     let x = (i as f64 * 0.05).sin() * 50.0 + if i % 13 == 0 { 70.0 } else { 0.0 };
+    ///     let x = I8F24::from_num(x);
+    ///     let (sa, sb) = (a.update_detailed(x), b.update_detailed(x.to_num()));
+    ///     assert_eq!(sa.is_outlier, sb.is_outlier);
+    ///     assert!((sa.value.to_num::<f64>() - sb.value).abs() < lsb);
+    ///     detected += sa.is_outlier as usize;
+    /// }
+    /// assert!(detected >= 5000 / 13);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_replacement(init_val: F, n_sigma: F, replacement: Replacement) -> Self {
+        let () = Self::VALID_SIZE;
+        let () = Self::VALID_TYPE;
+        let n_sigma = FixedCodec::widen(n_sigma);
+        // 固定小数点数は常に有限なので，正であれば`filter::check_n_sigma`と同じ（浮動小数点数の演算を使わない）
+        if n_sigma <= 0 {
+            panic!("{}", ConfigError::InvalidNSigma);
+        }
+
+        // coef_num / coef_den = 1.4826 * n_sigma
+        let coef_num = GAUSSIAN_SCALE_Q32 * n_sigma;
+        let coef_den = 1i128 << (32 + F::FRAC_NBITS);
+        Self {
+            window: [init_val; WINDOW_SIZE],
+            sorted: [init_val; WINDOW_SIZE],
+            core: ExactCore::new::<FixedCodec>(init_val, coef_num, coef_den, replacement),
+        }
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: F) -> F {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    ///
+    /// `absolute` of the result is always `false`.
+    pub fn update_detailed(&mut self, x: F) -> Sample<F> {
+        self.core.update::<FixedCodec>(&mut self.window, &mut self.sorted, x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: F) {
+        self.core.reset::<FixedCodec>(&mut self.window, &mut self.sorted, init_val);
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// See [`Window::median`](crate::Window::median) (rounded down to the resolution of `F`).
+    pub fn median(&self) -> F {
+        self.core.median
+    }
+
+    /// See [`Window::mad`](crate::Window::mad) (rounded down to the resolution of `F`).
+    pub fn mad(&self) -> F {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds) (the smallest and the largest accepted values of `F`).
+    pub fn bounds(&self) -> (F, F) {
+        self.core.bounds
+    }
+
+    /// See [`Window::iter`](crate::Window::iter).
+    pub fn iter(&self) -> Samples<'_, F> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> F {
+        self.core.latest(&self.window)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Same as [`Window`](crate::Window).
+impl<F: Fixed, const WINDOW_SIZE: usize> PartialEq for FixedWindow<F, WINDOW_SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.core == other.core && self.iter().eq(other.iter())
+    }
+}
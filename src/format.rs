@@ -0,0 +1,471 @@
+//! `Debug` and `defmt::Format` (`defmt` feature) for the windows.
+//!
+//! The window is printed in chronological order (oldest first), not in the order of the ring buffer.
+//! Windows longer than `MAX_SHOWN` samples are shortened to the oldest and the newest `MAX_SHOWN / 2`
+//! samples with an ellipsis between them, so that the output stays readable for large windows.
+
+use core::fmt;
+
+use num_traits::{float::FloatCore, PrimInt, Signed};
+
+use crate::{exact::ExactCore, CenteredWindow, DecimatingWindow, DualWindow, filter::{Chronological, Core}, IntWindow, LargeWindow, MedianWindow, QuantileWindow, SmoothedWindow, Window, WindowBank, WindowMut, ZScoreWindow};
+
+/// 省略せずに表示する要素数の上限
+const MAX_SHOWN: usize = 16;
+
+impl<'a, T> Chronological<'a, T> {
+    /// 表示する要素（省略する場合は古い方と新しい方の`MAX_SHOWN / 2`個ずつ）
+    fn shown(&self) -> (impl Iterator<Item = &'a T>, Option<impl Iterator<Item = &'a T>>) {
+        let len = self.window.len();
+        if len <= MAX_SHOWN {
+            (self.iter().take(len), None)
+        } else {
+            (self.iter().take(MAX_SHOWN / 2), Some(self.iter().skip(len - MAX_SHOWN / 2)))
+        }
+    }
+}
+
+/// 省略記号
+struct Ellipsis;
+
+impl fmt::Debug for Ellipsis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("...")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Chronological<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (head, tail) = self.shown();
+        let mut list = f.debug_list();
+        list.entries(head);
+        if let Some(tail) = tail {
+            list.entry(&Ellipsis).entries(tail);
+        }
+        list.finish()
+    }
+}
+
+fn debug_window<T: FloatCore + fmt::Debug>(
+    f: &mut fmt::Formatter,
+    name: &str,
+    window: &[T],
+    core: &Core<T>,
+) -> fmt::Result {
+    f.debug_struct(name)
+        .field("window", &Chronological { window, oldest: core.oldest })
+        .field("oldest", &core.oldest)
+        .field("coef_low", &core.coef_low)
+        .field("coef_high", &core.coef_high)
+        .field("seen", &core.seen)
+        .finish()
+}
+
+/// The window is printed in chronological order (oldest first) along with the index of
+/// the oldest sample in the ring buffer, the coefficients of the threshold and
+/// the number of samples received. The attached handler is not printed.
+///
+/// Windows longer than 16 samples are shortened to the 8 oldest and the 8 newest samples.
+///
+/// ```
+/// use hampel::Window;
+///
+/// let mut filter = Window::<f32, 5>::new(0.0, 3.0);
+/// for x in [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0] {
+///     filter.update(x);
+/// }
+/// let s = format!("{:?}", filter);
+/// assert!(s.starts_with("Window { window: [3.0, 4.0, 5.0, 6.0, 7.0], oldest: 2, "), "{}", s);
+///
+/// let mut filter = Window::<f32, 20>::new(0.0, 3.0);
+/// for i in 0..30 {
+///     filter.update(i as f32);
+/// }
+/// let s = format!("{:?}", filter);
+/// assert!(s.starts_with(
+///     "Window { window: [10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, ..., \
+///     22.0, 23.0, 24.0, 25.0, 26.0, 27.0, 28.0, 29.0], oldest: 10, "
+/// ), "{}", s);
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize, H> fmt::Debug for Window<T, WINDOW_SIZE, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "Window", &self.window, &self.core)
+    }
+}
+
+/// Same as [`Window`].
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for LargeWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "LargeWindow", &self.window, &self.core)
+    }
+}
+
+/// Same as [`Window`], with the window of the scale estimate.
+impl<T: FloatCore + fmt::Debug, const MEDIAN_WIN: usize, const SCALE_WIN: usize> fmt::Debug for DualWindow<T, MEDIAN_WIN, SCALE_WIN> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "DualWindow", &self.window, &self.core)
+    }
+}
+
+/// Same as [`Window`].
+impl<T: FloatCore + fmt::Debug> fmt::Debug for WindowMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "WindowMut", self.window, &self.core)
+    }
+}
+
+/// Same as [`Window`].
+#[cfg(feature = "alloc")]
+impl<T: FloatCore + fmt::Debug> fmt::Debug for crate::DynWindow<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "DynWindow", &self.window, &self.core)
+    }
+}
+
+impl<T, const WINDOW_SIZE: usize, const CHANNELS: usize> fmt::Debug for WindowBank<T, WINDOW_SIZE, CHANNELS>
+where
+    T: FloatCore + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WindowBank")
+            .field("windows", &self.windows)
+            .field("decision", &self.decision)
+            .field("joint_threshold", &self.joint_threshold)
+            .finish()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T, const WINDOW_SIZE: usize, const D: usize> fmt::Debug for crate::VectorWindow<T, WINDOW_SIZE, D>
+where
+    T: FloatCore + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VectorWindow")
+            .field("windows", &self.windows)
+            .finish()
+    }
+}
+
+/// The samples received (at most the last `WINDOW_SIZE`) are printed in chronological order,
+/// along with the number of samples that have not been returned yet.
+///
+/// ```
+/// use hampel::CenteredWindow;
+///
+/// let mut filter = CenteredWindow::<f32, 5>::new(2.0);
+/// for x in [1.0, 2.0, 3.0] {
+///     filter.update(x);
+/// }
+/// assert_eq!(format!("{:?}", filter), "CenteredWindow { window: [1.0, 2.0, 3.0], pending: 2, coef: 2.9652 }");
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for CenteredWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CenteredWindow")
+            .field("window", &Chronological { window: &self.window[..self.len], oldest: self.oldest })
+            .field("pending", &self.pending)
+            .field("coef", &self.coef)
+            .finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: FloatCore + fmt::Debug> fmt::Debug for crate::ChunkedFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkedFilter")
+            .field("buf", &self.buf)
+            .field("start", &self.start)
+            .field("next", &self.next)
+            .field("half_window", &self.half_window)
+            .field("coef", &self.coef)
+            .field("edge", &self.edge)
+            .finish()
+    }
+}
+
+/// The window is printed as [`Window`], along with the aggregation and the number of samples in the current period.
+///
+/// ```
+/// use hampel::{Aggregation, DecimatingWindow};
+///
+/// let mut filter = DecimatingWindow::<f32, 3, 4>::new(0.0, 3.0, Aggregation::Median);
+/// filter.update(2.0);
+/// assert_eq!(
+///     format!("{:?}", filter),
+///     "DecimatingWindow { window: Window { window: [0.0, 0.0, 2.0], oldest: 1, coef_low: 4.4477997, coef_high: 4.4477997, seen: 1 }, \
+///      aggregation: Median, pending: 1 }"
+/// );
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize, const FACTOR: usize> fmt::Debug for DecimatingWindow<T, WINDOW_SIZE, FACTOR> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DecimatingWindow")
+            .field("window", &self.window)
+            .field("aggregation", &self.aggregation)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+/// The window is printed as [`Window`], along with the smoothing factor and the smoothed value.
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for SmoothedWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SmoothedWindow")
+            .field("window", &self.window)
+            .field("alpha", &self.alpha)
+            .field("smoothed", &self.smoothed)
+            .finish()
+    }
+}
+
+/// The complex samples are printed in chronological order, along with the window of the magnitudes.
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::ComplexWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ComplexWindow")
+            .field("samples", &Chronological { window: &self.samples, oldest: self.oldest })
+            .field("window", &self.window)
+            .field("replacement", &self.replacement)
+            .finish()
+    }
+}
+
+/// The window is printed as [`Window`], along with the tags in chronological order.
+impl<T: FloatCore + fmt::Debug, U: Copy + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::TaggedWindow<T, U, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TaggedWindow")
+            .field("window", &self.window)
+            .field("tags", &Chronological { window: &self.tags, oldest: self.oldest })
+            .finish()
+    }
+}
+
+/// The `(value, weight)` pairs are printed in chronological order, along with the statistics of the last update.
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::WeightedWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WeightedWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("median", &self.median())
+            .field("mad", &self.mad())
+            .field("last_outlier", &self.last_outlier)
+            .finish()
+    }
+}
+
+/// The samples are printed in chronological order, along with the statistics of the last update.
+impl<P: Copy + fmt::Debug, T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::MetricWindow<P, T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MetricWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("medoid", &self.medoid())
+            .field("scale", &self.scale())
+            .field("last_outlier", &self.last_outlier)
+            .finish()
+    }
+}
+
+/// The window of the transformed samples is printed as [`Window`], along with the policy.
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::LogWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LogWindow")
+            .field("window", &self.window)
+            .field("policy", &self.policy)
+            .field("last_outlier", &self.last_outlier)
+            .finish()
+    }
+}
+
+/// The window is printed in chronological order, along with the period and the replacement.
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::AngularWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AngularWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("period", &self.period)
+            .field("replacement", &self.replacement)
+            .field("last_outlier", &self.last_outlier)
+            .finish()
+    }
+}
+
+/// The window is printed in chronological order, along with the number of samples received.
+///
+/// ```
+/// use hampel::MedianWindow;
+///
+/// let mut filter = MedianWindow::<f64, 3>::new(0.0);
+/// filter.update(2.0);
+/// assert_eq!(format!("{:?}", filter), "MedianWindow { window: [0.0, 0.0, 2.0], seen: 1 }");
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for MedianWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MedianWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("seen", &self.seen)
+            .finish()
+    }
+}
+
+/// The window is printed in chronological order, along with the probability and the number of samples received.
+///
+/// ```
+/// use hampel::QuantileWindow;
+///
+/// let mut filter = QuantileWindow::<f64, 3>::with_p(0.0, 0.9);
+/// filter.update(2.0);
+/// assert_eq!(format!("{:?}", filter), "QuantileWindow { window: [0.0, 0.0, 2.0], p: 0.9, seen: 1 }");
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for QuantileWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuantileWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("p", &self.p)
+            .field("seen", &self.seen)
+            .finish()
+    }
+}
+
+/// The window is printed in chronological order, along with the threshold and the last decision.
+///
+/// ```
+/// use hampel::ZScoreWindow;
+///
+/// let mut filter = ZScoreWindow::<f64, 3>::new(0.0, 3.0);
+/// filter.update(2.0);
+/// assert_eq!(format!("{:?}", filter), "ZScoreWindow { window: [0.0, 0.0, 2.0], n_sigma: 3.0, last_outlier: true }");
+/// ```
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for ZScoreWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ZScoreWindow")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("n_sigma", &self.n_sigma)
+            .field("last_outlier", &self.last_outlier)
+            .finish()
+    }
+}
+
+fn debug_exact<T: fmt::Debug>(f: &mut fmt::Formatter, name: &str, window: &[T], core: &ExactCore<T>) -> fmt::Result {
+    f.debug_struct(name)
+        .field("window", &Chronological { window, oldest: core.oldest })
+        .field("oldest", &core.oldest)
+        .field("coef_num", &core.coef_num)
+        .field("coef_den", &core.coef_den)
+        .field("seen", &core.seen)
+        .finish()
+}
+
+/// Same as [`Window`], but the coefficient of the threshold is printed as a fraction.
+///
+/// ```
+/// use hampel::IntWindow;
+///
+/// let mut filter = IntWindow::<i16, 3>::new(0, 3, 1);
+/// filter.update(7);
+/// assert_eq!(
+///     format!("{:?}", filter),
+///     "IntWindow { window: [0, 0, 7], oldest: 1, coef_num: 19103155539, coef_den: 4294967296, seen: 1 }"
+/// );
+/// ```
+impl<T: PrimInt + Signed + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for IntWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_exact(f, "IntWindow", &self.window, &self.core)
+    }
+}
+
+/// Same as [`IntWindow`].
+#[cfg(feature = "fixed")]
+impl<F: fixed::traits::Fixed, const WINDOW_SIZE: usize> fmt::Debug for crate::FixedWindow<F, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_exact(f, "FixedWindow", &self.window, &self.core)
+    }
+}
+
+/// Same as [`Window`].
+#[cfg(feature = "ordered-float")]
+impl<T: FloatCore + fmt::Debug, const WINDOW_SIZE: usize> fmt::Debug for crate::NotNanWindow<T, WINDOW_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_window(f, "NotNanWindow", &self.window.window, &self.window.core)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<const WINDOW_SIZE: usize, const CHANNELS: usize> fmt::Debug for crate::SimdBank<WINDOW_SIZE, CHANNELS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SimdBank")
+            .field("window", &Chronological { window: &self.window, oldest: self.oldest })
+            .field("oldest", &self.oldest)
+            .field("coef", &self.coef)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format> defmt::Format for Chronological<'_, T> {
+    fn format(&self, f: defmt::Formatter) {
+        let (head, tail) = self.shown();
+        defmt::write!(f, "[");
+        for (i, x) in head.enumerate() {
+            if i > 0 {
+                defmt::write!(f, ", ");
+            }
+            defmt::write!(f, "{}", x);
+        }
+        if let Some(tail) = tail {
+            defmt::write!(f, ", ...");
+            for x in tail {
+                defmt::write!(f, ", {}", x);
+            }
+        }
+        defmt::write!(f, "]");
+    }
+}
+
+#[cfg(feature = "defmt")]
+fn format_window<T: FloatCore + defmt::Format>(f: defmt::Formatter, name: &str, window: &[T], core: &Core<T>) {
+    defmt::write!(
+        f,
+        "{=str} {{ window: {}, oldest: {=usize}, coef_low: {}, coef_high: {}, seen: {=usize} }}",
+        name,
+        Chronological { window, oldest: core.oldest },
+        core.oldest,
+        core.coef_low,
+        core.coef_high,
+        core.seen,
+    );
+}
+
+/// Same output as [`Debug`](fmt::Debug).
+#[cfg(feature = "defmt")]
+impl<T: FloatCore + defmt::Format, const WINDOW_SIZE: usize, H> defmt::Format for Window<T, WINDOW_SIZE, H> {
+    fn format(&self, f: defmt::Formatter) {
+        format_window(f, "Window", &self.window, &self.core)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: FloatCore + defmt::Format, const WINDOW_SIZE: usize> defmt::Format for LargeWindow<T, WINDOW_SIZE> {
+    fn format(&self, f: defmt::Formatter) {
+        format_window(f, "LargeWindow", &self.window, &self.core)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: FloatCore + defmt::Format> defmt::Format for WindowMut<'_, T> {
+    fn format(&self, f: defmt::Formatter) {
+        format_window(f, "WindowMut", self.window, &self.core)
+    }
+}
+
+#[cfg(all(feature = "defmt", feature = "alloc"))]
+impl<T: FloatCore + defmt::Format> defmt::Format for crate::DynWindow<T> {
+    fn format(&self, f: defmt::Formatter) {
+        format_window(f, "DynWindow", &self.window, &self.core)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T, const WINDOW_SIZE: usize, const CHANNELS: usize> defmt::Format for WindowBank<T, WINDOW_SIZE, CHANNELS>
+where
+    T: FloatCore + defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "WindowBank {{ windows: {}, decision: {}, joint_threshold: {} }}", self.windows, self.decision, self.joint_threshold);
+    }
+}
@@ -0,0 +1,130 @@
+//! Offline Hampel filter over a 2-D grid (e.g. the frame of a thermopile array).
+
+use num_traits::float::FloatCore;
+
+use crate::offline::{coefficient, judge};
+
+/// Maximum `radius` of [`despike_grid`] and [`despike_grid_into`].
+///
+/// The neighborhood (at most `(2 * radius + 1)^2` cells) is copied to a buffer on the stack,
+/// so its size is bounded.
+pub const MAX_GRID_RADIUS: usize = 3;
+
+/// Maximum `width` of [`despike_grid`].
+///
+/// The outputs are written back after the original values are no longer needed by the neighborhoods,
+/// and the pending outputs (at most `MAX_GRID_RADIUS * MAX_GRID_WIDTH + MAX_GRID_RADIUS + 1` cells)
+/// are kept in a buffer on the stack. [`despike_grid_into`] has no limit.
+pub const MAX_GRID_WIDTH: usize = 64;
+
+const MAX_NEIGHBORHOOD: usize = (2 * MAX_GRID_RADIUS + 1) * (2 * MAX_GRID_RADIUS + 1);
+
+const MAX_PENDING: usize = MAX_GRID_RADIUS * MAX_GRID_WIDTH + MAX_GRID_RADIUS + 1;
+
+/// 2-D Hampel filter on a row-major grid of `width * height` cells, applied in place.
+///
+/// For each cell, the median `w0` and the MAD `s0` of its neighborhood of `(2 * radius + 1)^2` cells
+/// (including itself) are computed, and the cell is replaced by `w0` if `|x - w0| > 1.4826 * n_sigma * s0`,
+/// as [`filter_slice`](crate::filter_slice) does in 1-D.
+/// All neighborhoods are taken from the original data, and at the borders, they are clipped to the grid.
+///
+/// ```
+/// use hampel::despike_grid;
+///
+/// // 8x8 frame with a gentle gradient (°C)
+/// let frame = |r: usize, c: usize| 24.0 + 0.25 * r as f64 + 0.125 * c as f64;
+/// let mut data: Vec<f64> = (0..64).map(|i| frame(i / 8, i % 8)).collect();
+///
+/// // A hot pixel, a 2x2 hot cluster, and a hot pixel in the corner
+/// let hot = [(1, 2), (4, 5), (4, 6), (5, 5), (5, 6), (7, 0)];
+/// for &(r, c) in &hot {
+///     data[r * 8 + c] += 40.0;
+/// }
+/// despike_grid(&mut data, 8, 8, 1, 3.0);
+/// for r in 0..8 {
+///     for c in 0..8 {
+///         assert!((data[r * 8 + c] - frame(r, c)).abs() < 0.5, "({}, {}): {}", r, c, data[r * 8 + c]);
+///         if !hot.contains(&(r, c)) {
+///             assert_eq!(data[r * 8 + c], frame(r, c));
+///         }
+///     }
+/// }
+///
+/// // A sharp edge (e.g. a warm object in the field of view) is not smeared
+/// let edge = |r: usize, c: usize| if c >= 3 + r / 4 { 31.0 } else { 22.0 } + 0.01 * ((r * 8 + c) % 3) as f64;
+/// let original: Vec<f64> = (0..64).map(|i| edge(i / 8, i % 8)).collect();
+/// for radius in 1..=3 {
+///     let mut data = original.clone();
+///     despike_grid(&mut data, 8, 8, radius, 3.0);
+///     assert_eq!(data, original);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `data.len() != width * height`, `radius` > [`MAX_GRID_RADIUS`], or `width` > [`MAX_GRID_WIDTH`].
+pub fn despike_grid<T: FloatCore>(data: &mut [T], width: usize, height: usize, radius: usize, n_sigma: T) {
+    assert_eq!(data.len(), width * height, "data must have width * height cells");
+    assert!(radius <= MAX_GRID_RADIUS, "radius must be at most {}", MAX_GRID_RADIUS);
+    assert!(width <= MAX_GRID_WIDTH, "width must be at most {}", MAX_GRID_WIDTH);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_NEIGHBORHOOD];
+
+    // data[j]の元の値はi = j + radius * width + radiusまで参照されるので，
+    // 出力はその数 + 1個だけ保留してから書き込む
+    let cap = radius * width + radius + 1;
+    let mut pending = [T::zero(); MAX_PENDING];
+    for i in 0..data.len() {
+        if i >= cap {
+            data[i - cap] = pending[i % cap];
+        }
+        pending[i % cap] = despiked(data, width, height, i, radius, coef, &mut work);
+    }
+    for j in data.len().saturating_sub(cap)..data.len() {
+        data[j] = pending[j % cap];
+    }
+}
+
+/// Same as [`despike_grid`], but the result is written to `dst` and `src` is left unchanged.
+///
+/// ```
+/// use hampel::{despike_grid, despike_grid_into};
+///
+/// let src: Vec<f32> = (0..24 * 32).map(|i| ((i % 24) as f32 * 0.3).sin() + if i % 37 == 5 { 9.0 } else { 0.0 }).collect();
+/// let mut dst = vec![0.0; src.len()];
+/// despike_grid_into(&src, &mut dst, 24, 32, 2, 3.0);
+///
+/// let mut data = src.clone();
+/// despike_grid(&mut data, 24, 32, 2, 3.0);
+/// assert_eq!(data, dst);
+/// assert!(dst.iter().all(|v| v.abs() <= 1.0));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src` or `dst` does not have `width * height` cells, or `radius` > [`MAX_GRID_RADIUS`].
+pub fn despike_grid_into<T: FloatCore>(src: &[T], dst: &mut [T], width: usize, height: usize, radius: usize, n_sigma: T) {
+    assert_eq!(src.len(), width * height, "src must have width * height cells");
+    assert_eq!(dst.len(), src.len(), "src and dst must have the same length");
+    assert!(radius <= MAX_GRID_RADIUS, "radius must be at most {}", MAX_GRID_RADIUS);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_NEIGHBORHOOD];
+
+    for (i, y) in dst.iter_mut().enumerate() {
+        *y = despiked(src, width, height, i, radius, coef, &mut work);
+    }
+}
+
+/// `src[i]`の近傍（グリッドの端では切り詰める）で判定した出力値
+fn despiked<T: FloatCore>(src: &[T], width: usize, height: usize, i: usize, radius: usize, coef: T, work: &mut [T]) -> T {
+    let (r, c) = (i / width, i % width);
+    let rows = r.saturating_sub(radius)..height.min(r + radius + 1);
+    let cols = c.saturating_sub(radius)..width.min(c + radius + 1);
+    let mut n = 0;
+    for row in rows {
+        let line = &src[row * width + cols.start..row * width + cols.end];
+        work[n..n + line.len()].copy_from_slice(line);
+        n += line.len();
+    }
+    judge(src[i], &mut work[..n], coef)
+}
@@ -0,0 +1,148 @@
+//! Recent decisions of a filter, kept for post-mortem diagnostics.
+
+use num_traits::float::FloatCore;
+
+use crate::{OutlierEvent, OutlierHandler, Sample};
+
+/// One update recorded by a [`History`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Record<T> {
+    /// Input value (NaN for a missing sample, see [`Window::update_missing`](crate::Window::update_missing)).
+    pub raw: T,
+    /// Value returned by the update.
+    pub value: T,
+    /// Whether the input value was determined to be an outlier.
+    pub is_outlier: bool,
+    /// Median of the window used for the decision.
+    pub median: T,
+    /// Scale estimate of the window used for the decision (see [`Sample::mad`]).
+    pub mad: T,
+}
+
+/// Ring buffer of the last `K` updates of a [`Window`](crate::Window), attached by
+/// [`Window::with_history`](crate::Window::with_history).
+///
+/// The records are plain `Copy` structs in a fixed array (`#[repr(C)]`), so that the whole history
+/// can be copied out of a memory dump by a debugger. A window without a history does not store anything.
+///
+/// ```
+/// use hampel::{Replacement, Window};
+///
+/// let mut filter = Window::<f64, 7>::builder()
+///     .replacement(Replacement::Median)
+///     .undoable(true)
+///     .build()
+///     .with_history::<32>();
+/// let spikes = [75, 84, 96];
+/// for i in 0..100 {
+///     let noise = ((i * 7919) % 13) as f64 * 0.1;
+///     let x = noise + if spikes.contains(&i) { 10.0 } else { 0.0 };
+///     filter.update(x);
+/// }
+///
+/// let records: Vec<_> = filter.history().copied().collect();
+/// assert_eq!(records.len(), 32);
+/// for (r, i) in records.iter().zip(68..100) {
+///     let noise = ((i * 7919) % 13) as f64 * 0.1;
+///     assert_eq!(r.raw, noise + if spikes.contains(&i) { 10.0 } else { 0.0 });
+///     assert_eq!(r.is_outlier, spikes.contains(&i));
+///     assert_eq!(r.value, if r.is_outlier { r.median } else { r.raw });
+/// }
+///
+/// // An undone update is removed (the record it overwrote is not restored)
+/// filter.update(1.0);
+/// assert!(filter.undo_last_update());
+/// assert!(filter.history().eq(&records[1..]));
+///
+/// filter.clear_history();
+/// assert_eq!(filter.history().count(), 0);
+/// ```
+///
+/// The record of an undone update (see [`Window::undo_last_update`](crate::Window::undo_last_update))
+/// is removed, so a full history holds one record fewer. [`Window::reset`](crate::Window::reset) keeps the records.
+/// [`Window::with_history`](crate::Window::with_history) keeps the handler attached before by
+/// [`Window::on_outlier`](crate::Window::on_outlier), and invokes both.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct History<T, const K: usize> {
+    records: [Record<T>; K],
+    next: usize,  // 次に書き込むインデックス
+    len: usize,  // 記録した数（K以下）
+}
+
+impl<T: FloatCore, const K: usize> History<T, K> {
+    /// Empty history.
+    pub fn new() -> Self {
+        let zero = T::zero();
+        let record = Record { raw: zero, value: zero, is_outlier: false, median: zero, mad: zero };
+        Self { records: [record; K], next: 0, len: 0 }
+    }
+
+    /// Iterator over the records, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Record<T>> + '_ {
+        let start = (self.next + K - self.len) % K.max(1);
+        (0..self.len).map(move |i| &self.records[(start + i) % K])
+    }
+
+    /// Newest record.
+    pub fn latest(&self) -> Option<&Record<T>> {
+        self.iter().last()
+    }
+
+    /// Discard all the records.
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Number of records (at most `K`).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is no record.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maximum number of records (`K`).
+    pub fn capacity(&self) -> usize {
+        K
+    }
+}
+
+impl<T: FloatCore, const K: usize> Default for History<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FloatCore, const K: usize> OutlierHandler<T> for History<T, K> {
+    #[inline]
+    fn on_outlier(&mut self, _: OutlierEvent<T>) {}
+
+    fn on_update(&mut self, raw: T, sample: &Sample<T>) {
+        if K == 0 {
+            return;
+        }
+        self.records[self.next] = Record {
+            raw,
+            value: sample.value,
+            is_outlier: sample.is_outlier,
+            median: sample.median,
+            mad: sample.mad,
+        };
+        self.next = (self.next + 1) % K;
+        self.len = (self.len + 1).min(K);
+    }
+
+    fn on_undo(&mut self) {
+        // 最新の記録を取り除く
+        if self.len > 0 {
+            self.next = (self.next + K - 1) % K;
+            self.len -= 1;
+        }
+    }
+}
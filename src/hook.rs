@@ -0,0 +1,68 @@
+//! Hook invoked when an outlier is detected.
+
+use crate::Sample;
+
+/// Outlier detected by [`Window::update`](crate::Window::update), passed to the [`OutlierHandler`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutlierEvent<T> {
+    /// Input value determined to be an outlier.
+    pub raw: T,
+    /// Value returned in place of the input value.
+    pub replacement: T,
+    /// Median of the window used for the decision.
+    pub median: T,
+    /// Scale estimate of the window used for the decision (see [`Sample::mad`](crate::Sample::mad)).
+    pub mad: T,
+}
+
+/// Handler invoked by the filter at the moment an outlier is replaced.
+///
+/// It is implemented for `()` (does nothing), for closures `FnMut(OutlierEvent<T>)`,
+/// for a [`History`](crate::History), and for pairs of handlers (both are invoked in order).
+pub trait OutlierHandler<T> {
+    /// Called once for each outlier.
+    fn on_outlier(&mut self, event: OutlierEvent<T>);
+
+    /// Called after each update (including a missing sample, with `raw` NaN), whether or not it is an outlier.
+    /// Does nothing by default.
+    #[inline]
+    fn on_update(&mut self, _raw: T, _sample: &Sample<T>) {}
+
+    /// Called when the last update is undone (see [`Window::undo_last_update`](crate::Window::undo_last_update)).
+    /// Does nothing by default.
+    #[inline]
+    fn on_undo(&mut self) {}
+}
+
+impl<T> OutlierHandler<T> for () {
+    #[inline]
+    fn on_outlier(&mut self, _: OutlierEvent<T>) {}
+}
+
+impl<T, F: FnMut(OutlierEvent<T>)> OutlierHandler<T> for F {
+    #[inline]
+    fn on_outlier(&mut self, event: OutlierEvent<T>) {
+        self(event)
+    }
+}
+
+impl<T: Copy, A: OutlierHandler<T>, B: OutlierHandler<T>> OutlierHandler<T> for (A, B) {
+    #[inline]
+    fn on_outlier(&mut self, event: OutlierEvent<T>) {
+        self.0.on_outlier(event);
+        self.1.on_outlier(event);
+    }
+
+    #[inline]
+    fn on_update(&mut self, raw: T, sample: &Sample<T>) {
+        self.0.on_update(raw, sample);
+        self.1.on_update(raw, sample);
+    }
+
+    #[inline]
+    fn on_undo(&mut self) {
+        self.0.on_undo();
+        self.1.on_undo();
+    }
+}
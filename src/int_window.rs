@@ -0,0 +1,226 @@
+//! Window for primitive signed integers (e.g. raw ADC samples).
+
+use num_traits::{PrimInt, Signed};
+
+use crate::{exact::{Codec, ExactCore, GAUSSIAN_SCALE_Q32}, iter::Samples, ConfigError, OutlierStats, Replacement, Sample};
+
+/// 整数をそのまま`i128`として扱う
+pub(crate) struct IntCodec;
+
+impl<T: PrimInt> Codec<T> for IntCodec {
+    #[inline]
+    fn widen(x: T) -> i128 {
+        x.to_i128().unwrap()  // 32ビット以下なので必ず変換できる
+    }
+
+    #[inline]
+    fn narrow(v: i128) -> T {
+        let v = v.clamp(Self::widen(T::min_value()), Self::widen(T::max_value()));
+        T::from(v).unwrap()
+    }
+    #[inline]
+    fn unit() -> i128 {
+        1
+    }
+}
+
+/// Window of Hampel filter for primitive signed integers of at most 32 bits (`i8`, `i16`, `i32`),
+/// e.g. raw ADC samples.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// Like [`FixedWindow`](crate::FixedWindow), no floating-point arithmetic is used.
+/// `n_sigma` is given as a rational number `n_sigma_num / n_sigma_den`, and the coefficient
+/// `1.4826 * n_sigma` is precomputed as a rational number on construction, so that
+/// the decision `|x - w0| * den > coef_num * s0` is made exactly with widening (128-bit) integer arithmetic,
+/// which cannot overflow even for full-scale deviations.
+///
+/// The decisions are the same as [`Window`](crate::Window) with the values converted to a float,
+/// except within the rounding error of the coefficient (relative `2^-32`) from the threshold.
+/// The median and the MAD of [`Sample`] (and the median returned in place of an outlier)
+/// are rounded down to an integer when the exact value is halfway between two integers:
+///
+/// ```
+/// use hampel::{IntWindow, Replacement, Window};
+///
+/// let mut a = IntWindow::<i16, 4>::with_replacement(0, 3, 1, Replacement::Median);
+/// let mut b = Window::<f64, 4>::with_replacement(0.0, 3.0, Replacement::Median);
+///
+/// for x in [100, 102, 900, 98, 101, 99, 100, -500, 103] {
+///     let (sa, sb) = (a.update_detailed(x), b.update_detailed(x as f64));
+///     assert!((sa.median as f64 - sb.median).abs() < 1.0);
+///     assert!((sa.mad as f64 - sb.mad).abs() < 1.0);
+///     assert_eq!(sa.is_outlier, sb.is_outlier);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct IntWindow<T: PrimInt + Signed, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],
+    pub(crate) sorted: [T; WINDOW_SIZE],  // windowを昇順に並べたもの
+    pub(crate) core: ExactCore<T>,
+}
+
+impl<T: PrimInt + Signed, const WINDOW_SIZE: usize> IntWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+    /// `T`が32ビット以下であることをコンパイル時に確かめる
+    const VALID_TYPE: () = assert!(core::mem::size_of::<T>() <= 4, "integer types of more than 32 bits are not supported");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma_num / n_sigma_den`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// Outliers are replaced as specified by `Replacement::default()`.
+    ///
+    /// ```
+    /// use hampel::IntWindow;
+    ///
+    /// // n_sigma = 3
+    /// let mut filter = IntWindow::<i16, 5>::new(0, 3, 1);
+    /// for x in [100, 102, 101, 103, 102] {
+    ///     filter.update(x);
+    /// }
+    /// assert_eq!(filter.update(104), 104);
+    /// let s = filter.update_detailed(-30000);
+    /// assert!(s.is_outlier);
+    /// assert_eq!((s.median, s.mad), (102, 1));
+    /// assert!(s.value > 100);
+    /// ```
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::IntWindow::<i16, 2>::new(0, 3, 1);
+    /// ```
+    ///
+    /// So is an integer type of more than 32 bits:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::IntWindow::<i64, 5>::new(0, 3, 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma_num` or `n_sigma_den` is zero (`WINDOW_SIZE` < 3 and `T` of more than 32 bits are rejected at compile time).
+    pub fn new(init_val: T, n_sigma_num: u32, n_sigma_den: u32) -> Self {
+        Self::with_replacement(init_val, n_sigma_num, n_sigma_den, Replacement::default())
+    }
+
+    /// Same as [`new`](Self::new), but outliers are replaced as specified by `replacement`.
+    ///
+    /// The values of [`Replacement::LinearExtrapolation`] and [`Replacement::QuadraticExtrapolation`]
+    /// are rounded to the nearest integer (saturating at the range of `T`):
+    ///
+    /// ```
+    /// use hampel::{IntWindow, Replacement, Window};
+    ///
+    /// let input = |i: usize| {
+    ///     let t = i as f64 - 1500.0;
+    ///     let spike = if i % 11 == 0 { 5.0e8 } else { 0.0 };
+    ///     (200.0 * t * t - 1.0e5 * t + spike) as i32
+    /// };
+    /// for replacement in [Replacement::LinearExtrapolation, Replacement::QuadraticExtrapolation] {
+    ///     let mut a = IntWindow::<i32, 9>::with_replacement(input(1), 3, 1, replacement);
+    ///     let mut b = Window::<f64, 9>::with_replacement(input(1) as f64, 3.0, replacement);
+    ///     for i in 0..3000 {
+    ///         let (sa, sb) = (a.update_detailed(input(i)), b.update_detailed(input(i) as f64));
+    ///         assert_eq!(sa.is_outlier, sb.is_outlier);
+    ///         assert!((sa.value as f64 - sb.value).abs() <= 0.5 + 1e-9 * sb.value.abs());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_replacement(init_val: T, n_sigma_num: u32, n_sigma_den: u32, replacement: Replacement) -> Self {
+        let () = Self::VALID_SIZE;
+        let () = Self::VALID_TYPE;
+        // 有理数の閾値は有限なので，正であれば`filter::check_n_sigma`と同じ（浮動小数点数の演算を使わない）
+        if n_sigma_num == 0 || n_sigma_den == 0 {
+            panic!("{}", ConfigError::InvalidNSigma);
+        }
+
+        // coef_num / coef_den = 1.4826 * n_sigma_num / n_sigma_den
+        let coef_num = GAUSSIAN_SCALE_Q32 * n_sigma_num as i128;
+        let coef_den = (n_sigma_den as i128) << 32;
+        Self {
+            window: [init_val; WINDOW_SIZE],
+            sorted: [init_val; WINDOW_SIZE],
+            core: ExactCore::new::<IntCodec>(init_val, coef_num, coef_den, replacement),
+        }
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    ///
+    /// `absolute` of the result is always `false`.
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        self.core.update::<IntCodec>(&mut self.window, &mut self.sorted, x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset::<IntCodec>(&mut self.window, &mut self.sorted, init_val);
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// See [`Window::median`](crate::Window::median) (rounded down to an integer).
+    pub fn median(&self) -> T {
+        self.core.median
+    }
+
+    /// See [`Window::mad`](crate::Window::mad) (rounded down to an integer).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds) (the smallest and the largest accepted integers).
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// See [`Window::iter`](crate::Window::iter).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> T {
+        self.core.latest(&self.window)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Same as [`Window`](crate::Window).
+impl<T: PrimInt + Signed, const WINDOW_SIZE: usize> PartialEq for IntWindow<T, WINDOW_SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.core == other.core && self.iter().eq(other.iter())
+    }
+}
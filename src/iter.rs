@@ -0,0 +1,153 @@
+//! Iterator adapters, and the iterator over the samples in a window.
+
+use core::{iter::FusedIterator, slice};
+
+use num_traits::float::FloatCore;
+
+use crate::Window;
+
+/// Extension trait adding the Hampel filter to iterators.
+///
+/// ```
+/// use hampel::HampelExt;
+///
+/// let samples = [1.0, 1.1, 0.9, 1.0, 9.0, 1.1, 0.9];
+/// let filtered: Vec<f64> = samples.iter().copied().hampel_filter::<5>(3.0).collect();
+/// assert_ne!(filtered[4], 9.0);
+///
+/// let flags: Vec<bool> = samples.iter().copied()
+///     .hampel_filter_flagged::<5>(3.0)
+///     .map(|(_, is_outlier)| is_outlier)
+///     .collect();
+/// assert_eq!(flags, [false, false, false, false, true, false, false]);
+/// ```
+pub trait HampelExt: Iterator + Sized
+where
+    Self::Item: FloatCore,
+{
+    /// Filter the values with a [`Window`] seeded from the first value.
+    ///
+    /// The window is built with the `warm_up` option, so until it is filled the statistics
+    /// are computed only over the values received so far.
+    fn hampel_filter<const WINDOW_SIZE: usize>(self, n_sigma: Self::Item) -> HampelIter<Self, Self::Item, WINDOW_SIZE> {
+        HampelIter { iter: self, window: None, n_sigma }
+    }
+
+    /// Same as [`hampel_filter`](Self::hampel_filter), but the window is initialized with `init_val`.
+    fn hampel_filter_with_init<const WINDOW_SIZE: usize>(
+        self,
+        init_val: Self::Item,
+        n_sigma: Self::Item,
+    ) -> HampelIter<Self, Self::Item, WINDOW_SIZE> {
+        HampelIter { iter: self, window: Some(Window::new(init_val, n_sigma)), n_sigma }
+    }
+
+    /// Same as [`hampel_filter`](Self::hampel_filter), but yields `(value, is_outlier)`.
+    fn hampel_filter_flagged<const WINDOW_SIZE: usize>(
+        self,
+        n_sigma: Self::Item,
+    ) -> HampelFlagged<Self, Self::Item, WINDOW_SIZE> {
+        HampelFlagged { inner: self.hampel_filter(n_sigma) }
+    }
+}
+
+impl<I: Iterator> HampelExt for I where I::Item: FloatCore {}
+
+/// Iterator returned by [`HampelExt::hampel_filter`].
+pub struct HampelIter<I, T: FloatCore, const WINDOW_SIZE: usize> {
+    iter: I,
+    window: Option<Window<T, WINDOW_SIZE>>,
+    n_sigma: T,
+}
+
+impl<I, T, const WINDOW_SIZE: usize> HampelIter<I, T, WINDOW_SIZE>
+where
+    I: Iterator<Item = T>,
+    T: FloatCore,
+{
+    fn next_detailed(&mut self) -> Option<(T, bool)> {
+        let x = self.iter.next()?;
+        let n_sigma = self.n_sigma;
+        let window = self.window.get_or_insert_with(|| {
+            Window::builder().init_value(x).n_sigma(n_sigma).warm_up(true).build()
+        });
+        let s = window.update_detailed(x);
+        Some((s.value, s.is_outlier))
+    }
+}
+
+impl<I, T, const WINDOW_SIZE: usize> Iterator for HampelIter<I, T, WINDOW_SIZE>
+where
+    I: Iterator<Item = T>,
+    T: FloatCore,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.next_detailed().map(|(value, _)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterator returned by [`HampelExt::hampel_filter_flagged`].
+pub struct HampelFlagged<I, T: FloatCore, const WINDOW_SIZE: usize> {
+    inner: HampelIter<I, T, WINDOW_SIZE>,
+}
+
+impl<I, T, const WINDOW_SIZE: usize> Iterator for HampelFlagged<I, T, WINDOW_SIZE>
+where
+    I: Iterator<Item = T>,
+    T: FloatCore,
+{
+    type Item = (T, bool);
+
+    fn next(&mut self) -> Option<(T, bool)> {
+        self.inner.next_detailed()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Iterator over the samples in a window, from the oldest to the newest.
+///
+/// Returned by [`Window::iter`] and the `iter` method of the other window types.
+#[derive(Debug, Clone)]
+pub struct Samples<'a, T> {
+    head: slice::Iter<'a, T>,  // window[oldest..]
+    tail: slice::Iter<'a, T>,  // window[..oldest]
+}
+
+impl<'a, T> Samples<'a, T> {
+    pub(crate) fn new(window: &'a [T], oldest: usize) -> Self {
+        let (tail, head) = window.split_at(oldest);
+        Self { head: head.iter(), tail: tail.iter() }
+    }
+}
+
+impl<'a, T> Iterator for Samples<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.head.next().or_else(|| self.tail.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Samples<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.tail.next_back().or_else(|| self.head.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Samples<'_, T> {}
+
+impl<T> FusedIterator for Samples<'_, T> {}
@@ -0,0 +1,189 @@
+//! Window for large window sizes, backed by an order-statistic tree.
+
+use num_traits::float::FloatCore;
+
+use crate::{builder::default_core, filter::Core, iter::Samples, order::Tree, OutlierStats, Sample};
+
+/// Window of Hampel filter for very large window sizes (several thousand samples or more).
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// [`Window`](crate::Window) keeps a sorted array of the window, which costs O(`WINDOW_SIZE`)
+/// shifts per update. This keeps the elements in an order-statistic tree over a fixed arena instead
+/// (no allocation), so that inserting and evicting a sample takes O(log `WINDOW_SIZE`)
+/// and the median and the MAD are read in O(log² `WINDOW_SIZE`).
+/// Since shifting a contiguous array is cheap, [`Window`](crate::Window) is faster
+/// unless the window is very large (on a typical x86-64 machine, below about 5000 samples).
+///
+/// It is configured like [`Window::new`](crate::Window::new), and the results are exactly the same:
+///
+/// ```
+/// use hampel::{LargeWindow, Window};
+///
+/// let mut a = LargeWindow::<f64, 512>::new(0.0, 3.0);
+/// let mut b = Window::<f64, 512>::new(0.0, 3.0);
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct LargeWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],
+    pub(crate) work: [T; WINDOW_SIZE],
+    pub(crate) tree: Tree<T, WINDOW_SIZE>,  // windowの要素の順序統計木
+    pub(crate) core: Core<T>,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> LargeWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::LargeWindow::<f32, 2>::new(0.0, 3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(init_val: T, n_sigma: T) -> Self {
+        let () = Self::VALID_SIZE;
+
+        let core = match default_core(WINDOW_SIZE, init_val, n_sigma) {
+            Ok(core) => core,
+            Err(e) => panic!("{}", e),
+        };
+        let mut filter = Self {
+            window: [init_val; WINDOW_SIZE],
+            work: [init_val; WINDOW_SIZE],
+            tree: Tree::new(init_val),
+            core,
+        };
+        filter.reset(init_val);
+        filter
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        self.core.update(&mut self.window, &mut [], &mut self.work, &mut [], &mut self.tree, x)
+    }
+
+    /// See [`Window::update_evicting`](crate::Window::update_evicting).
+    pub fn update_evicting(&mut self, x: T) -> (T, T) {
+        let evicted = self.core.oldest_sample(&self.window);
+        (self.update(x), evicted)
+    }
+
+    /// See [`Window::would_flag`](crate::Window::would_flag).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    /// Unlike [`update`](Self::update), the statistics are computed by sorting a copy of the window.
+    pub fn would_flag(&mut self, x: T) -> bool {
+        self.core.would_flag(&self.window, &mut self.work, &mut [], x)
+    }
+
+    /// See [`Window::score`](crate::Window::score).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    /// Unlike [`update`](Self::update), the statistics are computed by sorting a copy of the window.
+    pub fn score(&mut self, x: T) -> T {
+        self.core.score_of(&self.window, &mut self.work, &mut [], x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset(&mut self.window, &mut self.tree, init_val);
+    }
+
+    /// See [`Window::seed`](crate::Window::seed).
+    pub fn seed(&mut self, samples: &[T]) {
+        let start = samples.len().saturating_sub(WINDOW_SIZE);
+        self.seed_from_iter(samples[start..].iter().copied());
+    }
+
+    /// See [`Window::seed_from_iter`](crate::Window::seed_from_iter).
+    pub fn seed_from_iter<I: IntoIterator<Item = T>>(&mut self, samples: I) {
+        self.core.seed_from_iter(&mut self.window, &mut self.work, &mut [], &mut self.tree, samples);
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::set_mad_floor`](crate::Window::set_mad_floor).
+    pub fn set_mad_floor(&mut self, mad_floor: T) {
+        self.core.mad_floor = mad_floor;
+    }
+
+    /// See [`Window::mad_floor`](crate::Window::mad_floor).
+    pub fn mad_floor(&self) -> T {
+        self.core.mad_floor
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// See [`Window::is_warmed_up`](crate::Window::is_warmed_up).
+    pub fn is_warmed_up(&self) -> bool {
+        self.core.seen >= WINDOW_SIZE
+    }
+
+    /// See [`Window::median`](crate::Window::median).
+    pub fn median(&self) -> T {
+        self.core.median
+    }
+
+    /// See [`Window::mad`](crate::Window::mad).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds).
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// See [`Window::iter`](crate::Window::iter).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> T {
+        self.core.latest(&self.window)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Same as [`Window`](crate::Window).
+impl<T: FloatCore, const WINDOW_SIZE: usize> PartialEq for LargeWindow<T, WINDOW_SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.core.eq_with(&self.window, &other.core, &other.window)
+    }
+}
@@ -1,6 +1,8 @@
 //! Sequential outlier detection and removal using Hampel identifiers.
 //! 
-//! It supports `f32` and `f64`.
+//! It supports `f32` and `f64`, and with the `half` feature, `half::f16` and `half::bf16`.
+//! Fixed-point numbers of the `fixed` crate are supported by `FixedWindow` with the `fixed` feature,
+//! and the NaN-free floats of the `ordered-float` crate by `NotNanWindow` with the `ordered-float` feature.
 //! 
 //! # Example
 //! 
@@ -21,127 +23,1127 @@
 //!     // filtered_vals <-- Outliers have been removed
 //! }
 //! ```
+//! 
+//! # Closures
+//! 
+//! Every filter can be turned into a closure by `into_fn`, or borrowed as one by `as_fn`, which forwards to `update`,
+//! e.g. for `Iterator::map` or a pipeline taking `impl FnMut` stages.
+//! The filters with `update_detailed` also have `into_detailed_fn` and `as_detailed_fn`.
+//! 
+//! ```
+//! use hampel::Window;
+//! 
+//! let input = [0.5, 0.25, 9.0, 0.75, 0.5];
+//! let filtered: Vec<f64> = input.iter().copied().map(Window::<f64, 5>::new(0.0, 3.0).into_fn()).collect();
+//! ```
+//! 
+//! # Code size
+//! 
+//! The statistics, the decisions and the replacements are compiled once per sample type, on slices of
+//! the length of the window, and `Window<T, WINDOW_SIZE>` only holds the arrays. Several window sizes
+//! in one program (e.g. six sizes of `Window<f32, N>`) share that code, and the results are the same as
+//! those of a window whose size is given at runtime:
+//! 
+//! ```
+//! use hampel::{Window, WindowMut};
+//! 
+//! let mut filter = Window::<f32, 7>::new(0.0, 3.0);
+//! let (mut buf, mut scratch, mut gaps) = ([0.0; 7], [0.0; 7], [0.0; 7]);
+//! let mut reference = WindowMut::new(&mut buf, &mut scratch, &mut gaps, 0.0, 3.0).unwrap();
+//! for x in [0.5, 0.25, 90.0, 0.75, 0.5, -40.0, 0.25] {
+//!     assert_eq!(filter.update_detailed(x), reference.update_detailed(x));
+//! }
+//! ```
+//! 
+//! # Constant-time updates
+//! 
+//! With the `constant-time` feature, an update of a full window without NaN takes the same steps
+//! regardless of the values: the sorted copy of the window is updated by a full pass over all elements
+//! instead of a binary search and a shift of the elements in between, and the replacement of an outlier
+//! is always computed and then selected. The results are exactly the same as without the feature:
+//! 
+//! ```
+//! use hampel::{Replacement, Window};
+//! 
+//! let mut filter = Window::<f64, 5>::builder().replacement(Replacement::Median).build();
+//! for x in [0.25, 0.5, 0.5, 0.75] {
+//!     filter.update(x);
+//! }
+//! let s = filter.update_detailed(100.0);
+//! assert!(s.is_outlier);
+//! assert_eq!((s.median, s.mad, s.value), (0.5, 0.25, 0.5));
+//! ```
+//! 
+//! The rest of the control flow depends only on the configuration, except for the search of the MAD
+//! (at most `log2(WINDOW_SIZE)` steps), the warm-up, and the windows containing NaN, which take the usual paths.
+//! The time per update and its spread are measured by `cargo bench` (see the `constant-time` feature in the README).
 
 #![no_std]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use num_traits::float::FloatCore;
+
+mod adaptive;
+mod angular_window;
+#[cfg(feature = "ndarray")]
+mod axis;
+mod bank;
+mod builder;
+#[cfg(feature = "alloc")]
+mod calibrate;
+mod centered;
+#[cfg(feature = "alloc")]
+mod chunked;
+mod closure;
+mod complex_window;
+mod counters;
+mod decimate;
+mod dual_window;
+#[cfg(feature = "alloc")]
+mod dyn_window;
+mod error;
+mod exact;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+#[cfg(feature = "fixed")]
+mod fixed_window;
+mod format;
+mod grid;
+mod history;
+mod hook;
+mod int_window;
+mod iter;
+mod large_window;
+mod log_window;
+mod mad_estimator;
+mod median_window;
+mod metric_window;
+mod network;
+#[cfg(feature = "ordered-float")]
+mod not_nan_window;
+mod offline;
+mod order;
+mod outlier_filter;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod precision;
+mod quantile_window;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "dasp")]
+mod signal;
+#[cfg(feature = "simd")]
+mod simd;
+mod smoothed;
+mod state;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+mod tagged_window;
+#[cfg(any(feature = "log", feature = "defmt"))]
+mod trace;
+#[cfg(feature = "nalgebra")]
+mod vector_window;
+mod weighted_window;
+mod window_mut;
+mod zero_phase;
+mod zscore;
+
+pub use adaptive::AdaptiveThreshold;
+pub use angular_window::AngularWindow;
+#[cfg(feature = "ndarray")]
+pub use axis::{filter_axis, filtered_axis, filtered_axis_with_mask};
+pub use bank::{Decision, Distance, WindowBank};
+pub use builder::WindowBuilder;
+#[cfg(feature = "alloc")]
+pub use calibrate::{calibrate, CalibrationResult};
+pub use centered::{CenteredWindow, Flush};
+#[cfg(feature = "alloc")]
+pub use chunked::ChunkedFilter;
+pub use complex_window::{ComplexReplacement, ComplexWindow};
+pub use counters::OutlierStats;
+pub use decimate::{Aggregation, DecimatingWindow};
+pub use dual_window::DualWindow;
+#[cfg(feature = "alloc")]
+pub use dyn_window::DynWindow;
+pub use error::{ConfigError, LengthMismatch, StrideError};
+#[cfg(feature = "fixed")]
+pub use fixed_window::FixedWindow;
+pub use history::{History, Record};
+pub use grid::{despike_grid, despike_grid_into, MAX_GRID_RADIUS, MAX_GRID_WIDTH};
+pub use hook::{OutlierEvent, OutlierHandler};
+pub use int_window::IntWindow;
+pub use iter::{HampelExt, HampelFlagged, HampelIter, Samples};
+pub use large_window::LargeWindow;
+pub use log_window::{LogWindow, NonPositive};
+pub use mad_estimator::MadEstimator;
+pub use median_window::MedianWindow;
+pub use metric_window::MetricWindow;
+#[cfg(feature = "ordered-float")]
+pub use not_nan_window::NotNanWindow;
+pub use offline::{filter_slice, filter_slice_filled, filter_slice_filled_into, filter_slice_into, EdgeMode, Fill, MAX_HALF_WINDOW};
+pub use outlier_filter::OutlierFilter;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_filter_slice, par_filter_slice_chunked, par_filter_slices, PAR_CHUNK_LEN};
+pub use quantile_window::QuantileWindow;
+#[cfg(feature = "dasp")]
+pub use signal::{HampelFrame, HampelSignal, HampelSignalExt};
+#[cfg(feature = "simd")]
+pub use simd::SimdBank;
+pub use smoothed::SmoothedWindow;
+pub use state::WindowState;
+#[cfg(feature = "std")]
+pub use stream::{filter_reader_writer, Endian, SampleFormat, StreamOptions, StreamStats};
+pub use tagged_window::{TagSource, TaggedWindow};
+#[cfg(feature = "nalgebra")]
+pub use vector_window::{VectorSample, VectorWindow};
+pub use weighted_window::WeightedWindow;
+pub use window_mut::WindowMut;
+pub use zero_phase::filter_zero_phase;
+pub use zscore::ZScoreWindow;
+
+use filter::{Checkpoint, Core, LineConstants};
+use network::Selection;
+
 
-use core::mem::MaybeUninit;
-use num_traits::{cast, float::FloatCore};
+/// Result of [`Window::update_detailed`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sample<T> {
+    /// Output value of the filter.
+    pub value: T,
+    /// `true` if the input value was determined to be an outlier.
+    pub is_outlier: bool,
+    /// Median of the window used for the decision.
+    pub median: T,
+    /// Scale estimate of the window used for the decision, before multiplying by the consistency constant
+    /// (the median absolute deviation, or Sn/IQR if [`ScaleEstimator::Sn`]/[`ScaleEstimator::Iqr`] is selected).
+    pub mad: T,
+    /// `true` if the relative threshold (`n_sigma`) tripped.
+    pub relative: bool,
+    /// `true` if the absolute threshold (see [`WindowBuilder::abs_threshold`]) tripped.
+    /// 
+    /// Both `relative` and `absolute` are `false` for non-finite values flagged by
+    /// [`NanPolicy::TreatAsOutlier`].
+    pub absolute: bool,
+    /// `true` if the difference test (see [`WindowBuilder::difference_test`]) tripped.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub difference: bool,
+    /// `true` if the input value was outside the physical range (see [`WindowBuilder::output_range`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub out_of_range: bool,
+    /// `true` if the window was rebuilt at this sample, because [`WindowBuilder::max_consecutive_replacements`]
+    /// was reached (see [`WindowBuilder::reseed_policy`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reseeded: bool,
+    /// `true` if the sample tripped the tests, before the confirmation (see [`WindowBuilder::confirmation`])
+    /// and [`WindowBuilder::max_consecutive_replacements`] are applied,
+    /// so that `tripped && !is_outlier` means "flagged but not confirmed".
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tripped: bool,
+    /// Normalized deviation of the input value, `|x - median| / (scale_const * mad)` (with the MAD floor applied),
+    /// so that `relative` is `true` if and only if `score > n_sigma` (the `n_sigma` in effect for this sample).
+    /// 
+    /// It is zero if no decision is made, and infinite for non-finite values flagged by [`NanPolicy::TreatAsOutlier`]
+    /// or, with [`ZeroMad::FlagEverything`], if the scale is zero.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub score: T,
+    /// Acceptance band `(lower, upper)` of the tests on the value, `median ∓ scale_const * n_sigma * mad`
+    /// combined with the absolute threshold. A sample outside the band trips the tests (see [`tripped`](Self::tripped)),
+    /// apart from the difference test.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bounds: (T, T),
+    /// Side of the median on which the outlier lies (`None` if the input value is not an outlier,
+    /// or is a non-finite value flagged by [`NanPolicy::TreatAsOutlier`]).
+    pub side: Option<Side>,
+    /// `true` if the output is an estimate for a missing sample (see [`Window::update_missing`])
+    /// after more than [`WindowBuilder::max_missing`] consecutive samples are missing,
+    /// so that it is no longer trustworthy.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stale: bool,
+    /// `true` if the scale estimate (with the MAD floor) was zero, so that the decision followed
+    /// [`WindowBuilder::zero_mad`] (see [`ZeroMad`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub zero_mad: bool,
+    /// Classification of the input value: `Outlier` if `is_outlier`, `Suspect` if it is outside the suspect band
+    /// (see [`WindowBuilder::suspect`]) but not an outlier, and `Normal` otherwise.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub class: Class,
+}
+
+/// Side of the median on which an outlier lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Side {
+    /// Below the median (judged by `n_sigma_low`).
+    Below,
+    /// Above the median (judged by `n_sigma_high`).
+    Above,
+}
+
+/// Classification of a sample (see [`WindowBuilder::suspect`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Class {
+    /// Within the suspect band (or within the acceptance band, if no suspect threshold is set).
+    #[default]
+    Normal,
+    /// Outside the suspect band, but not an outlier.
+    Suspect,
+    /// Determined to be an outlier (`is_outlier`).
+    Outlier,
+}
 
+/// Combination of the relative and absolute thresholds (see [`WindowBuilder::abs_threshold`]),
+/// or of the test on the values and the difference test (see [`WindowBuilder::difference_test`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThresholdMode {
+    /// Outlier if either threshold trips.
+    #[default]
+    Either,
+    /// Outlier only if both thresholds trip.
+    Both,
+}
+
+/// Value returned in place of an outlier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Replacement {
+    /// Median of the window.
+    Median,
+    /// Value linearly extrapolated from the window (least squares), ignoring the outlier itself
+    /// and the samples flagged as outliers by the previous updates (up to 128 updates ago).
+    /// If fewer than two samples remain, the median is returned instead.
+    LinearExtrapolation,
+    /// Value extrapolated by a quadratic polynomial fitted to the window (least squares), excluding the outliers
+    /// as `LinearExtrapolation` does. It follows accelerating signals better, but amplifies the noise more.
+    QuadraticExtrapolation,
+    /// Value linearly extrapolated by the Theil–Sen estimator (the median of the pairwise slopes), excluding the outliers
+    /// as `LinearExtrapolation` does, from at most the newest 32 samples. Unlike the least-squares line,
+    /// it is not dragged by an earlier outlier that was not detected.
+    TheilSenExtrapolation,
+    /// The value returned by the previous update.
+    HoldLast,
+    /// The input value as is (only the detection is reported).
+    PassThrough,
+    /// The edge of the acceptance band on the side of the outlier (see [`Sample::bounds`]).
+    /// Missing samples are replaced by the median.
+    Clamp,
+    /// The most recent output that was not determined to be an outlier (sample-and-hold),
+    /// or the initialization value if there is none yet.
+    HoldLastValid,
+}
+
+impl Replacement {
+    /// `Replacement::default()`（`const`の文脈でも使える）
+    pub(crate) const DEFAULT: Replacement = if cfg!(feature = "extrapolation") {
+        Replacement::LinearExtrapolation
+    } else {
+        Replacement::Median
+    };
+}
+
+impl Default for Replacement {
+    /// `LinearExtrapolation` if the `extrapolation` feature is enabled, otherwise `Median`.
+    fn default() -> Self {
+        Replacement::DEFAULT
+    }
+}
+
+/// Estimator of the center of the window, from which the deviations are measured.
+/// 
+/// The center is reported as [`Sample::median`], and returned by [`Replacement::Median`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Center {
+    /// Median of the window.
+    #[default]
+    Median,
+    /// Mean of the window without the `floor(trim_fraction * N)` smallest and largest samples (`0 <= trim_fraction < 0.5`).
+    /// Only [`ScaleEstimator::Mad`] without the forgetting factor is supported.
+    TrimmedMean {
+        /// Fraction of the samples dropped from each end.
+        trim_fraction: f32,
+    },
+    /// `p`-quantile of the window (`0 < p < 1`), interpolated linearly as NumPy's `percentile`.
+    /// Only [`ScaleEstimator::Mad`] without the forgetting factor is supported.
+    Quantile {
+        /// Probability of the quantile.
+        p: f32,
+    },
+}
+
+/// Reference from which the deviation of a new sample is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Reference {
+    /// Center of the window (see [`Center`]), and the scale of the window around it.
+    #[default]
+    Median,
+    /// One-step-ahead prediction of a polynomial fitted to the previous samples of the window
+    /// (as by the extrapolating [`Replacement`]), with the MAD of the residuals as the scale.
+    /// Only [`Center::Median`] and [`ScaleEstimator::Mad`] without the forgetting factor are supported.
+    Prediction,
+}
+
+/// Estimator of the spread of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScaleEstimator {
+    /// Median absolute deviation from the median (consistency constant: `1.4826`).
+    #[default]
+    Mad,
+    /// Rousseeuw–Croux Sn estimator, `lomed_i himed_j |x_i - x_j|` (consistency constant: `1.1926`).
+    /// O(`WINDOW_SIZE`²) per update.
+    Sn,
+    /// Interquartile range `Q3 - Q1` (consistency constant: `1.0`). `x` is an outlier if it lies outside
+    /// `[Q1 - k * IQR, Q3 + k * IQR]` with `k = scale * n_sigma`, i.e. Tukey's fences.
+    Iqr,
+}
+
+/// Decision when the scale estimate of the window (with the MAD floor) is exactly zero
+/// (reported by [`Sample::zero_mad`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZeroMad<T> {
+    /// Every sample that differs from the median is an outlier (e.g. for a quantized idle signal).
+    FlagEverything,
+    /// No sample is an outlier by the relative threshold (e.g. for a slow ADC with a coarse LSB).
+    PassEverything,
+    /// The given scale is used instead (`>= 0`). Unlike [`WindowBuilder::mad_floor`],
+    /// it does not change the decisions while the scale is positive.
+    UseFloor(T),
+}
+
+impl<T> Default for ZeroMad<T> {
+    /// `FlagEverything`.
+    fn default() -> Self {
+        ZeroMad::FlagEverything
+    }
+}
+
+/// Handling of non-finite (NaN or ±infinity) input values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NanPolicy {
+    /// Non-finite values are stored in the window as is. A NaN input is an outlier,
+    /// and the output may be meaningless until it leaves the window.
+    #[default]
+    Propagate,
+    /// Non-finite values are always outliers, and never stored in the window.
+    TreatAsOutlier,
+}
+
+/// What is stored in the window for a sample determined to be an outlier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StorePolicy {
+    /// The input value as is.
+    #[default]
+    Raw,
+    /// The value returned in place of the outlier (see [`Replacement`]), so that the window
+    /// always contains the cleaned signal and an outlier does not affect the later decisions.
+    Replacement,
+}
+
+/// How the window is rebuilt when [`WindowBuilder::max_consecutive_replacements`] is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReseedPolicy {
+    /// From the samples of the run and the accepted sample (as [`Window::seed`] does),
+    /// or from the accepted sample alone with [`StorePolicy::Replacement`].
+    #[default]
+    Run,
+    /// The entire window is filled with the accepted sample (a `mad_floor` should be set together).
+    Latest,
+}
 
 /// Window of Hampel filter
 /// 
 /// * `WINDOW_SIZE` >= 3
-pub struct Window<T: FloatCore, const WINDOW_SIZE: usize> {
+/// 
+/// If `WINDOW_SIZE` is even, the median is the average of the two central elements.
+/// The window keeps a sorted copy of its elements (or, for `WINDOW_SIZE` of 5, 7 and 9, finds the median
+/// by a selection network), and the results are exactly the same as sorting a copy of the window at every update.
+#[derive(Clone)]
+pub struct Window<T: FloatCore, const WINDOW_SIZE: usize, H = ()> {
     window: [T; WINDOW_SIZE],
-    working_array: [T; WINDOW_SIZE],
-    oldest: usize,  // window内の最も古い要素のインデックス
-    coef: T,  // 閾値判定に使う係数
+    sorted: [T; WINDOW_SIZE],  // windowを昇順に並べたもの（WINDOW_SIZEが5, 7, 9ならwindowの写し，network::Selectionを参照）
+    gaps: [T; WINDOW_SIZE],  // windowの各サンプルと1つ前のサンプルの時刻の差
+    time: Option<T>,  // 最新のサンプルの時刻（update_atを使うまではNone）
+    core: Core<T>,
+    undo: Option<(Checkpoint<T>, Option<T>)>,  // 直前の更新の前の状態と時刻（undo_last_updateで戻す）
+    handler: H,  // 外れ値を検出したときに呼び出す
 }
 
-// n_sigmaを大きくするほど判定が緩くなる（外れ値を見落としやすくなる）
 impl<T: FloatCore, const WINDOW_SIZE: usize> Window<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
     /// * `init_val`: Initialization value of window.
     /// * `n_sigma`: Threshold for determining an outlier.
     /// 
     /// If the window's input value exceeds the `window's standard deviation` * `n_sigma`, 
     /// it is determined to be an outlier.
     /// The larger n_sigma is, the harder it is to detect outliers.
+    /// Outliers are replaced as specified by `Replacement::default()`.
+    /// 
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    /// 
+    /// ```compile_fail
+    /// let filter = hampel::Window::<f32, 2>::new(0.0, 3.0);
+    /// ```
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if [`try_new`](Self::try_new) returns an error (e.g. `n_sigma` is not a positive finite number).
     pub fn new(init_val: T, n_sigma: T) -> Self {
-        assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
-
-        Self {
-            window: [init_val; WINDOW_SIZE],
-            working_array: unsafe { MaybeUninit::uninit().assume_init() },
-            oldest: 0,
-            coef: cast::<f32, T>(1.4826).unwrap() * n_sigma,  // 1.4826は正規分布にするための係数
+        match Self::try_new(init_val, n_sigma) {
+            Ok(window) => window,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    /// Same as [`new`](Self::new), but returns an error instead of panicking.
+    /// 
+    /// Returns [`ConfigError::InvalidNSigma`] if `n_sigma` is not a positive finite number,
+    /// or [`ConfigError::Cast`] if a constant cannot be represented in `T`.
+    /// 
+    /// ```
+    /// use hampel::{ConfigError, Window};
+    /// 
+    /// assert_eq!(Window::<f64, 5>::try_new(0.0, -1.0).err(), Some(ConfigError::InvalidNSigma));
+    /// ```
+    pub fn try_new(init_val: T, n_sigma: T) -> Result<Self, ConfigError> {
+        filter::check_n_sigma(n_sigma)?;
+        Self::builder()
+            .init_value(init_val)
+            .n_sigma(n_sigma)
+            .replacement(Replacement::default())
+            .try_build()
+    }
+
+    /// Same as [`new`](Self::new), but outliers are replaced as specified by `replacement`.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `n_sigma` is not a positive finite number (as [`new`](Self::new)).
+    pub fn with_replacement(init_val: T, n_sigma: T, replacement: Replacement) -> Self {
+        Self::builder()
+            .init_value(init_val)
+            .n_sigma(n_sigma)
+            .replacement(replacement)
+            .build()
+    }
+
+    /// Same as [`new`](Self::new), but with the consistency constant of the MAD specified by `scale_const`
+    /// instead of `1.4826` (see `Window::GAUSSIAN_SCALE`, `Window::LAPLACE_SCALE` and `Window::UNIFORM_SCALE`).
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `n_sigma` or `scale_const` is not a positive finite number.
+    pub fn with_scale(init_val: T, n_sigma: T, scale_const: T) -> Self {
+        Self::builder()
+            .init_value(init_val)
+            .n_sigma(n_sigma)
+            .scale(scale_const)
+            .build()
+    }
+
+    /// Same as [`new`](Self::new), but with separate thresholds for deviations below (`n_sigma_low`)
+    /// and above (`n_sigma_high`) the median.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `n_sigma_low` or `n_sigma_high` is not a positive finite number.
+    pub fn asymmetric(init_val: T, n_sigma_low: T, n_sigma_high: T) -> Self {
+        Self::builder()
+            .init_value(init_val)
+            .n_sigma_asymmetric(n_sigma_low, n_sigma_high)
+            .build()
+    }
+
+    /// Filter restored from `state` taken by [`to_state`](Window::to_state), configured as [`new`](Self::new).
+    /// 
+    /// `state` is trusted and not validated.
+    /// 
+    /// ```
+    /// use hampel::Window;
+    /// 
+    /// let mut filter = Window::<f32, 7>::new(0.0, 3.0);
+    /// filter.update(0.5);
+    /// let state = filter.to_state();  // e.g. written to flash and read back
+    /// assert!(Window::<f32, 7>::from_state(&state, 3.0) == filter);
+    /// ```
+    pub fn from_state(state: &WindowState<T, WINDOW_SIZE>, n_sigma: T) -> Self {
+        let mut filter = Self::new(state.last, n_sigma);
+        filter.time = state.restore(&mut filter.core, &mut filter.window, &mut filter.gaps);
+        filter.core.rebuild_sorted(&filter.window, &mut Selection(&mut filter.sorted));
+        filter
+    }
+
+    /// Window with the threshold and the MAD floor derived from the recording `clean` by [`calibrate`]
+    /// (`alloc` feature), seeded with the last `WINDOW_SIZE` samples of the recording.
+    /// 
+    /// ```
+    /// use hampel::Window;
+    /// 
+    /// let clean: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.7).sin() * 0.1).collect();
+    /// let mut filter = Window::<f64, 31>::new_calibrated(&clean, 0.01);
+    /// ```
+    /// 
+    /// # Panics
+    /// 
+    /// Same as [`calibrate`].
+    #[cfg(feature = "alloc")]
+    pub fn new_calibrated(clean: &[T], target_fpr: T) -> Self {
+        let result = calibrate::<T, WINDOW_SIZE>(clean, target_fpr);
+        let mut filter = Self::builder()
+            .init_value(clean[clean.len() - 1])
+            .n_sigma(result.n_sigma)
+            .mad_floor(result.mad_floor)
+            .build();
+        filter.seed(clean);
+        filter
+    }
+
+    /// Builder for configuring the filter (see [`WindowBuilder`]).
+    pub const fn builder() -> WindowBuilder<T, WINDOW_SIZE> {
+        WindowBuilder::new()
+    }
+
+    /// Attach `handler`, which is invoked each time an outlier is replaced (see [`OutlierHandler`]).
+    /// Without a handler (`H = ()`), there is no overhead.
+    /// 
+    /// ```
+    /// use hampel::{OutlierEvent, Window};
+    /// 
+    /// let mut filter = Window::<f64, 7>::new(0.0, 3.0).on_outlier(|e: OutlierEvent<f64>| println!("{} replaced", e.raw));
+    /// ```
+    pub fn on_outlier<H: OutlierHandler<T>>(self, handler: H) -> Window<T, WINDOW_SIZE, H> {
+        let Window { window, sorted, gaps, time, core, undo, .. } = self;
+        Window { window, sorted, gaps, time, core, undo, handler }
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, const K: usize> Window<T, WINDOW_SIZE, History<T, K>> {
+    /// Records of the last `K` updates, from the oldest to the newest (see [`History`]).
+    pub fn history(&self) -> impl Iterator<Item = &Record<T>> + '_ {
+        self.handler.iter()
+    }
+
+    /// Discard the records of the history.
+    pub fn clear_history(&mut self) {
+        self.handler.clear();
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, H: OutlierHandler<T>, const K: usize> Window<T, WINDOW_SIZE, (H, History<T, K>)> {
+    /// Records of the last `K` updates, from the oldest to the newest (see [`History`]).
+    pub fn history(&self) -> impl Iterator<Item = &Record<T>> + '_ {
+        self.handler.1.iter()
+    }
+
+    /// Discard the records of the history.
+    pub fn clear_history(&mut self) {
+        self.handler.1.clear();
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, H: OutlierHandler<T>> Window<T, WINDOW_SIZE, H> {
+    /// Handler attached by [`on_outlier`](Window::on_outlier).
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// Mutable reference to the handler attached by [`on_outlier`](Window::on_outlier).
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    /// Attach a [`History`] of the last `K` updates, for post-mortem diagnostics.
+    /// The handler attached by [`on_outlier`](Window::on_outlier) is kept, and invoked before the history.
+    /// 
+    /// ```
+    /// use hampel::Window;
+    /// 
+    /// let mut filter = Window::<f64, 5>::new(0.0, 3.0).with_history::<8>();
+    /// filter.update(0.5);
+    /// assert_eq!(filter.history().count(), 1);
+    /// ```
+    pub fn with_history<const K: usize>(self) -> Window<T, WINDOW_SIZE, (H, History<T, K>)> {
+        let Window { window, sorted, gaps, time, core, undo, handler } = self;
+        Window { window, sorted, gaps, time, core, undo, handler: (handler, History::new()) }
+    }
+
     /// Update element in window.
     /// 
-    /// When `x` is determined to be an outlier, the median value of the window is usually returned.
-    /// If the `extrapolation` feature is enabled, the linear extrapolated value is returned.
+    /// When `x` is determined to be an outlier, the value specified by [`Replacement`] is returned.
     /// 
     /// When `x` is judged not to be an outlier, `x` is returned as is.
     pub fn update(&mut self, x: T) -> T {
-        // Range of `oldest`: [0, WINDOW_SIZE)
-        unsafe {*self.window.get_unchecked_mut(self.oldest) = x};
-        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
-
-        self.working_array = self.window;
-        // ウィンドウの中央値を計算
-        let w0 = self.get_median();
-        // ウィンドウの各値に対して，中央値との絶対差分を取る
-        for w in self.working_array.iter_mut() {
-            *w = (*w - w0).abs();
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window, and return the output value together with the outlier decision
+    /// and the window statistics it was based on.
+    /// 
+    /// ```
+    /// use hampel::Window;
+    /// 
+    /// let mut filter = Window::<f64, 5>::new(0.0, 3.0);
+    /// filter.seed(&[0.1, -0.1, 0.2, -0.2]);
+    /// let s = filter.update_detailed(10.0);
+    /// assert!(s.is_outlier);
+    /// ```
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        let before = self.checkpoint();
+        let s = self.advance(x);
+        self.keep_checkpoint(before, &s);
+        s
+    }
+
+    /// Same as [`update`](Self::update), but the sample evicted from the window by `x` is also returned,
+    /// as `(output, evicted)`.
+    /// 
+    /// The evicted samples are the input stream as stored in the window, delayed by `WINDOW_SIZE`
+    /// (the initialization value for the first `WINDOW_SIZE` updates).
+    pub fn update_evicting(&mut self, x: T) -> (T, T) {
+        // 上書きされる前に取り出しておく
+        let evicted = self.core.oldest_sample(&self.window);
+        (self.update(x), evicted)
+    }
+
+    /// Filter the block `buf` in place, as by calling [`update`](Self::update) for each sample in order.
+    /// 
+    /// Only the last sample of the block can be undone by [`undo_last_update`](Self::undo_last_update).
+    pub fn process(&mut self, buf: &mut [T]) {
+        let Some((last, init)) = buf.split_last_mut() else {
+            return;
+        };
+        for x in init {
+            *x = self.advance(*x).value;
         }
-        // 絶対差分を取ったので再度中央値を計算
-        let s0 = self.get_median();
-
-        // 外れ値かどうか判定
-        if (x - w0).abs() <= self.coef * s0 {
-            x
-        } else {
-            #[cfg(feature = "extrapolation")]
-            {
-                // 線形外挿した値を返す
-                self.extrapolation()
-            }
-            
-            #[cfg(not(feature = "extrapolation"))]
-            {
-                // ウィンドウの中央値を返す
-                w0
+        *last = self.update(*last);
+    }
+
+    /// Same as [`process`](Self::process), but the input block `src` is left as is,
+    /// and the output is written to `dst`.
+    /// 
+    /// Returns an error without updating the window if `src` and `dst` have different lengths.
+    pub fn process_into(&mut self, src: &[T], dst: &mut [T]) -> Result<(), LengthMismatch> {
+        if src.len() != dst.len() {
+            return Err(LengthMismatch { src: src.len(), dst: dst.len() });
+        }
+        if let Some((&last, init)) = src.split_last() {
+            for (y, &x) in dst.iter_mut().zip(init) {
+                *y = self.advance(x).value;
             }
+            dst[src.len() - 1] = self.update(last);
         }
+        Ok(())
     }
 
-    /// working_arrayの中央値を返す
-    fn get_median(&mut self) -> T {
-        // Insertion sort
-        for i in 1..WINDOW_SIZE {
-            let mut j = i;
-            while j > 0 {
-                let j_pre = j - 1;
-                if unsafe{ self.working_array.get_unchecked(j_pre) > self.working_array.get_unchecked(j) } {
-                    self.working_array.swap(j_pre, j);
-                    j = j_pre;
-                } else {
-                    break;
-                }
-            }
+    /// Same as [`process`](Self::process) applied to the elements `offset, offset + stride, offset + 2 * stride, ...`
+    /// of `buf` (e.g. one channel of interleaved data), without copying them out.
+    /// 
+    /// Returns an error without updating the window if `offset >= stride`.
+    pub fn process_strided(&mut self, buf: &mut [T], offset: usize, stride: usize) -> Result<(), StrideError> {
+        if offset >= stride {
+            return Err(StrideError::InvalidOffset { offset, stride });
+        }
+        // 最後の要素だけupdateで処理し，直前の更新を取り消せるようにする
+        let count = buf.len().saturating_sub(offset).div_ceil(stride);
+        for (k, x) in buf.iter_mut().skip(offset).step_by(stride).enumerate() {
+            *x = if k + 1 < count { self.advance(*x).value } else { self.update(*x) };
         }
-        
-        self.working_array[WINDOW_SIZE / 2]
+        Ok(())
     }
 
-    /// 一番最後に追加されたデータ（外れ値）を無視して線形外挿する
-    #[cfg(feature = "extrapolation")]
-    fn extrapolation(&self) -> T {
-        // x座標を(0, 1, 2, ...)と取った場合の平均値（等差数列の平均）
-        let mu_x = cast::<usize, T>(WINDOW_SIZE - 2).unwrap() * cast::<f32, T>(0.5).unwrap();
+    /// Update for a sample that is missing (e.g. a dropped packet), and return the estimate of it
+    /// as specified by [`Replacement`] (the previous output for `HoldLast` and `PassThrough`).
+    /// 
+    /// By default the window is left unchanged (see [`WindowBuilder::fill_missing`]),
+    /// and after more than [`WindowBuilder::max_missing`] consecutive missing samples, [`Sample::stale`] is set.
+    /// 
+    /// ```
+    /// use hampel::{Replacement, Window};
+    /// 
+    /// let mut filter = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    /// filter.seed(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    /// assert_eq!(filter.update_missing(), 6.0);
+    /// ```
+    pub fn update_missing(&mut self) -> T {
+        self.update_missing_detailed().value
+    }
+
+    /// Same as [`update_missing`](Self::update_missing), but the estimate is returned together with
+    /// the statistics of the window it was based on. `is_outlier` is `false`.
+    pub fn update_missing_detailed(&mut self) -> Sample<T> {
+        let before = self.checkpoint();
+        if self.core.fill_missing {
+            self.time = self.time.map(|t| t + T::one());
+        }
+        let mut work = self.window;
+        let mut aux = self.window;
+        let s = self.core.update_missing(&mut self.window, &mut self.gaps, &mut work, &mut aux, &mut Selection(&mut self.sorted));
+        self.keep_checkpoint(before, &s);
+        self.handler.on_update(T::nan(), &s);
+        s
+    }
+
+    /// Update element in window with the timestamp `t` of `x`, for irregularly sampled data.
+    /// 
+    /// The decision is the same as [`update`](Self::update), but the extrapolating replacements use the timestamps
+    /// as the x-coordinates of the fit. A timestamp that is not greater than the previous one (or NaN)
+    /// is clamped to the previous one, and [`update`](Self::update) is the same as `update_at` with
+    /// the previous timestamp plus one.
+    /// 
+    /// ```
+    /// use hampel::{Replacement, Window};
+    /// 
+    /// let mut filter = Window::<f64, 4>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    /// for (t, x) in [(0.0, 0.0), (1.0, 2.0), (2.0, 4.0), (3.0, 6.0)] {
+    ///     filter.update_at(t, x);
+    /// }
+    /// assert_eq!(filter.update_at(10.0, 100.0), 20.0);  // x = 2t
+    /// ```
+    pub fn update_at(&mut self, t: T, x: T) -> T {
+        let before = self.checkpoint();
+        let gap = match self.time {
+            Some(prev) if t > prev => t - prev,
+            Some(_) => T::zero(),  // 時刻が進んでいない（またはNaN）ので直前のサンプルと同時刻とみなす
+            None => T::one(),
+        };
+        if self.time.is_none_or(|prev| t > prev) && !t.is_nan() {
+            self.time = Some(t);
+        }
+        let s = self.update_with_gap(gap, x);
+        self.keep_checkpoint(before, &s);
+        s.value
+    }
+
+    /// Undo the last update ([`update`](Self::update), [`update_at`](Self::update_at)
+    /// or [`update_missing`](Self::update_missing) and their detailed versions), e.g. for a sample
+    /// found later to be a duplicate, and return `true`.
+    /// 
+    /// Only one update can be undone, and only if the window is built with [`WindowBuilder::undoable`].
+    /// Returns `false` (and does nothing) if there is no update to undo, e.g. after a method changing the state
+    /// other than the updates, or if the last update re-seeded the window.
+    pub fn undo_last_update(&mut self) -> bool {
+        match self.undo.take() {
+            Some((c, time)) => {
+                self.core.rollback(&mut self.window, &mut self.gaps, &mut Selection(&mut self.sorted), &c);
+                self.time = time;
+                self.handler.on_undo();
+                true
+            },
+            None => false,
+        }
+    }
 
-        // windowの平均値（外れ値を除いた平均値なので WINDOW_SIZE-1 になっている）
-        let mut mu_y = T::zero();
-        for i in 0..(WINDOW_SIZE - 1) {
-            mu_y = mu_y + self.window[(self.oldest + i) % WINDOW_SIZE];
+    /// 更新の前の状態を写す（取り消せない設定ならNone）
+    fn checkpoint(&self) -> Option<(Checkpoint<T>, Option<T>)> {
+        self.core.undoable.then(|| (self.core.checkpoint(&self.window, &self.gaps), self.time))
+    }
+
+    /// 更新`s`を取り消せるように，その前の状態`before`を取っておく
+    fn keep_checkpoint(&mut self, before: Option<(Checkpoint<T>, Option<T>)>, s: &Sample<T>) {
+        self.undo = before.filter(|_| !s.reseeded);
+    }
+
+    /// 取り消し用の状態を取らずに，サンプル`x`でwindowを更新する
+    fn advance(&mut self, x: T) -> Sample<T> {
+        let gap = self.core.pending_gap();
+        self.time = self.time.map(|t| t + gap);
+        self.update_with_gap(gap, x)
+    }
+
+    /// 1つ前のサンプルとの時刻の差が`gap`のサンプル`x`でwindowを更新する
+    fn update_with_gap(&mut self, gap: T, x: T) -> Sample<T> {
+        let mut work = self.window;
+        let mut aux = self.window;
+        let s = self.core.update_at(&mut self.window, &mut self.gaps, &mut work, &mut aux, &mut Selection(&mut self.sorted), gap, x);
+        if s.is_outlier {
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            trace::replaced(self.core.label, self.core.seen, x, &s);
+            self.handler.on_outlier(OutlierEvent { raw: x, replacement: s.value, median: s.median, mad: s.mad });
         }
-        mu_y = mu_y / cast::<usize, T>(WINDOW_SIZE - 1).unwrap();
+        self.handler.on_update(x, &s);
+        s
+    }
 
-        let mut numer = T::zero();
-        let mut denom = T::zero();
-        for i in 0..(WINDOW_SIZE - 1) {
-            let dev_x = cast::<usize, T>(i).unwrap() - mu_x;
-            let dev_y = self.window[(self.oldest + i) % WINDOW_SIZE] - mu_y;
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    /// 
+    /// If the `warm_up` option is enabled, the filter enters the warm-up again.
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset(&mut self.window, &mut Selection(&mut self.sorted), init_val);
+        self.gaps = [T::one(); WINDOW_SIZE];
+        self.time = None;
+        self.undo = None;
+    }
 
-            numer = numer + dev_x * dev_y;
-            denom = denom + dev_x * dev_x;
+    /// Pre-load the window with recent measurements (`samples` is in chronological order).
+    /// 
+    /// Only the last `WINDOW_SIZE` samples are used, and if fewer are given, the window is filled
+    /// by repeating them (or, with the `warm_up` option, the filter stays in the warm-up).
+    /// An empty slice leaves the window unchanged.
+    pub fn seed(&mut self, samples: &[T]) {
+        let start = samples.len().saturating_sub(WINDOW_SIZE);
+        self.seed_from_iter(samples[start..].iter().copied());
+    }
+
+    /// Same as [`seed`](Self::seed), but the samples are taken from an iterator.
+    pub fn seed_from_iter<I: IntoIterator<Item = T>>(&mut self, samples: I) {
+        let mut work = self.window;
+        let mut aux = self.window;
+        let mut seeded = false;
+        let samples = samples.into_iter().inspect(|_| seeded = true);
+        self.core.seed_from_iter(&mut self.window, &mut work, &mut aux, &mut Selection(&mut self.sorted), samples);
+        if seeded {
+            self.gaps = [T::one(); WINDOW_SIZE];
+            self.undo = None;
         }
+    }
+
+    /// Snapshot of the state that can be copied byte by byte, without `serde` (see [`WindowState`]),
+    /// restored by [`from_state`](Window::from_state).
+    /// 
+    /// The configuration of the filter is not included.
+    pub fn to_state(&self) -> WindowState<T, WINDOW_SIZE> {
+        WindowState::capture(&self.core, &self.window, &self.gaps, self.time)
+    }
+
+    /// Number of samples stored in the window since construction (saturating).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
 
-        // 最小二乗法で求めた傾きと切片
-        let a = numer / denom;  // denom=0となることは無い
-        let b = mu_y - a * mu_x;
+    /// Returns `true` if the window has been filled with received samples.
+    /// 
+    /// If the `warm_up` option of [`WindowBuilder`] is enabled, the statistics are computed only over
+    /// the received samples until the filter is warmed up.
+    pub fn is_warmed_up(&self) -> bool {
+        self.core.seen >= WINDOW_SIZE
+    }
 
-        a * cast::<usize, T>(WINDOW_SIZE - 1).unwrap() + b
+    /// Median of the window used for the decision by the last update (see [`Sample::median`]).
+    /// 
+    /// It is the initialization value before the first update, and is recomputed by [`seed`](Self::seed).
+    pub fn median(&self) -> T {
+        self.core.median
     }
+
+    /// Scale estimate of the window used for the decision by the last update (see [`Sample::mad`]).
+    /// 
+    /// It is zero before the first update, and is recomputed by [`seed`](Self::seed).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// Acceptance band of the value used for the decision by the last update (see [`Sample::bounds`]).
+    /// 
+    /// Before the first update, both ends are the initialization value.
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// Iterator over the samples in the window, from the oldest to the newest.
+    /// 
+    /// It always yields `WINDOW_SIZE` samples; until the window is filled, the oldest ones are
+    /// the initialization value (or the samples repeated by [`seed`](Self::seed)).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.core.oldest)
+    }
+
+    /// Newest sample in the window (the initialization value before the first update).
+    /// 
+    /// Non-finite inputs are stored as their replacement if [`NanPolicy::TreatAsOutlier`] is selected.
+    pub fn latest(&self) -> T {
+        self.core.latest(&self.window)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Current `n_sigma`, adapted to the recent outlier rate if [`WindowBuilder::adaptive`] is enabled
+    /// (for asymmetric thresholds, that of the upper side).
+    pub fn effective_n_sigma(&self) -> T {
+        self.core.effective_n_sigma()
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier, so that the next sample
+    /// is tested against the exit threshold of [`WindowBuilder::hysteresis`] (if enabled).
+    /// It is cleared by [`reset`](Self::reset) and [`seed`](Self::seed), and missing samples do not change it.
+    pub fn in_outlier_run(&self) -> bool {
+        self.core.in_run
+    }
+
+    /// Statistics of the outlier decisions since construction or the last [`reset_stats`](Self::reset_stats).
+    /// 
+    /// The statistics are not cleared by [`reset`](Self::reset) or [`seed`](Self::seed).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// Clear the statistics of the outlier decisions.
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+        self.undo = None;
+    }
+
+    /// Set the lower bound of the scale estimate (the MAD, or the one selected by the `scale_estimator`).
+    /// 
+    /// The decision uses `max(s0, mad_floor)` instead of `s0`, so that a constant (or finely quantized)
+    /// signal, whose MAD is exactly zero, does not turn every small change into an outlier.
+    /// The default floor is `0.0`. A NaN floor is ignored.
+    pub fn set_mad_floor(&mut self, mad_floor: T) {
+        self.core.mad_floor = mad_floor;
+        self.undo = None;
+    }
+
+    /// Lower bound of the scale estimate currently in use (see [`set_mad_floor`](Self::set_mad_floor)).
+    pub fn mad_floor(&self) -> T {
+        self.core.mad_floor
+    }
+
+    /// Returns `true` if `x` would be determined to be an outlier by [`update`](Self::update),
+    /// without modifying the window.
+    pub fn would_flag(&self, x: T) -> bool {
+        let mut work = self.window;
+        let mut aux = self.window;
+        self.core.would_flag(&self.window, &mut work, &mut aux, x)
+    }
+
+    /// Returns the [`Sample::score`] that [`update`](Self::update) would give for `x`,
+    /// without modifying the window (see [`would_flag`](Self::would_flag)).
+    pub fn score(&self, x: T) -> T {
+        let mut work = self.window;
+        let mut aux = self.window;
+        self.core.score_of(&self.window, &mut work, &mut aux, x)
+    }
+}
+
+/// Two windows are equal if they hold the same samples in the same chronological order
+/// and the filters have the same state and configuration.
+/// The attached handler is not compared. (As with floats, a window containing NaN is not equal to itself.)
+impl<T: FloatCore, const WINDOW_SIZE: usize, H> PartialEq for Window<T, WINDOW_SIZE, H> {
+    fn eq(&self, other: &Self) -> bool {
+        let gaps = Samples::new(&self.gaps, self.core.oldest);
+        let other_gaps = Samples::new(&other.gaps, other.core.oldest);
+        self.core.eq_with(&self.window, &other.core, &other.window) && self.time == other.time && gaps.eq(other_gaps)
+    }
+}
+
+macro_rules! impl_scale_consts {
+    ($(#[$attr:meta])* $t:ty, $from:path) => {
+        $(#[$attr])*
+        impl<const WINDOW_SIZE: usize> Window<$t, WINDOW_SIZE> {
+            /// Consistency constant for Gaussian noise (`1 / Φ⁻¹(3/4)`).
+            pub const GAUSSIAN_SCALE: $t = $from(1.4826);
+            /// Consistency constant for Laplacian noise (`√2 / ln 2`).
+            pub const LAPLACE_SCALE: $t = $from(2.0403);
+            /// Consistency constant for uniform noise (`2 / √3`).
+            pub const UNIFORM_SCALE: $t = $from(1.1547);
+        }
+    };
+}
+impl_scale_consts!(f32, core::convert::identity);
+impl_scale_consts!(f64, core::convert::identity);
+impl_scale_consts!(
+    /// Half-precision windows (`half` feature).
+    /// 
+    /// The window is stored in `T`, but the computations that could overflow or lose precision in `T`
+    /// (the threshold, the average of two values and the linear extrapolation) are done in `f32`,
+    /// so that the decisions are those of an `f32` window except within about one step of the threshold.
+    #[cfg(feature = "half")]
+    half::f16,
+    half::f16::from_f32_const
+);
+#[cfg(feature = "half")]
+impl_scale_consts!(half::bf16, half::bf16::from_f32_const);
+
+macro_rules! impl_new_const {
+    ($(#[$attr:meta])* $t:ty) => {
+        impl<const WINDOW_SIZE: usize> Window<$t, WINDOW_SIZE> {
+            /// Same as [`new`](Self::new), but a `const fn`, so that the filter can be initialized
+            /// in a `static` at compile time.
+            $(#[$attr])*
+            /// 
+            /// # Panics
+            /// 
+            /// Same as [`new`](Self::new) (in a constant, it fails to compile).
+            pub const fn new_const(init_val: $t, n_sigma: $t) -> Self {
+                let () = Self::VALID_SIZE;
+                assert!(n_sigma > 0.0 && n_sigma.is_finite(), "n_sigma must be a positive finite number");
+                // 1.4826は正規分布にするための係数（`new`と同じくf32の値を使う）
+                let scale = 1.4826f32 as $t;
+                let nan_count = if init_val.is_nan() { WINDOW_SIZE } else { 0 };
+                let mut core = Core::new_const(init_val, n_sigma, scale, scale * n_sigma, 0.0, WINDOW_SIZE, nan_count);
+                // 外挿に使う定数（`LineConstants::new`と同じ順序で求める）
+                let n = WINDOW_SIZE - 1;
+                let (mut count, mut sum, mut x, mut i) = (0.0, 0.0, 0.0, 0);
+                while i < n {
+                    if i > 0 {
+                        x += 1.0;
+                    }
+                    count += 1.0;
+                    sum += x;
+                    i += 1;
+                }
+                let mu_x = sum / count;
+                let (mut denom, mut d, mut i) = (0.0, 0.0, 0);
+                while i < n {
+                    if i > 0 {
+                        d += 1.0;
+                    }
+                    denom += (d - mu_x) * (d - mu_x);
+                    i += 1;
+                }
+                core.line = Some(LineConstants { n, count, mu_x, denom, last_x: x });
+                let window = [init_val; WINDOW_SIZE];
+                Window { window, sorted: window, gaps: [1.0; WINDOW_SIZE], time: None, core, undo: None, handler: () }
+            }
+        }
+    };
 }
+impl_new_const!(
+    /// 
+    /// ```
+    /// use std::sync::Mutex;
+    /// use hampel::Window;
+    /// 
+    /// static FILTER: Mutex<Window<f32, 11>> = Mutex::new(Window::<f32, 11>::new_const(0.0, 3.0));
+    /// 
+    /// let y = FILTER.lock().unwrap().update(0.5);
+    /// ```
+    f32
+);
+impl_new_const!(f64);
@@ -0,0 +1,245 @@
+//! Filter for strictly positive signals, with the outliers detected in the log domain.
+
+use num_traits::float::FloatCore;
+#[cfg(feature = "std")]
+use num_traits::cast;
+
+use crate::{NanPolicy, Sample, Window};
+
+/// How a [`LogWindow`] handles the samples outside the domain of the transform (zero, negative and NaN).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NonPositive<T> {
+    /// Determined to be an outlier and replaced, without being stored in the window.
+    FlagAsOutlier,
+    /// Samples below the floor (> 0) are raised to it before the test,
+    /// and the floor is returned if the raised sample is not an outlier.
+    /// NaN is still determined to be an outlier.
+    Floor(T),
+}
+
+impl<T> Default for NonPositive<T> {
+    /// `FlagAsOutlier`.
+    fn default() -> Self {
+        NonPositive::FlagAsOutlier
+    }
+}
+
+/// Hampel filter applied to a monotone transform of the samples (the natural logarithm by default),
+/// for signals spanning several decades such as photodiode currents or particle counts,
+/// whose multiplicative noise becomes additive in the log domain.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The transformed samples are filtered by a [`Window`], and the replacements of the outliers are
+/// converted back by the inverse transform. A sample that is not an outlier is returned exactly as it was given,
+/// and so is a sample of the window returned as the replacement (e.g. the median for odd `WINDOW_SIZE`).
+///
+/// The spikes of 10× and 1/10× are caught uniformly over six decades:
+///
+/// ```
+/// use hampel::{LogWindow, NonPositive};
+///
+/// // Ramp from 1e-9 to 1e-3 with multiplicative noise of a few percent
+/// let noise = |i: usize| ((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) as f64 / (1u64 << 24) as f64 - 0.5;
+/// let level = |i: usize| 1e-9 * 10f64.powf(6.0 * i as f64 / 3000.0);
+/// let signal = |i: usize| level(i) * (0.05 * noise(i)).exp();
+///
+/// let mut filter = LogWindow::<f64, 9>::with_transform(signal(0), 3.0, NonPositive::FlagAsOutlier, f64::ln, f64::exp);
+/// let mut caught = [0; 6];  // Per decade
+/// for i in 0..=3000 {
+///     let gain = match i % 50 {
+///         25 => 10.0,
+///         40 => 0.1,
+///         _ => 1.0,
+///     };
+///     let x = signal(i) * gain;
+///     let y = filter.update(x);
+///     if i < 9 {
+///         continue;  // Until the initial window is flushed out
+///     }
+///     assert_eq!(filter.is_last_outlier(), gain != 1.0);
+///     if gain == 1.0 {
+///         assert_eq!(y.to_bits(), x.to_bits());
+///     } else {
+///         assert!((y / level(i) - 1.0).abs() < 0.1);
+///         caught[(i / 500).min(5)] += 1;
+///     }
+/// }
+/// assert_eq!(caught, [20; 6]);
+/// ```
+///
+/// Samples outside the domain of the transform are handled as [`NonPositive`] specifies:
+///
+/// ```
+/// use hampel::{LogWindow, NonPositive};
+///
+/// let mut flag = LogWindow::<f64, 5>::with_transform(1e-6, 3.0, NonPositive::FlagAsOutlier, f64::ln, f64::exp);
+/// for x in [0.0, -1e-6, f64::NAN] {
+///     assert_eq!(flag.update(x), 1e-6);
+///     assert!(flag.is_last_outlier());
+/// }
+///
+/// let mut floor = LogWindow::<f64, 5>::with_transform(1e-12, 3.0, NonPositive::Floor(1e-12), f64::ln, f64::exp);
+/// assert_eq!(floor.update(0.0), 1e-12);
+/// assert_eq!(floor.update(-5.0), 1e-12);
+/// assert!(!floor.is_last_outlier());
+/// assert!(floor.update(f64::NAN).is_finite());
+/// assert!(floor.is_last_outlier());
+/// ```
+#[derive(Clone)]
+pub struct LogWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: Window<T, WINDOW_SIZE>,  // 変換した値のwindow
+    samples: [T; WINDOW_SIZE],  // windowに格納した値の変換前の値（windowと同じ順に格納する）
+    oldest: usize,  // samples内の最も古い要素のインデックス
+    pub(crate) policy: NonPositive<T>,
+    transform: fn(T) -> T,
+    inverse: fn(T) -> T,
+    pub(crate) last_outlier: bool,  // 直前のサンプルを外れ値と判定したか
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> LogWindow<T, WINDOW_SIZE> {
+    /// Filter of the natural logarithm of the samples (`std` feature).
+    ///
+    /// * `init_val`: Initialization value of window (in linear units).
+    /// * `n_sigma`: Threshold for determining an outlier in the log domain (see [`Window::new`]).
+    /// * `policy`: How zero, negative and NaN samples are handled.
+    ///
+    /// ```
+    /// use hampel::{LogWindow, NonPositive};
+    ///
+    /// let mut filter = LogWindow::<f32, 5>::new(1e-6, 3.0, NonPositive::FlagAsOutlier);
+    /// for x in [1.1e-6, 0.9e-6, 1.0e-6, 1.2e-6, 0.8e-6] {
+    ///     filter.update(x);
+    /// }
+    /// assert_eq!(filter.update(1.05e-6), 1.05e-6);
+    /// assert!(!filter.is_last_outlier());
+    /// assert!((filter.update(5e-5) - 1e-6).abs() < 0.3e-6);
+    /// assert!(filter.is_last_outlier());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`with_transform`](Self::with_transform).
+    #[cfg(feature = "std")]
+    pub fn new(init_val: T, n_sigma: T, policy: NonPositive<T>) -> Self {
+        Self::with_transform(init_val, n_sigma, policy, ln, exp)
+    }
+
+    /// Filter of `transform(x)`, whose outputs are converted back by `inverse`.
+    ///
+    /// * `init_val`: Initialization value of window (in linear units).
+    /// * `n_sigma`: Threshold for determining an outlier in the transformed domain (see [`Window::new`]).
+    /// * `policy`: How zero, negative and NaN samples are handled.
+    /// * `transform`: Monotone transform, finite for the positive samples (e.g. `f64::ln` or `f64::log10`).
+    /// * `inverse`: Inverse of `transform` (e.g. `f64::exp`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arguments are invalid for [`Window::new`], the floor of [`NonPositive::Floor`]
+    /// is not positive and finite, or `init_val` is not positive (after raising to the floor).
+    pub fn with_transform(init_val: T, n_sigma: T, policy: NonPositive<T>, transform: fn(T) -> T, inverse: fn(T) -> T) -> Self {
+        if let NonPositive::Floor(floor) = policy {
+            assert!(floor > T::zero() && floor.is_finite(), "floor must be a positive finite number");
+        }
+        let mut filter = Self {
+            window: Window::builder().n_sigma(n_sigma).nan_policy(NanPolicy::TreatAsOutlier).build(),
+            samples: [init_val; WINDOW_SIZE],
+            oldest: 0,
+            policy,
+            transform,
+            inverse,
+            last_outlier: false,
+        };
+        filter.reset(init_val);
+        filter
+    }
+
+    /// Update the window, and return the filtered value (in linear units).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).0
+    }
+
+    /// Same as [`update`](Self::update), but the result of the filter in the transformed domain is also returned.
+    pub fn update_detailed(&mut self, x: T) -> (T, Sample<T>) {
+        let x = self.domain(x);
+        // 定義域外のサンプルはNaNとして渡せば，windowに入れずに外れ値と判定される
+        let v = if x > T::zero() { (self.transform)(x) } else { T::nan() };
+        let s = self.window.update_detailed(v);
+        self.last_outlier = s.is_outlier;
+        let value = if s.is_outlier { self.original(s.value) } else { x };
+        // NaNの代わりにwindowに入った値（置き換えた値）を記録する
+        self.samples[self.oldest] = if v.is_nan() { value } else { x };
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        (value, s)
+    }
+
+    /// 変換した値`v`を元に戻した値（windowのサンプルを変換した値なら，そのサンプルそのもの）
+    fn original(&self, v: T) -> T {
+        let transform = self.transform;
+        match self.samples.iter().find(|&&x| transform(x) == v) {
+            Some(&x) => x,
+            None => (self.inverse)(v),
+        }
+    }
+
+    /// `policy`に従って下限まで引き上げたサンプル
+    fn domain(&self, x: T) -> T {
+        match self.policy {
+            NonPositive::Floor(floor) if x < floor => floor,
+            _ => x,
+        }
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier.
+    pub fn is_last_outlier(&self) -> bool {
+        self.last_outlier
+    }
+
+    /// Restore the state right after construction, with the window filled with `transform(init_val)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `init_val` is not positive (after raising to the floor).
+    pub fn reset(&mut self, init_val: T) {
+        let init_val = self.domain(init_val);
+        assert!(init_val > T::zero(), "init_val must be positive");
+        self.window.reset((self.transform)(init_val));
+        self.samples = [init_val; WINDOW_SIZE];
+        self.oldest = 0;
+        self.last_outlier = false;
+    }
+
+    /// How zero, negative and NaN samples are handled.
+    pub fn policy(&self) -> NonPositive<T> {
+        self.policy
+    }
+
+    /// Window of the transformed samples.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// 自然対数（f64で計算する）
+#[cfg(feature = "std")]
+fn ln<T: FloatCore>(x: T) -> T {
+    cast::<f64, T>(cast::<T, f64>(x).unwrap().ln()).unwrap()
+}
+
+/// 指数関数（f64で計算する）
+#[cfg(feature = "std")]
+fn exp<T: FloatCore>(x: T) -> T {
+    cast::<f64, T>(cast::<T, f64>(x).unwrap().exp()).unwrap()
+}
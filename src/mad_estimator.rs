@@ -0,0 +1,117 @@
+//! Running MAD of a window, as a robust estimate of the noise level.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{precision, MedianWindow};
+
+/// Running MAD (median absolute deviation) of the last `WINDOW_SIZE` samples,
+/// e.g. as an estimate of the noise level for thresholds elsewhere.
+///
+/// * `WINDOW_SIZE` >= 1
+///
+/// [`update`](Self::update) returns `scale_const * MAD`, the estimate of the standard deviation
+/// (`scale_const = 1.4826` by default, as [`Window`](crate::Window) uses; see [`with_scale`](Self::with_scale)).
+/// The median is kept by a [`MedianWindow`], and the MAD is read from its sorted window
+/// in the same way as [`Window`](crate::Window). The result is the same as the MAD computed offline:
+///
+/// ```
+/// use hampel::MadEstimator;
+///
+/// let mut estimator = MadEstimator::<f64, 5>::new(0.0);
+/// for x in [1.0, 2.0, 4.0, 8.0, 16.0] {
+///     estimator.update(x);
+/// }
+///
+/// // Median 4, absolute deviations [3, 2, 0, 4, 12]
+/// assert_eq!(estimator.mad(), 3.0);
+/// assert!((estimator.sigma() - 1.4826 * 3.0).abs() < 1e-6);
+/// ```
+#[derive(Clone)]
+pub struct MadEstimator<T: FloatCore, const WINDOW_SIZE: usize> {
+    window: MedianWindow<T, WINDOW_SIZE>,
+    scale_const: T,  // MADに掛ける係数
+    mad: T,  // 直近の更新で求めた中央絶対偏差
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> MadEstimator<T, WINDOW_SIZE> {
+    /// * `init_val`: Initialization value of window.
+    ///
+    /// The MAD is scaled by `1.4826`, the consistency constant for normally distributed samples.
+    ///
+    /// ```
+    /// use hampel::MadEstimator;
+    ///
+    /// // Constant input
+    /// let mut estimator = MadEstimator::<f64, 5>::new(0.0);
+    /// for _ in 0..10 {
+    ///     assert_eq!(estimator.update(3.0), 0.0);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WINDOW_SIZE` is zero.
+    pub fn new(init_val: T) -> Self {
+        Self::with_scale(init_val, cast::<f32, T>(1.4826).unwrap())
+    }
+
+    /// Same as [`new`](Self::new), but the MAD is scaled by `scale_const` (`1.0` for the raw MAD).
+    ///
+    /// # Panics
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_scale(init_val: T, scale_const: T) -> Self {
+        Self { window: MedianWindow::new(init_val), scale_const, mad: T::zero() }
+    }
+
+    /// If `true`, until the window is filled, the MAD is taken only over the received samples
+    /// (as the `warm_up` option of [`WindowBuilder`](crate::WindowBuilder)).
+    ///
+    /// ```
+    /// use hampel::MadEstimator;
+    ///
+    /// let mut estimator = MadEstimator::<f64, 8>::with_scale(0.0, 1.0).warm_up(true);
+    /// assert_eq!(estimator.update(10.0), 0.0);
+    /// assert_eq!(estimator.update(12.0), 1.0);
+    /// assert_eq!(estimator.update(11.0), 1.0);
+    /// assert_eq!(estimator.update(15.0), 1.0);  // Median 11.5, deviations [1.5, 0.5, 0.5, 3.5]
+    /// ```
+    pub fn warm_up(mut self, warm_up: bool) -> Self {
+        self.window.warm_up = warm_up;
+        self
+    }
+
+    /// Add a sample, and return the scaled MAD of the window ([`sigma`](Self::sigma)).
+    pub fn update(&mut self, x: T) -> T {
+        self.window.update(x);
+        self.mad = self.window.deviation();
+        self.sigma()
+    }
+
+    /// MAD of the window computed by the last update (zero before the first update).
+    pub fn mad(&self) -> T {
+        self.mad
+    }
+
+    /// `scale_const * MAD`, the estimate of the standard deviation.
+    pub fn sigma(&self) -> T {
+        precision::mul(self.scale_const, self.mad)
+    }
+
+    /// Median of the window computed by the last update (the initialization value before the first update).
+    pub fn median(&self) -> T {
+        self.window.median()
+    }
+
+    /// The underlying moving median, for the introspection of the window
+    /// (e.g. [`MedianWindow::iter`], [`MedianWindow::samples_seen`]).
+    pub fn window(&self) -> &MedianWindow<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    pub fn reset(&mut self, init_val: T) {
+        self.window.reset(init_val);
+        self.mad = T::zero();
+    }
+}
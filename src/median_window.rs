@@ -0,0 +1,191 @@
+//! Moving median filter without the outlier detection.
+
+use num_traits::float::FloatCore;
+
+use crate::{iter::Samples, order::Ordered, stats};
+
+/// Moving median of the last `WINDOW_SIZE` samples, e.g. as a pre- or post-stage of a [`Window`](crate::Window).
+///
+/// * `WINDOW_SIZE` >= 1
+///
+/// The window and its sorted copy are kept in the same way as [`Window`](crate::Window),
+/// so an update takes a binary search and a shift of the elements in between.
+/// For even window sizes, the median is the average of the two middle elements:
+///
+/// ```
+/// use hampel::MedianWindow;
+///
+/// let mut filter = MedianWindow::<f64, 4>::new(0.0);
+/// for x in [5.0, 1.0, 3.0] {
+///     filter.update(x);
+/// }
+/// assert_eq!(filter.median(), 2.0);  // [0, 5, 1, 3]
+/// assert_eq!(filter.update(-2.0), 2.0);  // [5, 1, 3, -2]
+/// assert_eq!(filter.update(4.0), 2.0);  // [1, 3, -2, 4]
+/// ```
+///
+/// NaN samples are kept in the window. While the window contains a NaN,
+/// the median is computed from a copy of the window, as [`Window`](crate::Window) does.
+#[derive(Clone)]
+pub struct MedianWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],
+    sorted: [T; WINDOW_SIZE],  // windowを昇順に並べたもの（NaNを含む間は使わない）
+    work: [T; WINDOW_SIZE],  // sortedを使えない場合の中央値計算用の作業領域
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) seen: usize,  // windowに格納したサンプル数
+    pub(crate) warm_up: bool,  // 埋まるまでは受け取ったサンプルだけの中央値を返す
+    nan_count: usize,  // window内のNaNの数
+    median: T,  // 直近の更新で求めた中央値
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> MedianWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が1以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 1, "WINDOW_SIZE must be at least 1");
+
+    /// * `init_val`: Initialization value of window.
+    ///
+    /// `WINDOW_SIZE` = 0 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::MedianWindow::<f32, 0>::new(0.0);
+    /// ```
+    pub fn new(init_val: T) -> Self {
+        let () = Self::VALID_SIZE;
+
+        let mut filter = Self {
+            window: [init_val; WINDOW_SIZE],
+            sorted: [init_val; WINDOW_SIZE],
+            work: [init_val; WINDOW_SIZE],
+            oldest: 0,
+            seen: 0,
+            warm_up: false,
+            nan_count: 0,
+            median: init_val,
+        };
+        filter.reset(init_val);
+        filter
+    }
+
+    /// Same as [`new`](Self::new), but until the window is filled, the median is taken only over
+    /// the received samples (as the `warm_up` option of [`WindowBuilder`](crate::WindowBuilder)).
+    ///
+    /// `init_val` is returned by [`median`](Self::median) before the first update.
+    ///
+    /// ```
+    /// use hampel::MedianWindow;
+    ///
+    /// let mut filter = MedianWindow::<f64, 5>::with_warm_up(0.0);
+    /// assert_eq!(filter.update(10.0), 10.0);
+    /// assert_eq!(filter.update(11.0), 10.5);
+    /// assert_eq!(filter.update(30.0), 11.0);
+    /// assert!(!filter.is_warmed_up());
+    /// ```
+    pub fn with_warm_up(init_val: T) -> Self {
+        let mut filter = Self::new(init_val);
+        filter.warm_up = true;
+        filter
+    }
+
+    /// Add a sample, and return the median of the window.
+    pub fn update(&mut self, x: T) -> T {
+        let slot = self.oldest;
+        let evicted = self.window[slot];
+        self.window[slot] = x;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        self.seen = self.seen.saturating_add(1);
+
+        let was_clean = self.nan_count == 0;
+        self.nan_count = self.nan_count + x.is_nan() as usize - evicted.is_nan() as usize;
+        if self.nan_count == 0 {
+            if was_clean {
+                self.sorted.replace(slot, evicted, x);
+            } else {
+                self.sorted.rebuild(&self.window);  // NaNが無くなったので作り直す
+            }
+        }
+
+        let n = self.filled();
+        self.median = if n == WINDOW_SIZE && self.nan_count == 0 {
+            self.sorted.median()
+        } else {
+            // 埋まるまでは受け取ったサンプル（window[..n]）だけを使う
+            let work = &mut self.work[..n];
+            work.copy_from_slice(&self.window[..n]);
+            stats::median(work)
+        };
+        self.median
+    }
+
+    /// 統計量の計算に使うサンプル数
+    fn filled(&self) -> usize {
+        if self.warm_up && self.seen < WINDOW_SIZE {
+            self.seen
+        } else {
+            WINDOW_SIZE
+        }
+    }
+
+    /// 直近の更新で求めた中央値からの偏差の絶対値の中央値（`update`と同じサンプルを使う）
+    pub(crate) fn deviation(&mut self) -> T {
+        let n = self.filled();
+        if n == WINDOW_SIZE && self.nan_count == 0 {
+            self.sorted.mad(self.median, &mut self.work)
+        } else {
+            let work = &mut self.work[..n];
+            for (w, x) in work.iter_mut().zip(&self.window[..n]) {
+                *w = (*x - self.median).abs();
+            }
+            stats::median(work)
+        }
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    ///
+    /// If the filter was created by [`with_warm_up`](Self::with_warm_up), it enters the warm-up again.
+    pub fn reset(&mut self, init_val: T) {
+        self.window = [init_val; WINDOW_SIZE];
+        self.nan_count = if init_val.is_nan() { WINDOW_SIZE } else { 0 };
+        if self.nan_count == 0 {
+            self.sorted.rebuild(&self.window);
+        }
+        self.oldest = 0;
+        self.seen = 0;
+        self.median = init_val;
+    }
+
+    /// Number of samples stored in the window since construction (saturating).
+    pub fn samples_seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Returns `true` if the window has been filled with received samples.
+    pub fn is_warmed_up(&self) -> bool {
+        self.seen >= WINDOW_SIZE
+    }
+
+    /// Median returned by the last update (the initialization value before the first update).
+    pub fn median(&self) -> T {
+        self.median
+    }
+
+    /// Iterator over the samples in the window, from the oldest to the newest
+    /// (see [`Window::iter`](crate::Window::iter)).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.oldest)
+    }
+
+    /// Newest sample in the window (the initialization value before the first update).
+    pub fn latest(&self) -> T {
+        self.window[(self.oldest + WINDOW_SIZE - 1) % WINDOW_SIZE]
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least one element.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
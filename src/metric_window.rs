@@ -0,0 +1,193 @@
+//! Hampel filter for samples of a metric space, with the medoid as the center.
+
+use num_traits::float::FloatCore;
+
+use crate::{filter, iter::Samples, offline, precision, stats};
+
+/// Hampel filter for samples that are not scalars (e.g. 2-D points, unit quaternions or RSSI fingerprints),
+/// given a distance between two samples.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The center of the window is the medoid (the sample of the window whose summed distance to the others is the smallest,
+/// the oldest one on a tie), and the scale is the median of the distances of the samples of the window to the medoid.
+/// A new sample is an outlier if its distance to the medoid exceeds `1.4826 * n_sigma * scale`, as for [`Window`](crate::Window),
+/// and is replaced by the medoid. The medoid is found from all the pairwise distances, i.e. `WINDOW_SIZE²` calls of the distance
+/// per update.
+///
+/// The distance must be non-negative and symmetric, and zero between a sample and itself.
+///
+/// Positions in 2-D with occasional jumps of the receiver are replaced by points of the track:
+///
+/// ```
+/// use hampel::MetricWindow;
+///
+/// let distance = |a: &(f64, f64), b: &(f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+/// // Walk along a circle of radius 100 m, with a jitter of a few centimeters
+/// let track = |i: usize| {
+///     let theta = i as f64 * 1e-4;
+///     let jitter = ((i * 7919) % 11) as f64 * 0.01 - 0.05;
+///     (100.0 * theta.cos() + jitter, 100.0 * theta.sin() - jitter)
+/// };
+///
+/// let mut filter = MetricWindow::<(f64, f64), f64, 9>::new(track(0), 3.0, distance);
+/// let mut recent = vec![track(0); 9];
+/// for i in 1..2000 {
+///     // A jump of 3 m in a random direction every 50 samples
+///     let p = if i % 50 == 25 {
+///         let phi = i as f64;
+///         (track(i).0 + 3.0 * phi.cos(), track(i).1 + 3.0 * phi.sin())
+///     } else {
+///         track(i)
+///     };
+///     recent.remove(0);
+///     recent.push(p);
+///     let y = filter.update(p);
+///     if i < 9 {
+///         continue;  // Until the initial window is flushed out
+///     }
+///     assert_eq!(filter.is_last_outlier(), i % 50 == 25);
+///     if filter.is_last_outlier() {
+///         // The medoid is the sample of the window closest to the others, without the jump
+///         assert_eq!(y, filter.medoid());
+///         assert!(recent.contains(&y));
+///         assert!(distance(&y, &track(i)) < 0.5);
+///     } else {
+///         assert_eq!(y, p);
+///     }
+///     assert!(filter.scale() < 0.1);
+/// }
+/// ```
+///
+/// With `|a - b|` as the distance, the medoid of an odd window is the median and the scale is the MAD,
+/// so that the filter is the same as [`Window`](crate::Window) with [`Replacement::Median`](crate::Replacement::Median).
+/// (For even `WINDOW_SIZE`, the medoid is one of the two middle samples instead of their average.)
+///
+/// ```
+/// use hampel::{MetricWindow, Replacement, Window};
+///
+/// let mut metric = MetricWindow::<f64, f64, 5>::new(0.0, 3.0, |a, b| (a - b).abs());
+/// let mut plain = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     let s = plain.update_detailed(x);
+///     assert_eq!(metric.update(x), s.value);
+///     assert_eq!(metric.is_last_outlier(), s.is_outlier);
+///     assert_eq!((metric.medoid(), metric.scale()), (s.median, s.mad));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MetricWindow<P: Copy, T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [P; WINDOW_SIZE],
+    work: [T; WINDOW_SIZE],  // 距離の作業領域
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    distance: fn(&P, &P) -> T,
+    coef: T,  // 閾値判定に使う係数
+    medoid: P,  // 直近の更新で求めたmedoid
+    scale: T,  // 直近の更新で求めたmedoidまでの距離の中央値
+    pub(crate) last_outlier: bool,  // 直前のサンプルを外れ値と判定したか
+}
+
+impl<P: Copy, T: FloatCore, const WINDOW_SIZE: usize> MetricWindow<P, T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    /// * `distance`: Distance between two samples.
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::MetricWindow::<f32, f32, 2>::new(0.0, 3.0, |a, b| (a - b).abs());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(init_val: P, n_sigma: T, distance: fn(&P, &P) -> T) -> Self {
+        let () = Self::VALID_SIZE;
+        filter::assert_n_sigma(n_sigma);
+
+        Self {
+            window: [init_val; WINDOW_SIZE],
+            work: [T::zero(); WINDOW_SIZE],
+            oldest: 0,
+            distance,
+            coef: offline::coefficient(n_sigma),
+            medoid: init_val,
+            scale: T::zero(),
+            last_outlier: false,
+        }
+    }
+
+    /// Update element in window, and return the filtered sample.
+    pub fn update(&mut self, p: P) -> P {
+        let latest = self.oldest;
+        self.window[latest] = p;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+
+        let medoid = self.find_medoid();
+        for (w, q) in self.work.iter_mut().zip(&self.window) {
+            *w = (self.distance)(q, &self.window[medoid]);
+        }
+        let d = self.work[latest];
+        self.medoid = self.window[medoid];
+        self.scale = stats::median(&mut self.work);
+        self.last_outlier = precision::exceeds(d, T::zero(), self.coef, self.scale);
+        if self.last_outlier { self.medoid } else { p }
+    }
+
+    /// 他のサンプルまでの距離の合計が最小のサンプルのインデックス（同じなら古いもの）
+    fn find_medoid(&self) -> usize {
+        let mut best = self.oldest;
+        let mut best_sum = T::infinity();
+        for k in 0..WINDOW_SIZE {
+            let i = (self.oldest + k) % WINDOW_SIZE;
+            let sum = self.window.iter().fold(T::zero(), |s, q| s + (self.distance)(&self.window[i], q));
+            if sum < best_sum {
+                (best, best_sum) = (i, sum);
+            }
+        }
+        best
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier.
+    pub fn is_last_outlier(&self) -> bool {
+        self.last_outlier
+    }
+
+    /// Medoid of the window used by the last update.
+    pub fn medoid(&self) -> P {
+        self.medoid
+    }
+
+    /// Median of the distances to the medoid of the window used by the last update,
+    /// before multiplying by the consistency constant.
+    pub fn scale(&self) -> T {
+        self.scale
+    }
+
+    /// Fill the window with `init_val`, as right after construction.
+    pub fn reset(&mut self, init_val: P) {
+        self.window = [init_val; WINDOW_SIZE];
+        self.oldest = 0;
+        self.medoid = init_val;
+        self.scale = T::zero();
+        self.last_outlier = false;
+    }
+
+    /// Iterator over the samples in the window in chronological order (oldest first).
+    pub fn iter(&self) -> Samples<'_, P> {
+        Samples::new(&self.window, self.oldest)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
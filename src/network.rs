@@ -0,0 +1,133 @@
+//! Median-selection networks for small windows.
+//!
+//! For 5, 7 and 9 elements, the median is found by a fixed sequence of compare-exchange operations
+//! (7, 13 and 19 of them, fewer than the optimal sorting networks of 9, 16 and 25), instead of
+//! the insertion sort of `stats::median`. The sequence does not depend on the values.
+//!
+//! The compare-exchange orders the values totally (`-NaN < -inf < -0.0 < +0.0 < +inf < +NaN`), so the
+//! median is the same element whatever the order of the input, also with NaN and signed zeros.
+//! [`Selection`] applies the networks to `Window` when `WINDOW_SIZE` is one of these sizes.
+//! `stats::median` does not use them while the values contain NaN, so that the results with NaN
+//! are those of the insertion sort for every size.
+
+use num_traits::float::FloatCore;
+
+use crate::{order::Ordered, stats};
+
+/// 比較交換の列`(a, b)`（`work[a]`に小さい方，`work[b]`に大きい方を置く）を展開して，中央の要素を返す関数を定義する
+macro_rules! network {
+    ($name:ident, $n:literal, [$(($a:literal, $b:literal)),* $(,)?]) => {
+        #[inline(always)]
+        fn $name<T: FloatCore>(work: &mut [T; $n]) -> T {
+            $(
+                let (lo, hi) = exchange(work[$a], work[$b]);
+                work[$a] = lo;
+                work[$b] = hi;
+            )*
+            work[$n / 2]
+        }
+    };
+}
+
+// 5個の中央値の選択ネットワーク
+network!(median_5, 5, [(0, 1), (3, 4), (0, 3), (1, 4), (1, 2), (2, 3), (1, 2)]);
+
+// 7個の中央値の選択ネットワーク
+network!(median_7, 7, [
+    (0, 5), (0, 3), (1, 6), (2, 4), (0, 1), (3, 5), (2, 6),
+    (2, 3), (3, 6), (4, 5), (1, 4), (1, 3), (3, 4),
+]);
+
+// 9個の中央値の選択ネットワーク
+network!(median_9, 9, [
+    (1, 2), (4, 5), (7, 8), (0, 1), (3, 4), (6, 7), (1, 2), (4, 5), (7, 8), (0, 3),
+    (5, 8), (4, 7), (3, 6), (1, 4), (2, 5), (4, 7), (4, 2), (6, 4), (4, 2),
+]);
+
+/// 要素数が5, 7, 9のいずれかなら，選択ネットワークで求めた`work`の中央値を返す（`work`は並べ替えられる）
+#[inline(always)]
+pub(crate) fn median<T: FloatCore>(work: &mut [T]) -> Option<T> {
+    match work.len() {
+        5 => work.try_into().ok().map(median_5),
+        7 => work.try_into().ok().map(median_7),
+        9 => work.try_into().ok().map(median_9),
+        _ => None,
+    }
+}
+
+/// `x`と`y`を全順序で小さい方，大きい方の順に返す
+#[inline(always)]
+fn exchange<T: FloatCore>(x: T, y: T) -> (T, T) {
+    // min/maxで並べられないのは，NaNを含むか符号の異なるゼロの組だけ（通常の入力では分岐の予測が外れない，
+    // constant-timeフィーチャでは分岐せずに常に選ぶ）
+    let ordered = (x < y) | (x > y);
+    let special = !ordered & (x.is_nan() | y.is_nan() | (x.is_sign_negative() != y.is_sign_negative()));
+    #[cfg(not(feature = "constant-time"))]
+    if !special {
+        return (x.min(y), x.max(y));
+    }
+    // 符号とNaNかどうかで比べる（-NaN: -2，負: -1，正: 1，+NaN: 2）
+    let key = |v: T| (1 - 2 * v.is_sign_negative() as i8) << v.is_nan() as u8;
+    let swap = (x > y) | (special & (key(x) > key(y)));
+    (stats::choose(swap, y, x), stats::choose(swap, x, y))
+}
+
+/// `Window`の順序統計量を読む入れ物（`N`が5, 7, 9ならwindowの写しを持ち，選択ネットワークで求める）
+///
+/// それ以外の`N`では昇順に並べたwindowを持ち，`[T]`と同じように読む。どちらにするかはコンパイル時に決まる。
+pub(crate) struct Selection<'a, T, const N: usize>(pub(crate) &'a mut [T; N]);
+
+impl<T: FloatCore, const N: usize> Selection<'_, T, N> {
+    const NETWORK: bool = matches!(N, 5 | 7 | 9);
+}
+
+impl<T: FloatCore, const N: usize> Ordered<T> for Selection<'_, T, N> {
+    fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    fn rebuild(&mut self, window: &[T]) {
+        if Self::NETWORK {
+            self.0.copy_from_slice(window);
+        } else {
+            self.0[..].rebuild(window);
+        }
+    }
+
+    fn replace(&mut self, slot: usize, evicted: T, inserted: T) {
+        if Self::NETWORK {
+            self.0[slot] = inserted;
+        } else {
+            self.0[..].replace(slot, evicted, inserted);
+        }
+    }
+
+    fn median(&self) -> T {
+        if Self::NETWORK {
+            if let Some(m) = median(&mut { *self.0 }) {
+                return m;
+            }
+        }
+        self.0[..].median()
+    }
+
+    fn mad(&self, w0: T, work: &mut [T]) -> T {
+        if Self::NETWORK {
+            if let Some(s) = median(&mut self.0.map(|v| (v - w0).abs())) {
+                return s;
+            }
+        }
+        self.0[..].mad(w0, work)
+    }
+
+    fn as_sorted<'a>(&'a self, work: &'a mut [T]) -> &'a [T] {
+        if Self::NETWORK {
+            let work = &mut work[..N];
+            work.copy_from_slice(self.0);
+            stats::sort(work);
+            work
+        } else {
+            self.0[..].as_sorted(work)
+        }
+    }
+}
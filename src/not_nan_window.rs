@@ -0,0 +1,100 @@
+//! Window for the NaN-free floats of the `ordered-float` crate (`ordered-float` feature).
+
+use num_traits::float::FloatCore;
+use ordered_float::NotNan;
+
+use crate::{OutlierStats, Sample, Window};
+
+/// Window of Hampel filter for `ordered_float::NotNan<f32>` and `NotNan<f64>`, so that the guarantee
+/// that the samples are not NaN is kept through the filter.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The samples are filtered by a [`Window`] of the underlying float, and the results are exactly the same.
+/// `OrderedFloat<T>` implements `FloatCore`, and can be used with [`Window`] itself:
+///
+/// ```
+/// use ordered_float::{NotNan, OrderedFloat};
+/// use hampel::{NotNanWindow, Window};
+///
+/// let mut a = NotNanWindow::<f64, 5>::new(NotNan::new(0.0).unwrap(), 3.0);
+/// let mut b = Window::<OrderedFloat<f64>, 5>::new(OrderedFloat(0.0), OrderedFloat(3.0));
+/// let mut reference = Window::<f64, 5>::new(0.0, 3.0);
+///
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     let s = reference.update_detailed(x);
+///     assert_eq!(a.update_detailed(NotNan::new(x).unwrap()), s);
+///     let sb = b.update_detailed(OrderedFloat(x));
+///     assert_eq!((sb.value.0, sb.median.0, sb.mad.0), (s.value, s.median, s.mad));
+///     assert_eq!(sb.is_outlier, s.is_outlier);
+/// }
+/// ```
+///
+/// With infinite samples, the statistics of the window can be NaN (e.g. the MAD of `[inf, inf, inf]`),
+/// and a NaN replacement is never returned: the sample is passed through instead.
+#[derive(Clone, PartialEq)]
+pub struct NotNanWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: Window<T, WINDOW_SIZE>,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> NotNanWindow<T, WINDOW_SIZE> {
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arguments are invalid for [`Window::new`].
+    pub fn new(init_val: NotNan<T>, n_sigma: T) -> Self {
+        Self::from_window(Window::new(init_val.into_inner(), n_sigma))
+    }
+
+    /// Filter with `window` (e.g. configured by [`Window::builder`]).
+    pub fn from_window(window: Window<T, WINDOW_SIZE>) -> Self {
+        Self { window }
+    }
+
+    /// Update element in window (see [`Window::update`]).
+    pub fn update(&mut self, x: NotNan<T>) -> NotNan<T> {
+        let s = self.update_detailed(x);
+        NotNan::new(s.value).unwrap_or(x)
+    }
+
+    /// Update element in window (see [`Window::update_detailed`]). [`Sample::value`] is not NaN.
+    pub fn update_detailed(&mut self, x: NotNan<T>) -> Sample<T> {
+        let mut s = self.window.update_detailed(x.into_inner());
+        if s.value.is_nan() {
+            s.value = x.into_inner();
+        }
+        s
+    }
+
+    /// See [`Window::reset`].
+    pub fn reset(&mut self, init_val: NotNan<T>) {
+        self.window.reset(init_val.into_inner());
+    }
+
+    /// See [`Window::stats`].
+    pub fn stats(&self) -> &OutlierStats {
+        self.window.stats()
+    }
+
+    /// See [`Window::samples_seen`].
+    pub fn samples_seen(&self) -> usize {
+        self.window.samples_seen()
+    }
+
+    /// Window that filters the samples.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,455 @@
+//! Offline (centered) Hampel filter over a slice.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{precision, stats};
+
+/// Maximum `half_window` of the offline filter.
+///
+/// The window (`2 * half_window + 1` samples) is copied to a buffer on the stack,
+/// so its size is bounded.
+pub const MAX_HALF_WINDOW: usize = 127;
+
+pub(crate) const MAX_WINDOW: usize = 2 * MAX_HALF_WINDOW + 1;
+
+/// How [`filter_slice`] handles the samples near the boundaries, whose full window does not fit in the slice.
+///
+/// The padded modes map the indices outside of the slice to the samples inside it (no padding is materialized),
+/// so that every window has `2 * half_window + 1` samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EdgeMode {
+    /// The window shrinks to the samples that are available.
+    /// If it has an even number of samples, the median is the average of the two central elements.
+    #[default]
+    Shrink,
+    /// The slice is reflected about the edge samples, without repeating them (`d c b | a b c d | c b a`).
+    Mirror,
+    /// The slice is extended with the edge samples (`a a a | a b c d | d d d`).
+    /// An isolated spike at the first or the last sample is never replaced, since it fills more than half of its window.
+    Nearest,
+    /// The first and the last `half_window` samples are left untouched (all of them if the slice is shorter than the window).
+    Skip,
+}
+
+/// How [`filter_slice_filled`] replaces the outliers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Fill {
+    /// Median of the window of each outlier (the same as [`filter_slice`]).
+    #[default]
+    Median,
+    /// Straight line between the samples before and after each run of outliers.
+    Linear,
+    /// Monotone piecewise cubic (PCHIP) through the samples that are not outliers,
+    /// with the slopes of Fritsch and Carlson (as SciPy's `PchipInterpolator`).
+    /// The fill does not overshoot the samples around a run, and follows their slopes.
+    Pchip,
+}
+
+/// Centered Hampel filter, applied in place.
+///
+/// For each index `i`, the median `w0` and the MAD `s0` of `data[i - half_window ..= i + half_window]`
+/// are computed, and `data[i]` is replaced by `w0` if `|data[i] - w0| > 1.4826 * n_sigma * s0`.
+/// All windows are taken from the original data (replaced values are never used for the decisions).
+///
+/// At the boundaries, where the full window does not fit, the samples are handled as `edge` specifies.
+/// With [`EdgeMode::Shrink`], the window shrinks to the samples that are available.
+///
+/// ```
+/// use hampel::{filter_slice, EdgeMode};
+///
+/// let mut data = [1.0, 1.1, 0.9, 1.0, 8.0, 1.1, 0.9, 1.0, 1.2, -5.0];
+/// filter_slice(&mut data, 2, 3.0, EdgeMode::Shrink);
+/// assert_eq!(data, [1.0, 1.1, 0.9, 1.0, 1.0, 1.1, 0.9, 1.0, 1.2, 1.0]);
+/// ```
+///
+/// The edge mode decides which samples near the boundaries are replaced.
+/// With `half_window = 2` and `n_sigma = 3`, a sample is replaced if it deviates from the median by more than `4.4478 * MAD`:
+///
+/// ```
+/// use hampel::{filter_slice, EdgeMode};
+///
+/// let filtered = |data: &[f64], edge| {
+///     let mut data = data.to_vec();
+///     filter_slice(&mut data, 2, 3.0, edge);
+///     data
+/// };
+///
+/// // Spikes at the first and the last samples
+/// let data = [9.0, 0.0, 1.0, 0.0, 1.0, 0.0, -9.0];
+/// // data[0]: window [9, 0, 1] (median 1, MAD 1)
+/// assert_eq!(filtered(&data, EdgeMode::Shrink), [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0]);
+/// // data[0]: window [1, 0, 9, 0, 1] (median 1, MAD 1)
+/// assert_eq!(filtered(&data, EdgeMode::Mirror), [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0]);
+/// // data[0]: window [9, 9, 9, 0, 1], whose median is the spike itself
+/// assert_eq!(filtered(&data, EdgeMode::Nearest), data);
+/// assert_eq!(filtered(&data, EdgeMode::Skip), data);
+///
+/// // Spike next to the first sample
+/// let data = [0.0, 3.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+/// // data[1]: window [0, 3, 0, 1] (median 0.5, MAD 0.5)
+/// assert_eq!(filtered(&data, EdgeMode::Shrink), [0.0, 0.5, 0.0, 1.0, 0.0, 1.0, 0.0]);
+/// // data[1]: window [3, 0, 3, 0, 1], where the spike is reflected (median 1, MAD 1)
+/// assert_eq!(filtered(&data, EdgeMode::Mirror), data);
+/// // data[1]: window [0, 0, 3, 0, 1] (median 0, MAD 0), data[5]: window [1, 0, 1, 0, 0] (median 0, MAD 0)
+/// assert_eq!(filtered(&data, EdgeMode::Nearest), [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+/// assert_eq!(filtered(&data, EdgeMode::Skip), data);
+/// ```
+///
+/// The medians of 5, 7 and 9 samples are found by median-selection networks, with the same results as sorting:
+///
+/// ```
+/// use hampel::{filter_slice, EdgeMode};
+///
+/// // data[3]: window sorted as [0, 0, 1, 1, 2, 2, 50] (median 1, MAD 1)
+/// let mut data = [1.0, 2.0, 0.0, 50.0, 1.0, 2.0, 0.0];
+/// filter_slice(&mut data, 3, 3.0, EdgeMode::Shrink);
+/// assert_eq!(data[3], 1.0);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `half_window` > [`MAX_HALF_WINDOW`].
+pub fn filter_slice<T: FloatCore>(data: &mut [T], half_window: usize, n_sigma: T, edge: EdgeMode) {
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_WINDOW];
+
+    // data[j]の元の値はi = j + half_windowまで参照されるので，
+    // 出力は最大half_window + 1個だけ保留してから書き込む
+    let cap = half_window + 1;
+    let mut pending = [T::zero(); MAX_HALF_WINDOW + 1];
+    for i in 0..data.len() {
+        if i >= cap {
+            data[i - cap] = pending[i % cap];
+        }
+        pending[i % cap] = decide(data, i, half_window, edge, coef, &mut work).0;
+    }
+    for j in data.len().saturating_sub(cap)..data.len() {
+        data[j] = pending[j % cap];
+    }
+}
+
+/// Same as [`filter_slice`], but the result is written to `dst` and `src` is left unchanged.
+///
+/// ```
+/// use hampel::{filter_slice, filter_slice_into, EdgeMode};
+///
+/// let src: Vec<f64> = (0..200).map(|i| (i as f64 * 0.2).sin() + if i % 17 == 3 { 4.0 } else { 0.0 }).collect();
+/// for edge in [EdgeMode::Shrink, EdgeMode::Mirror, EdgeMode::Nearest, EdgeMode::Skip] {
+///     let mut dst = vec![0.0; src.len()];
+///     filter_slice_into(&src, &mut dst, 5, 3.0, edge);
+///
+///     let mut data = src.clone();
+///     filter_slice(&mut data, 5, 3.0, edge);
+///     assert_eq!(data, dst);
+///
+///     // Slices shorter than the window (reflected more than once by `Mirror`)
+///     for len in 0..12 {
+///         let mut dst = vec![0.0; len];
+///         filter_slice_into(&src[..len], &mut dst, 5, 3.0, edge);
+///         let mut data = src[..len].to_vec();
+///         filter_slice(&mut data, 5, 3.0, edge);
+///         assert_eq!(data, dst);
+///     }
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src` and `dst` have different lengths, or `half_window` > [`MAX_HALF_WINDOW`].
+pub fn filter_slice_into<T: FloatCore>(src: &[T], dst: &mut [T], half_window: usize, n_sigma: T, edge: EdgeMode) {
+    assert_eq!(src.len(), dst.len(), "src and dst must have the same length");
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_WINDOW];
+
+    for (i, y) in dst.iter_mut().enumerate() {
+        *y = decide(src, i, half_window, edge, coef, &mut work).0;
+    }
+}
+
+/// Same as [`filter_slice`], but the outliers are replaced as `fill` specifies.
+///
+/// The outliers are detected first over the original data, as by [`filter_slice`], and then each run of
+/// adjacent outliers is filled by interpolating between the samples before and after it, so that a dropout
+/// of several samples does not become a flat plateau. The fill never influences the detection.
+/// A run at the start or the end of the slice is filled with the nearest sample that is not an outlier.
+///
+/// Except with [`Fill::Median`], NaN samples are filled as well (as missing samples),
+/// and a slice without any other sample is left as NaN.
+///
+/// ```
+/// use hampel::{filter_slice, filter_slice_filled, EdgeMode, Fill};
+///
+/// // Sine with a dropout of 5 samples near its peak
+/// let clean: Vec<f64> = (0..200).map(|i| (i as f64 * 0.05).sin()).collect();
+/// let mut input = clean.clone();
+/// input[40..45].fill(-2.0);
+///
+/// // Maximum error over the dropout
+/// let error = |fill| {
+///     let mut data = input.clone();
+///     filter_slice_filled(&mut data, 5, 3.0, EdgeMode::Shrink, fill);
+///     data[40..45].iter().zip(&clean[40..45]).map(|(y, x)| (y - x).abs()).fold(0.0, f64::max)
+/// };
+/// let (median, linear, pchip) = (error(Fill::Median), error(Fill::Linear), error(Fill::Pchip));
+/// assert!(median > 0.1);  // plateau at the median of the window
+/// assert!(linear < 0.1 * median);
+/// assert!(pchip < 0.5 * linear);
+///
+/// // The other samples are detected over the original data, and left as they are
+/// let mut data = input.clone();
+/// filter_slice_filled(&mut data, 5, 3.0, EdgeMode::Shrink, Fill::Pchip);
+/// let mut median = input.clone();
+/// filter_slice(&mut median, 5, 3.0, EdgeMode::Shrink);
+/// for i in (0..200).filter(|i| !(40..45).contains(i)) {
+///     assert_eq!(data[i], median[i]);
+///     assert_eq!(data[i], input[i]);
+/// }
+///
+/// // Runs at the boundaries are filled with the nearest sample
+/// let mut data = [9.0, 1.0, 1.1, 0.9, 1.0, 1.2, 1.0, -9.0];
+/// filter_slice_filled(&mut data, 2, 3.0, EdgeMode::Shrink, Fill::Linear);
+/// assert_eq!(data, [1.0, 1.0, 1.1, 0.9, 1.0, 1.2, 1.0, 1.0]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `half_window` > [`MAX_HALF_WINDOW`].
+pub fn filter_slice_filled<T: FloatCore>(data: &mut [T], half_window: usize, n_sigma: T, edge: EdgeMode, fill: Fill) {
+    if fill == Fill::Median {
+        return filter_slice(data, half_window, n_sigma, edge);
+    }
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_WINDOW];
+
+    // 外れ値をNaNで印を付けてから（filter_sliceと同じく出力を保留する），NaNの連続を埋める
+    let cap = half_window + 1;
+    let mut pending = [T::zero(); MAX_HALF_WINDOW + 1];
+    for i in 0..data.len() {
+        if i >= cap {
+            data[i - cap] = pending[i % cap];
+        }
+        let (y, outlier) = decide(data, i, half_window, edge, coef, &mut work);
+        pending[i % cap] = if outlier { T::nan() } else { y };
+    }
+    for j in data.len().saturating_sub(cap)..data.len() {
+        data[j] = pending[j % cap];
+    }
+    fill_gaps(data, fill);
+}
+
+/// Same as [`filter_slice_filled`], but the result is written to `dst` and `src` is left unchanged.
+///
+/// ```
+/// use hampel::{filter_slice_filled, filter_slice_filled_into, EdgeMode, Fill};
+///
+/// let src: Vec<f64> = (0..200).map(|i| (i as f64 * 0.2).sin() + if i % 17 < 2 { 4.0 } else { 0.0 }).collect();
+/// for edge in [EdgeMode::Shrink, EdgeMode::Mirror, EdgeMode::Nearest, EdgeMode::Skip] {
+///     for fill in [Fill::Median, Fill::Linear, Fill::Pchip] {
+///         let mut dst = vec![0.0; src.len()];
+///         filter_slice_filled_into(&src, &mut dst, 5, 3.0, edge, fill);
+///
+///         let mut data = src.clone();
+///         filter_slice_filled(&mut data, 5, 3.0, edge, fill);
+///         assert_eq!(data, dst);
+///     }
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `src` and `dst` have different lengths, or `half_window` > [`MAX_HALF_WINDOW`].
+pub fn filter_slice_filled_into<T: FloatCore>(src: &[T], dst: &mut [T], half_window: usize, n_sigma: T, edge: EdgeMode, fill: Fill) {
+    if fill == Fill::Median {
+        return filter_slice_into(src, dst, half_window, n_sigma, edge);
+    }
+    assert_eq!(src.len(), dst.len(), "src and dst must have the same length");
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_WINDOW];
+
+    for (i, y) in dst.iter_mut().enumerate() {
+        let (v, outlier) = decide(src, i, half_window, edge, coef, &mut work);
+        *y = if outlier { T::nan() } else { v };
+    }
+    fill_gaps(dst, fill);
+}
+
+/// NaNの連続を，前後のNaNでないサンプルから`fill`で補間して埋める（端の連続は最も近いサンプルで埋める）
+fn fill_gaps<T: FloatCore>(data: &mut [T], fill: Fill) {
+    let n = data.len();
+    let next_valid = |data: &[T], from: usize| (from..n).find(|&j| !data[j].is_nan());
+    let mut before = None;  // 直前のNaNでないサンプルの，さらに前のNaNでないサンプル
+    let mut prev = None;  // 直前のNaNでないサンプル
+    let mut i = 0;
+    while i < n {
+        if !data[i].is_nan() {
+            (before, prev) = (prev, Some(i));
+            i += 1;
+            continue;
+        }
+        let next = next_valid(data, i);
+        match (prev, next) {
+            (None, None) => return,
+            (Some(p), None) => {
+                let v = data[p];
+                data[i..].fill(v);
+            },
+            (None, Some(q)) => {
+                let v = data[q];
+                data[i..q].fill(v);
+            },
+            (Some(p), Some(q)) => {
+                let after = next_valid(data, q + 1);
+                let (dp, dq) = match fill {
+                    Fill::Pchip => slopes(data, before, p, q, after),
+                    _ => (T::zero(), T::zero()),
+                };
+                let x = |j: usize| cast::<usize, T>(j).unwrap();
+                let h = x(q - p);
+                for j in i..q {
+                    let t = x(j - p) / h;
+                    data[j] = match fill {
+                        Fill::Pchip => hermite(data[p], data[q], dp * h, dq * h, t),
+                        _ => data[p] + (data[q] - data[p]) * t,
+                    };
+                }
+            },
+        }
+        i = next.unwrap_or(n);
+    }
+}
+
+/// 区間`[p, q]`の両端でのPCHIPの傾き（`before`, `after`: その前後のNaNでないサンプル）
+fn slopes<T: FloatCore>(data: &[T], before: Option<usize>, p: usize, q: usize, after: Option<usize>) -> (T, T) {
+    let x = |j: usize| cast::<usize, T>(j).unwrap();
+    // 区間の幅と傾き
+    let secant = |a: usize, b: usize| (x(b - a), (data[b] - data[a]) / x(b - a));
+    let mid = secant(p, q);
+    let left = before.map(|b| secant(b, p));
+    let right = after.map(|a| secant(q, a));
+    let dp = match (left, right) {
+        (Some(l), _) => interior(l, mid),
+        (None, Some(r)) => edge(mid, r),
+        (None, None) => mid.1,
+    };
+    let dq = match (left, right) {
+        (_, Some(r)) => interior(mid, r),
+        (Some(l), None) => edge(mid, l),
+        (None, None) => mid.1,
+    };
+    (dp, dq)
+}
+
+/// 幅と傾きが`(h0, d0)`, `(h1, d1)`の二つの区間の間の点での傾き（重み付き調和平均，単調でなければ0）
+fn interior<T: FloatCore>((h0, d0): (T, T), (h1, d1): (T, T)) -> T {
+    if d0 * d1 <= T::zero() {
+        return T::zero();
+    }
+    let two = T::one() + T::one();
+    let (w0, w1) = (two * h1 + h0, h1 + two * h0);
+    (w0 + w1) / (w0 / d0 + w1 / d1)
+}
+
+/// 端の点での傾き（`(h0, d0)`: 端の区間，`(h1, d1)`: その隣の区間）
+fn edge<T: FloatCore>((h0, d0): (T, T), (h1, d1): (T, T)) -> T {
+    let two = T::one() + T::one();
+    let three = two + T::one();
+    let d = ((two * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+    if d * d0 <= T::zero() {
+        T::zero()
+    } else if d0 * d1 < T::zero() && d.abs() > three * d0.abs() {
+        three * d0
+    } else {
+        d
+    }
+}
+
+/// 両端の値`y0`, `y1`と，区間の幅を掛けた傾き`m0`, `m1`によるエルミート補間の`t`（0以上1以下）における値
+fn hermite<T: FloatCore>(y0: T, y1: T, m0: T, m1: T, t: T) -> T {
+    let two = T::one() + T::one();
+    let three = two + T::one();
+    let (t2, t3) = (t * t, t * t * t);
+    let h00 = two * t3 - three * t2 + T::one();
+    let h10 = t3 - two * t2 + t;
+    let h01 = three * t2 - two * t3;
+    let h11 = t3 - t2;
+    h00 * y0 + h10 * m0 + h01 * y1 + h11 * m1
+}
+
+pub(crate) fn coefficient<T: FloatCore>(n_sigma: T) -> T {
+    // 1.4826は正規分布にするための係数
+    precision::mul(cast::<f32, T>(1.4826).unwrap(), n_sigma)
+}
+
+/// `filter_slice_into`と同じだが，外れ値と判定した要素の`flags`を`true`にする（それ以外は`false`）
+#[cfg(feature = "ndarray")]
+pub(crate) fn filter_slice_flagged<T: FloatCore>(src: &[T], dst: &mut [T], flags: &mut [bool], half_window: usize, n_sigma: T) {
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = coefficient(n_sigma);
+    let mut work = [T::zero(); MAX_WINDOW];
+
+    for (i, (y, flag)) in dst.iter_mut().zip(flags.iter_mut()).enumerate() {
+        (*y, *flag) = decide(src, i, half_window, EdgeMode::Shrink, coef, &mut work);
+    }
+}
+
+/// `src[i]`を中心とする窓で判定した出力値と，外れ値かどうか
+///
+/// どのモードでも，窓の要素は`src[i - half_window ..= i + half_window]`の範囲内から取る（`filter_slice`の保留に必要）
+pub(crate) fn decide<T: FloatCore>(src: &[T], i: usize, half_window: usize, edge: EdgeMode, coef: T, work: &mut [T]) -> (T, bool) {
+    let n = src.len();
+    let begin = i.saturating_sub(half_window);
+    let end = n.min(i + half_window + 1);
+    let fits = end - begin == 2 * half_window + 1;
+    let work = match edge {
+        EdgeMode::Shrink => &mut work[..(end - begin)],
+        EdgeMode::Skip if !fits => return (src[i], false),
+        _ => &mut work[..(2 * half_window + 1)],
+    };
+    if fits || edge == EdgeMode::Shrink {
+        work.copy_from_slice(&src[begin..end]);
+    } else {
+        // 窓からはみ出した位置を，スライス内の位置に写す
+        for (k, w) in work.iter_mut().enumerate() {
+            let j = (i + k) as isize - half_window as isize;
+            *w = src[match edge {
+                EdgeMode::Mirror => reflect(j, n),
+                _ => j.clamp(0, n as isize - 1) as usize,
+            }];
+        }
+    }
+    judge_flagged(src[i], work, coef)
+}
+
+/// 端の要素を繰り返さずに折り返した位置（周期`2 * (n - 1)`で，窓がスライスより長くても範囲内に収まる）
+fn reflect(j: isize, n: usize) -> usize {
+    if n == 1 {
+        return 0;
+    }
+    let period = 2 * (n as isize - 1);
+    let m = j.rem_euclid(period);
+    (if m < n as isize { m } else { period - m }) as usize
+}
+
+/// `work`の窓（中身は書き換わる）で`x`を判定した出力値
+pub(crate) fn judge<T: FloatCore>(x: T, work: &mut [T], coef: T) -> T {
+    judge_flagged(x, work, coef).0
+}
+
+/// `judge`と同じだが，外れ値かどうかも返す
+fn judge_flagged<T: FloatCore>(x: T, work: &mut [T], coef: T) -> (T, bool) {
+    let (w0, s0) = stats::statistics(work);
+    let outlier = if x < w0 {
+        precision::exceeds(w0, x, coef, s0)
+    } else {
+        precision::exceeds(x, w0, coef, s0)
+    };
+    if outlier { (w0, true) } else { (x, false) }
+}
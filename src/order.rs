@@ -0,0 +1,272 @@
+//! Containers that keep the elements of the window in ascending order.
+//!
+//! `Core` reads the order statistics through the `Ordered` trait, so that the window types can
+//! choose the container: a sorted slice (`[T]`, where an empty slice means none) or `Tree`,
+//! an order-statistic tree over a fixed arena for large windows.
+
+use num_traits::float::FloatCore;
+
+use crate::{precision, stats};
+
+pub(crate) trait Ordered<T: FloatCore> {
+    /// 要素を保持していない（統計量の計算には使わない）
+    fn is_empty(&self) -> bool;
+
+    /// `window`の要素で作り直す（NaNを含まないこと）
+    fn rebuild(&mut self, window: &[T]);
+
+    /// windowの`slot`番目の要素が`evicted`から`inserted`に置き換わったのを反映する（NaNを含まないこと）
+    fn replace(&mut self, slot: usize, evicted: T, inserted: T);
+
+    /// 中央値
+    fn median(&self) -> T;
+
+    /// 中央値`w0`からの偏差の絶対値の中央値（`work`は作業用）
+    fn mad(&self, w0: T, work: &mut [T]) -> T;
+
+    /// 昇順に並べた要素（必要なら`work`に書き出して返す）
+    fn as_sorted<'a>(&'a self, work: &'a mut [T]) -> &'a [T];
+}
+
+impl<T: FloatCore> Ordered<T> for [T] {
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn rebuild(&mut self, window: &[T]) {
+        self.copy_from_slice(window);
+        stats::sort(self);
+    }
+
+    fn replace(&mut self, _slot: usize, evicted: T, inserted: T) {
+        stats::replace_sorted(self, evicted, inserted);
+    }
+
+    fn median(&self) -> T {
+        stats::median_of_sorted(self)
+    }
+
+    fn mad(&self, w0: T, work: &mut [T]) -> T {
+        if w0.is_finite() {
+            #[cfg(not(feature = "constant-time"))]
+            let p = self.partition_point(|v| *v < w0);
+            #[cfg(feature = "constant-time")]
+            let p = self.iter().fold(0, |c, v| c + (*v < w0) as usize);  // 二分探索の代わりに全要素を数える
+            return stats::median_of_deviations(self.len(), p, w0, |i| self[i]);
+        }
+        // 偏差にNaNが含まれ得るので，ソートする場合と同じ結果になるように偏差の中央値を求める
+        for (w, v) in work.iter_mut().zip(self) {
+            *w = (*v - w0).abs();
+        }
+        stats::median(work)
+    }
+
+    fn as_sorted<'a>(&'a self, _work: &'a mut [T]) -> &'a [T] {
+        self
+    }
+}
+
+const NIL: u32 = u32::MAX;
+
+/// 順序統計木（treap）
+///
+/// windowの`i`番目の要素を`i`番目のノードとして持つので，容量は`N`で固定される。
+/// 値が等しいノードはインデックス順に並べる。
+/// 優先度はインデックスから決めるので，入力値とは無関係に木の高さの期待値はO(log N)になる。
+#[derive(Clone)]
+pub(crate) struct Tree<T, const N: usize> {
+    key: [T; N],
+    left: [u32; N],
+    right: [u32; N],
+    size: [u32; N],  // そのノードを根とする部分木の要素数
+    root: u32,
+}
+
+impl<T: FloatCore, const N: usize> Tree<T, N> {
+    pub(crate) fn new(init_val: T) -> Self {
+        assert!(N < NIL as usize, "WINDOW_SIZE is too large");
+        let mut tree = Self { key: [init_val; N], left: [NIL; N], right: [NIL; N], size: [0; N], root: NIL };
+        tree.build();
+        tree
+    }
+
+    /// `key`から木を作り直す
+    fn build(&mut self) {
+        self.root = NIL;
+        for i in 0..N as u32 {
+            self.insert(i);
+        }
+    }
+
+    /// ノード`i`の優先度（インデックスのハッシュ値）
+    fn priority(i: u32) -> u32 {
+        let mut h = i.wrapping_add(0x9e37_79b9);
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xc2b2_ae35);
+        h ^ (h >> 16)
+    }
+
+    fn size_of(&self, t: u32) -> u32 {
+        if t == NIL { 0 } else { self.size[t as usize] }
+    }
+
+    fn update_size(&mut self, t: u32) {
+        let t = t as usize;
+        self.size[t] = 1 + self.size_of(self.left[t]) + self.size_of(self.right[t]);
+    }
+
+    /// ノード`a`がノード`b`より前に並ぶ
+    fn precedes(&self, a: u32, b: u32) -> bool {
+        let (ka, kb) = (self.key[a as usize], self.key[b as usize]);
+        ka < kb || (ka == kb && a < b)
+    }
+
+    /// 根が`a`の木と根が`b`の木を連結する（`a`の要素は全て`b`の要素より前）
+    fn merge(&mut self, a: u32, b: u32) -> u32 {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if Self::priority(a) > Self::priority(b) {
+            self.right[a as usize] = self.merge(self.right[a as usize], b);
+            self.update_size(a);
+            a
+        } else {
+            self.left[b as usize] = self.merge(a, self.left[b as usize]);
+            self.update_size(b);
+            b
+        }
+    }
+
+    /// 根が`t`の木を，ノード`i`より前の要素とそれ以外に分ける
+    fn split(&mut self, t: u32, i: u32) -> (u32, u32) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        if self.precedes(t, i) {
+            let (l, r) = self.split(self.right[t as usize], i);
+            self.right[t as usize] = l;
+            self.update_size(t);
+            (t, r)
+        } else {
+            let (l, r) = self.split(self.left[t as usize], i);
+            self.left[t as usize] = r;
+            self.update_size(t);
+            (l, t)
+        }
+    }
+
+    /// 根が`t`の木からノード`i`を取り除く
+    fn remove(&mut self, t: u32, i: u32) -> u32 {
+        if t == i {
+            return self.merge(self.left[t as usize], self.right[t as usize]);
+        }
+        if self.precedes(i, t) {
+            self.left[t as usize] = self.remove(self.left[t as usize], i);
+        } else {
+            self.right[t as usize] = self.remove(self.right[t as usize], i);
+        }
+        self.update_size(t);
+        t
+    }
+
+    /// ノード`i`を挿入する
+    fn insert(&mut self, i: u32) {
+        self.left[i as usize] = NIL;
+        self.right[i as usize] = NIL;
+        self.size[i as usize] = 1;
+        let (l, r) = self.split(self.root, i);
+        let l = self.merge(l, i);
+        self.root = self.merge(l, r);
+    }
+
+    /// `k`番目（0始まり）に小さい要素
+    fn select(&self, mut k: u32) -> T {
+        let mut t = self.root;
+        loop {
+            let l = self.size_of(self.left[t as usize]);
+            if k < l {
+                t = self.left[t as usize];
+            } else if k == l {
+                return self.key[t as usize];
+            } else {
+                k -= l + 1;
+                t = self.right[t as usize];
+            }
+        }
+    }
+
+    /// `x`より小さい要素の数
+    fn rank(&self, x: T) -> u32 {
+        let mut t = self.root;
+        let mut count = 0;
+        while t != NIL {
+            if self.key[t as usize] < x {
+                count += self.size_of(self.left[t as usize]) + 1;
+                t = self.right[t as usize];
+            } else {
+                t = self.left[t as usize];
+            }
+        }
+        count
+    }
+
+    /// 根が`t`の木の要素を昇順に`out`の先頭から書き出し，書き出した数を返す
+    fn write_sorted(&self, t: u32, out: &mut [T]) -> usize {
+        if t == NIL {
+            return 0;
+        }
+        let l = self.write_sorted(self.left[t as usize], out);
+        out[l] = self.key[t as usize];
+        l + 1 + self.write_sorted(self.right[t as usize], &mut out[l + 1..])
+    }
+}
+
+impl<T: FloatCore, const N: usize> Ordered<T> for Tree<T, N> {
+    fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    fn rebuild(&mut self, window: &[T]) {
+        self.key.copy_from_slice(window);
+        self.build();
+    }
+
+    fn replace(&mut self, slot: usize, _evicted: T, inserted: T) {
+        let i = slot as u32;
+        self.root = self.remove(self.root, i);
+        self.key[slot] = inserted;
+        self.insert(i);
+    }
+
+    fn median(&self) -> T {
+        let mid = N as u32 / 2;
+        if N.is_multiple_of(2) {
+            precision::midpoint(self.select(mid - 1), self.select(mid))
+        } else {
+            self.select(mid)
+        }
+    }
+
+    fn mad(&self, w0: T, work: &mut [T]) -> T {
+        if !w0.is_finite() {
+            // 偏差にNaNが含まれるので，ソートする場合と同じ結果になるように計算する
+            self.write_sorted(self.root, work);
+            for v in work.iter_mut() {
+                *v = (*v - w0).abs();
+            }
+            return stats::median(work);
+        }
+
+        stats::median_of_deviations(N, self.rank(w0) as usize, w0, |i| self.select(i as u32))
+    }
+
+    fn as_sorted<'a>(&'a self, work: &'a mut [T]) -> &'a [T] {
+        self.write_sorted(self.root, work);
+        work
+    }
+}
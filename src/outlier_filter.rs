@@ -0,0 +1,123 @@
+//! Common interface of the streaming outlier filters.
+
+use num_traits::float::FloatCore;
+
+use crate::{DualWindow, LargeWindow, OutlierHandler, SmoothedWindow, Window, ZScoreWindow};
+
+/// Streaming outlier filter, so that the detection strategy can be selected at runtime
+/// (the trait is object safe).
+///
+/// It is implemented for [`Window`], [`LargeWindow`], [`DualWindow`], [`SmoothedWindow`] and [`ZScoreWindow`].
+///
+/// ```
+/// use hampel::{OutlierFilter, Window, ZScoreWindow};
+///
+/// let input: Vec<f32> = (0..300).map(|i| (i as f32 * 0.1).sin() + if i % 29 == 5 { 8.0 } else { 0.0 }).collect();
+/// let mut hampel = Window::<f32, 9>::new(0.0, 3.0);
+/// let mut zscore = ZScoreWindow::<f32, 9>::new(0.0, 3.0);
+/// let mut reference = (Window::<f32, 9>::new(0.0, 3.0), ZScoreWindow::<f32, 9>::new(0.0, 3.0));
+///
+/// for filter in [&mut hampel as &mut dyn OutlierFilter<f32>, &mut zscore] {
+///     // Same input through the trait object
+///     let mut flagged = Vec::new();
+///     for (i, &x) in input.iter().enumerate() {
+///         let y = filter.update(x);
+///         if filter.is_last_outlier() {
+///             assert_ne!(y, x);
+///             flagged.push(i);
+///         }
+///     }
+///     // Both detect the spikes after the start
+///     assert!((1..10).all(|k| flagged.contains(&(29 * k + 5))));
+///     filter.reset(0.0);
+///     assert!(!filter.is_last_outlier());
+/// }
+///
+/// // The same results as the inherent methods
+/// for &x in &input {
+///     assert_eq!(OutlierFilter::update(&mut hampel, x), reference.0.update(x));
+///     assert_eq!(OutlierFilter::update(&mut zscore, x), reference.1.update(x));
+/// }
+/// ```
+pub trait OutlierFilter<T> {
+    /// Update the filter with `x`, and return the filtered value.
+    fn update(&mut self, x: T) -> T;
+
+    /// Returns `true` if the last sample given to [`update`](Self::update) was determined to be an outlier.
+    fn is_last_outlier(&self) -> bool;
+
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    fn reset(&mut self, init_val: T);
+}
+
+/// `is_last_outlier` is [`Window::in_outlier_run`].
+impl<T: FloatCore, const WINDOW_SIZE: usize, H: OutlierHandler<T>> OutlierFilter<T> for Window<T, WINDOW_SIZE, H> {
+    fn update(&mut self, x: T) -> T {
+        Window::update(self, x)
+    }
+
+    fn is_last_outlier(&self) -> bool {
+        self.in_outlier_run()
+    }
+
+    fn reset(&mut self, init_val: T) {
+        Window::reset(self, init_val)
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> OutlierFilter<T> for LargeWindow<T, WINDOW_SIZE> {
+    fn update(&mut self, x: T) -> T {
+        LargeWindow::update(self, x)
+    }
+
+    fn is_last_outlier(&self) -> bool {
+        self.core.in_run
+    }
+
+    fn reset(&mut self, init_val: T) {
+        LargeWindow::reset(self, init_val)
+    }
+}
+
+impl<T: FloatCore, const MEDIAN_WIN: usize, const SCALE_WIN: usize> OutlierFilter<T> for DualWindow<T, MEDIAN_WIN, SCALE_WIN> {
+    fn update(&mut self, x: T) -> T {
+        DualWindow::update(self, x)
+    }
+
+    fn is_last_outlier(&self) -> bool {
+        self.core.in_run
+    }
+
+    fn reset(&mut self, init_val: T) {
+        DualWindow::reset(self, init_val)
+    }
+}
+
+/// `update` returns the smoothed value.
+impl<T: FloatCore, const WINDOW_SIZE: usize> OutlierFilter<T> for SmoothedWindow<T, WINDOW_SIZE> {
+    fn update(&mut self, x: T) -> T {
+        SmoothedWindow::update(self, x)
+    }
+
+    fn is_last_outlier(&self) -> bool {
+        self.window.in_outlier_run()
+    }
+
+    fn reset(&mut self, init_val: T) {
+        SmoothedWindow::reset(self, init_val)
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> OutlierFilter<T> for ZScoreWindow<T, WINDOW_SIZE> {
+    fn update(&mut self, x: T) -> T {
+        ZScoreWindow::update(self, x)
+    }
+
+    fn is_last_outlier(&self) -> bool {
+        ZScoreWindow::is_last_outlier(self)
+    }
+
+    fn reset(&mut self, init_val: T) {
+        ZScoreWindow::reset(self, init_val)
+    }
+}
@@ -0,0 +1,83 @@
+//! Parallel offline filter with `rayon` (`rayon` feature).
+
+use alloc::vec::Vec;
+
+use num_traits::float::FloatCore;
+use rayon::prelude::*;
+
+use crate::{filter_slice, offline, EdgeMode, MAX_HALF_WINDOW};
+
+/// Number of samples filtered by a task of [`par_filter_slice`].
+pub const PAR_CHUNK_LEN: usize = 1 << 14;
+
+/// Same as [`filter_slice`] applied to each slice of `data` (e.g. the channels of multi-channel data),
+/// with the slices filtered in parallel.
+///
+/// ```
+/// use hampel::{filter_slice, par_filter_slices, EdgeMode};
+///
+/// let channels: Vec<Vec<f64>> = (0..8)
+///     .map(|c| (0..1000).map(|i| (i as f64 * 0.05 + c as f64).sin() + if (i + c) % 37 == 0 { 3.0 } else { 0.0 }).collect())
+///     .collect();
+///
+/// let mut a = channels.clone();
+/// let mut slices: Vec<&mut [f64]> = a.iter_mut().map(|c| c.as_mut_slice()).collect();
+/// par_filter_slices(&mut slices, 5, 3.0, EdgeMode::Mirror);
+///
+/// for (a, mut b) in a.into_iter().zip(channels) {
+///     filter_slice(&mut b, 5, 3.0, EdgeMode::Mirror);
+///     assert_eq!(a, b);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `half_window` > [`MAX_HALF_WINDOW`].
+pub fn par_filter_slices<T: FloatCore + Send + Sync>(data: &mut [&mut [T]], half_window: usize, n_sigma: T, edge: EdgeMode) {
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    data.par_iter_mut().for_each(|s| filter_slice(s, half_window, n_sigma, edge));
+}
+
+/// Same as [`filter_slice`], but a long slice is split into chunks of [`PAR_CHUNK_LEN`] samples,
+/// which are filtered in parallel (see [`par_filter_slice_chunked`]).
+///
+/// # Panics
+///
+/// Panics if `half_window` > [`MAX_HALF_WINDOW`].
+pub fn par_filter_slice<T: FloatCore + Send + Sync>(data: &mut [T], half_window: usize, n_sigma: T, edge: EdgeMode) {
+    par_filter_slice_chunked(data, PAR_CHUNK_LEN, half_window, n_sigma, edge);
+}
+
+/// Same as [`par_filter_slice`], but with chunks of `chunk_len` samples.
+///
+/// The windows are taken from a copy of the original data, so that each sample is judged
+/// against the same window as [`filter_slice`] (including the edges), and the output is exactly the same:
+///
+/// ```
+/// use hampel::{filter_slice, par_filter_slice_chunked, EdgeMode};
+///
+/// let data: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.1).sin() + if i % 31 == 0 { 8.0 } else { 0.0 }).collect();
+/// let mut expected = data.clone();
+/// filter_slice(&mut expected, 3, 3.0, EdgeMode::Nearest);
+/// let mut a = data.clone();
+/// par_filter_slice_chunked(&mut a, 64, 3, 3.0, EdgeMode::Nearest);
+/// assert_eq!(a, expected);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `chunk_len` is zero, or `half_window` > [`MAX_HALF_WINDOW`].
+pub fn par_filter_slice_chunked<T: FloatCore + Send + Sync>(data: &mut [T], chunk_len: usize, half_window: usize, n_sigma: T, edge: EdgeMode) {
+    assert!(chunk_len > 0, "chunk_len must not be zero");
+    assert!(half_window <= MAX_HALF_WINDOW, "half_window must be at most {}", MAX_HALF_WINDOW);
+    let coef = offline::coefficient(n_sigma);
+
+    // 隣のチャンクに書き換えられる前の値で判定するので，元のデータを取っておく
+    let src: Vec<T> = data.to_vec();
+    data.par_chunks_mut(chunk_len).enumerate().for_each(|(k, chunk)| {
+        let mut work = [T::zero(); offline::MAX_WINDOW];
+        for (j, y) in chunk.iter_mut().enumerate() {
+            *y = offline::decide(&src, k * chunk_len + j, half_window, edge, coef, &mut work).0;
+        }
+    });
+}
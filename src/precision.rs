@@ -0,0 +1,69 @@
+//! Arithmetic for floating-point types narrower than `f32` (e.g. `half::f16` and `half::bf16`).
+//!
+//! With such types, the computations that can overflow or accumulate rounding errors
+//! (sums, products and the midpoint of two values) are done in `f32` and rounded once.
+//! For `f32` and `f64`, they are done in `T` as before, so that the results do not change.
+
+use num_traits::{cast, float::FloatCore};
+
+/// `T`がf32より精度の低い型か
+#[inline]
+pub(crate) fn is_narrow<T: FloatCore>() -> bool {
+    cast::<T, f32>(T::epsilon()).is_some_and(|eps| eps > f32::EPSILON)
+}
+
+/// f32に変換する（精度の低い型はf32で誤差なく表せるので，NaNになるのは変換できない型だけ）
+#[inline]
+pub(crate) fn widen<T: FloatCore>(x: T) -> f32 {
+    cast::<T, f32>(x).unwrap_or(f32::NAN)
+}
+
+/// f32から変換する（範囲外は±infになり，NaNになるのは変換できない型だけ）
+#[inline]
+pub(crate) fn narrow<T: FloatCore>(x: f32) -> T {
+    cast::<f32, T>(x).unwrap_or_else(T::nan)
+}
+
+/// `a * b`
+#[inline]
+pub(crate) fn mul<T: FloatCore>(a: T, b: T) -> T {
+    if is_narrow::<T>() {
+        narrow(widen(a) * widen(b))
+    } else {
+        a * b
+    }
+}
+
+/// `(a + b) / 2`
+#[inline]
+pub(crate) fn midpoint<T: FloatCore>(a: T, b: T) -> T {
+    if is_narrow::<T>() {
+        narrow((widen(a) + widen(b)) * 0.5)
+    } else {
+        (a + b) / (T::one() + T::one())
+    }
+}
+
+/// `d / (c * scale)`（`d`が0なら0，`c * scale`が0なら無限大）
+#[inline]
+pub(crate) fn ratio<T: FloatCore>(d: T, c: T, scale: T) -> T {
+    if d == T::zero() {
+        return T::zero();
+    }
+    if is_narrow::<T>() {
+        narrow(widen(d) / (widen(c) * widen(scale)))
+    } else {
+        d / (c * scale)
+    }
+}
+
+/// `!(a - b <= coef * scale)`（どれかがNaNなら`true`）
+#[inline]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+pub(crate) fn exceeds<T: FloatCore>(a: T, b: T, coef: T, scale: T) -> bool {
+    if is_narrow::<T>() {
+        !(widen(a) - widen(b) <= widen(coef) * widen(scale))
+    } else {
+        !(a - b <= coef * scale)
+    }
+}
@@ -0,0 +1,150 @@
+//! Moving quantile filter without the outlier detection.
+
+use num_traits::float::FloatCore;
+
+use crate::{iter::Samples, order::Ordered, stats::QuantilePosition};
+
+/// Moving `p`-quantile of the last `WINDOW_SIZE` samples, a generalization of [`MedianWindow`](crate::MedianWindow).
+///
+/// * `WINDOW_SIZE` >= 1
+///
+/// The quantile is interpolated linearly between the order statistics at the position `p * (WINDOW_SIZE - 1)`,
+/// as NumPy's `percentile` with `method="linear"`. With `p = 0.5` (see [`new`](Self::new)),
+/// the result is exactly the median of [`MedianWindow`](crate::MedianWindow):
+///
+/// ```
+/// use hampel::{MedianWindow, QuantileWindow};
+///
+/// let mut quantile = QuantileWindow::<f64, 4>::new(0.0);
+/// let mut median = MedianWindow::<f64, 4>::new(0.0);
+/// for x in [5.0, 1.0, 3.0, -2.0, 4.0] {
+///     assert_eq!(quantile.update(x), median.update(x));
+/// }
+///
+/// let mut filter = QuantileWindow::<f64, 5>::with_p(0.0, 0.1);
+/// for x in [1.0, 2.0, 3.0, 4.0, 5.0] {
+///     filter.update(x);
+/// }
+/// assert!((filter.quantile() - 1.4).abs() < 1e-6);  // numpy.percentile([1, 2, 3, 4, 5], 10), with p rounded to f32
+/// ```
+///
+/// While the window contains a NaN, the quantile is NaN.
+#[derive(Clone)]
+pub struct QuantileWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],
+    sorted: [T; WINDOW_SIZE],  // windowを昇順に並べたもの（NaNを含む間は使わない）
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) seen: usize,  // windowに格納したサンプル数
+    nan_count: usize,  // window内のNaNの数
+    pub(crate) p: f32,
+    position: QuantilePosition<T>,  // sortedの中でのpの分位数の位置
+    quantile: T,  // 直近の更新で求めた分位数
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> QuantileWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が1以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 1, "WINDOW_SIZE must be at least 1");
+
+    /// Moving median (`p = 0.5`).
+    ///
+    /// * `init_val`: Initialization value of window.
+    ///
+    /// `WINDOW_SIZE` = 0 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::QuantileWindow::<f32, 0>::new(0.0);
+    /// ```
+    pub fn new(init_val: T) -> Self {
+        Self::with_p(init_val, 0.5)
+    }
+
+    /// * `init_val`: Initialization value of window.
+    /// * `p`: Probability of the quantile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in `(0, 1)` (`WINDOW_SIZE` = 0 is rejected at compile time).
+    pub fn with_p(init_val: T, p: f32) -> Self {
+        let () = Self::VALID_SIZE;
+        assert!(p > 0.0 && p < 1.0, "p must be in (0, 1)");
+
+        let mut filter = Self {
+            window: [init_val; WINDOW_SIZE],
+            sorted: [init_val; WINDOW_SIZE],
+            oldest: 0,
+            seen: 0,
+            nan_count: 0,
+            p,
+            position: QuantilePosition::new(WINDOW_SIZE, p),
+            quantile: init_val,
+        };
+        filter.reset(init_val);
+        filter
+    }
+
+    /// Add a sample, and return the quantile of the window.
+    pub fn update(&mut self, x: T) -> T {
+        let slot = self.oldest;
+        let evicted = self.window[slot];
+        self.window[slot] = x;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        self.seen = self.seen.saturating_add(1);
+
+        let was_clean = self.nan_count == 0;
+        self.nan_count = self.nan_count + x.is_nan() as usize - evicted.is_nan() as usize;
+        self.quantile = if self.nan_count == 0 {
+            if was_clean {
+                self.sorted.replace(slot, evicted, x);
+            } else {
+                self.sorted.rebuild(&self.window);  // NaNが無くなったので作り直す
+            }
+            self.position.of_sorted(&self.sorted)
+        } else {
+            T::nan()
+        };
+        self.quantile
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`.
+    pub fn reset(&mut self, init_val: T) {
+        self.window = [init_val; WINDOW_SIZE];
+        self.nan_count = if init_val.is_nan() { WINDOW_SIZE } else { 0 };
+        if self.nan_count == 0 {
+            self.sorted.rebuild(&self.window);
+        }
+        self.oldest = 0;
+        self.seen = 0;
+        self.quantile = init_val;
+    }
+
+    /// Probability of the quantile.
+    pub fn p(&self) -> f32 {
+        self.p
+    }
+
+    /// Number of samples stored in the window since construction (saturating).
+    pub fn samples_seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Quantile returned by the last update (the initialization value before the first update).
+    pub fn quantile(&self) -> T {
+        self.quantile
+    }
+
+    /// Iterator over the samples in the window, from the oldest to the newest
+    /// (see [`Window::iter`](crate::Window::iter)).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.oldest)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least one element.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
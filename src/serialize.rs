@@ -0,0 +1,534 @@
+//! `serde` support for the windows (`serde` feature).
+//!
+//! A window is serialized as its elements in chronological order (oldest first) together with
+//! the configuration (`config`, the options of `WindowBuilder`) and the state (`state`) of the filter.
+//! Buffers that can be derived from them (the sorted copy, the order-statistic tree and the scratch buffers)
+//! are not serialized, but rebuilt on deserialization. The configuration is validated as by `WindowBuilder::try_build`.
+
+use core::{fmt, marker::PhantomData};
+
+use num_traits::float::FloatCore;
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    adaptive::Adaptation, filter::{Chronological, Core}, network::Selection, order::Tree, AdaptiveThreshold, Center,
+    Decision, LargeWindow, NanPolicy, OutlierStats, Reference, ReseedPolicy, Replacement, ScaleEstimator, StorePolicy, ThresholdMode,
+    Window, WindowBank, ZeroMad,
+};
+
+/// フィルタの設定（`WindowBuilder`の設定に対応する。ヒステリシス・疑わしいサンプル・差分の判定は係数を掛けたもの）
+#[derive(Serialize, Deserialize)]
+struct Config<T> {
+    n_sigma_low: T,
+    n_sigma_high: T,
+    scale: T,
+    scale_estimator: ScaleEstimator,
+    center: Center,
+    reference: Reference,
+    mad_floor: T,
+    zero_mad: ZeroMad<T>,
+    abs_threshold: Option<T>,
+    threshold_mode: ThresholdMode,
+    output_range: Option<(T, T)>,
+    max_slew: Option<T>,
+    difference_coef: Option<(T, ThresholdMode)>,  // 1.4826 * n_sigma
+    max_consecutive_replacements: usize,
+    reseed_policy: ReseedPolicy,
+    confirmation: Option<(u8, u8)>,
+    hysteresis_coef: Option<T>,  // scale * n_sigma_exit
+    suspect_coef: Option<(T, bool)>,  // scale * n_sigma_suspect
+    adaptive: Option<AdaptiveThreshold<T>>,
+    forgetting_factor: Option<T>,
+    replacement: Replacement,
+    soft_replacement: Option<T>,
+    store_policy: StorePolicy,
+    nan_policy: NanPolicy,
+    fill_missing: bool,
+    max_missing: usize,
+    warm_up: bool,
+    undoable: bool,
+}
+
+/// 更新で変わるフィルタの状態
+#[derive(Serialize, Deserialize)]
+struct State<T> {
+    oldest: usize,
+    seen: usize,
+    last: T,
+    last_valid: T,
+    stats: OutlierStats,
+    run: usize,
+    in_run: bool,
+    votes: u8,
+    missing: usize,
+    flagged: u128,
+    median: T,
+    scale: T,
+    bounds: (T, T),
+    adaptation: Option<(T, u128, u32)>,  // 適応の状態（offset, recent, count）
+}
+
+impl<T: FloatCore> Config<T> {
+    fn of(core: &Core<T>) -> Self {
+        Config {
+            n_sigma_low: core.n_sigma_low,
+            n_sigma_high: core.n_sigma_high,
+            scale: core.scale_const,
+            scale_estimator: core.estimator,
+            center: core.center,
+            reference: core.reference,
+            mad_floor: core.mad_floor,
+            zero_mad: core.zero_mad,
+            abs_threshold: core.abs_threshold,
+            threshold_mode: core.threshold_mode,
+            output_range: core.output_range,
+            max_slew: core.max_slew,
+            difference_coef: core.difference,
+            max_consecutive_replacements: core.max_run,
+            reseed_policy: core.reseed,
+            confirmation: core.confirmation,
+            hysteresis_coef: core.hysteresis,
+            suspect_coef: core.suspect,
+            adaptive: core.adaptation.map(|a| a.config),
+            forgetting_factor: core.forgetting,
+            replacement: core.replacement,
+            soft_replacement: core.soft,
+            store_policy: core.store,
+            nan_policy: core.nan_policy,
+            fill_missing: core.fill_missing,
+            max_missing: core.max_missing,
+            warm_up: core.warm_up,
+            undoable: core.undoable,
+        }
+    }
+
+    /// 設定と状態から`Core`を作る（係数などwindowから求める状態は`Core::restore`で求める）
+    fn with_state(self, state: State<T>) -> Core<T> {
+        let adaptation = match (self.adaptive, state.adaptation) {
+            (Some(config), Some(parts)) => Some(Adaptation::from_parts(config, parts)),
+            (Some(config), None) => Some(Adaptation::new(config)),
+            (None, _) => None,
+        };
+        Core {
+            oldest: state.oldest,
+            coef_low: T::zero(),
+            coef_high: T::zero(),
+            scale_const: self.scale,
+            n_sigma_low: self.n_sigma_low,
+            n_sigma_high: self.n_sigma_high,
+            adaptation,
+            estimator: self.scale_estimator,
+            center: self.center,
+            reference: self.reference,
+            center_len: 0,
+            mad_floor: self.mad_floor,
+            zero_mad: self.zero_mad,
+            abs_threshold: self.abs_threshold,
+            threshold_mode: self.threshold_mode,
+            output_range: self.output_range,
+            max_slew: self.max_slew,
+            difference: self.difference_coef,
+            replacement: self.replacement,
+            soft: self.soft_replacement,
+            store: self.store_policy,
+            nan_policy: self.nan_policy,
+            warm_up: self.warm_up,
+            undoable: self.undoable,
+            seen: state.seen,
+            last: state.last,
+            last_valid: state.last_valid,
+            stats: state.stats,
+            max_run: self.max_consecutive_replacements,
+            run: state.run,
+            reseed: self.reseed_policy,
+            hysteresis: self.hysteresis_coef,
+            suspect: self.suspect_coef,
+            in_run: state.in_run,
+            confirmation: self.confirmation,
+            votes: state.votes,
+            fill_missing: self.fill_missing,
+            max_missing: self.max_missing,
+            missing: state.missing,
+            missing_gap: None,
+            flagged: state.flagged,
+            forgetting: self.forgetting_factor,
+            median: state.median,
+            scale: state.scale,
+            bounds: state.bounds,
+            #[cfg(any(feature = "log", feature = "defmt"))]
+            label: crate::filter::DEFAULT_LABEL,
+            nan_count: 0,
+            sorted_dirty: false,
+            line: None,
+            quantiles: None,
+        }
+    }
+}
+
+impl<T: FloatCore> State<T> {
+    fn of(core: &Core<T>) -> Self {
+        State {
+            oldest: core.oldest,
+            seen: core.seen,
+            last: core.last,
+            last_valid: core.last_valid,
+            stats: core.stats,
+            run: core.run,
+            in_run: core.in_run,
+            votes: core.votes,
+            missing: core.missing,
+            flagged: core.flagged,
+            median: core.median,
+            scale: core.scale,
+            bounds: core.bounds,
+            adaptation: core.adaptation.map(|a| a.parts()),
+        }
+    }
+}
+
+/// リングバッファを時系列順（最も古い要素から）のシーケンスとしてシリアライズする
+impl<T: Serialize> Serialize for Chronological<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+fn serialize_window<S: Serializer, T: FloatCore + Serialize>(
+    serializer: S,
+    name: &'static str,
+    window: &[T],
+    core: &Core<T>,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct(name, 3)?;
+    state.serialize_field("window", &Chronological { window, oldest: core.oldest })?;
+    state.serialize_field("config", &Config::of(core))?;
+    state.serialize_field("state", &State::of(core))?;
+    state.end()
+}
+
+/// 長さ`N`のシーケンスを配列としてデシリアライズする（長さが違えばエラー）
+fn deserialize_array<'de, D, E, const N: usize>(deserializer: D) -> Result<[E; N], D::Error>
+where
+    D: Deserializer<'de>,
+    E: Deserialize<'de>,
+{
+    struct ArrayVisitor<E, const N: usize>(PhantomData<E>);
+
+    impl<'de, E: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<E, N> {
+        type Value = [E; N];
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a sequence of length {}", N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[E; N], A::Error> {
+            let mut items: [Option<E>; N] = core::array::from_fn(|_| None);
+            for (i, item) in items.iter_mut().enumerate() {
+                *item = Some(seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?);
+            }
+            // 余分な要素があれば，全体の長さを数えてエラーにする
+            let mut len = N;
+            while seq.next_element::<IgnoredAny>()?.is_some() {
+                len += 1;
+            }
+            if len != N {
+                return Err(de::Error::invalid_length(len, &self));
+            }
+            Ok(items.map(|item| item.unwrap()))
+        }
+    }
+
+    deserializer.deserialize_seq(ArrayVisitor(PhantomData))
+}
+
+/// `deserialize_array`と同じだが，フィールドが無ければ`None`（`#[serde(default)]`と合わせて使う）
+fn deserialize_optional_array<'de, D, E, const N: usize>(deserializer: D) -> Result<Option<[E; N]>, D::Error>
+where
+    D: Deserializer<'de>,
+    E: Deserialize<'de>,
+{
+    deserialize_array(deserializer).map(Some)
+}
+
+/// 可変長のシーケンスをデシリアライズする（フィールドが無ければ`None`，`#[serde(default)]`と合わせて使う）
+#[cfg(feature = "alloc")]
+fn deserialize_optional_vec<'de, D, E>(deserializer: D) -> Result<Option<alloc::vec::Vec<E>>, D::Error>
+where
+    D: Deserializer<'de>,
+    E: Deserialize<'de>,
+{
+    alloc::vec::Vec::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "T: Deserialize<'de>")]
+struct WindowState<T, const N: usize> {
+    #[serde(deserialize_with = "deserialize_array")]
+    window: [T; N],
+    config: Config<T>,
+    state: State<T>,
+}
+
+/// `Window`は時刻の差と最新の時刻も持つ（それ以前の形式では省略されているので等間隔とみなす）
+#[derive(Deserialize)]
+#[serde(bound = "T: Deserialize<'de>")]
+struct TimedWindowState<T, const N: usize> {
+    #[serde(deserialize_with = "deserialize_array")]
+    window: [T; N],
+    config: Config<T>,
+    state: State<T>,
+    #[serde(default, deserialize_with = "deserialize_optional_array")]
+    gaps: Option<[T; N]>,
+    #[serde(default)]
+    time: Option<T>,
+}
+
+/// The window is serialized in chronological order with the state and the configuration of the filter,
+/// so that a restored filter resumes exactly where it left off.
+/// The attached [`OutlierHandler`](crate::OutlierHandler) is not serialized.
+/// The timestamps given by [`update_at`](Window::update_at) are serialized as the differences between consecutive samples.
+///
+/// ```
+/// use hampel::{Replacement, Window};
+///
+/// let input = |i: usize| (i as f64 * 0.1).sin() + if i % 37 == 0 { 5.0 } else { 0.0 };
+/// let mut uninterrupted = Window::<f64, 7>::builder().replacement(Replacement::HoldLast).build();
+/// let mut filter = uninterrupted.clone();
+/// for i in 0..500 {
+///     uninterrupted.update(input(i));
+///     filter.update(input(i));
+/// }
+///
+/// // Snapshot in the middle of the stream
+/// let json = serde_json::to_string(&filter).unwrap();
+/// let bytes = postcard::to_allocvec(&filter).unwrap();
+/// let mut from_json: Window<f64, 7> = serde_json::from_str(&json).unwrap();
+/// let mut from_postcard: Window<f64, 7> = postcard::from_bytes(&bytes).unwrap();
+/// for i in 500..1000 {
+///     let s = uninterrupted.update_detailed(input(i));
+///     assert_eq!(from_json.update_detailed(input(i)), s);
+///     assert_eq!(from_postcard.update_detailed(input(i)), s);
+/// }
+///
+/// // A snapshot between missing samples resumes with the same gap
+/// let mut line = Window::<f64, 7>::builder().replacement(Replacement::LinearExtrapolation).build();
+/// for i in 0..20 {
+///     line.update(i as f64);
+/// }
+/// line.update_missing();
+/// let mut restored: Window<f64, 7> = serde_json::from_str(&serde_json::to_string(&line).unwrap()).unwrap();
+/// assert_eq!(restored.update_missing_detailed(), line.update_missing_detailed());
+/// assert_eq!(restored.update_detailed(22.0), line.update_detailed(22.0));
+///
+/// // A window of another size, or an inconsistent state, is rejected.
+/// assert!(serde_json::from_str::<Window<f64, 5>>(&json).is_err());
+/// assert!(postcard::from_bytes::<Window<f64, 9>>(&bytes).is_err());
+/// assert!(json.contains("\"oldest\":3,"));
+/// assert!(serde_json::from_str::<Window<f64, 7>>(&json.replace("\"oldest\":3,", "\"oldest\":7,")).is_err());
+/// ```
+///
+/// ```
+/// use hampel::{Replacement, Window};
+///
+/// let mut a = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+/// for i in 0..23 {
+///     a.update_at((i * i) as f64, i as f64);
+/// }
+/// let mut b: Window<f64, 5> = serde_json::from_str(&serde_json::to_string(&a).unwrap()).unwrap();
+/// assert_eq!(b, a);
+/// assert_eq!(b.update_at(600.0, 100.0), a.update_at(600.0, 100.0));
+/// ```
+impl<T: FloatCore + Serialize, const WINDOW_SIZE: usize, H> Serialize for Window<T, WINDOW_SIZE, H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Window", 5)?;
+        state.serialize_field("window", &Chronological { window: &self.window, oldest: self.core.oldest })?;
+        state.serialize_field("config", &Config::of(&self.core))?;
+        state.serialize_field("state", &State::of(&self.core))?;
+        state.serialize_field("gaps", &Chronological { window: &self.gaps, oldest: self.core.oldest })?;
+        state.serialize_field("time", &self.time)?;
+        state.end()
+    }
+}
+
+/// The handler is initialized with `H::default()`.
+impl<'de, T, const WINDOW_SIZE: usize, H> Deserialize<'de> for Window<T, WINDOW_SIZE, H>
+where
+    T: FloatCore + Deserialize<'de>,
+    H: Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let TimedWindowState { mut window, config, state, gaps, time } = TimedWindowState::<T, WINDOW_SIZE>::deserialize(deserializer)?;
+        let mut core = config.with_state(state);
+        let mut sorted = window;
+        core.restore(&mut window, &mut Selection(&mut sorted)).map_err(de::Error::custom)?;
+        let mut gaps = gaps.unwrap_or([T::one(); WINDOW_SIZE]);
+        gaps.rotate_right(core.oldest);
+        Ok(Window { window, sorted, gaps, time, core, undo: None, handler: H::default() })
+    }
+}
+
+/// Same as [`Window`].
+///
+/// ```
+/// use hampel::LargeWindow;
+///
+/// let mut a = LargeWindow::<f64, 64>::new(0.0, 3.0);
+/// for i in 0..100 {
+///     a.update((i as f64 * 0.1).sin());
+/// }
+/// let mut b: LargeWindow<f64, 64> = serde_json::from_str(&serde_json::to_string(&a).unwrap()).unwrap();
+/// for i in 100..300 {
+///     let x = (i as f64 * 0.1).sin() + if i % 37 == 0 { 5.0 } else { 0.0 };
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+/// }
+/// ```
+impl<T: FloatCore + Serialize, const WINDOW_SIZE: usize> Serialize for LargeWindow<T, WINDOW_SIZE> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_window(serializer, "LargeWindow", &self.window, &self.core)
+    }
+}
+
+impl<'de, T, const WINDOW_SIZE: usize> Deserialize<'de> for LargeWindow<T, WINDOW_SIZE>
+where
+    T: FloatCore + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let WindowState { mut window, config, state } = WindowState::<T, WINDOW_SIZE>::deserialize(deserializer)?;
+        let mut core = config.with_state(state);
+        if window.is_empty() {
+            return Err(de::Error::custom("window size must be at least 3"));
+        }
+        let mut tree = Tree::new(window[0]);
+        core.restore(&mut window, &mut tree).map_err(de::Error::custom)?;
+        Ok(LargeWindow { window, work: window, tree, core })
+    }
+}
+
+/// Same as [`Window`], but the window size is taken from the serialized data (at least 3).
+///
+/// ```
+/// use hampel::DynWindow;
+///
+/// let mut a = DynWindow::new(9, 0.0, 3.0).unwrap();
+/// for i in 0..100 {
+///     a.update((i as f64 * 0.1).sin());
+/// }
+/// let mut b: DynWindow<f64> = postcard::from_bytes(&postcard::to_allocvec(&a).unwrap()).unwrap();
+/// assert_eq!(b.len(), 9);
+/// for i in 100..300 {
+///     let x = (i as f64 * 0.1).sin() + if i % 37 == 0 { 5.0 } else { 0.0 };
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+/// }
+/// ```
+#[cfg(feature = "alloc")]
+impl<T: FloatCore + Serialize> Serialize for crate::DynWindow<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DynWindow", 4)?;
+        state.serialize_field("window", &Chronological { window: &self.window, oldest: self.core.oldest })?;
+        state.serialize_field("config", &Config::of(&self.core))?;
+        state.serialize_field("state", &State::of(&self.core))?;
+        state.serialize_field("gaps", &Chronological { window: &self.gaps, oldest: self.core.oldest })?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T> Deserialize<'de> for crate::DynWindow<T>
+where
+    T: FloatCore + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "DynWindow", bound = "T: Deserialize<'de>")]
+        struct DynState<T> {
+            window: alloc::vec::Vec<T>,
+            config: Config<T>,
+            state: State<T>,
+            #[serde(default, deserialize_with = "deserialize_optional_vec")]
+            gaps: Option<alloc::vec::Vec<T>>,
+        }
+
+        let DynState { window, config, state, gaps } = DynState::<T>::deserialize(deserializer)?;
+        let mut core = config.with_state(state);
+        let mut window = window.into_boxed_slice();
+        core.restore(&mut window, &mut [][..]).map_err(de::Error::custom)?;
+        let mut gaps = gaps.unwrap_or_else(|| alloc::vec![T::one(); window.len()]).into_boxed_slice();
+        if gaps.len() != window.len() {
+            return Err(de::Error::invalid_length(gaps.len(), &"as many gaps as samples"));
+        }
+        gaps.rotate_right(core.oldest);
+        Ok(crate::DynWindow { work: window.clone(), window, gaps, core })
+    }
+}
+
+/// Each channel is serialized as a [`Window`].
+///
+/// ```
+/// use hampel::{Decision, WindowBank};
+///
+/// let mut a = WindowBank::<f64, 5, 2>::new(0.0, 3.0, Decision::Any);
+/// a.set_joint_threshold(2.5);
+/// for i in 0..50 {
+///     a.update([(i as f64 * 0.3).sin(), (i as f64 * 0.2).cos()]);
+/// }
+/// let bytes = postcard::to_allocvec(&a).unwrap();
+/// let mut b: WindowBank<f64, 5, 2> = postcard::from_bytes(&bytes).unwrap();
+/// assert_eq!(b.joint_threshold(), 2.5);
+/// for i in 50..100 {
+///     let x = [(i as f64 * 0.3).sin(), if i % 9 == 0 { 4.0 } else { (i as f64 * 0.2).cos() }];
+///     assert_eq!(a.update(x), b.update(x));
+/// }
+///
+/// // A bank with another number of channels is rejected.
+/// assert!(postcard::from_bytes::<WindowBank<f64, 5, 3>>(&bytes).is_err());
+/// ```
+impl<T, const WINDOW_SIZE: usize, const CHANNELS: usize> Serialize for WindowBank<T, WINDOW_SIZE, CHANNELS>
+where
+    T: FloatCore + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Windows<'a, W>(&'a [W]);
+
+        impl<W: Serialize> Serialize for Windows<'_, W> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.0)
+            }
+        }
+
+        let mut state = serializer.serialize_struct("WindowBank", 3)?;
+        state.serialize_field("windows", &Windows(&self.windows))?;
+        state.serialize_field("decision", &self.decision)?;
+        state.serialize_field("joint_threshold", &Some(self.joint_threshold))?;
+        state.end()
+    }
+}
+
+impl<'de, T, const WINDOW_SIZE: usize, const CHANNELS: usize> Deserialize<'de> for WindowBank<T, WINDOW_SIZE, CHANNELS>
+where
+    T: FloatCore + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "WindowBank", bound = "T: FloatCore + Deserialize<'de>")]
+        struct State<T: FloatCore, const N: usize, const C: usize> {
+            #[serde(deserialize_with = "deserialize_array")]
+            windows: [Window<T, N>; C],
+            decision: Decision,
+            #[serde(default = "Option::default")]
+            joint_threshold: Option<T>,
+        }
+
+        let State { windows, decision, joint_threshold } = State::<T, WINDOW_SIZE, CHANNELS>::deserialize(deserializer)?;
+        // 閾値のない（以前の）状態では，from_windowと同じくn_sigmaを使う
+        let joint_threshold = match (joint_threshold, windows.first()) {
+            (Some(t), _) => t,
+            (None, Some(w)) => w.effective_n_sigma(),
+            (None, None) => T::zero(),
+        };
+        Ok(WindowBank { windows, decision, joint_threshold })
+    }
+}
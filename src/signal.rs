@@ -0,0 +1,163 @@
+//! Adapter of the Hampel filter to `dasp` signals (`dasp` feature).
+
+use dasp::{Frame, Sample, Signal};
+use num_traits::float::FloatCore;
+
+use crate::Window;
+
+/// Frame types that can be filtered by [`HampelSignal`]: `f32` and `f64` (mono),
+/// and arrays of them (one element per channel, e.g. `[f32; 2]` for stereo).
+pub trait HampelFrame<const WINDOW_SIZE: usize>: Frame {
+    /// Windows of the channels (one [`Window`] per channel).
+    type Windows: Clone;
+
+    /// Windows of the channels, seeded from the channels of `init` (see [`HampelExt::hampel_filter`](crate::HampelExt::hampel_filter)).
+    fn windows(init: &Self, n_sigma: Self::Sample) -> Self::Windows;
+
+    /// Filter each channel of `self` by its window.
+    fn filter(self, windows: &mut Self::Windows) -> Self;
+}
+
+/// 最初のサンプルで初期化したwindow（`HampelIter`と同じくwarm upあり）
+fn seeded<T: FloatCore, const WINDOW_SIZE: usize>(init: T, n_sigma: T) -> Window<T, WINDOW_SIZE> {
+    Window::builder().init_value(init).n_sigma(n_sigma).warm_up(true).build()
+}
+
+macro_rules! impl_hampel_frame {
+    ($($t:ty),*) => {$(
+        impl<const WINDOW_SIZE: usize> HampelFrame<WINDOW_SIZE> for $t {
+            type Windows = Window<$t, WINDOW_SIZE>;
+
+            fn windows(init: &Self, n_sigma: $t) -> Self::Windows {
+                seeded(*init, n_sigma)
+            }
+
+            fn filter(self, windows: &mut Self::Windows) -> Self {
+                windows.update(self)
+            }
+        }
+    )*};
+}
+
+impl_hampel_frame!(f32, f64);
+
+impl<T, const CHANNELS: usize, const WINDOW_SIZE: usize> HampelFrame<WINDOW_SIZE> for [T; CHANNELS]
+where
+    T: FloatCore + Sample,
+    [T; CHANNELS]: Frame<Sample = T>,
+{
+    type Windows = [Window<T, WINDOW_SIZE>; CHANNELS];
+
+    fn windows(init: &Self, n_sigma: T) -> Self::Windows {
+        core::array::from_fn(|i| seeded(init[i], n_sigma))
+    }
+
+    fn filter(self, windows: &mut Self::Windows) -> Self {
+        core::array::from_fn(|i| windows[i].update(self[i]))
+    }
+}
+
+/// Extension trait adding the Hampel filter to `dasp` signals.
+pub trait HampelSignalExt: Signal + Sized {
+    /// Filter each channel of the frames with a [`Window`] seeded from the first frame
+    /// (see [`HampelExt::hampel_filter`](crate::HampelExt::hampel_filter)).
+    ///
+    /// A click on one channel of a stereo signal is removed, and the other channel is left untouched:
+    ///
+    /// ```
+    /// use dasp::{signal, Signal};
+    /// use hampel::HampelSignalExt;
+    ///
+    /// // Tones with a little noise, and a click every 250 frames on the left channel
+    /// let noise = |i: usize| ((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) as f32 / (1u64 << 24) as f32 * 0.02;
+    /// let tone = |i: usize, f: f32| (i as f32 * f).sin() * 0.5 + noise(i);
+    /// let input: Vec<[f32; 2]> = (0..2000).map(|i| {
+    ///     let click = if i % 250 == 100 { 0.9 } else { 0.0 };
+    ///     [tone(i, 0.02) + click, tone(i + 7, 0.03)]
+    /// }).collect();
+    ///
+    /// let output: Vec<[f32; 2]> = signal::from_iter(input.iter().copied())
+    ///     .hampel::<11>(4.0)
+    ///     .until_exhausted()
+    ///     .collect();
+    /// assert_eq!(output.len(), input.len());
+    /// for (i, (y, x)) in output.iter().zip(&input).enumerate() {
+    ///     assert_eq!(y[1].to_bits(), x[1].to_bits());
+    ///     if i % 250 == 100 {
+    ///         assert!((y[0] - tone(i, 0.02)).abs() < 0.05);
+    ///     } else {
+    ///         assert_eq!(y[0], x[0]);
+    ///     }
+    /// }
+    /// ```
+    fn hampel<const WINDOW_SIZE: usize>(self, n_sigma: <Self::Frame as Frame>::Sample) -> HampelSignal<Self, WINDOW_SIZE>
+    where
+        Self::Frame: HampelFrame<WINDOW_SIZE>,
+    {
+        HampelSignal { signal: self, windows: None, n_sigma }
+    }
+}
+
+impl<S: Signal> HampelSignalExt for S {}
+
+/// Signal returned by [`HampelSignalExt::hampel`].
+///
+/// The signal is exhausted when the source signal is.
+///
+/// ```
+/// use dasp::{signal, Signal};
+/// use hampel::HampelSignalExt;
+///
+/// let mut mono = signal::from_iter([0.0f64, 0.1, -0.1, 0.0, 5.0, 0.1]).hampel::<5>(3.0);
+/// let output: Vec<f64> = mono.by_ref().take(6).collect();
+/// assert_eq!(output[..4], [0.0, 0.1, -0.1, 0.0]);
+/// assert_ne!(output[4], 5.0);
+/// assert!(mono.is_exhausted());
+/// ```
+#[derive(Clone)]
+pub struct HampelSignal<S: Signal, const WINDOW_SIZE: usize>
+where
+    S::Frame: HampelFrame<WINDOW_SIZE>,
+{
+    signal: S,
+    windows: Option<<S::Frame as HampelFrame<WINDOW_SIZE>>::Windows>,
+    n_sigma: <S::Frame as Frame>::Sample,
+}
+
+impl<S: Signal, const WINDOW_SIZE: usize> HampelSignal<S, WINDOW_SIZE>
+where
+    S::Frame: HampelFrame<WINDOW_SIZE>,
+{
+    /// Windows of the channels, or `None` before the first frame.
+    pub fn windows(&self) -> Option<&<S::Frame as HampelFrame<WINDOW_SIZE>>::Windows> {
+        self.windows.as_ref()
+    }
+
+    /// Source signal.
+    pub fn get_ref(&self) -> &S {
+        &self.signal
+    }
+
+    /// Return the source signal.
+    pub fn into_inner(self) -> S {
+        self.signal
+    }
+}
+
+impl<S: Signal, const WINDOW_SIZE: usize> Signal for HampelSignal<S, WINDOW_SIZE>
+where
+    S::Frame: HampelFrame<WINDOW_SIZE>,
+{
+    type Frame = S::Frame;
+
+    fn next(&mut self) -> S::Frame {
+        let frame = self.signal.next();
+        let n_sigma = self.n_sigma;
+        let windows = self.windows.get_or_insert_with(|| HampelFrame::windows(&frame, n_sigma));
+        frame.filter(windows)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.signal.is_exhausted()
+    }
+}
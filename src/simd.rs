@@ -0,0 +1,228 @@
+//! Multi-channel filter for `f32` vectorized across the channels (`simd` feature).
+
+use core::simd::{cmp::SimdPartialEq, cmp::SimdPartialOrd, num::SimdFloat, Mask, Select, Simd};
+
+use crate::{builder::default_core, filter::Chronological, stats, Class, Sample, Side};
+
+/// Bank of Hampel filters for `f32`, in which the channels are processed together as SIMD lanes.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// It behaves exactly like a [`WindowBank`](crate::WindowBank) of
+/// `Window::<f32, WINDOW_SIZE>::with_replacement(init_val, n_sigma, Replacement::Median)` with
+/// [`Decision::Independent`](crate::Decision::Independent).
+///
+/// For `WINDOW_SIZE` = 5, 7, 9 and 11, the medians of all channels are computed at once by
+/// a sorting network of lane-wise min/max, and so are the absolute deviations.
+/// The channels whose window contains NaN or negative zero, or whose median is not finite,
+/// are computed by the scalar path, since the order of such values is determined by the sort.
+/// For the other sizes, every channel is computed by the scalar path.
+///
+/// ```
+/// use hampel::{Replacement, SimdBank, Window};
+///
+/// let mut a = SimdBank::<5, 4>::new(0.0, 3.0);
+/// let mut b: [_; 4] = core::array::from_fn(|_| Window::<f32, 5>::with_replacement(0.0, 3.0, Replacement::Median));
+///
+/// let inputs = [
+///     [0.5, 1.0, -2.0, 0.0],
+///     [0.4, 1.1, -2.1, -0.0],
+///     [9.0, 1.0, -2.0, f32::NAN],
+///     [0.6, 0.9, 50.0, 1.0],
+///     [0.5, 1.0, -1.9, 1.0],
+///     [0.45, f32::INFINITY, -2.0, 1.0],
+///     [0.55, 1.0, -2.0, 1.0],
+/// ];
+/// for x in inputs {
+///     let sa = a.update_detailed(x);
+///     for (i, w) in b.iter_mut().enumerate() {
+///         let sb = w.update_detailed(x[i]);
+///         // Compare the bits, since NaN != NaN
+///         assert_eq!(sa[i].value.to_bits(), sb.value.to_bits());
+///         assert_eq!(sa[i].is_outlier, sb.is_outlier);
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SimdBank<const WINDOW_SIZE: usize, const CHANNELS: usize> {
+    pub(crate) window: [Simd<f32, CHANNELS>; WINDOW_SIZE],
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) coef: f32,  // 閾値判定に使う係数
+}
+
+impl<const WINDOW_SIZE: usize, const CHANNELS: usize> SimdBank<WINDOW_SIZE, CHANNELS> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
+    /// * `init_val`: Initialization value of the windows.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let bank = hampel::SimdBank::<2, 4>::new(0.0, 3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(init_val: f32, n_sigma: f32) -> Self {
+        let () = Self::VALID_SIZE;
+
+        let core = match default_core(WINDOW_SIZE, init_val, n_sigma) {
+            Ok(core) => core,
+            Err(e) => panic!("{}", e),
+        };
+        Self { window: [Simd::splat(init_val); WINDOW_SIZE], oldest: 0, coef: core.coef_high }
+    }
+
+    /// Update the windows of all channels (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: [f32; CHANNELS]) -> [f32; CHANNELS] {
+        self.update_detailed(x).map(|s| s.value)
+    }
+
+    /// Update the windows of all channels (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    pub fn update_detailed(&mut self, x: [f32; CHANNELS]) -> [Sample<f32>; CHANNELS] {
+        let x = Simd::from_array(x);
+        self.window[self.oldest] = x;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+
+        let (w0, s0) = match network(WINDOW_SIZE) {
+            Some(net) => self.statistics(net),
+            None => self.scalar_statistics(Simd::splat(0.0), Simd::splat(0.0), Mask::splat(true)),
+        };
+
+        // 閾値判定（Core::deviatesと同じ計算）
+        let below = x.simd_lt(w0);
+        let deviation = below.select(w0 - x, x - w0);
+        let limit = Simd::splat(self.coef) * s0.simd_max(Simd::splat(0.0));
+        let is_outlier = !deviation.simd_le(limit);  // NaNの偏差も外れ値とする
+        let (lower, upper) = ((w0 - limit).to_array(), (w0 + limit).to_array());
+        let value = is_outlier.select(w0, x);
+
+        // score = deviation / (1.4826 * s0)（偏差0なら0，s0 = 0なら無限大）
+        let scaled = deviation / (Simd::splat(1.4826) * s0);
+        let score = deviation.simd_eq(Simd::splat(0.0)).select(Simd::splat(0.0), scaled);
+
+        let (value, w0, s0, score) = (value.to_array(), w0.to_array(), s0.to_array(), score.to_array());
+        let (is_outlier, below) = (is_outlier.to_array(), below.to_array());
+        core::array::from_fn(|i| Sample {
+            value: value[i],
+            is_outlier: is_outlier[i],
+            median: w0[i],
+            mad: s0[i],
+            bounds: (lower[i], upper[i]),
+            relative: is_outlier[i],
+            absolute: false,
+            difference: false,
+            out_of_range: false,
+            reseeded: false,
+            tripped: is_outlier[i],
+            score: score[i],
+            side: match is_outlier[i] {
+                true if below[i] => Some(Side::Below),
+                true => Some(Side::Above),
+                false => None,
+            },
+            stale: false,
+            zero_mad: s0[i] == 0.0,
+            class: if is_outlier[i] { Class::Outlier } else { Class::Normal },
+        })
+    }
+
+    /// Restore the state right after construction, with the windows filled with `init_val`.
+    pub fn reset(&mut self, init_val: f32) {
+        self.window = [Simd::splat(init_val); WINDOW_SIZE];
+        self.oldest = 0;
+    }
+
+    /// ソーティングネットワークで全チャンネルの中央値と中央絶対偏差を求める
+    fn statistics(&self, net: &[(usize, usize)]) -> (Simd<f32, CHANNELS>, Simd<f32, CHANNELS>) {
+        let mid = WINDOW_SIZE / 2;
+        let mut work = self.window;
+        sort(&mut work, net);
+        let w0 = work[mid];
+        for w in work.iter_mut() {
+            *w = (*w - w0).abs();
+        }
+        sort(&mut work, net);
+        let s0 = work[mid];
+
+        // ソートした場合と結果が変わり得るチャンネルはスカラーで計算し直す
+        let zero = Simd::splat(0.0);
+        let mut scalar = !w0.is_finite();
+        for v in &self.window {
+            scalar |= v.is_nan() | (v.simd_eq(zero) & v.is_sign_negative());
+        }
+        if scalar.any() {
+            self.scalar_statistics(w0, s0, scalar)
+        } else {
+            (w0, s0)
+        }
+    }
+
+    /// `lanes`のチャンネルの中央値と中央絶対偏差をスカラーで計算して`w0`と`s0`を書き換える
+    fn scalar_statistics(
+        &self,
+        w0: Simd<f32, CHANNELS>,
+        s0: Simd<f32, CHANNELS>,
+        lanes: Mask<i32, CHANNELS>,
+    ) -> (Simd<f32, CHANNELS>, Simd<f32, CHANNELS>) {
+        let (mut w0, mut s0) = (w0.to_array(), s0.to_array());
+        for lane in 0..CHANNELS {
+            if lanes.test(lane) {
+                let mut work: [f32; WINDOW_SIZE] = core::array::from_fn(|i| self.window[i][lane]);
+                (w0[lane], s0[lane]) = stats::statistics(&mut work);
+            }
+        }
+        (Simd::from_array(w0), Simd::from_array(s0))
+    }
+}
+
+/// `n`個の要素のソーティングネットワーク（比較交換する要素の組）
+fn network(n: usize) -> Option<&'static [(usize, usize)]> {
+    const N5: [(usize, usize); 9] = [
+        (0, 3), (1, 4), (0, 2), (1, 3), (0, 1), (2, 4), (1, 2), (3, 4), (2, 3),
+    ];
+    const N7: [(usize, usize); 16] = [
+        (0, 6), (2, 3), (4, 5), (0, 2), (1, 4), (3, 6), (0, 1), (2, 5),
+        (3, 4), (1, 2), (4, 6), (2, 3), (4, 5), (1, 2), (3, 4), (5, 6),
+    ];
+    const N9: [(usize, usize); 25] = [
+        (0, 3), (1, 7), (2, 5), (4, 8), (0, 7), (2, 4), (3, 8), (5, 6), (0, 2),
+        (1, 3), (4, 5), (7, 8), (1, 4), (3, 6), (5, 7), (0, 1), (2, 4), (3, 5),
+        (6, 8), (2, 3), (4, 5), (6, 7), (1, 2), (3, 4), (5, 6),
+    ];
+    const N11: [(usize, usize); 35] = [
+        (0, 9), (1, 6), (2, 4), (3, 7), (5, 8), (0, 1), (3, 5), (4, 10), (6, 9),
+        (7, 8), (1, 3), (2, 5), (4, 7), (8, 10), (0, 4), (1, 2), (3, 7), (5, 9),
+        (6, 8), (0, 1), (2, 6), (4, 5), (7, 8), (9, 10), (2, 4), (3, 6), (5, 7),
+        (8, 9), (1, 2), (3, 4), (5, 6), (7, 8), (2, 3), (4, 5), (6, 7),
+    ];
+    match n {
+        5 => Some(&N5),
+        7 => Some(&N7),
+        9 => Some(&N9),
+        11 => Some(&N11),
+        _ => None,
+    }
+}
+
+/// 各レーンを昇順に並べる（NaNと負のゼロを含むレーンは正しく並ばない）
+fn sort<const L: usize>(work: &mut [Simd<f32, L>], net: &[(usize, usize)]) {
+    for &(i, j) in net {
+        let (a, b) = (work[i], work[j]);
+        work[i] = a.simd_min(b);
+        work[j] = a.simd_max(b);
+    }
+}
+
+/// Two banks are equal if their windows hold the same samples in the same chronological order
+/// and they have the same threshold.
+impl<const WINDOW_SIZE: usize, const CHANNELS: usize> PartialEq for SimdBank<WINDOW_SIZE, CHANNELS> {
+    fn eq(&self, other: &Self) -> bool {
+        let a = Chronological { window: &self.window, oldest: self.oldest };
+        let b = Chronological { window: &other.window, oldest: other.oldest };
+        self.coef == other.coef && a.iter().eq(b.iter())
+    }
+}
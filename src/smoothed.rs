@@ -0,0 +1,154 @@
+//! Hampel filter followed by exponential smoothing.
+
+use num_traits::float::FloatCore;
+
+use crate::{Sample, Window};
+
+/// Hampel filter followed by a single-pole low-pass filter `y += alpha * (v - y)`,
+/// where `v` is the output of the Hampel filter (the input, or the replacement of an outlier).
+///
+/// * `WINDOW_SIZE` >= 3
+/// * 0 < `alpha` <= 1
+///
+/// The smoothed value `y` starts from the first sample that is not an outlier, not from the initialization
+/// value of the window; until then, the output of the Hampel filter is returned as is.
+/// A NaN output of the Hampel filter is returned without being fed to the smoother.
+///
+/// ```
+/// use hampel::{SmoothedWindow, Window};
+///
+/// let mut filter = SmoothedWindow::<f64, 5>::from_window(Window::builder().warm_up(true).build(), 0.5);
+/// assert_eq!(filter.smoothed(), None);
+/// assert_eq!(filter.update(10.0), 10.0);
+/// assert_eq!(filter.update(12.0), 11.0);
+/// assert_eq!((filter.smoothed(), filter.unsmoothed()), (Some(11.0), 12.0));
+/// ```
+///
+/// A spike moves `y` only towards its replacement, and with `alpha = 1` the output is exactly the Hampel filter's:
+///
+/// ```
+/// use hampel::{Replacement, SmoothedWindow, Window};
+///
+/// let input = |i: usize| (i as f64 * 0.05).sin() + if i % 37 == 20 { 50.0 } else { 0.0 };
+///
+/// let mut filter = SmoothedWindow::<f64, 9>::from_window(Window::with_replacement(0.0, 3.0, Replacement::Median), 0.2);
+/// let mut spikes = 0;
+/// for i in 0..1000 {
+///     let before = filter.smoothed();
+///     let y = filter.update(input(i));
+///     if i % 37 == 20 && i > 9 {
+///         let replacement = filter.unsmoothed();
+///         let y0 = before.unwrap();
+///         assert!((y - y0).abs() <= 0.2 * (replacement - y0).abs() + 1e-12);
+///         assert!((y - input(i - 1)).abs() < 0.5);
+///         spikes += 1;
+///     }
+/// }
+/// assert_eq!(spikes, 27);
+///
+/// let mut cascade = SmoothedWindow::<f64, 9>::new(0.0, 3.0, 1.0);
+/// let mut plain = Window::<f64, 9>::new(0.0, 3.0);
+/// for i in 0..1000 {
+///     assert_eq!(cascade.update(input(i)).to_bits(), plain.update(input(i)).to_bits());
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct SmoothedWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: Window<T, WINDOW_SIZE>,
+    pub(crate) alpha: T,
+    pub(crate) smoothed: Option<T>,  // 平滑化した値（外れ値でないサンプルを受け取るまではNone）
+    pub(crate) unsmoothed: T,  // 直近のHampelフィルタの出力値
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> SmoothedWindow<T, WINDOW_SIZE> {
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`]).
+    /// * `alpha`: Smoothing factor (1: no smoothing).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not in `(0, 1]`, or the arguments are invalid for [`Window::new`].
+    pub fn new(init_val: T, n_sigma: T, alpha: T) -> Self {
+        Self::from_window(Window::new(init_val, n_sigma), alpha)
+    }
+
+    /// Smooth the output of `window` (e.g. configured by [`Window::builder`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not in `(0, 1]`.
+    pub fn from_window(window: Window<T, WINDOW_SIZE>, alpha: T) -> Self {
+        assert!(alpha > T::zero() && alpha <= T::one(), "alpha must be in (0, 1]");
+
+        let unsmoothed = window.latest();
+        Self { window, alpha, smoothed: None, unsmoothed }
+    }
+
+    /// Filter `x`, and return the smoothed value.
+    pub fn update(&mut self, x: T) -> T {
+        let s = self.window.update_detailed(x);
+        self.smooth(&s)
+    }
+
+    /// Same as [`update`](Self::update), but the result of the Hampel filter is also returned.
+    pub fn update_detailed(&mut self, x: T) -> (T, Sample<T>) {
+        let s = self.window.update_detailed(x);
+        (self.smooth(&s), s)
+    }
+
+    /// Hampelフィルタの出力を平滑化する
+    fn smooth(&mut self, s: &Sample<T>) -> T {
+        let v = s.value;
+        self.unsmoothed = v;
+        if v.is_nan() {
+            return v;
+        }
+        let y = match self.smoothed {
+            // alpha = 1では，丸め誤差なくHampelフィルタの出力と一致させる
+            Some(_) if self.alpha == T::one() => v,
+            Some(y) => y + self.alpha * (v - y),
+            None if !s.is_outlier => v,
+            None => return v,
+        };
+        self.smoothed = Some(y);
+        y
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val`
+    /// (see [`Window::reset`]). The smoother starts again from the next sample that is not an outlier.
+    pub fn reset(&mut self, init_val: T) {
+        self.window.reset(init_val);
+        self.smoothed = None;
+        self.unsmoothed = init_val;
+    }
+
+    /// Smoothed value at the last update (`None` until a sample that is not an outlier is received).
+    pub fn smoothed(&self) -> Option<T> {
+        self.smoothed
+    }
+
+    /// Output of the Hampel filter at the last update, before smoothing.
+    pub fn unsmoothed(&self) -> T {
+        self.unsmoothed
+    }
+
+    /// Smoothing factor.
+    pub fn alpha(&self) -> T {
+        self.alpha
+    }
+
+    /// Change the smoothing factor. The smoothed value is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is not in `(0, 1]`.
+    pub fn set_alpha(&mut self, alpha: T) {
+        assert!(alpha > T::zero() && alpha <= T::one(), "alpha must be in (0, 1]");
+        self.alpha = alpha;
+    }
+
+    /// Hampel filter before the smoother.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+}
@@ -0,0 +1,128 @@
+//! Plain-old-data snapshot of the state of a window (without `serde`).
+
+use num_traits::float::FloatCore;
+
+use crate::{filter::Core, OutlierStats};
+
+/// State of a [`Window`](crate::Window) that can be copied byte by byte (e.g. to flash or FRAM),
+/// taken by [`Window::to_state`](crate::Window::to_state) and restored by [`Window::from_state`](crate::Window::from_state).
+///
+/// The samples are stored in chronological order (oldest first), so that the position of the ring buffer
+/// is not needed. The configuration of the filter is not included.
+///
+/// The struct is `#[repr(C)]`, and has no padding for `f32` and `f64`
+/// (the counters come first, then the values of type `T`).
+/// It contains no pointers or references, so that it is valid to copy it with `memcpy`.
+/// The layout depends on the target (the size of the counters is fixed, but not the endianness).
+///
+/// The counters that hold a `usize` in the window saturate at `u32::MAX`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowState<T, const WINDOW_SIZE: usize> {
+    /// See [`OutlierStats::samples_total`](crate::OutlierStats::samples_total).
+    pub samples_total: u32,
+    /// See [`OutlierStats::outliers_total`](crate::OutlierStats::outliers_total).
+    pub outliers_total: u32,
+    /// See [`OutlierStats::current_run`](crate::OutlierStats::current_run).
+    pub current_run: u32,
+    /// See [`OutlierStats::longest_run`](crate::OutlierStats::longest_run).
+    pub longest_run: u32,
+    /// [`OutlierStats::recent`](crate::OutlierStats::recent) split into 32-bit words (least significant first).
+    pub recent: [u32; 2],
+    /// Decisions of the last 128 samples (bit 0 of `flagged[0]`: latest, `1`: outlier),
+    /// which excludes the outliers from the extrapolation.
+    pub flagged: [u32; 4],
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub seen: u32,
+    /// Number of consecutive replacements (see [`WindowBuilder::max_consecutive_replacements`](crate::WindowBuilder::max_consecutive_replacements)).
+    pub run: u32,
+    /// Number of consecutive missing samples (see [`Window::update_missing`](crate::Window::update_missing)).
+    pub missing: u32,
+    /// Decisions before the confirmation (bit 0: latest, see [`WindowBuilder::confirmation`](crate::WindowBuilder::confirmation)).
+    pub votes: u8,
+    /// `1` if the filter is in an outlier run (see [`Window::in_outlier_run`](crate::Window::in_outlier_run)), `0` otherwise.
+    pub in_run: u8,
+    /// `1` if `time` holds a timestamp, `0` otherwise.
+    pub has_time: u8,
+    _reserved: u8,
+    /// Samples in the window in chronological order.
+    pub samples: [T; WINDOW_SIZE],
+    /// Time elapsed since the previous sample for each of `samples` (`1` unless [`Window::update_at`](crate::Window::update_at) is used).
+    pub gaps: [T; WINDOW_SIZE],
+    /// Timestamp of the latest sample given by [`Window::update_at`](crate::Window::update_at) (`0` if none).
+    pub time: T,
+    /// Output value of the last update.
+    pub last: T,
+    /// Output value of the last update that was not an outlier.
+    pub last_valid: T,
+    /// See [`Window::median`](crate::Window::median).
+    pub median: T,
+    /// See [`Window::mad`](crate::Window::mad).
+    pub scale: T,
+    /// Lower end of [`Window::bounds`](crate::Window::bounds).
+    pub lower: T,
+    /// Upper end of [`Window::bounds`](crate::Window::bounds).
+    pub upper: T,
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> WindowState<T, WINDOW_SIZE> {
+    /// windowの状態を写す
+    pub(crate) fn capture(core: &Core<T>, window: &[T; WINDOW_SIZE], gaps: &[T; WINDOW_SIZE], time: Option<T>) -> Self {
+        let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+        let chronological = |buf: &[T; WINDOW_SIZE]| core::array::from_fn(|i| buf[(core.oldest + i) % WINDOW_SIZE]);
+        let stats = &core.stats;
+        Self {
+            samples_total: stats.samples_total,
+            outliers_total: stats.outliers_total,
+            current_run: stats.current_run,
+            longest_run: stats.longest_run,
+            recent: [stats.recent as u32, (stats.recent >> 32) as u32],
+            flagged: core::array::from_fn(|i| (core.flagged >> (32 * i)) as u32),
+            seen: count(core.seen),
+            run: count(core.run),
+            missing: count(core.missing),
+            votes: core.votes,
+            in_run: core.in_run as u8,
+            has_time: time.is_some() as u8,
+            _reserved: 0,
+            samples: chronological(window),
+            gaps: chronological(gaps),
+            time: time.unwrap_or_else(T::zero),
+            last: core.last,
+            last_valid: core.last_valid,
+            median: core.median,
+            scale: core.scale,
+            lower: core.bounds.0,
+            upper: core.bounds.1,
+        }
+    }
+
+    /// `core`と`window`・`gaps`を写した状態に戻し，最新の時刻を返す（`sorted`は呼び出し側で作り直す）
+    ///
+    /// 窓が埋まっていれば`oldest`は0にする（埋まるまでは受け取ったサンプルの次の位置）
+    pub(crate) fn restore(&self, core: &mut Core<T>, window: &mut [T; WINDOW_SIZE], gaps: &mut [T; WINDOW_SIZE]) -> Option<T> {
+        let count = |n: u32| usize::try_from(n).unwrap_or(usize::MAX);
+        core.stats = OutlierStats {
+            samples_total: self.samples_total,
+            outliers_total: self.outliers_total,
+            current_run: self.current_run,
+            longest_run: self.longest_run,
+            recent: u64::from(self.recent[0]) | (u64::from(self.recent[1]) << 32),
+        };
+        core.flagged = self.flagged.iter().rev().fold(0, |f, &w| (f << 32) | u128::from(w));
+        core.seen = count(self.seen);
+        core.run = count(self.run);
+        core.missing = count(self.missing);
+        core.recount_missing_gap();
+        core.votes = self.votes;
+        core.in_run = self.in_run != 0;
+        (core.last, core.last_valid) = (self.last, self.last_valid);
+        (core.median, core.scale) = (self.median, self.scale);
+        core.bounds = (self.lower, self.upper);
+        core.oldest = if core.seen < WINDOW_SIZE { core.seen } else { 0 };
+        (*window, *gaps) = (self.samples, self.gaps);
+        window.rotate_right(core.oldest);
+        gaps.rotate_right(core.oldest);
+        if self.has_time != 0 { Some(self.time) } else { None }
+    }
+}
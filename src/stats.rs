@@ -0,0 +1,401 @@
+//! Order statistics shared by the filters.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{network, precision::{self, midpoint}};
+
+/// `work`の中央値と中央絶対偏差を返す（`work`の中身は書き換わる）
+pub(crate) fn statistics<T: FloatCore>(work: &mut [T]) -> (T, T) {
+    // ウィンドウの中央値を計算
+    let w0 = median(work);
+    // ウィンドウの各値に対して，中央値との絶対差分を取る
+    for w in work.iter_mut() {
+        *w = (*w - w0).abs();
+    }
+    // 絶対差分を取ったので再度中央値を計算
+    let s0 = median(work);
+
+    (w0, s0)
+}
+
+/// これ以下の要素数の中央値はselectではなくソートで求める
+const SORT_THRESHOLD: usize = 32;
+
+/// selectで探索範囲がこれ以下の要素数になったらソートする
+const SELECT_CUTOFF: usize = 12;
+
+/// `work`の中央値を返す（`work`は並べ替えられる）
+///
+/// 中央の要素だけが必要なので，ソートではなくselectで求める（5, 7, 9個なら選択ネットワーク）。
+/// ソートした場合と結果のビット列が変わり得る（NaNや負のゼロを含む）場合はソートする。
+/// NaNを含む場合は，選択ネットワークではなく挿入ソートの結果にする（`NanPolicy::Propagate`の結果を要素数によらず揃える）。
+pub(crate) fn median<T: FloatCore>(work: &mut [T]) -> T {
+    let nan = work.iter().any(|v| v.is_nan());
+    if !nan {
+        if let Some(m) = network::median(work) {
+            return m;
+        }
+    }
+    let n = work.len();
+    if nan || n <= SORT_THRESHOLD || work.iter().any(|v| *v == T::zero() && v.is_sign_negative()) {
+        sort(work);
+        return median_of_sorted(work);
+    }
+
+    let mid = n / 2;
+    select(work, mid);
+    if n.is_multiple_of(2) {
+        // work[..mid]の中で最大のものがmid - 1番目の要素
+        let lower = work[..mid].iter().fold(T::neg_infinity(), |m, v| m.max(*v));
+        midpoint(lower, work[mid])
+    } else {
+        work[mid]
+    }
+}
+
+/// `work[k]`が昇順でk番目の要素になるように並べ替える（NaNは含まないこと）
+///
+/// `work[..k]`には`work[k]`以下，`work[k + 1..]`には`work[k]`以上の要素が入る。
+/// ピボットは三つの要素の中央値で選び，分割の偏りが続いた場合は中央値の中央値に切り替えて
+/// 最悪の場合でもO(n)に抑える。
+fn select<T: FloatCore>(work: &mut [T], k: usize) {
+    let (mut lo, mut hi) = (0, work.len());  // 探索範囲は[lo, hi)
+    let mut budget = 2 * (usize::BITS - work.len().leading_zeros());  // 偏った分割を許す回数
+    while hi - lo > SELECT_CUTOFF {
+        let range = &mut work[lo..hi];
+        let len = range.len();
+        let pivot = if budget > 0 {
+            median_of_three(range[0], range[len / 2], range[len - 1])
+        } else {
+            median_of_medians(range)
+        };
+        let (lt, gt) = partition(range, pivot);
+        if lt.max(len - gt) > len - len / 4 {
+            budget = budget.saturating_sub(1);
+        }
+        if k < lo + lt {
+            hi = lo + lt;
+        } else if k >= lo + gt {
+            lo += gt;
+        } else {
+            return;  // work[k] == pivot
+        }
+    }
+    sort(&mut work[lo..hi]);
+}
+
+fn median_of_three<T: FloatCore>(a: T, b: T, c: T) -> T {
+    a.max(b).min(a.min(b).max(c))
+}
+
+/// 5個ずつの組の中央値の中央値（`work`は並べ替えられる）
+fn median_of_medians<T: FloatCore>(work: &mut [T]) -> T {
+    let groups = work.len() / 5;
+    for g in 0..groups {
+        let group = &mut work[5 * g..5 * g + 5];
+        sort(group);
+        work.swap(g, 5 * g + 2);
+    }
+    let mid = groups / 2;
+    select(&mut work[..groups], mid);
+    work[mid]
+}
+
+/// `work`を`pivot`より小さい要素，等しい要素，大きい要素の順に並べ替え，
+/// 等しい要素の範囲`[lt, gt)`を返す
+fn partition<T: FloatCore>(work: &mut [T], pivot: T) -> (usize, usize) {
+    let (mut lt, mut i, mut gt) = (0, 0, work.len());
+    while i < gt {
+        if work[i] < pivot {
+            work.swap(lt, i);
+            lt += 1;
+            i += 1;
+        } else if work[i] > pivot {
+            gt -= 1;
+            work.swap(i, gt);
+        } else {
+            i += 1;
+        }
+    }
+    (lt, gt)
+}
+
+/// 昇順に並んだ`n`個の要素（`get(i)`: `i`番目の要素）の，有限の`w0`からの絶対偏差の中央値を返す
+/// （`p`: `w0`より小さい要素の数）
+///
+/// 偏差をソートしなくても，`w0`より小さい要素の偏差（`w0`から離れるほど大きい）と
+/// それ以外の要素の偏差はそれぞれ昇順に並んでいるので，二つの昇順の列の中央の要素を二分探索で求める。
+pub(crate) fn median_of_deviations<T: FloatCore>(n: usize, p: usize, w0: T, get: impl Fn(usize) -> T) -> T {
+    let mid = n / 2;
+    if n.is_multiple_of(2) {
+        // 偶数個の場合は中央の二つの平均を取る
+        midpoint(kth_deviation(n, p, w0, mid - 1, &get), kth_deviation(n, p, w0, mid, &get))
+    } else {
+        kth_deviation(n, p, w0, mid, &get)
+    }
+}
+
+/// `median_of_deviations`の偏差のうち`k`番目（0始まり）に小さいもの
+fn kth_deviation<T: FloatCore>(n: usize, p: usize, w0: T, k: usize, get: &impl Fn(usize) -> T) -> T {
+    kth_of_two(n, p, k, |t| (get(p - 1 - t) - w0).abs(), |t| (get(p + t) - w0).abs())
+}
+
+/// 昇順に並んだ二つの列`below(0..p)`と`above(0..n - p)`を合わせたもののうち`k`番目（0始まり）に小さいもの（NaNは含まないこと）
+pub(crate) fn kth_of_two<D: Copy + PartialOrd>(
+    n: usize,
+    p: usize,
+    k: usize,
+    below: impl Fn(usize) -> D,
+    above: impl Fn(usize) -> D,
+) -> D {
+    // 小さい方からk + 1個のうち，belowの要素の個数をiとする
+    let mut lo = (k + 1).saturating_sub(n - p);
+    let mut hi = (k + 1).min(p);
+    while lo < hi {
+        let i = lo + (hi - lo) / 2;
+        if below(i) < above(k - i) {
+            lo = i + 1;
+        } else {
+            hi = i;
+        }
+    }
+    let j = k + 1 - lo;
+    match (lo, j) {
+        (0, _) => above(j - 1),
+        (_, 0) => below(lo - 1),
+        _ => {
+            let (b, a) = (below(lo - 1), above(j - 1));
+            if b < a { a } else { b }
+        },
+    }
+}
+
+/// Insertion sort
+pub(crate) fn sort<T: FloatCore>(work: &mut [T]) {
+    for i in 1..work.len() {
+        let mut j = i;
+        while j > 0 {
+            let j_pre = j - 1;
+            if unsafe{ work.get_unchecked(j_pre) > work.get_unchecked(j) } {
+                work.swap(j_pre, j);
+                j = j_pre;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// ソート済みの`sorted`に含まれる`evicted`を取り除き，`inserted`を挿入する（NaNは含まないこと）
+///
+/// 二分探索で位置を求め，その間の要素だけをずらす
+#[cfg(not(feature = "constant-time"))]
+pub(crate) fn replace_sorted<T: Copy + PartialOrd>(sorted: &mut [T], evicted: T, inserted: T) {
+    let i = sorted.partition_point(|v| *v < evicted);
+    if inserted > evicted {
+        // iより後ろでinsertedを置く位置まで左に詰める
+        let j = i + sorted[i..].partition_point(|v| *v < inserted);
+        sorted.copy_within(i + 1..j, i);
+        sorted[j - 1] = inserted;
+    } else {
+        // iより前でinsertedを置く位置から右にずらす
+        let j = sorted[..i].partition_point(|v| *v <= inserted);
+        sorted.copy_within(j..i, j + 1);
+        sorted[j] = inserted;
+    }
+}
+
+/// `replace_sorted`と同じ結果を，値によらない手順で求める（`constant-time`フィーチャ）
+///
+/// 二分探索の代わりに全要素と比べて位置を数え，左に詰める場合と右にずらす場合の両方の走査で全要素を選び直す
+#[cfg(feature = "constant-time")]
+pub(crate) fn replace_sorted<T: Copy + PartialOrd>(sorted: &mut [T], evicted: T, inserted: T) {
+    let n = sorted.len();
+    let i = sorted.iter().fold(0, |c, v| c + (*v < evicted) as usize);
+    let up = inserted > evicted;
+    // upならjの手前まで左に詰めてj - 1に置き，そうでなければjからiまで右にずらしてjに置く
+    let j_up = sorted.iter().fold(0, |c, v| c + (*v < inserted) as usize);
+    let j_down = sorted.iter().fold(0, |c, v| c + (*v <= inserted) as usize).min(i);
+    for k in 0..n {
+        let next = sorted[(k + 1).min(n - 1)];
+        let v = choose(up & (k >= i) & (k + 1 < j_up), next, sorted[k]);
+        sorted[k] = choose(up & (k + 1 == j_up), inserted, v);
+    }
+    for k in (0..n).rev() {
+        let prev = sorted[k.saturating_sub(1)];
+        let v = choose(!up & (k > j_down) & (k <= i), prev, sorted[k]);
+        sorted[k] = choose(!up & (k == j_down), inserted, v);
+    }
+}
+
+/// `c`なら`a`，そうでなければ`b`を分岐せずに選ぶ
+pub(crate) fn choose<T: Copy>(c: bool, a: T, b: T) -> T {
+    [b, a][c as usize]
+}
+
+/// `work`のトリム平均（両端から`trim`の割合ずつ除いた残りの平均）と，それからの絶対偏差の中央値を返す
+/// （`work`の中身は書き換わる）
+pub(crate) fn trimmed_statistics<T: FloatCore>(work: &mut [T], trim: f32) -> (T, T) {
+    sort(work);
+    let w0 = trimmed_mean_of_sorted(work, trim);
+    for w in work.iter_mut() {
+        *w = (*w - w0).abs();
+    }
+    (w0, median(work))
+}
+
+/// ソート済みの`sorted`の両端から`floor(trim * n)`個ずつ除いた残りの平均を返す（少なくとも1個は残す）
+pub(crate) fn trimmed_mean_of_sorted<T: FloatCore>(sorted: &[T], trim: f32) -> T {
+    let n = sorted.len();
+    // 0.29 * 100などが丸め誤差で切り捨てられないように，わずかに足してから切り捨てる
+    let k = ((f64::from(trim) * n as f64 + 1e-9) as usize).min((n - 1) / 2);
+    let kept = &sorted[k..(n - k)];
+    if precision::is_narrow::<T>() {
+        // 精度の低い型では和の丸め誤差が大きいので，f32で計算する
+        let sum = kept.iter().fold(0.0, |s, v| s + precision::widen(*v));
+        precision::narrow(sum / kept.len() as f32)
+    } else {
+        // 個数もTで数える（windowの長さはTで誤差なく表せる）
+        let (sum, count) = kept.iter().fold((T::zero(), T::zero()), |(s, c), v| (s + *v, c + T::one()));
+        sum / count
+    }
+}
+
+/// ソート済みの`sorted`の中央値を返す
+pub(crate) fn median_of_sorted<T: FloatCore>(sorted: &[T]) -> T {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        // 偶数個の場合は中央の二つの平均を取る
+        midpoint(sorted[mid - 1], sorted[mid])
+    } else {
+        sorted[mid]
+    }
+}
+
+/// 重み`weights`付きの`work`の中央値と中央絶対偏差を返す（`work`と`weights`の中身は書き換わる）
+pub(crate) fn weighted_statistics<T: FloatCore>(work: &mut [T], weights: &mut [T]) -> (T, T) {
+    let w0 = weighted_median(work, weights);
+    for w in work.iter_mut() {
+        *w = (*w - w0).abs();
+    }
+    let s0 = weighted_median(work, weights);
+
+    (w0, s0)
+}
+
+/// 重み付き中央値（累積の重みが総和の半分に達する最小の値）を返す
+///
+/// 累積の重みがちょうど半分になる場合は次の値との平均を取るので，重みが全て等しければ`median`と一致する．
+/// `work`と`weights`は値の昇順に並べ替えられる
+pub(crate) fn weighted_median<T: FloatCore>(work: &mut [T], weights: &mut [T]) -> T {
+    // 値と重みを組にして挿入ソート
+    for i in 1..work.len() {
+        let mut j = i;
+        while j > 0 && work[j - 1] > work[j] {
+            work.swap(j - 1, j);
+            weights.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+
+    let mut total = T::zero();
+    for w in weights.iter() {
+        total = total + *w;
+    }
+    let half = total / (T::one() + T::one());
+    let mut cum = T::zero();
+    for k in 0..work.len() {
+        cum = cum + weights[k];
+        if cum >= half {
+            if cum == half && k + 1 < work.len() {
+                return midpoint(work[k], work[k + 1]);
+            }
+            return work[k];
+        }
+    }
+    work[work.len() - 1]
+}
+
+/// ソート済みの長さ`n`の配列の`p`分位数の位置（長さと`p`だけで決まるので，windowの長さについては求めておく）
+///
+/// numpyの`percentile`（`method="linear"`）と同じく，位置`p * (n - 1)`で隣接要素を線形補間する
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum QuantilePosition<T> {
+    /// `p = 0.5`（`median_of_sorted`と同じ値）
+    Median,
+    /// 補間しない要素のインデックス
+    At(usize),
+    /// `i`番目と`i + 1`番目の要素の間の位置（`i`, 補間の割合）
+    Between(usize, T),
+}
+
+impl<T: FloatCore> QuantilePosition<T> {
+    pub(crate) fn new(n: usize, p: f32) -> Self {
+        if p == 0.5 {
+            return Self::Median;
+        }
+        let pos = f64::from(p) * (n - 1) as f64;
+        let i = pos as usize;  // pos >= 0なので切り捨てになる
+        let frac = pos - i as f64;
+        if frac == 0.0 {
+            return Self::At(i);
+        }
+        cast::<f64, T>(frac).map_or(Self::At(i), |frac| Self::Between(i, frac))
+    }
+
+    /// ソート済みの`sorted`の分位数
+    pub(crate) fn of_sorted(self, sorted: &[T]) -> T {
+        match self {
+            Self::Median => median_of_sorted(sorted),
+            Self::At(i) => sorted[i],
+            Self::Between(i, frac) => sorted[i] + (sorted[i + 1] - sorted[i]) * frac,
+        }
+    }
+}
+
+/// `work`の`position`の分位数と，それからの偏差の絶対値の中央値を返す（`work`の中身は書き換わる）
+pub(crate) fn quantile_statistics<T: FloatCore>(work: &mut [T], position: QuantilePosition<T>) -> (T, T) {
+    sort(work);
+    let w0 = position.of_sorted(work);
+    for w in work.iter_mut() {
+        *w = (*w - w0).abs();
+    }
+    (w0, median(work))
+}
+
+/// ソート済みの`sorted`に対するRousseeuw–CrouxのSn（係数を掛ける前の値）を返す
+///
+/// `Sn = lomed_i himed_j |x_i - x_j|`
+pub(crate) fn sn<T: FloatCore>(sorted: &[T], aux: &mut [T]) -> T {
+    let n = sorted.len();
+    let aux = &mut aux[..n];
+    for (i, a) in aux.iter_mut().enumerate() {
+        *a = himed_distance(sorted, i);
+    }
+    sort(aux);
+    // low median
+    aux[n.div_ceil(2) - 1]
+}
+
+/// ソート済みの`sorted`について，`himed_j |x_i - x_j|`を返す
+///
+/// jについて距離はiの左右それぞれで単調増加なので，二つの昇順列のマージとして数える
+fn himed_distance<T: FloatCore>(sorted: &[T], i: usize) -> T {
+    let n = sorted.len();
+    let xi = sorted[i];
+    let mut left = i;  // 次に見る左側の要素は left - 1
+    let mut right = i + 1;  // 次に見る右側の要素
+    let mut d = T::zero();  // j = i の距離（0番目）
+    // high median: 0始まりで n / 2 番目
+    for _ in 0..(n / 2) {
+        let dl = if left > 0 { Some(xi - sorted[left - 1]) } else { None };
+        let dr = if right < n { Some(sorted[right] - xi) } else { None };
+        d = match (dl, dr) {
+            (Some(l), Some(r)) if l <= r => { left -= 1; l },
+            (Some(l), None) => { left -= 1; l },
+            (_, Some(r)) => { right += 1; r },
+            (None, None) => unreachable!(),
+        };
+    }
+    d
+}
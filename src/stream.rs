@@ -0,0 +1,225 @@
+//! Filtering of raw binary sample files through `std::io` (`std` feature).
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::vec;
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::DynWindow;
+
+/// Type of the samples of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleFormat {
+    /// `f32` (4 bytes per sample).
+    #[default]
+    F32,
+    /// `f64` (8 bytes per sample).
+    F64,
+}
+
+/// Byte order of the samples of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    /// Little-endian.
+    #[default]
+    Little,
+    /// Big-endian.
+    Big,
+}
+
+/// Options of [`filter_reader_writer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamOptions {
+    /// Type of the samples (default: `F32`).
+    pub format: SampleFormat,
+    /// Byte order of the samples (default: `Little`).
+    pub endian: Endian,
+    /// Window size (>= 3, default: 5).
+    pub window_size: usize,
+    /// Threshold for determining an outlier (see [`Window::new`](crate::Window::new), default: 3).
+    pub n_sigma: f64,
+    /// Number of samples read and written at a time (default: 4096).
+    pub chunk_len: usize,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self { format: SampleFormat::F32, endian: Endian::Little, window_size: 5, n_sigma: 3.0, chunk_len: 4096 }
+    }
+}
+
+/// Result of [`filter_reader_writer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamStats {
+    /// Number of samples processed.
+    pub samples: u64,
+    /// Number of samples determined to be outliers and replaced.
+    pub outliers: u64,
+}
+
+/// Filter the raw samples read from `reader`, and write the output samples to `writer` in the same format.
+///
+/// The samples are processed in chunks of `opts.chunk_len` samples by a [`DynWindow`] of `opts.window_size`,
+/// whose state is carried over from chunk to chunk, so that the output is the same as filtering the whole stream
+/// at once. The window is initialized with the first sample.
+///
+/// ```
+/// use hampel::{filter_reader_writer, Endian, SampleFormat, StreamOptions, Window};
+///
+/// // Synthetic file of little-endian f32 samples with spikes
+/// let samples: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.01).sin() + if i % 97 == 13 { 20.0 } else { 0.0 }).collect();
+/// let file: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+///
+/// let opts = StreamOptions { window_size: 7, chunk_len: 1000, ..Default::default() };
+/// let mut output = Vec::new();
+/// let stats = filter_reader_writer(&file[..], &mut output, opts).unwrap();
+/// assert_eq!(stats.samples, 10_000);
+/// assert!(stats.outliers >= 103);
+///
+/// // The same bytes as the slice API in memory
+/// let mut data = samples.clone();
+/// Window::<f32, 7>::new(data[0], 3.0).process(&mut data);
+/// assert_eq!(output, data.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>());
+///
+/// // Big-endian f64, and a chunk that does not divide the stream
+/// let file: Vec<u8> = samples.iter().flat_map(|&x| (x as f64).to_be_bytes()).collect();
+/// let opts = StreamOptions { format: SampleFormat::F64, endian: Endian::Big, window_size: 7, chunk_len: 333, ..Default::default() };
+/// let mut output = Vec::new();
+/// filter_reader_writer(&file[..], &mut output, opts).unwrap();
+/// let mut data: Vec<f64> = samples.iter().map(|&x| x as f64).collect();
+/// Window::<f64, 7>::new(data[0], 3.0).process(&mut data);
+/// assert_eq!(output, data.iter().flat_map(|x| x.to_be_bytes()).collect::<Vec<u8>>());
+///
+/// // A reader that returns a few bytes at a time
+/// struct Trickle<'a>(&'a [u8]);
+/// impl std::io::Read for Trickle<'_> {
+///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+///         let n = buf.len().min(self.0.len()).min(3);
+///         buf[..n].copy_from_slice(&self.0[..n]);
+///         self.0 = &self.0[n..];
+///         Ok(n)
+///     }
+/// }
+/// let mut trickled = Vec::new();
+/// filter_reader_writer(Trickle(&file), &mut trickled, opts).unwrap();
+/// assert_eq!(trickled, output);
+/// ```
+///
+/// # Errors
+///
+/// Returns the errors of `reader` and `writer` (other than [`ErrorKind::Interrupted`], which is retried),
+/// an error of [`ErrorKind::InvalidInput`] if the options are invalid,
+/// and an error of [`ErrorKind::UnexpectedEof`] if the stream ends with a partial sample.
+/// The samples before the error may have been written.
+///
+/// ```
+/// use std::io::ErrorKind;
+/// use hampel::{filter_reader_writer, StreamOptions};
+///
+/// let file = [0u8; 4 * 10 + 3];
+/// let err = filter_reader_writer(&file[..], Vec::new(), StreamOptions::default()).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+///
+/// let opts = StreamOptions { window_size: 2, ..Default::default() };
+/// let err = filter_reader_writer(&file[..40], Vec::new(), opts).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::InvalidInput);
+/// ```
+pub fn filter_reader_writer<R: Read, W: Write>(reader: R, writer: W, opts: StreamOptions) -> io::Result<StreamStats> {
+    if opts.chunk_len == 0 {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "chunk_len must be positive"));
+    }
+    match opts.format {
+        SampleFormat::F32 => filter_samples::<f32, _, _>(reader, writer, &opts),
+        SampleFormat::F64 => filter_samples::<f64, _, _>(reader, writer, &opts),
+    }
+}
+
+/// バイト列との変換
+trait Raw: FloatCore {
+    const SIZE: usize;
+
+    fn decode(bytes: &[u8], endian: Endian) -> Self;
+
+    fn encode(self, endian: Endian, out: &mut [u8]);
+}
+
+impl Raw for f32 {
+    const SIZE: usize = 4;
+
+    fn decode(bytes: &[u8], endian: Endian) -> Self {
+        let bytes = bytes.try_into().unwrap();
+        match endian {
+            Endian::Little => f32::from_le_bytes(bytes),
+            Endian::Big => f32::from_be_bytes(bytes),
+        }
+    }
+
+    fn encode(self, endian: Endian, out: &mut [u8]) {
+        out.copy_from_slice(&match endian {
+            Endian::Little => self.to_le_bytes(),
+            Endian::Big => self.to_be_bytes(),
+        });
+    }
+}
+
+impl Raw for f64 {
+    const SIZE: usize = 8;
+
+    fn decode(bytes: &[u8], endian: Endian) -> Self {
+        let bytes = bytes.try_into().unwrap();
+        match endian {
+            Endian::Little => f64::from_le_bytes(bytes),
+            Endian::Big => f64::from_be_bytes(bytes),
+        }
+    }
+
+    fn encode(self, endian: Endian, out: &mut [u8]) {
+        out.copy_from_slice(&match endian {
+            Endian::Little => self.to_le_bytes(),
+            Endian::Big => self.to_be_bytes(),
+        });
+    }
+}
+
+fn filter_samples<T: Raw, R: Read, W: Write>(mut reader: R, mut writer: W, opts: &StreamOptions) -> io::Result<StreamStats> {
+    let invalid = |e: crate::ConfigError| io::Error::new(ErrorKind::InvalidInput, e);
+    let n_sigma = cast::<f64, T>(opts.n_sigma).ok_or_else(|| invalid(crate::ConfigError::Cast))?;
+    let mut buf = vec![0u8; opts.chunk_len * T::SIZE];
+    let mut window = DynWindow::new(opts.window_size, T::zero(), n_sigma).map_err(invalid)?;
+    let mut stats = StreamStats::default();
+    let mut filled = 0;  // bufに読み込んだバイト数
+    loop {
+        let n = match reader.read(&mut buf[filled..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        filled += n;
+        // バッファが埋まるか，ストリームが終わったら処理する
+        if n > 0 && filled < buf.len() {
+            continue;
+        }
+
+        let whole = filled - filled % T::SIZE;
+        for bytes in buf[..whole].chunks_exact_mut(T::SIZE) {
+            let x = T::decode(bytes, opts.endian);
+            if stats.samples == 0 {
+                window.reset(x);  // 最初のサンプルで初期化する
+            }
+            let s = window.update_detailed(x);
+            stats.samples += 1;
+            stats.outliers += s.is_outlier as u64;
+            s.value.encode(opts.endian, bytes);
+        }
+        writer.write_all(&buf[..whole])?;
+
+        if n == 0 {
+            if whole < filled {
+                return Err(io::Error::new(ErrorKind::UnexpectedEof, "stream ends with a partial sample"));
+            }
+            writer.flush()?;
+            return Ok(stats);
+        }
+        filled = 0;
+    }
+}
@@ -0,0 +1,134 @@
+//! Filter carrying a user payload (tag) with each sample.
+
+use num_traits::float::FloatCore;
+
+use crate::{iter::Samples, Sample, Window};
+
+/// Origin of the value returned by [`TaggedWindow::update_tagged`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TagSource<U> {
+    /// The input sample with its tag, passed through.
+    Input(U),
+    /// A replacement equal to the value of an earlier sample of the window, with the tag of that sample
+    /// (e.g. the median for odd `WINDOW_SIZE`).
+    Window(U),
+    /// A replacement that is not the value of any earlier sample of the window
+    /// (e.g. an extrapolation, or the average of the two middle samples for even `WINDOW_SIZE`).
+    Synthesized,
+}
+
+/// [`Window`] that keeps a user payload `U` (e.g. a sequence number and a hardware timestamp) alongside each sample,
+/// so that the value coming out of the filter stays associated with the sample it came from.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The samples are filtered by the [`Window`] as is, and the tags are kept in a separate ring of `WINDOW_SIZE`
+/// elements, so that [`Window`] itself does not pay for the tags.
+/// When an outlier is replaced, the tag of the newest earlier sample whose stored value equals the replacement is returned:
+///
+/// ```
+/// use hampel::{Replacement, TagSource, TaggedWindow, Window};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Tag {
+///     seq: usize,
+///     timestamp_us: u64,
+/// }
+///
+/// let window = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+/// // The initialization values are tagged as the sample 0
+/// let mut filter = TaggedWindow::from_window(window, Tag { seq: 0, timestamp_us: 0 });
+/// for (seq, x) in (1..).zip([1.0, 1.2, 0.9, 1.1, 1.0]) {
+///     filter.update_tagged(x, Tag { seq, timestamp_us: 1000 * seq as u64 });
+/// }
+///
+/// // The median of [1.2, 0.9, 1.1, 1.0, 50.0] is the sample 4
+/// let (y, source) = filter.update_tagged(50.0, Tag { seq: 6, timestamp_us: 6000 });
+/// assert_eq!(y, 1.1);
+/// assert_eq!(source, TagSource::Window(Tag { seq: 4, timestamp_us: 4000 }));
+/// ```
+///
+/// Zero-sized tags take no space, and only the index of the ring is added to the window:
+///
+/// ```
+/// use std::mem::size_of;
+/// use hampel::{TaggedWindow, Window};
+///
+/// assert_eq!(size_of::<TaggedWindow<f64, (), 9>>(), size_of::<(Window<f64, 9>, usize)>());
+/// ```
+#[derive(Clone)]
+pub struct TaggedWindow<T: FloatCore, U: Copy, const WINDOW_SIZE: usize> {
+    pub(crate) window: Window<T, WINDOW_SIZE>,
+    pub(crate) tags: [U; WINDOW_SIZE],  // windowと同じ順に格納したタグ
+    pub(crate) oldest: usize,  // tags内の最も古い要素のインデックス
+}
+
+impl<T: FloatCore, U: Copy, const WINDOW_SIZE: usize> TaggedWindow<T, U, WINDOW_SIZE> {
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`]).
+    /// * `init_tag`: Tag of the initialization values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arguments are invalid for [`Window::new`].
+    pub fn new(init_val: T, n_sigma: T, init_tag: U) -> Self {
+        Self::from_window(Window::new(init_val, n_sigma), init_tag)
+    }
+
+    /// Filter with `window` as configured, whose samples are all tagged `init_tag`.
+    pub fn from_window(window: Window<T, WINDOW_SIZE>, init_tag: U) -> Self {
+        Self { window, tags: [init_tag; WINDOW_SIZE], oldest: 0 }
+    }
+
+    /// Update element in window, and return the filtered value and where it came from.
+    pub fn update_tagged(&mut self, x: T, tag: U) -> (T, TagSource<U>) {
+        let (s, source) = self.update_tagged_detailed(x, tag);
+        (s.value, source)
+    }
+
+    /// Same as [`update_tagged`](Self::update_tagged), but the whole result of [`Window::update_detailed`] is returned.
+    pub fn update_tagged_detailed(&mut self, x: T, tag: U) -> (Sample<T>, TagSource<U>) {
+        let s = self.window.update_detailed(x);
+        self.tags[self.oldest] = tag;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        if !s.is_outlier {
+            return (s, TagSource::Input(tag));
+        }
+
+        // 今回のサンプルを除いて，新しい方から置き換えた値と同じ値のサンプルを探す
+        let tags = Samples::new(&self.tags, self.oldest);
+        let source = self.window.iter().zip(tags).rev().skip(1)
+            .find(|(v, _)| **v == s.value)
+            .map_or(TagSource::Synthesized, |(_, &t)| TagSource::Window(t));
+        (s, source)
+    }
+
+    /// Restore the state right after construction, with the window filled with `init_val` tagged `init_tag`.
+    pub fn reset(&mut self, init_val: T, init_tag: U) {
+        self.window.reset(init_val);
+        self.tags = [init_tag; WINDOW_SIZE];
+        self.oldest = 0;
+    }
+
+    /// Underlying window.
+    pub fn window(&self) -> &Window<T, WINDOW_SIZE> {
+        &self.window
+    }
+
+    /// Iterator over the tags of the samples in the window in chronological order (oldest first).
+    pub fn tags(&self) -> Samples<'_, U> {
+        Samples::new(&self.tags, self.oldest)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,69 @@
+//! Tracing of the replacements by [`Window`](crate::Window) (`log` and `defmt` features).
+
+use num_traits::float::FloatCore;
+
+use crate::Sample;
+
+/// 外れ値を置換したことを記録する（`count`: windowが受け取ったサンプル数）
+pub(crate) fn replaced<T: FloatCore>(label: &'static str, count: usize, raw: T, s: &Sample<T>) {
+    // 要素の型によらず記録できるように，f64にして渡す
+    let f = |v: T| v.to_f64().unwrap_or(f64::NAN);
+    #[cfg(feature = "log")]
+    log_replaced(label, count, f(raw), f(s.value), f(s.median), f(s.mad));
+    #[cfg(feature = "defmt")]
+    defmt::debug!(
+        "{=str}: sample {=usize}: replaced {=f64} by {=f64} (median {=f64}, MAD {=f64})",
+        label, count, f(raw), f(s.value), f(s.median), f(s.mad)
+    );
+}
+
+/// `log`の`debug!`で記録する（target: `hampel`）
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// use hampel::Window;
+/// use log::{Level, LevelFilter, Log, Metadata, Record};
+///
+/// struct Capture(Mutex<Vec<String>>);
+///
+/// impl Log for Capture {
+///     fn enabled(&self, metadata: &Metadata) -> bool {
+///         metadata.level() <= Level::Debug
+///     }
+///     fn log(&self, record: &Record) {
+///         assert_eq!((record.target(), record.level()), ("hampel", Level::Debug));
+///         self.0.lock().unwrap().push(record.args().to_string());
+///     }
+///     fn flush(&self) {}
+/// }
+///
+/// static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+/// log::set_logger(&LOGGER).unwrap();
+/// log::set_max_level(LevelFilter::Debug);
+///
+/// let input = |i: usize| i as f64 * 0.01 + ((i * 7919) % 13) as f64 * 0.01;
+/// let mut left = Window::<f64, 7>::builder().warm_up(true).label("left").build();
+/// let mut right = Window::<f64, 7>::builder().warm_up(true).label("right").build();
+///
+/// // Clean signal: no events
+/// for i in 0..200 {
+///     left.update(input(i));
+///     right.update(input(i));
+/// }
+/// assert!(LOGGER.0.lock().unwrap().is_empty());
+///
+/// // One event per spike, tagged with the label of the filter
+/// for i in 200..400 {
+///     left.update(input(i) + if i % 50 == 0 { 10.0 } else { 0.0 });
+///     right.update(input(i));
+/// }
+/// let events = LOGGER.0.lock().unwrap();
+/// assert_eq!(events.len(), 4);
+/// assert!(events.iter().all(|e| e.starts_with("left: sample ")));
+/// assert!(events[0].starts_with("left: sample 201: replaced 12.1")); // spike at i = 200
+/// ```
+#[cfg(feature = "log")]
+fn log_replaced(label: &'static str, count: usize, raw: f64, value: f64, median: f64, mad: f64) {
+    log::debug!(target: "hampel", "{}: sample {}: replaced {} by {} (median {}, MAD {})", label, count, raw, value, median, mad);
+}
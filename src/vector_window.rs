@@ -0,0 +1,127 @@
+//! Filter for the static vectors of `nalgebra` (`nalgebra` feature).
+
+use nalgebra::{SVector, Scalar};
+use num_traits::float::FloatCore;
+
+use crate::{Sample, Window};
+
+/// Result of [`VectorWindow::update_detailed`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorSample<T: Scalar, const D: usize> {
+    /// Output vector (the outlier components are replaced).
+    pub value: SVector<T, D>,
+    /// `true` for the components determined to be outliers.
+    pub is_outlier: SVector<bool, D>,
+    /// Results of the filters of each component.
+    pub components: [Sample<T>; D],
+}
+
+/// Hampel filter for `nalgebra::SVector<T, D>`, which filters each component independently.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// Each component has its own [`Window`], so that the results are exactly the same as filtering
+/// the components separately:
+///
+/// ```
+/// use hampel::{VectorWindow, Window};
+/// use nalgebra::Vector3;
+///
+/// let trajectory = |i: usize| {
+///     let t = i as f64 * 0.1;
+///     let spike = if i % 17 == 9 { 5.0 } else { 0.0 };
+///     Vector3::new(t.cos(), t.sin() + spike, 0.5 * t)
+/// };
+///
+/// let mut filter = VectorWindow::<f64, 7, 3>::new(Vector3::zeros(), 3.0);
+/// let mut scalar: [Window<f64, 7>; 3] = core::array::from_fn(|_| Window::new(0.0, 3.0));
+/// for i in 0..500 {
+///     let x = trajectory(i);
+///     let s = filter.update_detailed(x);
+///     // The spikes on the y axis do not affect the other axes.
+///     for k in 0..3 {
+///         let expected = scalar[k].update_detailed(x[k]);
+///         assert_eq!(s.value[k].to_bits(), expected.value.to_bits());
+///         assert_eq!(s.is_outlier[k], expected.is_outlier);
+///     }
+///     if i % 17 == 9 {
+///         assert!(s.is_outlier.y);
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct VectorWindow<T: FloatCore, const WINDOW_SIZE: usize, const D: usize> {
+    pub(crate) windows: [Window<T, WINDOW_SIZE>; D],
+}
+
+impl<T: FloatCore + Scalar, const WINDOW_SIZE: usize, const D: usize> VectorWindow<T, WINDOW_SIZE, D> {
+    /// * `init_val`: Initialization value of the windows.
+    /// * `n_sigma`: Threshold for determining an outlier, shared by all components (see [`Window::new`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WINDOW_SIZE` < 3, or `n_sigma` is not a positive finite number.
+    pub fn new(init_val: SVector<T, D>, n_sigma: T) -> Self {
+        Self::with_n_sigmas(init_val, SVector::repeat(n_sigma))
+    }
+
+    /// Same as [`new`](Self::new), but with a threshold for each component.
+    ///
+    /// ```
+    /// use hampel::VectorWindow;
+    /// use nalgebra::Vector2;
+    ///
+    /// let mut filter = VectorWindow::<f64, 5, 2>::with_n_sigmas(Vector2::zeros(), Vector2::new(3.0, 100.0));
+    /// for x in [0.1, -0.1, 0.2, 0.0] {
+    ///     filter.update(Vector2::new(x, x));
+    /// }
+    /// let s = filter.update_detailed(Vector2::new(5.0, 5.0));
+    /// assert_eq!(s.is_outlier, Vector2::new(true, false));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_n_sigmas(init_val: SVector<T, D>, n_sigma: SVector<T, D>) -> Self {
+        Self::from_windows(core::array::from_fn(|i| Window::new(init_val[i], n_sigma[i])))
+    }
+
+    /// Filter whose components are filtered by `windows` (e.g. built with [`Window::builder`]).
+    pub fn from_windows(windows: [Window<T, WINDOW_SIZE>; D]) -> Self {
+        Self { windows }
+    }
+
+    /// Update the windows of all components, and return the filtered vector.
+    pub fn update(&mut self, x: SVector<T, D>) -> SVector<T, D> {
+        self.update_detailed(x).value
+    }
+
+    /// Same as [`update`](Self::update), but also returns which components are outliers,
+    /// and the results of each component.
+    pub fn update_detailed(&mut self, x: SVector<T, D>) -> VectorSample<T, D> {
+        let components: [_; D] = core::array::from_fn(|i| self.windows[i].update_detailed(x[i]));
+        VectorSample {
+            value: SVector::from(components.map(|s| s.value)),
+            is_outlier: SVector::from(components.map(|s| s.is_outlier)),
+            components,
+        }
+    }
+
+    /// See [`Window::reset`].
+    pub fn reset(&mut self, init_val: SVector<T, D>) {
+        for (i, w) in self.windows.iter_mut().enumerate() {
+            w.reset(init_val[i]);
+        }
+    }
+
+    /// Windows of each component.
+    pub fn windows(&self) -> &[Window<T, WINDOW_SIZE>; D] {
+        &self.windows
+    }
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize, const D: usize> PartialEq for VectorWindow<T, WINDOW_SIZE, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.windows == other.windows
+    }
+}
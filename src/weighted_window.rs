@@ -0,0 +1,213 @@
+//! Hampel filter with per-sample weights.
+
+use num_traits::float::FloatCore;
+
+use crate::{filter, iter::Samples, offline, precision, stats};
+
+/// Hampel filter whose samples carry weights, e.g. a quality indicator such as the GPS fix quality
+/// or the strength of a correlation peak, so that the low-quality samples count less in the statistics.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// The window keeps `(value, weight)` pairs. The center is the weighted median of the window
+/// (the value at which the cumulative weight of the sorted samples reaches half of the total,
+/// or the average of two values if it is exactly half), and the scale is the weighted MAD
+/// (the weighted median of the absolute deviations from it, with the same weights).
+/// The new sample is then tested as by [`Window`](crate::Window), and an outlier is replaced by the weighted median.
+/// With all weights equal, the filter is the same as [`Window`](crate::Window) with [`Replacement::Median`](crate::Replacement::Median):
+///
+/// ```
+/// use hampel::{Replacement, WeightedWindow, Window};
+///
+/// let mut weighted = WeightedWindow::<f64, 5>::new(0.0, 3.0);
+/// let mut plain = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+/// for x in [0.5, 0.4, 9.0, 0.6, 0.5, 0.45, 0.55, 0.5, 0.52, -7.0, 0.48] {
+///     let s = plain.update_detailed(x);
+///     assert_eq!(weighted.update_weighted(x, 1.0), s.value);
+///     assert_eq!(weighted.is_last_outlier(), s.is_outlier);
+///     assert_eq!((weighted.median(), weighted.mad()), (s.median, s.mad));
+/// }
+/// ```
+///
+/// Down-weighted samples hardly move the statistics, which track the high-weight samples:
+///
+/// ```
+/// use hampel::WeightedWindow;
+///
+/// let mut filter = WeightedWindow::<f64, 10>::new(10.0, 3.0);
+/// for i in 0..100 {
+///     let wobble = 0.01 * (i % 7) as f64;
+///     // Every other sample is of low quality, with a bias of +10
+///     let (x, w) = if i % 2 == 0 { (10.0 + wobble, 1.0) } else { (20.0 + wobble, 0.1) };
+///     let y = filter.update_weighted(x, w);
+///     if i >= 10 {
+///         assert!((filter.median() - 10.03).abs() <= 0.03);
+///         assert!(filter.mad() < 0.05);
+///         // The biased samples are replaced by the weighted median
+///         assert_eq!(filter.is_last_outlier(), i % 2 == 1);
+///         assert_eq!(y, if i % 2 == 0 { x } else { filter.median() });
+///     }
+/// }
+/// ```
+///
+/// A sample of zero weight occupies a slot of the window, but contributes nothing to the statistics
+/// (negative and NaN weights, and NaN values, are treated as zero). If the whole window has zero weight,
+/// no sample is determined to be an outlier:
+///
+/// ```
+/// use hampel::WeightedWindow;
+///
+/// let mut filter = WeightedWindow::<f64, 5>::new(1.0, 3.0);
+/// for x in [1.0, 1.2, 0.9, 1.1, 1.0] {
+///     filter.update(x);
+/// }
+/// assert_eq!(filter.update_weighted(1e6, 0.0), 1.05);  // Still an outlier of the window
+/// assert_eq!(filter.median(), 1.05);  // Median of [1.2, 0.9, 1.1, 1.0]
+///
+/// let mut filter = WeightedWindow::<f64, 3>::new(1.0, 3.0);
+/// for x in [5.0, -5.0, 1e6] {
+///     filter.update_weighted(x, 0.0);
+/// }
+/// // The initial samples of weight 1 have left the window
+/// assert_eq!(filter.update_weighted(-1e6, 0.0), -1e6);
+/// assert!(!filter.is_last_outlier());
+/// ```
+///
+/// Infinite samples are accepted, and do not affect the statistics once they have left the window:
+///
+/// ```
+/// use hampel::WeightedWindow;
+///
+/// let mut filter = WeightedWindow::<f64, 3>::new(1.0, 3.0);
+/// assert_eq!(filter.update(f64::INFINITY), 1.0);
+/// assert_eq!(filter.update(f64::INFINITY), f64::INFINITY);  // The majority of the window
+/// for x in [2.0, 1.5, 1.2] {
+///     filter.update(x);
+/// }
+/// assert_eq!(filter.update(f64::NEG_INFINITY), 1.2);
+/// assert!(filter.is_last_outlier());
+/// ```
+#[derive(Clone)]
+pub struct WeightedWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [(T, T); WINDOW_SIZE],  // (値, 重み)
+    work: [T; WINDOW_SIZE],  // 重みが正のサンプルの値を並べ替える作業領域
+    weights: [T; WINDOW_SIZE],  // workの各値の重み
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    coef: T,  // 閾値判定に使う係数
+    median: T,  // 直近の更新で求めた重み付き中央値
+    mad: T,  // 直近の更新で求めた重み付きMAD
+    pub(crate) last_outlier: bool,  // 直前のサンプルを外れ値と判定したか
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> WeightedWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が3以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 3, "WINDOW_SIZE must be at least 3");
+
+    /// * `init_val`: Initialization value of window (with weight 1).
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    ///
+    /// `WINDOW_SIZE` < 3 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::WeightedWindow::<f32, 2>::new(0.0, 3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`WINDOW_SIZE` < 3 is rejected at compile time).
+    pub fn new(init_val: T, n_sigma: T) -> Self {
+        let () = Self::VALID_SIZE;
+        filter::assert_n_sigma(n_sigma);
+
+        Self {
+            window: [(init_val, T::one()); WINDOW_SIZE],
+            work: [init_val; WINDOW_SIZE],
+            weights: [T::one(); WINDOW_SIZE],
+            oldest: 0,
+            coef: offline::coefficient(n_sigma),
+            median: init_val,
+            mad: T::zero(),
+            last_outlier: false,
+        }
+    }
+
+    /// Same as [`update_weighted`](Self::update_weighted) with weight 1.
+    pub fn update(&mut self, x: T) -> T {
+        self.update_weighted(x, T::one())
+    }
+
+    /// Update the window with the sample `x` of weight `w` (>= 0), and return the filtered value.
+    pub fn update_weighted(&mut self, x: T, w: T) -> T {
+        let w = if w > T::zero() && !x.is_nan() { w } else { T::zero() };
+        self.window[self.oldest] = (x, w);
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+
+        self.last_outlier = match self.estimate() {
+            Some((median, mad)) => {
+                (self.median, self.mad) = (median, mad);
+                if x < median {
+                    precision::exceeds(median, x, self.coef, mad)
+                } else {
+                    precision::exceeds(x, median, self.coef, mad)
+                }
+            },
+            None => false,
+        };
+        if self.last_outlier { self.median } else { x }
+    }
+
+    /// 重み付き中央値と重み付きMAD（重みの合計が0ならNone）
+    fn estimate(&mut self) -> Option<(T, T)> {
+        let mut k = 0;
+        for &(v, w) in &self.window {
+            if w > T::zero() {
+                (self.work[k], self.weights[k]) = (v, w);
+                k += 1;
+            }
+        }
+        if k == 0 {
+            return None;
+        }
+        Some(stats::weighted_statistics(&mut self.work[..k], &mut self.weights[..k]))
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier.
+    pub fn is_last_outlier(&self) -> bool {
+        self.last_outlier
+    }
+
+    /// Weighted median of the window used by the last update.
+    pub fn median(&self) -> T {
+        self.median
+    }
+
+    /// Weighted MAD of the window used by the last update, before multiplying by the consistency constant.
+    pub fn mad(&self) -> T {
+        self.mad
+    }
+
+    /// Fill the window with `init_val` of weight 1, as right after construction.
+    pub fn reset(&mut self, init_val: T) {
+        self.window = [(init_val, T::one()); WINDOW_SIZE];
+        self.oldest = 0;
+        self.median = init_val;
+        self.mad = T::zero();
+        self.last_outlier = false;
+    }
+
+    /// Iterator over the `(value, weight)` pairs in the window in chronological order (oldest first).
+    /// The weights are as stored, i.e. zero for negative and NaN weights.
+    pub fn iter(&self) -> Samples<'_, (T, T)> {
+        Samples::new(&self.window, self.oldest)
+    }
+
+    /// Window size (`WINDOW_SIZE`).
+    pub fn len(&self) -> usize {
+        WINDOW_SIZE
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,195 @@
+//! Window of runtime size on caller-provided buffers.
+
+use num_traits::float::FloatCore;
+
+use crate::{builder::default_core, filter::Core, iter::Samples, ConfigError, OutlierStats, Sample};
+
+/// Window of Hampel filter whose size is determined at runtime.
+///
+/// The ring buffer, a scratch buffer and a buffer of the time gaps between the samples,
+/// all of the same length, are borrowed from the caller, so no allocation is needed. Apart from that, it behaves exactly like [`Window`](crate::Window).
+///
+/// ```
+/// use hampel::{Window, WindowMut};
+///
+/// let (mut buf, mut scratch, mut gaps) = ([0.0; 7], [0.0; 7], [0.0; 7]);
+/// let mut a = WindowMut::new(&mut buf, &mut scratch, &mut gaps, 0.0, 3.0).unwrap();
+/// let mut b = Window::<f64, 7>::new(0.0, 3.0);
+///
+/// let (mut big_buf, mut big_scratch, mut big_gaps) = ([0.0; 301], [0.0; 301], [0.0; 301]);
+/// let mut c = WindowMut::new(&mut big_buf, &mut big_scratch, &mut big_gaps, 0.0, 3.0).unwrap();
+/// let mut d = Window::<f64, 301>::new(0.0, 3.0);
+///
+/// for i in 0..1000 {
+///     let x = (i as f64 * 0.1).sin() + if i % 37 == 0 { 5.0 } else { 0.0 };
+///     assert_eq!(a.update_detailed(x), b.update_detailed(x));
+///     assert_eq!(c.update_detailed(x), d.update_detailed(x));
+/// }
+///
+/// let (mut small, mut small_scratch, mut small_gaps) = ([0.0; 2], [0.0; 2], [0.0; 2]);
+/// assert!(WindowMut::new(&mut small, &mut small_scratch, &mut small_gaps, 0.0, 3.0).is_err());
+/// ```
+pub struct WindowMut<'a, T: FloatCore> {
+    pub(crate) window: &'a mut [T],
+    pub(crate) work: &'a mut [T],
+    pub(crate) gaps: &'a mut [T],  // windowの各サンプルと1つ前のサンプルの時刻の差
+    pub(crate) core: Core<T>,
+}
+
+impl<'a, T: FloatCore> WindowMut<'a, T> {
+    /// * `buffer`: Ring buffer of the window (its length is the window size, >= 3).
+    /// * `scratch`: Scratch buffer with the same length as `buffer`.
+    /// * `gaps`: Buffer of the time gaps between the samples, with the same length as `buffer`
+    ///   (used by the extrapolation after [`update_missing`](Self::update_missing)).
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold for determining an outlier (see [`Window::new`](crate::Window::new)).
+    pub fn new(buffer: &'a mut [T], scratch: &'a mut [T], gaps: &'a mut [T], init_val: T, n_sigma: T) -> Result<Self, ConfigError> {
+        if buffer.len() < 3 {
+            return Err(ConfigError::InvalidWindowSize);
+        }
+        if scratch.len() != buffer.len() || gaps.len() != buffer.len() {
+            return Err(ConfigError::ScratchSizeMismatch);
+        }
+
+        let mut core = default_core(buffer.len(), init_val, n_sigma)?;
+        core.reset(buffer, &mut [][..], init_val);
+        gaps.fill(T::one());
+        Ok(Self { window: buffer, work: scratch, gaps, core })
+    }
+
+    /// Update element in window (see [`Window::update`](crate::Window::update)).
+    pub fn update(&mut self, x: T) -> T {
+        self.update_detailed(x).value
+    }
+
+    /// Update element in window (see [`Window::update_detailed`](crate::Window::update_detailed)).
+    pub fn update_detailed(&mut self, x: T) -> Sample<T> {
+        self.core.update(self.window, self.gaps, self.work, &mut [], &mut [][..], x)
+    }
+
+    /// See [`Window::update_missing`](crate::Window::update_missing).
+    pub fn update_missing(&mut self) -> T {
+        self.update_missing_detailed().value
+    }
+
+    /// See [`Window::update_missing_detailed`](crate::Window::update_missing_detailed).
+    pub fn update_missing_detailed(&mut self) -> Sample<T> {
+        self.core.update_missing(self.window, self.gaps, self.work, &mut [], &mut [][..])
+    }
+
+    /// See [`Window::update_evicting`](crate::Window::update_evicting).
+    pub fn update_evicting(&mut self, x: T) -> (T, T) {
+        let evicted = self.core.oldest_sample(self.window);
+        (self.update(x), evicted)
+    }
+
+    /// See [`Window::would_flag`](crate::Window::would_flag).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    pub fn would_flag(&mut self, x: T) -> bool {
+        self.core.would_flag(self.window, self.work, &mut [], x)
+    }
+
+    /// See [`Window::score`](crate::Window::score).
+    ///
+    /// This takes `&mut self` only because the scratch buffer is used; the window is not modified.
+    pub fn score(&mut self, x: T) -> T {
+        self.core.score_of(self.window, self.work, &mut [], x)
+    }
+
+    /// See [`Window::reset`](crate::Window::reset).
+    pub fn reset(&mut self, init_val: T) {
+        self.core.reset(self.window, &mut [][..], init_val);
+        self.gaps.fill(T::one());
+    }
+
+    /// See [`Window::seed`](crate::Window::seed).
+    pub fn seed(&mut self, samples: &[T]) {
+        let start = samples.len().saturating_sub(self.window.len());
+        self.seed_from_iter(samples[start..].iter().copied());
+    }
+
+    /// See [`Window::seed_from_iter`](crate::Window::seed_from_iter).
+    pub fn seed_from_iter<I: IntoIterator<Item = T>>(&mut self, samples: I) {
+        let mut seeded = false;
+        let samples = samples.into_iter().inspect(|_| seeded = true);
+        self.core.seed_from_iter(self.window, self.work, &mut [], &mut [][..], samples);
+        if seeded {
+            self.gaps.fill(T::one());
+        }
+    }
+
+    /// See [`Window::stats`](crate::Window::stats).
+    pub fn stats(&self) -> &OutlierStats {
+        &self.core.stats
+    }
+
+    /// See [`Window::reset_stats`](crate::Window::reset_stats).
+    pub fn reset_stats(&mut self) {
+        self.core.stats = OutlierStats::default();
+    }
+
+    /// See [`Window::set_mad_floor`](crate::Window::set_mad_floor).
+    pub fn set_mad_floor(&mut self, mad_floor: T) {
+        self.core.mad_floor = mad_floor;
+    }
+
+    /// See [`Window::mad_floor`](crate::Window::mad_floor).
+    pub fn mad_floor(&self) -> T {
+        self.core.mad_floor
+    }
+
+    /// Window size.
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Always `false`, since the window has at least three elements.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// See [`Window::samples_seen`](crate::Window::samples_seen).
+    pub fn samples_seen(&self) -> usize {
+        self.core.seen
+    }
+
+    /// See [`Window::is_warmed_up`](crate::Window::is_warmed_up).
+    pub fn is_warmed_up(&self) -> bool {
+        self.core.seen >= self.window.len()
+    }
+
+    /// See [`Window::median`](crate::Window::median).
+    pub fn median(&self) -> T {
+        self.core.median
+    }
+
+    /// See [`Window::mad`](crate::Window::mad).
+    pub fn mad(&self) -> T {
+        self.core.scale
+    }
+
+    /// See [`Window::bounds`](crate::Window::bounds).
+    pub fn bounds(&self) -> (T, T) {
+        self.core.bounds
+    }
+
+    /// See [`Window::iter`](crate::Window::iter).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(self.window, self.core.oldest)
+    }
+
+    /// See [`Window::latest`](crate::Window::latest).
+    pub fn latest(&self) -> T {
+        self.core.latest(self.window)
+    }
+}
+
+/// Same as [`Window`](crate::Window). Windows of different sizes are not equal.
+impl<T: FloatCore> PartialEq for WindowMut<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        let gaps = Samples::new(self.gaps, self.core.oldest);
+        let other_gaps = Samples::new(other.gaps, other.core.oldest);
+        self.core.eq_with(self.window, &other.core, other.window) && gaps.eq(other_gaps)
+    }
+}
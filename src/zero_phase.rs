@@ -0,0 +1,78 @@
+//! Forward-backward (zero-phase) offline filter with the streaming window.
+
+use num_traits::float::FloatCore;
+
+use crate::{Replacement, Window};
+
+/// Forward-backward Hampel filter, applied in place (like `filtfilt`).
+///
+/// The slice is filtered by a causal [`Window`] (with [`Replacement::Median`]) from the first sample to the last,
+/// and the result is filtered again from the last sample to the first, so that the delay of the first pass
+/// is undone by the second. At the start of each pass, the window is seeded with the first `WINDOW_SIZE`
+/// samples in the direction of the pass (see [`Window::seed`]) instead of a constant,
+/// so that a spike at the very first or last sample is judged against its neighbors and replaced.
+///
+/// * `WINDOW_SIZE` >= 3
+///
+/// ```
+/// use hampel::filter_zero_phase;
+///
+/// // Slow signal with noise, and spikes at the first and the last samples
+/// let noise = |i: u64| (i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) as f64 / (1u64 << 24) as f64 * 0.2;
+/// let original: Vec<f64> = (0..50).map(|i| (i as f64 * 0.05).sin() + noise(i)).collect();
+/// let mut data = original.clone();
+/// data[0] = 10.0;
+/// data[49] = -10.0;
+/// filter_zero_phase::<f64, 7>(&mut data, 3.0);
+/// assert!(data.iter().all(|y| (0.0..1.2).contains(y)));
+/// assert!(data.iter().zip(&original).filter(|(y, x)| y != x).count() < 10);
+/// ```
+///
+/// A single causal pass replaces the leading edge of a pulse wider than half the window,
+/// and extends its trailing edge, so the pulse is delayed.
+/// The backward pass moves it back, and a symmetric pulse stays at its place:
+///
+/// ```
+/// use hampel::{filter_zero_phase, Replacement, Window};
+///
+/// // Pulse of 11 samples at the center, on a baseline with symmetric noise
+/// let input: Vec<f64> = (0..61).map(|i: i32| {
+///     let d = (i - 30).abs();
+///     let noise = ((d * 7919) % 13) as f64 * 0.01;
+///     noise + if d <= 5 { 5.0 } else { 0.0 }
+/// }).collect();
+/// let pulse = |y: &[f64]| (0..61).filter(|&i| y[i] > 2.5).collect::<Vec<_>>();
+///
+/// let mut single = Window::<f64, 9>::with_replacement(0.0, 3.0, Replacement::Median);
+/// single.seed(&input[..9]);
+/// let single: Vec<f64> = input.iter().map(|&x| single.update(x)).collect();
+/// assert_eq!(pulse(&single), (29..=39).collect::<Vec<_>>());
+///
+/// let mut data = input.clone();
+/// filter_zero_phase::<f64, 9>(&mut data, 3.0);
+/// assert_eq!(pulse(&data), (25..=35).collect::<Vec<_>>());
+/// assert!(data.iter().zip(&input).all(|(y, x)| (y - x).abs() < 0.1));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the arguments are invalid for [`Window::new`].
+pub fn filter_zero_phase<T: FloatCore, const WINDOW_SIZE: usize>(data: &mut [T], n_sigma: T) {
+    let Some(&first) = data.first() else {
+        return;
+    };
+    let mut window = Window::<T, WINDOW_SIZE>::with_replacement(first, n_sigma, Replacement::Median);
+
+    // 順方向
+    window.seed_from_iter(data.iter().take(WINDOW_SIZE).copied());
+    for x in data.iter_mut() {
+        *x = window.update(*x);
+    }
+
+    // 逆方向（順方向の出力の末尾から）
+    window.reset(data[data.len() - 1]);
+    window.seed_from_iter(data.iter().rev().take(WINDOW_SIZE).copied());
+    for x in data.iter_mut().rev() {
+        *x = window.update(*x);
+    }
+}
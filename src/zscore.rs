@@ -0,0 +1,109 @@
+//! Rolling z-score filter.
+
+use num_traits::{cast, float::FloatCore};
+
+use crate::{filter, iter::Samples, precision};
+
+/// Outlier filter by the rolling mean and standard deviation of the last `WINDOW_SIZE` samples,
+/// as an alternative to the Hampel identifier behind [`OutlierFilter`](crate::OutlierFilter).
+///
+/// * `WINDOW_SIZE` >= 2
+///
+/// A sample `x` is determined to be an outlier if `|x - mean| > n_sigma * std`, where the mean and
+/// the (population) standard deviation are taken over the window before `x` is added,
+/// and is replaced by the mean. The input value is stored in the window either way,
+/// so the mean and the standard deviation are themselves affected by the outliers (unlike the median and the MAD).
+///
+/// ```
+/// use hampel::ZScoreWindow;
+///
+/// let mut filter = ZScoreWindow::<f64, 4>::new(0.0, 3.0);
+/// for x in [1.0, 2.0, 1.0, 2.0] {
+///     filter.update(x);
+/// }
+/// // mean: 1.5, std: 0.5
+/// assert_eq!(filter.update(2.9), 2.9);
+/// assert!(!filter.is_last_outlier());
+///
+/// let mut filter = ZScoreWindow::<f64, 4>::new(0.0, 3.0);
+/// for x in [1.0, 2.0, 1.0, 2.0] {
+///     filter.update(x);
+/// }
+/// assert_eq!(filter.update(3.1), 1.5);
+/// assert!(filter.is_last_outlier());
+/// assert!(filter.iter().eq([2.0, 1.0, 2.0, 3.1].iter()));
+/// ```
+///
+/// While the window contains a NaN, the mean is NaN and no sample is determined to be an outlier.
+#[derive(Clone)]
+pub struct ZScoreWindow<T: FloatCore, const WINDOW_SIZE: usize> {
+    pub(crate) window: [T; WINDOW_SIZE],
+    pub(crate) oldest: usize,  // window内の最も古い要素のインデックス
+    pub(crate) n_sigma: T,
+    pub(crate) last_outlier: bool,  // 直前のサンプルを外れ値と判定したか
+}
+
+impl<T: FloatCore, const WINDOW_SIZE: usize> ZScoreWindow<T, WINDOW_SIZE> {
+    /// `WINDOW_SIZE`が2以上であることをコンパイル時に確かめる（参照した関数を使うと評価される）
+    const VALID_SIZE: () = assert!(WINDOW_SIZE >= 2, "WINDOW_SIZE must be at least 2");
+
+    /// * `init_val`: Initialization value of window.
+    /// * `n_sigma`: Threshold in units of the standard deviation.
+    ///
+    /// `WINDOW_SIZE` < 2 is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = hampel::ZScoreWindow::<f32, 1>::new(0.0, 3.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_sigma` is not a positive finite number (`WINDOW_SIZE` < 2 is rejected at compile time).
+    pub fn new(init_val: T, n_sigma: T) -> Self {
+        let () = Self::VALID_SIZE;
+        filter::assert_n_sigma(n_sigma);
+
+        Self { window: [init_val; WINDOW_SIZE], oldest: 0, n_sigma, last_outlier: false }
+    }
+
+    /// Update the window, and return the filtered value.
+    pub fn update(&mut self, x: T) -> T {
+        let (mean, is_outlier) = if precision::is_narrow::<T>() {
+            let samples = self.iter().map(|v| precision::widen(*v));
+            let (mean, is_outlier) = decide(samples, precision::widen(x), precision::widen(self.n_sigma));
+            (precision::narrow(mean), is_outlier)
+        } else {
+            decide(self.iter().copied(), x, self.n_sigma)
+        };
+        self.window[self.oldest] = x;
+        self.oldest = (self.oldest + 1) % WINDOW_SIZE;
+        self.last_outlier = is_outlier;
+        if is_outlier { mean } else { x }
+    }
+
+    /// Returns `true` if the last sample was determined to be an outlier.
+    pub fn is_last_outlier(&self) -> bool {
+        self.last_outlier
+    }
+
+    /// Fill the window with `init_val`, as right after construction.
+    pub fn reset(&mut self, init_val: T) {
+        self.window = [init_val; WINDOW_SIZE];
+        self.oldest = 0;
+        self.last_outlier = false;
+    }
+
+    /// Iterator over the samples in the window in chronological order (oldest first).
+    pub fn iter(&self) -> Samples<'_, T> {
+        Samples::new(&self.window, self.oldest)
+    }
+}
+
+/// `samples`の平均と，`x`が平均から標準偏差の`n_sigma`倍より離れているか（平方根を使わずに2乗で比べる）
+fn decide<F: FloatCore>(samples: impl Iterator<Item = F> + Clone, x: F, n_sigma: F) -> (F, bool) {
+    let n = cast::<usize, F>(samples.clone().count()).unwrap();
+    let mean = samples.clone().fold(F::zero(), |s, v| s + v) / n;
+    let var = samples.fold(F::zero(), |s, v| s + (v - mean) * (v - mean)) / n;
+    let d = x - mean;
+    (mean, d * d > n_sigma * n_sigma * var)
+}
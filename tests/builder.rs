@@ -0,0 +1,503 @@
+//! Options of `WindowBuilder`, on small inputs and on long pseudo-random inputs.
+
+mod common;
+
+use common::{Xorshift, SEED};
+use hampel::{AdaptiveThreshold, Class, NanPolicy, Replacement, ReseedPolicy, ScaleEstimator, StorePolicy, ThresholdMode, Window};
+
+/// 値の判定では通るゆっくりした正弦波の傾きの上の小さなグリッチを，差分の判定が捕らえる
+#[test]
+fn difference_test_catches_glitches() {
+    let signal = |i: usize| 10.0 * (i as f64 * 0.05).sin();
+    let glitch = |i: usize| i % 23 == 11 && (i as f64 * 0.05).cos().abs() > 0.8;
+
+    let mut values = Window::<f64, 15>::with_replacement(0.0, 3.0, Replacement::Median);
+    let build = |mode| Window::<f64, 15>::builder().replacement(Replacement::Median).difference_test(4.0, mode).build();
+    let (mut either, mut both) = (build(ThresholdMode::Either), build(ThresholdMode::Both));
+    let mut rng = Xorshift::new(SEED);
+    let (mut glitches, mut false_alarms) = (0, 0);
+    for i in 0..10_000 {
+        let noise = rng.uniform() * 0.1 - 0.05;
+        let x = signal(i) + noise + if glitch(i) { 1.5 } else { 0.0 };
+        let (v, e, b) = (values.update_detailed(x), either.update_detailed(x), both.update_detailed(x));
+        if i < 30 {
+            continue;  // windowは0で埋まった状態から始まる
+        }
+        if glitch(i) {
+            assert!(!v.is_outlier);
+            assert!(e.is_outlier && e.difference);
+            assert!((e.value - signal(i)).abs() < 0.25);
+            assert_eq!(b.value, x);
+            glitches += 1;
+        } else {
+            false_alarms += e.difference as usize;
+        }
+    }
+    assert!(glitches > 150);
+    assert!(false_alarms < 100);
+}
+
+/// 雑音の上の段差と外れ値の連続に対する`ReseedPolicy::Latest`
+#[test]
+fn latest_reseed_policy_with_noise() {
+    // 標準偏差が約0.1の雑音
+    let mut rng = Xorshift::new(SEED);
+    let mut noise = move || (rng.uniform() - 0.5) * 0.35;
+    let base = [0.0; 9].map(|_| noise());
+    let build = || {
+        let mut w = Window::<f64, 9>::builder()
+            .replacement(Replacement::Median)
+            .max_consecutive_replacements(3)
+            .reseed_policy(ReseedPolicy::Latest)
+            .mad_floor(0.1)
+            .build();
+        w.seed(&base);
+        w
+    };
+
+    // 50シグマの段差：3つが置換され，4つ目でwindowを埋め直す
+    let mut w = build();
+    let samples: Vec<_> = (0..40).map(|i| w.update_detailed(if i < 10 { 0.0 } else { 5.0 } + noise())).collect();
+    assert!(samples[..10].iter().all(|s| !s.is_outlier));
+    assert!(samples[10..13].iter().all(|s| s.is_outlier && s.value.abs() < 0.5));
+    assert!(samples[13].reseeded && !samples[13].is_outlier);
+    assert!(samples[14..].iter().all(|s| !s.is_outlier && !s.reseeded));
+
+    // 6つの外れ値の連続：4つ目で誤って埋め直し，連続の後でもう一度埋め直す
+    let mut w = build();
+    let samples: Vec<_> = (0..40).map(|i| w.update_detailed(if (10..16).contains(&i) { 20.0 } else { 0.0 } + noise())).collect();
+    let reseeds: Vec<usize> = (0..40).filter(|&i| samples[i].reseeded).collect();
+    assert_eq!(reseeds, [13, 19]);
+    assert!(samples[13..19].iter().all(|s| s.value > 19.0));
+    assert!(samples[19..].iter().all(|s| !s.is_outlier && s.value.abs() < 0.5));
+}
+
+/// 閾値の前後を行き来する区間で，ヒステリシスが判定の切り替わりを減らす
+#[test]
+fn hysteresis_suppresses_chattering() {
+    // ±0.5の雑音と，閾値の前後を交互に取る10サンプルの区間
+    let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+    let signal: Vec<f64> = (0..5000)
+        .map(|i| {
+            let noise = rng.uniform() - 0.5;
+            match (i % 100 >= 90, i % 2 == 0) {
+                (true, true) => 1.0 + 0.1 * noise,
+                (true, false) => 0.7 + 0.1 * noise,
+                (false, _) => noise,
+            }
+        })
+        .collect();
+    let toggles = |mut filter: Window<f64, 61>| {
+        let mut last = false;
+        let mut count = 0;
+        for &x in &signal {
+            let s = filter.update_detailed(x);
+            count += (s.is_outlier != last) as usize;
+            last = s.is_outlier;
+        }
+        count
+    };
+    let plain = toggles(Window::builder().n_sigma(2.0).build());
+    let hysteresis = toggles(Window::builder().n_sigma(2.0).hysteresis(1.0).build());
+    assert!(hysteresis * 3 < plain);
+}
+
+/// 目標より外れ値が少なければ閾値は元の値のままで，多ければ上限まで上がる
+#[test]
+fn adaptive_threshold_follows_contamination() {
+    // [-0.5, 0.5)の一様な雑音と，`percent`%の大きなスパイク
+    let run = |percent: u64| {
+        let mut filter = Window::<f64, 15>::builder()
+            .n_sigma(3.0)
+            .warm_up(true)
+            .adaptive(AdaptiveThreshold::new(0.03, 6.0))
+            .build();
+        let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            let (noise, state) = rng.next_f64();
+            let spike = if state % 100 < percent { 50.0 } else { 0.0 };
+            filter.update(noise - 0.5 + spike);
+        }
+        filter.effective_n_sigma()
+    };
+    assert_eq!(run(1), 3.0);
+    assert_eq!(run(5), 6.0);
+    assert_eq!(run(20), 6.0);
+}
+
+/// `λ = 1`なら重みのないフィルタと全く同じ結果になる
+#[test]
+fn unit_forgetting_factor_is_unweighted() {
+    let mut plain = Window::<f64, 6>::new(0.0, 3.0);
+    let mut weighted = Window::<f64, 6>::builder().forgetting_factor(1.0).build();
+    let mut rng = Xorshift::new(SEED);
+    for _ in 0..10_000 {
+        let state = rng.next_u64();
+        let x = (state % 1000) as f64 * 0.001 + if state.is_multiple_of(37) { 5.0 } else { 0.0 };
+        assert_eq!(plain.update_detailed(x), weighted.update_detailed(x));
+    }
+}
+
+/// 偏ったwindowでは，SnがMADの見逃すサンプルを外れ値とする
+#[test]
+fn sn_flags_in_a_skewed_window() {
+    let mut mad = Window::<f64, 9>::new(0.0, 3.0);
+    let mut sn = Window::<f64, 9>::builder().scale_estimator(ScaleEstimator::Sn).build();
+    let samples = [0.0, 0.0, 0.1, 0.2, 0.4, 1.2, 1.6, 2.0];
+    mad.seed(&samples);
+    sn.seed(&samples);
+    assert!(!mad.update_detailed(2.0).is_outlier);
+    assert!(sn.update_detailed(2.0).is_outlier);
+}
+
+#[test]
+fn abs_threshold_is_combined_by_the_mode() {
+    let samples = [1000.0, 1002.0, 998.0, 1001.0, 999.0, 1000.0, 1003.0, 997.0];
+    // (入力, 相対閾値を超えるか, 絶対閾値を超えるか)
+    let cases = [(1060.0, true, true), (1020.0, true, false), (1000.5, false, false)];
+    for mode in [ThresholdMode::Either, ThresholdMode::Both] {
+        let mut w = Window::<f64, 9>::builder().abs_threshold(50.0).threshold_mode(mode).build();
+        for (x, relative, absolute) in cases {
+            w.seed(&samples);
+            let s = w.update_detailed(x);
+            assert_eq!((s.relative, s.absolute), (relative, absolute));
+            let expected = match mode {
+                ThresholdMode::Either => relative || absolute,
+                ThresholdMode::Both => relative && absolute,
+            };
+            assert_eq!(s.is_outlier, expected);
+        }
+
+        // 雑音の大きなwindowでは絶対閾値だけを超える
+        let mut w = Window::<f64, 9>::builder().abs_threshold(50.0).threshold_mode(mode).build();
+        w.seed(&[1000.0, 900.0, 1100.0, 950.0, 1050.0, 1000.0, 880.0, 1120.0]);
+        let s = w.update_detailed(1070.0);
+        assert_eq!((s.relative, s.absolute), (false, true));
+        assert_eq!(s.is_outlier, mode == ThresholdMode::Either);
+    }
+}
+
+/// [-40, 85] °Cの温度センサ
+#[test]
+fn output_range_flags_and_clamps() {
+    // 範囲外の入力は，雑音の大きなwindowでも外れ値になる
+    let mut filter = Window::<f64, 7>::builder().output_range(-40.0, 85.0).replacement(Replacement::Median).build();
+    filter.seed(&[-30.0, 80.0, -35.0, 75.0, 20.0, -38.0]);
+    assert!(filter.would_flag(120.0));
+    let s = filter.update_detailed(120.0);
+    assert!(s.is_outlier && s.out_of_range && !s.relative);
+    assert_eq!(s.value, s.median);
+
+    // 急な傾きを外挿した値は範囲内に収める
+    let build = |range: bool| {
+        let builder = Window::<f64, 5>::builder().replacement(Replacement::LinearExtrapolation);
+        if range { builder.output_range(-40.0, 85.0).build() } else { builder.build() }
+    };
+    let (mut bounded, mut unbounded) = (build(true), build(false));
+    for x in [40.0, 55.0, 70.0, 85.0] {
+        assert_eq!(bounded.update(x), unbounded.update(x));
+    }
+    assert_eq!(unbounded.update(300.0), 100.0);
+    assert_eq!(bounded.update(300.0), 85.0);
+
+    assert!(Window::<f64, 5>::builder().output_range(85.0, -40.0).try_build().is_err());
+}
+
+#[test]
+fn max_slew_limits_the_output() {
+    let build = |slew: bool| {
+        let builder = Window::<f64, 5>::builder();
+        if slew { builder.max_slew(0.5).build() } else { builder.build() }
+    };
+
+    // 10 * deltaの段差は，受け入れられてから10サンプルで新しい水準に達する
+    let (mut limited, mut free) = (build(true), build(false));
+    let mut ramp = Vec::new();
+    for _ in 0..20 {
+        let y = limited.update(5.0);
+        if free.update(5.0) == 5.0 {
+            ramp.push(y);
+        }
+    }
+    assert_eq!(ramp[..10], [0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5, 5.0]);
+    assert!(ramp[10..].iter().all(|&y| y == 5.0));
+
+    // 外れ値は直前の出力の近くの値で置き換えられるので，制限されない
+    let (mut limited, mut free) = (build(true), build(false));
+    let level = |i: usize| 1.0 + (i % 3) as f64 * 0.1;
+    let history: Vec<f64> = (0..5).map(level).collect();
+    limited.seed(&history);
+    free.seed(&history);
+    for i in 5..50 {
+        let x = if i == 30 { 50.0 } else { level(i) };
+        let s = limited.update_detailed(x);
+        assert_eq!(s.value, free.update(x));
+        assert_eq!(s.is_outlier, i == 30);
+    }
+
+    // 直前の出力はリセットで消える
+    limited.reset(10.0);
+    assert_eq!(limited.update(10.0), 10.0);
+
+    assert!(Window::<f64, 5>::builder().max_slew(-1.0).try_build().is_err());
+}
+
+#[test]
+fn max_consecutive_replacements_accepts_a_level_shift() {
+    let base = [1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.1, 1.0, 0.9];
+    let build = || {
+        let mut w = Window::<f64, 9>::builder()
+            .replacement(Replacement::Median)
+            .max_consecutive_replacements(2)
+            .build();
+        w.seed(&base);
+        w
+    };
+
+    // 段差の3つ目のサンプルを受け入れ，残りは新しい水準に従う
+    let mut w = build();
+    let step = [5.0, 5.1, 4.9, 5.0, 5.2, 4.8, 5.1];
+    let flags: Vec<bool> = step.iter().map(|&x| w.update_detailed(x).is_outlier).collect();
+    assert_eq!(flags, [true, true, false, false, false, false, false]);
+
+    // 直前に外れ値がある段差
+    let mut w = build();
+    assert!(w.update_detailed(30.0).is_outlier);
+    let flags: Vec<bool> = step.iter().map(|&x| w.update_detailed(x).is_outlier).collect();
+    assert_eq!(flags, [true, false, false, false, false, false, false]);
+
+    // 上限より短い外れ値の連続
+    let mut w = build();
+    for x in [9.0, 9.5] {
+        assert!(w.update_detailed(x).is_outlier);
+    }
+    for &x in &base {
+        assert_eq!(w.update(x), x);
+    }
+}
+
+#[test]
+fn confirmation_needs_m_of_k() {
+    let base = [1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.1, 1.0, 0.9];
+    let build = || {
+        let mut w = Window::<f64, 9>::builder().replacement(Replacement::Median).confirmation(2, 3).build();
+        w.seed(&base);
+        w
+    };
+
+    // 孤立したサンプルは閾値を超えても通す
+    let mut w = build();
+    let s = w.update_detailed(1.5);
+    assert!(s.tripped && !s.is_outlier);
+    assert_eq!(s.value, 1.5);
+    for &x in &base {
+        assert_eq!(w.update(x), x);
+    }
+
+    // 隣り合う2つでは2つ目を置き換える
+    let mut w = build();
+    let (a, b) = (w.update_detailed(1.5), w.update_detailed(1.55));
+    assert!(a.tripped && !a.is_outlier && a.value == 1.5);
+    assert!(b.tripped && b.is_outlier && b.value == b.median);
+
+    // 間に正常なサンプルを挟んだ，直近3つのうちの2つ
+    let mut w = build();
+    let flags: Vec<bool> = [1.5, 1.0, 1.5].iter().map(|&x| w.update_detailed(x).is_outlier).collect();
+    assert_eq!(flags, [false, false, true]);
+
+    assert!(Window::<f64, 9>::builder().confirmation(3, 2).try_build().is_err());
+    assert!(Window::<f64, 9>::builder().confirmation(1, 9).try_build().is_err());
+}
+
+/// 次のサンプルが終了の範囲に戻れば，1つの外れ値は1つのまま
+#[test]
+fn hysteresis_ends_the_run() {
+    let mut filter = Window::<f64, 9>::builder().hysteresis(1.0).build();
+    let base = [0.0, 0.3, -0.3, 0.1, -0.1];
+    filter.seed(&[base, base].concat());
+    let mut flagged = Vec::new();
+    for i in 0..100 {
+        let x = if i == 49 { 5.0 } else { base[i % 5] };
+        if filter.update_detailed(x).is_outlier {
+            flagged.push(i);
+        }
+        assert_eq!(filter.in_outlier_run(), i == 49);
+    }
+    assert_eq!(flagged, [49]);
+
+    filter.update(5.0);
+    assert!(filter.in_outlier_run());
+    filter.reset(0.0);
+    assert!(!filter.in_outlier_run());
+
+    assert!(Window::<f64, 9>::builder().n_sigma(2.0).hysteresis(2.5).try_build().is_err());
+}
+
+/// サンプルを上向きに動かして両方の範囲の端を横切る
+#[test]
+fn suspect_band_between_normal_and_outlier() {
+    let base = [1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.1, 1.0, 0.9];  // 中央値1.0，MAD 0.1
+    let classify = |replace: bool, x: f64| {
+        let mut filter = Window::<f64, 9>::builder()
+            .n_sigma(4.0)
+            .suspect(2.0, replace)
+            .replacement(Replacement::Median)
+            .build();
+        filter.seed(&base);
+        filter.update_detailed(x)
+    };
+
+    let sigma = 1.4826 * 0.1;
+    let mut classes = Vec::new();
+    for k in 0..60 {
+        let x = 1.0 + (0.1 * k as f64 + 0.05) * sigma;
+        let s = classify(false, x);
+        classes.push(s.class);
+        let expected = if s.score > 4.0 {
+            Class::Outlier
+        } else if s.score > 2.0 {
+            Class::Suspect
+        } else {
+            Class::Normal
+        };
+        assert_eq!(s.class, expected, "{}", x);
+        assert_eq!(s.is_outlier, s.class == Class::Outlier);
+        // 既定では外れ値だけを置き換える
+        assert_eq!(s.value, if s.is_outlier { 1.0 } else { x });
+
+        let t = classify(true, x);
+        assert_eq!((t.class, t.is_outlier), (s.class, s.is_outlier));
+        assert_eq!(t.value, if t.class == Class::Normal { x } else { 1.0 });
+    }
+    // 2シグマまでは正常，4シグマまでは疑わしく，その先は外れ値
+    assert!(classes.is_sorted_by_key(|c| *c as u8));
+    assert_eq!(classes.iter().filter(|c| **c == Class::Normal).count(), 20);
+    assert_eq!(classes.iter().filter(|c| **c == Class::Suspect).count(), 20);
+    assert_eq!(classify(false, 1.0 + 2.5 * sigma).class, Class::Suspect);
+    assert_eq!(classify(false, 1.0 - 2.5 * sigma).class, Class::Suspect);
+    assert_eq!(classify(false, 1.0 - 4.5 * sigma).class, Class::Outlier);
+
+    assert!(Window::<f64, 9>::builder().n_sigma(3.0).suspect(3.5, false).try_build().is_err());
+}
+
+/// ドリフトする基線では，重み付き中央値のほうが最新のサンプルから遅れない
+#[test]
+fn forgetting_factor_follows_a_drift() {
+    let mut plain = Window::<f64, 15>::new(0.0, 3.0);
+    let mut decaying = Window::<f64, 15>::builder().forgetting_factor(0.8).build();
+    for i in 0..100 {
+        let x = i as f64 * 0.01;
+        let (p, d) = (plain.update_detailed(x), decaying.update_detailed(x));
+        if i >= 15 {
+            assert_eq!(p.median, (i - 7) as f64 * 0.01);
+            assert!(d.median > p.median);
+        }
+    }
+}
+
+/// 閾値をまたいでxを動かすと，出力は単調かつ連続で，広げた範囲から出ない
+#[test]
+fn soft_replacement_is_monotone_and_continuous() {
+    let mut filter = Window::<f64, 9>::builder().n_sigma(3.0).soft_replacement(1.0).build();
+    filter.seed(&[0.1, -0.2, 0.3, 0.0, -0.1, 0.2, -0.3, 0.1]);
+    let threshold = 3.0 * f64::from(1.4826f32) * 0.2;  // 中央値0.1から3シグマ（MAD = 0.2）
+
+    // 0.3より大きいxでは中央値とMADはxによらない
+    let mut last = filter.clone().update(0.3);
+    for k in 1..=20_000 {
+        let x = 0.3 + k as f64 * 1e-4;
+        let y = filter.clone().update(x);
+        if x - 0.1 <= threshold {
+            assert_eq!(y, x);
+        }
+        assert!(y >= last && y - last < 2e-4);
+        assert!(y <= x && y < 0.1 + 2.0 * threshold);
+        last = y;
+    }
+}
+
+/// 置換値を保存すれば，外れ値が後の更新のMADを広げない
+#[test]
+fn stored_replacement_keeps_the_mad() {
+    let samples = [0.0, 0.3, -0.3, 0.6, -0.6, 0.9, -0.9];
+    let build = |store_policy| {
+        let mut filter = Window::<f64, 7>::builder()
+            .replacement(Replacement::Median)
+            .store_policy(store_policy)
+            .build();
+        filter.seed(&samples);
+        filter
+    };
+    let mut raw = build(StorePolicy::Raw);
+    let mut replaced = build(StorePolicy::Replacement);
+    // windowに入る前に中央値で置き換えたもの
+    let mut reference = build(StorePolicy::Raw);
+
+    let (r, s) = (raw.update_detailed(100.0), replaced.update_detailed(100.0));
+    assert!(r.is_outlier && s.is_outlier);
+    reference.update(s.value);
+
+    for x in [0.2, -0.4, 0.5] {
+        let (r, s, c) = (raw.update_detailed(x), replaced.update_detailed(x), reference.update_detailed(x));
+        assert_eq!(s, c);
+        assert!(r.mad > s.mad);
+    }
+    assert!(replaced.iter().all(|x| x.abs() < 1.0));
+    assert!(raw.iter().any(|x| *x == 100.0));
+}
+
+/// 置換値を保存するときは，受け入れたサンプルだけでwindowを作り直す
+#[test]
+fn stored_replacement_reseeds_from_the_accepted_sample() {
+    let mut filter = Window::<f64, 7>::builder()
+        .replacement(Replacement::Median)
+        .store_policy(StorePolicy::Replacement)
+        .max_consecutive_replacements(2)
+        .mad_floor(0.1)
+        .build();
+    filter.seed(&[0.0, 0.3, -0.3, 0.6, -0.6, 0.9, -0.9]);
+    let out: Vec<f64> = [10.0, 10.1, 10.2, 10.3, 9.9, 10.0].iter().map(|x| filter.update(*x)).collect();
+    assert_eq!(out, [0.3, 0.3, 10.2, 10.3, 9.9, 10.0]);
+}
+
+/// windowにNaNが入らないので，通常の追従に戻る
+#[test]
+fn treat_as_outlier_recovers() {
+    let mut filter = Window::<f32, 5>::builder().nan_policy(NanPolicy::TreatAsOutlier).build();
+    assert_eq!(filter.update(f32::NAN), 0.0);
+    for x in [0.1, -0.1, f32::NAN, 0.2, f32::NAN, f32::NAN, f32::INFINITY, -0.2, f32::NEG_INFINITY] {
+        assert!(filter.update(x).is_finite());
+    }
+    for x in [0.1, -0.1, 0.2, -0.2, 0.1] {
+        filter.update(x);
+    }
+    for x in [-0.1, 0.2, -0.2, 0.1] {
+        assert_eq!(filter.update(x), x);
+    }
+}
+
+/// 受け取ったサンプルだけで統計を求める
+#[test]
+fn warm_up_uses_the_received_samples() {
+    let mut filter = Window::<f64, 5>::builder().warm_up(true).build();
+    assert_eq!(filter.update(10.0), 10.0);
+    assert_eq!(filter.update(10.1), 10.1);
+    assert_eq!(filter.update(9.9), 9.9);
+    assert!(!filter.is_warmed_up());
+    let s = filter.update_detailed(50.0);
+    assert!(s.is_outlier);
+    assert_eq!(s.median, 10.05);
+    assert_eq!(filter.samples_seen(), 4);
+}
+
+#[test]
+fn only_undoable_windows_undo() {
+    let mut filter = Window::<f64, 5>::new(0.0, 3.0);
+    filter.update(1.0);
+    assert!(!filter.undo_last_update());
+
+    let mut filter = Window::<f64, 5>::builder().undoable(true).build();
+    filter.update(1.0);
+    assert!(filter.undo_last_update());
+    assert_eq!(filter.samples_seen(), 0);
+}
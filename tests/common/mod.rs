@@ -0,0 +1,65 @@
+//! Helpers shared by the integration tests.
+
+// 各テストで使うものだけが使われる
+#![allow(dead_code)]
+
+/// Seed of most of the tests.
+pub const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Pseudo-random numbers (xorshift64), the same sequence on every run.
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// 次の状態
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// 次の状態の上位53ビットから作った`[0, 1)`の一様乱数と，その状態
+    pub fn next_f64(&mut self) -> (f64, u64) {
+        let state = self.next_u64();
+        ((state >> 11) as f64 / (1u64 << 53) as f64, state)
+    }
+
+    /// 次の状態の上位24ビットから作った`[0, 1)`の一様乱数と，その状態
+    pub fn next_f32(&mut self) -> (f32, u64) {
+        let state = self.next_u64();
+        ((state >> 40) as f32 / (1u32 << 24) as f32, state)
+    }
+
+    /// `[0, 1)`の一様乱数
+    pub fn uniform(&mut self) -> f64 {
+        self.next_f64().0
+    }
+
+    /// `(0, 1)`の一様乱数（対数を取れる）
+    pub fn open_uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    }
+
+    /// 標準正規分布に従う乱数（Box-Muller法）
+    pub fn gaussian(&mut self) -> f64 {
+        (-2.0 * self.open_uniform().ln()).sqrt() * (std::f64::consts::TAU * self.open_uniform()).cos()
+    }
+}
+
+/// 並べ替えで求めた中央値（要素数が偶数なら中央の2つの平均）
+pub fn median(v: &mut [f64]) -> f64 {
+    v.sort_by(f64::total_cmp);
+    let n = v.len();
+    if n.is_multiple_of(2) { (v[n / 2 - 1] + v[n / 2]) / 2.0 } else { v[n / 2] }
+}
+
+/// 並べ替えで求めた窓の中央値とMAD
+pub fn median_mad(window: &[f64]) -> (f64, f64) {
+    let w0 = median(&mut window.to_vec());
+    let s0 = median(&mut window.iter().map(|v| (v - w0).abs()).collect::<Vec<_>>());
+    (w0, s0)
+}
@@ -0,0 +1,76 @@
+//! Validation of the thresholds and the scale by every constructor.
+
+use std::panic::catch_unwind;
+
+use hampel::{
+    AngularWindow, CenteredWindow, ConfigError, IntWindow, MetricWindow, Replacement, ThresholdMode, WeightedWindow, Window,
+    ZScoreWindow,
+};
+
+const INVALID: [f64; 5] = [f64::NAN, -1.0, 0.0, -0.0, f64::INFINITY];
+
+#[test]
+fn builder_rejects_invalid_n_sigma() {
+    for n_sigma in INVALID {
+        let result = Window::<f64, 5>::builder().n_sigma(n_sigma).try_build();
+        assert_eq!(result.err(), Some(ConfigError::InvalidNSigma), "n_sigma = {}", n_sigma);
+        for (low, high) in [(n_sigma, 3.0), (3.0, n_sigma)] {
+            let result = Window::<f64, 5>::builder().n_sigma_asymmetric(low, high).try_build();
+            assert_eq!(result.err(), Some(ConfigError::InvalidNSigma), "n_sigma = ({}, {})", low, high);
+        }
+    }
+    assert!(Window::<f64, 5>::builder().n_sigma_asymmetric(1.0, 4.0).try_build().is_ok());
+}
+
+#[test]
+fn builder_rejects_invalid_scale() {
+    for scale in INVALID {
+        let result = Window::<f64, 5>::builder().scale(scale).try_build();
+        assert_eq!(result.err(), Some(ConfigError::InvalidScale), "scale = {}", scale);
+    }
+    assert!(Window::<f64, 5>::builder().scale(1.0).try_build().is_ok());
+}
+
+#[test]
+fn constructors_panic_as_new() {
+    for x in INVALID {
+        assert!(catch_unwind(|| Window::<f64, 5>::new(0.0, x)).is_err());
+        assert!(catch_unwind(|| Window::<f64, 5>::with_replacement(0.0, x, Replacement::Median)).is_err());
+        assert!(catch_unwind(|| Window::<f64, 5>::with_scale(0.0, x, 1.0)).is_err());
+        assert!(catch_unwind(|| Window::<f64, 5>::with_scale(0.0, 3.0, x)).is_err());
+        assert!(catch_unwind(|| Window::<f64, 5>::asymmetric(0.0, x, 3.0)).is_err());
+        assert!(catch_unwind(|| Window::<f64, 5>::asymmetric(0.0, 3.0, x)).is_err());
+    }
+}
+
+#[test]
+fn difference_test_rejects_invalid_n_sigma() {
+    for n_sigma in INVALID {
+        let result = Window::<f64, 5>::builder().difference_test(n_sigma, ThresholdMode::Either).try_build();
+        assert_eq!(result.err(), Some(ConfigError::InvalidNSigma), "n_sigma = {}", n_sigma);
+    }
+}
+
+#[test]
+fn other_filters_panic_as_window() {
+    for x in INVALID {
+        assert!(catch_unwind(|| AngularWindow::<f64, 5>::new(0.0, x, 360.0, Replacement::Median)).is_err());
+        assert!(catch_unwind(|| MetricWindow::<f64, f64, 5>::new(0.0, x, |a, b| (a - b).abs())).is_err());
+        assert!(catch_unwind(|| WeightedWindow::<f64, 5>::new(0.0, x)).is_err());
+        assert!(catch_unwind(|| ZScoreWindow::<f64, 5>::new(0.0, x)).is_err());
+        assert!(catch_unwind(|| CenteredWindow::<f64, 5>::new(x)).is_err());
+    }
+    assert!(catch_unwind(|| IntWindow::<i16, 5>::new(0, 0, 1)).is_err());
+    assert!(catch_unwind(|| IntWindow::<i16, 5>::new(0, 3, 0)).is_err());
+}
+
+#[cfg(feature = "fixed")]
+#[test]
+fn fixed_window_rejects_non_positive_n_sigma() {
+    use fixed::types::I16F16;
+    use hampel::FixedWindow;
+
+    for n_sigma in [I16F16::ZERO, I16F16::from_num(-3)] {
+        assert!(catch_unwind(|| FixedWindow::<I16F16, 5>::new(I16F16::ZERO, n_sigma)).is_err());
+    }
+}
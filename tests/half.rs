@@ -0,0 +1,80 @@
+//! Half-precision windows against `f32` windows (`half` feature).
+
+#![cfg(feature = "half")]
+
+mod common;
+
+use common::Xorshift;
+use half::{bf16, f16};
+use hampel::{Replacement, Window};
+use num_traits::{cast, float::FloatCore};
+
+/// 閾値付近を除いて`f32`のwindowと同じ判定と（`T`の1ステップ以内の）値になる
+fn same_as_f32<T: FloatCore, const N: usize>(replacement: Replacement, level: f32) {
+    let h = |v: f32| -> T { cast(v).unwrap() };
+    let f = |v: T| -> f32 { cast(v).unwrap() };
+    // vの大きさでのTの1ステップの上限
+    let step = |v: f32| v.abs().max(f(T::min_positive_value())) * f(T::epsilon());
+
+    let mut a = Window::<T, N>::with_replacement(h(level), h(3.0), replacement);
+    let mut b = Window::<f32, N>::with_replacement(level, 3.0, replacement);
+    let (mut detected, mut mismatches) = (0, 0);
+    let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+    for i in 0..3000 {
+        let (noise, state) = rng.next_f32();
+        let noise = noise - 0.5;
+        let spike = if state.is_multiple_of(50) { 200.0 } else { 0.0 };
+        let x = h(level + 10.0 * (i as f32 * 0.05).sin() + noise + spike);
+
+        let sa = a.update_detailed(x);
+        let sb = b.update_detailed(f(x));
+        detected += sa.is_outlier as usize;
+        if sa.is_outlier == sb.is_outlier {
+            assert!((f(sa.value) - sb.value).abs() <= step(sb.value));
+        } else {
+            // 閾値ちょうどの場合だけ
+            let deviation = (f(x) - sb.median).abs();
+            let threshold = 1.4826 * 3.0 * sb.mad;
+            assert!((deviation - threshold).abs() <= step(deviation) + step(threshold));
+            mismatches += 1;
+        }
+    }
+    assert!(detected >= 50 && mismatches <= 5);
+}
+
+#[test]
+fn decisions_match_f32() {
+    same_as_f32::<f16, 5>(Replacement::Median, 0.0);
+    same_as_f32::<f16, 9>(Replacement::LinearExtrapolation, 100.0);
+    // 直線の当てはめの和はf16では溢れる
+    same_as_f32::<f16, 101>(Replacement::LinearExtrapolation, 2000.0);
+    same_as_f32::<bf16, 7>(Replacement::Median, 0.0);
+    same_as_f32::<bf16, 101>(Replacement::LinearExtrapolation, 2000.0);
+}
+
+#[test]
+fn median_replacement_matches_f32() {
+    let mut a = Window::<f16, 5>::with_replacement(f16::ZERO, f16::from_f32(3.0), Replacement::Median);
+    let mut b = Window::<f32, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+    for x in [0.5, 0.25, 200.0, 0.75, 0.5] {
+        let (sa, sb) = (a.update_detailed(f16::from_f32(x)), b.update_detailed(x));
+        assert_eq!(sa.is_outlier, sb.is_outlier);
+        assert_eq!(sa.value.to_f32(), sb.value);
+    }
+}
+
+/// 欠損の数は`T`で数えるので，`T`で正確に表せる整数を超えると丸められる
+#[test]
+fn missing_count_is_rounded() {
+    let mut a = Window::<f16, 5>::with_replacement(f16::ZERO, f16::from_f32(3.0), Replacement::LinearExtrapolation);
+    let mut b = Window::<bf16, 5>::with_replacement(bf16::ZERO, bf16::from_f32(3.0), Replacement::LinearExtrapolation);
+    for x in [1.0, 2.0, 3.0, 4.0, 5.0] {
+        a.update(f16::from_f32(x));
+        b.update(bf16::from_f32(x));
+    }
+    for k in 1..=3000 {
+        let expected = 5.0 + k as f32;
+        assert!((a.update_missing().to_f32() - expected).abs() <= 4.0);
+        assert!((b.update_missing().to_f32() - expected).abs() <= expected / 64.0);
+    }
+}
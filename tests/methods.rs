@@ -0,0 +1,563 @@
+//! Constructors and methods of `Window`, on small inputs.
+
+use std::collections::VecDeque;
+
+use hampel::{
+    CenteredWindow, ConfigError, Decision, LengthMismatch, MedianWindow, OutlierEvent, Replacement, Sample, Side,
+    StorePolicy, StrideError, ThresholdMode, Window, WindowBank, WindowMut, WindowState,
+};
+
+/// 偶数サイズのwindowの中央値は中央の2つの平均
+#[test]
+fn even_size_median_is_the_average() {
+    let mut filter = Window::<f64, 4>::new(0.0, 1.05);
+    for x in [1.0, 2.0, 3.0] {
+        filter.update(x);
+    }
+    let s = filter.update_detailed(0.0);
+    assert_eq!((s.median, s.mad), (1.5, 1.0));
+    assert!(!s.is_outlier);
+
+    // 並べると[1, 1.5, 3, 4]で，偏差は[0.75, 0.75, 1.25, 1.75]
+    let mut filter = Window::<f64, 4>::new(0.0, 3.0);
+    for x in [3.0, 1.0, 4.0] {
+        filter.update(x);
+    }
+    let s = filter.update_detailed(1.5);
+    assert_eq!((s.median, s.mad), (2.25, 1.0));
+}
+
+/// 並べたコピーを保つwindowが，毎回windowの写しから求めるものと同じ結果になる
+#[test]
+fn sorted_copy_matches_a_copy_of_the_window() {
+    let (mut buf, mut scratch, mut gaps) = ([0.0; 11], [0.0; 11], [0.0; 11]);
+    let mut a = WindowMut::new(&mut buf, &mut scratch, &mut gaps, 0.0, 3.0).unwrap();
+    let mut b = Window::<f64, 11>::new(0.0, 3.0);
+    for x in [0.5, 0.25, 0.25, 90.0, f64::INFINITY, 0.75, -0.5, 0.5] {
+        assert_eq!(a.update_detailed(x), b.update_detailed(x));
+    }
+}
+
+/// 選択ネットワークは`f64::total_cmp`の順に並べる
+#[test]
+fn selection_network_orders_signed_zeros() {
+    // [-0.0, -0.0, 0.0, 1.0, 2.0]に並ぶ
+    let mut filter = Window::<f64, 5>::new(0.0, 3.0);
+    filter.seed(&[-0.0, 1.0, -0.0, 2.0]);
+    let s = filter.update_detailed(0.0);
+    assert_eq!(s.median.to_bits(), 0.0f64.to_bits());
+}
+
+#[test]
+fn try_new_rejects_invalid_n_sigma() {
+    assert!(Window::<f64, 5>::try_new(0.0, 3.0).unwrap() == Window::new(0.0, 3.0));
+    for n_sigma in [f64::NAN, -1.0, 0.0, -0.0, f64::INFINITY] {
+        assert_eq!(Window::<f64, 5>::try_new(0.0, n_sigma).err(), Some(ConfigError::InvalidNSigma));
+    }
+    assert!(Window::<f32, 3>::try_new(0.0, f32::MIN_POSITIVE).is_ok());
+}
+
+#[test]
+fn with_replacement_selects_the_replacement() {
+    let mut median = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+    let mut pass = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::PassThrough);
+    for x in [1.0, 2.0, 3.0, 4.0] {
+        median.update(x);
+        pass.update(x);
+    }
+    assert_eq!(median.update(100.0), 3.0);
+    let s = pass.update_detailed(100.0);
+    assert!(s.is_outlier);
+    assert_eq!(s.value, 100.0);
+}
+
+/// `new`は`f32`に丸めた1.4826を使うので，スコアと範囲だけが異なる
+#[test]
+fn with_scale_of_the_gaussian_constant_is_new() {
+    let mut a = Window::<f64, 5>::new(0.0, 3.0);
+    let mut b = Window::<f64, 5>::with_scale(0.0, 3.0, 1.4826);
+    for i in 0..100 {
+        let x = (i as f64 * 0.3).sin() + if i % 7 == 0 { 2.0 } else { 0.0 };
+        let (s, t) = (a.update_detailed(x), b.update_detailed(x));
+        assert!(s.score == t.score || (s.score - t.score).abs() <= s.score * 1e-7);
+        assert_eq!(Sample { score: 0.0, bounds: (0.0, 0.0), ..s }, Sample { score: 0.0, bounds: (0.0, 0.0), ..t });
+    }
+}
+
+/// 上側の閾値を緩めれば，下側の脱落だけを置き換えて上側の実際の上昇は通す
+#[test]
+fn asymmetric_thresholds() {
+    let mut asymmetric = Window::<f64, 9>::asymmetric(10.0, 2.0, 6.0);
+    let mut symmetric = Window::<f64, 9>::new(10.0, 2.0);
+    for i in 0..60 {
+        let noise = ((i * 7919) % 13) as f64 * 0.02 - 0.12;
+        let x = match i % 10 {
+            4 => 10.0 + noise - 5.0,
+            8 => 10.0 + noise + 0.35,
+            _ => 10.0 + noise,
+        };
+        let a = asymmetric.update_detailed(x);
+        let s = symmetric.update_detailed(x);
+        if i % 10 == 4 {
+            assert_eq!(a.side, Some(Side::Below));
+        } else if i % 10 == 8 {
+            assert_eq!(a.value, x);
+            assert_eq!(s.side, Some(Side::Above));
+        }
+        assert_ne!(a.side, Some(Side::Above));
+    }
+}
+
+/// バイト単位で写した状態から，windowが埋まる前後，リングバッファのどの位置でも復元できる
+#[test]
+fn state_survives_a_byte_copy() {
+    // パディングなし：カウンタ，サンプル，時刻の差，5つの値
+    assert_eq!(size_of::<WindowState<f32, 7>>(), 56 + 4 * (2 * 7 + 7));
+    assert_eq!(size_of::<WindowState<f64, 7>>(), 56 + 8 * (2 * 7 + 7));
+
+    let input = |i: usize| (i as f32 * 0.1).sin() + if i.is_multiple_of(23) { 5.0 } else { 0.0 };
+    for stop in [0, 3, 7, 500, 503] {
+        let mut filter = Window::<f32, 7>::new(0.0, 3.0);
+        for i in 0..stop {
+            if i % 50 == 49 {
+                filter.update_missing();
+            } else {
+                filter.update(input(i));
+            }
+        }
+
+        // フラッシュメモリへの`memcpy`のように写す
+        let state = filter.to_state();
+        let mut flash = [0u8; size_of::<WindowState<f32, 7>>()];
+        unsafe {
+            core::ptr::copy_nonoverlapping(&state as *const WindowState<f32, 7> as *const u8, flash.as_mut_ptr(), flash.len());
+        }
+        let loaded = unsafe { core::ptr::read_unaligned(flash.as_ptr() as *const WindowState<f32, 7>) };
+        assert_eq!(loaded, state);
+
+        let mut restored = Window::<f32, 7>::from_state(&loaded, 3.0);
+        assert_eq!(restored, filter);
+        assert!(restored.iter().eq(loaded.samples.iter()));
+        if stop >= 7 {
+            assert!(format!("{:?}", restored).contains(" oldest: 0,"));
+        }
+        for i in stop..stop + 1000 {
+            assert_eq!(restored.update_detailed(input(i)), filter.update_detailed(input(i)));
+        }
+        assert_eq!(restored.stats(), filter.stats());
+    }
+}
+
+/// ウォームアップ中に通したサンプルではハンドラを呼ばない
+#[test]
+fn handler_is_invoked_for_each_replacement() {
+    let mut faults = 0;
+    {
+        let mut filter = Window::<f64, 7>::builder()
+            .warm_up(true)
+            .build()
+            .on_outlier(|e: OutlierEvent<f64>| {
+                assert!(e.raw >= 10.0);
+                assert_ne!(e.raw, e.replacement);
+                faults += 1;
+            });
+        for i in 0..30 {
+            let noise = ((i * 7919) % 13) as f64 * 0.1;
+            let x = noise + if i == 7 || i == 15 || i == 23 { 10.0 } else { 0.0 };
+            filter.update(x);
+        }
+    }
+    assert_eq!(faults, 3);
+}
+
+/// 履歴を付けても先に付けたハンドラは呼ばれる
+#[test]
+fn history_keeps_the_handler() {
+    let mut faults = 0;
+    let outliers = {
+        let mut filter = Window::<f64, 5>::new(0.0, 3.0)
+            .on_outlier(|_: OutlierEvent<f64>| faults += 1)
+            .with_history::<8>();
+        for x in [0.1, -0.1, 9.0, 0.2, 0.0] {
+            filter.update(x);
+        }
+        assert_eq!(filter.history().count(), 5);
+        filter.history().filter(|r| r.is_outlier).count()
+    };
+    assert!(outliers > 0 && faults == outliers);
+}
+
+/// 押し出されるサンプルは，windowに保存された入力をWINDOW_SIZEだけ遅らせたもの
+#[test]
+fn evicted_samples_are_the_delayed_input() {
+    let input = |i: usize| (i as f64 * 0.1).sin() + if i % 13 == 5 { 10.0 } else { 0.0 };
+
+    let mut filter = Window::<f64, 7>::new(-1.0, 3.0);
+    let mut residuals = 0;
+    for i in 0..1000 {
+        let (y, evicted) = filter.update_evicting(input(i));
+        // 外れ値を含む生の入力
+        assert_eq!(evicted, if i < 7 { -1.0 } else { input(i - 7) });
+        if y != input(i) {
+            residuals += 1;
+        }
+    }
+    assert!(residuals > 0);
+
+    // 置換値を保存すれば，外れ値は置換値として押し出される
+    let mut filter = Window::<f64, 7>::builder()
+        .init_value(-1.0)
+        .replacement(Replacement::Median)
+        .store_policy(StorePolicy::Replacement)
+        .build();
+    let mut outputs = Vec::new();
+    for i in 0..1000 {
+        let (y, evicted) = filter.update_evicting(input(i));
+        assert_eq!(evicted, if i < 7 { -1.0 } else { outputs[i - 7] });
+        outputs.push(y);
+    }
+}
+
+/// ブロックに分けても，サンプルごとの更新と同じ結果になる
+#[test]
+fn process_in_blocks() {
+    let input = [0.5, 0.25, 10.0, 0.75, 0.5, 0.25, -8.0, 0.5];
+    let mut reference = Window::<f32, 5>::new(0.0, 3.0);
+    let expected: Vec<f32> = input.iter().map(|&x| reference.update(x)).collect();
+
+    let mut filter = Window::<f32, 5>::new(0.0, 3.0);
+    let mut output = input;
+    let (head, tail) = output.split_at_mut(3);
+    filter.process(head);
+    filter.process(tail);
+    assert_eq!(output[..], expected[..]);
+    assert_eq!(filter, reference);
+}
+
+#[test]
+fn process_into_is_process() {
+    let src: Vec<f64> = (0..256).map(|i| (i as f64 * 0.2).sin() + if i % 17 == 3 { 4.0 } else { 0.0 }).collect();
+    let mut filter = Window::<f64, 9>::new(0.0, 3.0);
+    let mut dst = vec![0.0; 256];
+    filter.process_into(&src, &mut dst).unwrap();
+
+    let mut reference = Window::<f64, 9>::new(0.0, 3.0);
+    let mut buf = src.clone();
+    reference.process(&mut buf);
+    assert_eq!(dst, buf);
+
+    // 長さが異なればwindowを更新しない
+    assert_eq!(filter.process_into(&src, &mut dst[..255]), Err(LengthMismatch { src: 256, dst: 255 }));
+    assert_eq!(filter, reference);
+}
+
+/// チャネルを取り出して処理し，戻したものと同じになる
+#[test]
+fn process_strided_is_deinterleaved_process() {
+    // 長さがストライドの倍数でない3チャネル
+    let input: Vec<f64> = (0..3001).map(|i| (i as f64 * 0.01).sin() + if i % 37 == 0 { 5.0 } else { 0.0 }).collect();
+
+    let mut strided = input.clone();
+    let mut filters: Vec<Window<f64, 9>> = (0..3).map(|_| Window::new(0.0, 3.0)).collect();
+    for (c, filter) in filters.iter_mut().enumerate() {
+        // 2つのブロックにまたがって状態を引き継ぐ
+        let (a, b) = strided.split_at_mut(1500);
+        filter.process_strided(a, c, 3).unwrap();
+        filter.process_strided(b, c, 3).unwrap();
+    }
+
+    let mut expected = input.clone();
+    for c in 0..3 {
+        let mut channel: Vec<f64> = input.iter().skip(c).step_by(3).copied().collect();
+        Window::<f64, 9>::new(0.0, 3.0).process(&mut channel);
+        for (k, y) in channel.into_iter().enumerate() {
+            expected[c + 3 * k] = y;
+        }
+    }
+    assert_eq!(strided, expected);
+
+    let mut filter = Window::<f64, 9>::new(0.0, 3.0);
+    assert_eq!(filter.process_strided(&mut strided, 3, 3), Err(StrideError::InvalidOffset { offset: 3, stride: 3 }));
+    assert!(filter.process_strided(&mut strided, 0, 0).is_err());
+}
+
+/// 正弦波の途中で1, 3, 9サンプル欠けたとき
+#[test]
+fn missing_bursts_in_a_sine_wave() {
+    let signal = |i: usize| (i as f64 * 0.1).sin();
+    for fill in [false, true] {
+        let mut filter = Window::<f64, 9>::builder()
+            .replacement(Replacement::QuadraticExtrapolation)
+            .fill_missing(fill)
+            .build();
+        let mut i = 0;
+        for burst in [1, 3, 9] {
+            for _ in 0..30 {
+                filter.update(signal(i));
+                i += 1;
+            }
+            for k in 1..=burst {
+                let s = filter.update_missing_detailed();
+                assert_eq!(s.stale, k > 4);  // max_missing: WINDOW_SIZE / 2
+                if k <= 3 {
+                    assert!((s.value - signal(i)).abs() < 0.05);
+                }
+                i += 1;
+            }
+            let s = filter.update_detailed(signal(i));
+            assert!(!s.stale);
+            // windowを埋めなければ，最後の欠損の後は9サンプル遅れる
+            assert_eq!(s.is_outlier, !fill && burst == 9);
+        }
+    }
+}
+
+/// `update`は前の時刻に1を足した`update_at`と同じで，戻る時刻は前の時刻とみなす
+#[test]
+fn update_is_update_at_unit_steps() {
+    let input = |i: usize| (i as f64 * 0.2).sin() + if i % 9 == 4 { 3.0 } else { 0.0 };
+    let mut a = Window::<f64, 7>::with_replacement(0.0, 3.0, Replacement::QuadraticExtrapolation);
+    let mut b = a.clone();
+    for i in 0..200 {
+        assert_eq!(a.update_at(i as f64 + 1000.0, input(i)), b.update(input(i)));
+    }
+
+    let mut c = a.clone();
+    assert_eq!(a.update_at(1199.0, 10.0), c.update_at(1150.0, 10.0));
+    assert_eq!(a, c);
+}
+
+#[test]
+fn undo_forgets_a_duplicate() {
+    let mut filter = Window::<f64, 5>::builder().undoable(true).build();
+    let mut reference = filter.clone();
+    for x in [0.5, 0.25, 0.75] {
+        filter.update(x);
+        reference.update(x);
+    }
+    filter.update(0.75);
+    assert!(filter.undo_last_update());
+    assert!(!filter.undo_last_update());
+    assert_eq!(filter, reference);
+    assert_eq!(filter.update_detailed(0.5), reference.update_detailed(0.5));
+}
+
+/// リセットすればウォームアップをやり直し，新しい水準のサンプルを外れ値としない
+#[test]
+fn reset_restarts_the_warm_up() {
+    let mut filter = Window::<f64, 5>::builder().warm_up(true).build();
+    for x in [1.0, 1.1, 0.9, 1.0, 1.2, 1.1] {
+        filter.update(x);
+    }
+    assert!(filter.is_warmed_up());
+    filter.reset(0.0);
+    assert_eq!(filter.samples_seen(), 0);
+    assert_eq!(filter.update(20.0), 20.0);
+    assert_eq!(filter.update(20.5), 20.5);
+}
+
+/// 与えたサンプルで更新したものと同じになる
+#[test]
+fn seed_is_updating() {
+    let samples = [1.0, 1.2, 0.9, 1.1, 1.0, 0.8, 1.3];
+    let mut organic = Window::<f64, 5>::new(0.0, 3.0);
+    for x in samples {
+        organic.update(x);
+    }
+    let mut seeded = Window::<f64, 5>::new(0.0, 3.0);
+    seeded.seed(&samples);
+    for x in [1.1, 5.0, 0.9] {
+        assert_eq!(seeded.update_detailed(x), organic.update_detailed(x));
+    }
+}
+
+#[test]
+fn median_and_mad_of_the_last_update() {
+    let mut filter = Window::<f64, 5>::new(0.0, 3.0);
+    assert_eq!((filter.median(), filter.mad()), (0.0, 0.0));
+    for i in 0..100 {
+        let x = (i as f64 * 0.3).sin() + if i % 17 == 0 { 5.0 } else { 0.0 };
+        let s = filter.update_detailed(x);
+        assert_eq!((filter.median(), filter.mad()), (s.median, s.mad));
+    }
+
+    filter.seed(&[1.0, 4.0, 2.0, 5.0, 3.0]);
+    assert_eq!((filter.median(), filter.mad()), (3.0, 1.0));
+}
+
+#[test]
+fn bounds_of_the_last_update() {
+    let mut window = Window::<f64, 9>::builder()
+        .n_sigma_asymmetric(2.0, 4.0)
+        .mad_floor(0.05)
+        .abs_threshold(1.5)
+        .threshold_mode(ThresholdMode::Either)
+        .build();
+    assert_eq!(window.bounds(), (0.0, 0.0));
+
+    for x in [0.5, 0.25, 0.75, 0.5, 3.0] {
+        let s = window.update_detailed(x);
+        assert_eq!(window.bounds(), s.bounds);
+        let (lower, upper) = s.bounds;
+        assert_eq!(s.tripped, !(lower <= x && x <= upper));
+    }
+}
+
+/// リングバッファを何周しても古い順に返す
+#[test]
+fn iter_is_chronological() {
+    let mut filter = Window::<f64, 4>::new(0.0, 3.0);
+    let mut expected = VecDeque::from([0.0; 4]);
+    assert!(filter.iter().eq(&expected));
+
+    for i in 1..=15 {
+        let x = if i % 5 == 0 { 100.0 } else { i as f64 };
+        filter.update(x);
+        expected.pop_front();
+        expected.push_back(x);
+        assert!(filter.iter().eq(&expected));
+        assert!(filter.iter().rev().eq(expected.iter().rev()));
+        assert_eq!(filter.iter().len(), filter.len());
+        assert_eq!(filter.latest(), x);
+    }
+}
+
+#[test]
+fn stats_count_the_runs() {
+    let mut filter = Window::<f64, 7>::new(0.0, 3.0);
+    filter.seed(&[0.1, -0.1, 0.2, -0.2, 0.0, 0.1]);
+    for x in [0.0, 5.0, 0.1, 6.0, 7.0, -0.1, 0.2] {
+        filter.update(x);
+    }
+    let stats = filter.stats();
+    assert_eq!((stats.samples_total, stats.outliers_total), (7, 3));
+    assert_eq!((stats.current_run, stats.longest_run), (0, 2));
+
+    filter.reset_stats();
+    assert_eq!(filter.stats().samples_total, 0);
+}
+
+/// 下限がなければ，小さな段差はwindowの過半数になるまで外れ値になる
+#[test]
+fn mad_floor_passes_a_small_step() {
+    let mut plain = Window::<f64, 7>::new(1.0, 3.0);
+    let mut floored = Window::<f64, 7>::new(1.0, 3.0);
+    floored.set_mad_floor(0.01);
+    assert_eq!(floored.mad_floor(), 0.01);
+    for _ in 0..100 {
+        plain.update(1.0);
+        floored.update(1.0);
+    }
+    for i in 0..100 {
+        assert_eq!(plain.update_detailed(1.001).is_outlier, i < 3);
+        assert_eq!(floored.update(1.001), 1.001);
+    }
+}
+
+/// `would_flag`は後の更新の結果を変えない
+#[test]
+fn would_flag_does_not_update() {
+    let mut a = Window::<f64, 5>::new(0.0, 3.0);
+    let mut b = Window::<f64, 5>::new(0.0, 3.0);
+    for x in [0.1, -0.1, 0.2, -0.2, 10.0, 0.3] {
+        let flagged = a.would_flag(x);
+        assert!(a.would_flag(100.0));
+        let s = a.update_detailed(x);
+        assert_eq!(s.is_outlier, flagged);
+        assert_eq!(s.value, b.update(x));
+    }
+}
+
+#[test]
+fn score_is_that_of_the_update() {
+    let mut window = Window::<f64, 5>::new(0.0, 3.0);
+    window.seed(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    let score = window.score(9.0);
+    let s = window.update_detailed(9.0);
+    assert_eq!(s.score, score);
+    assert_eq!(s.relative, score > 3.0);
+
+    // MADが0なら無限大
+    let mut window = Window::<f64, 5>::new(1.0, 3.0);
+    assert_eq!(window.score(1.0), 0.0);
+    assert_eq!(window.score(1.5), f64::INFINITY);
+    assert_eq!(window.update_detailed(1.5).score, f64::INFINITY);
+}
+
+/// 複製したwindowは互いに独立で，リングバッファの位置によらず比較される
+#[test]
+fn clones_are_independent() {
+    let template = Window::<f64, 5>::builder().replacement(Replacement::HoldLast).build();
+    let mut a = template.clone();
+    let b = template.clone();
+    for i in 0..12 {
+        a.update(i as f64);
+    }
+    assert_ne!(a, template);
+    assert_eq!(b, template);
+
+    let mut c = a.clone();
+    assert_eq!(c, a);
+    c.update(100.0);
+    assert_ne!(c, a);
+    assert!(format!("{:?}", a).starts_with("Window { window: [7.0, 8.0, 9.0, 10.0, 11.0], "));
+    assert!(format!("{:?}", c).starts_with("Window { window: [8.0, 9.0, 10.0, 11.0, 100.0], "));
+}
+
+/// `static`に置いたwindowと，コンパイル時に評価したNaNの初期値
+#[test]
+fn new_const_is_new() {
+    use core::cell::RefCell;
+    use std::sync::Mutex;
+
+    static FILTER: Mutex<RefCell<Window<f32, 11>>> = Mutex::new(RefCell::new(Window::<f32, 11>::new_const(0.0, 3.0)));
+
+    let mut reference = Window::<f32, 11>::new(0.0, 3.0);
+    assert!(*FILTER.lock().unwrap().borrow() == reference);
+    for i in 0..500 {
+        let x = (i as f32 * 0.1).sin() + if i % 17 == 0 { 4.0 } else { 0.0 };
+        let y = FILTER.lock().unwrap().borrow_mut().update_detailed(x);
+        assert_eq!(y, reference.update_detailed(x));
+    }
+
+    const NAN: Window<f64, 5> = Window::<f64, 5>::new_const(f64::NAN, 2.5);
+    let mut a = NAN;
+    let mut b = Window::<f64, 5>::new(f64::NAN, 2.5);
+    for x in [1.0, 1.1, 0.9, 1.0, 8.0, 1.2] {
+        assert_eq!(a.update(x).to_bits(), b.update(x).to_bits());
+    }
+}
+
+/// `into_fn`と`as_fn`は`update`に転送する
+#[test]
+fn closures_forward_to_update() {
+    let input: Vec<f64> = (0..200).map(|i| (i as f64 * 0.1).sin() + if i % 23 == 7 { 9.0 } else { 0.0 }).collect();
+
+    let filtered: Vec<f64> = input.iter().copied().map(Window::<f64, 7>::new(0.0, 3.0).into_fn()).collect();
+    let mut reference = Window::<f64, 7>::new(0.0, 3.0);
+    assert!(input.iter().zip(&filtered).all(|(&x, &y)| reference.update(x) == y));
+
+    // 2段の合成
+    fn compose<A, B, C>(mut f: impl FnMut(A) -> B, mut g: impl FnMut(B) -> C) -> impl FnMut(A) -> C {
+        move |x| g(f(x))
+    }
+    let mut stages = compose(Window::<f64, 7>::new(0.0, 3.0).into_fn(), MedianWindow::<f64, 3>::new(0.0).into_fn());
+    let (mut first, mut second) = (Window::<f64, 7>::new(0.0, 3.0), MedianWindow::<f64, 3>::new(0.0));
+    for &x in &input {
+        assert_eq!(stages(x), second.update(first.update(x)));
+    }
+
+    // 借りたフィルタは状態を保つ
+    let mut filter = Window::<f64, 7>::new(0.0, 3.0);
+    let flagged = input.iter().copied().map(filter.as_detailed_fn()).filter(|s| s.is_outlier).count();
+    assert!(flagged >= 9);
+    assert_eq!(filter.stats().outliers_total as usize, flagged);
+
+    // バンクと，`filter_map`に渡す中心窓
+    let pairs: Vec<[f64; 2]> = input.iter().map(|&x| [x, -x]).map(WindowBank::<f64, 7, 2>::new(0.0, 3.0, Decision::Any).into_fn()).collect();
+    let mut reference = WindowBank::<f64, 7, 2>::new(0.0, 3.0, Decision::Any);
+    assert!(input.iter().zip(&pairs).all(|(&x, &p)| reference.update([x, -x]) == p));
+    let centered: Vec<f64> = input.iter().copied().filter_map(CenteredWindow::<f64, 7>::new(3.0).into_fn()).collect();
+    assert_eq!(centered.len(), input.len() - 3);
+    assert!(centered.iter().all(|y| y.abs() <= 1.0));
+}
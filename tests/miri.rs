@@ -0,0 +1,57 @@
+//! Short runs of the basic update loops, small enough for Miri (`cargo +nightly miri test --test miri`).
+//!
+//! The differential tests of the other files run thousands of updates, which take too long under Miri.
+
+use hampel::Window;
+
+/// 並べ替えで求めた窓の中央値とMAD
+fn reference(window: &[f64]) -> (f64, f64) {
+    let median = |v: &mut Vec<f64>| {
+        v.sort_by(f64::total_cmp);
+        v[v.len() / 2]
+    };
+    let w0 = median(&mut window.to_vec());
+    let s0 = median(&mut window.iter().map(|v| (v - w0).abs()).collect());
+    (w0, s0)
+}
+
+#[test]
+fn update_loop() {
+    let input = [1.0, 0.5, -0.25, 0.75, 10.0, 0.25, -1.0, 0.5, 0.0, -8.0, 1.0, 0.75, 0.5, 3.0, 0.25];
+    let mut a = Window::<f64, 5>::new(0.0, 3.0);
+    let mut b = Window::<f64, 5>::new(0.0, 3.0);
+    let mut window = vec![0.0; 5];
+    for x in input {
+        window.remove(0);
+        window.push(x);
+        let (w0, s0) = reference(&window);
+
+        let s = a.update_detailed(x);
+        assert_eq!((s.median, s.mad), (w0, s0));
+        assert_eq!(s.is_outlier, (x - w0).abs() > 1.4826 * 3.0 * s0);
+        if !s.is_outlier {
+            assert_eq!(s.value, x);
+        }
+        assert_eq!(b.update(x).to_bits(), s.value.to_bits());
+    }
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn ffi_update_loop() {
+    use core::mem::MaybeUninit;
+    use hampel::ffi::*;
+
+    let mut state = MaybeUninit::<HampelF32W5>::uninit();
+    let state = state.as_mut_ptr();
+    let mut reference = Window::<f32, 5>::new(0.0, 3.0);
+    unsafe {
+        assert_eq!(hampel_f32_w5_init(state, 0.0, 3.0), HAMPEL_OK);
+        for x in [1.0, 0.5, 8.0, 0.25, f32::NAN, 0.75] {
+            assert_eq!(hampel_f32_w5_update(state, x).to_bits(), reference.update(x).to_bits());
+        }
+        assert_eq!(hampel_f32_w5_reset(state), HAMPEL_OK);
+        reference.reset(0.0);
+        assert_eq!(hampel_f32_w5_update(state, 1.0), reference.update(1.0));
+    }
+}
@@ -0,0 +1,62 @@
+//! Missing samples in the windows of runtime size.
+
+use hampel::{Window, WindowMut};
+
+#[test]
+fn runtime_size_has_the_same_default_max_missing() {
+    let (mut buf, mut scratch, mut gaps) = ([0.0; 9], [0.0; 9], [0.0; 9]);
+    let mut a = WindowMut::new(&mut buf, &mut scratch, &mut gaps, 0.0, 3.0).unwrap();
+    let mut b = Window::<f64, 9>::new(0.0, 3.0);
+    for i in 0..20 {
+        let x = (i as f64 * 0.3).sin();
+        assert_eq!(a.update_detailed(x), b.update_detailed(x));
+    }
+    // max_missing: 9 / 2
+    for k in 1..=6 {
+        let s = a.update_missing_detailed();
+        assert_eq!(s, b.update_missing_detailed());
+        assert_eq!(s.stale, k > 4, "k = {}", k);
+    }
+    let s = a.update_detailed(0.5);
+    assert_eq!(s, b.update_detailed(0.5));
+    assert!(!a.update_missing_detailed().stale);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn dyn_window_has_the_same_default_max_missing() {
+    use hampel::DynWindow;
+
+    let mut a = DynWindow::new(5, 0.0, 3.0).unwrap();
+    let mut b = Window::<f64, 5>::new(0.0, 3.0);
+    for i in 0..20 {
+        let x = (i as f64 * 0.3).sin();
+        assert_eq!(a.update_detailed(x), b.update_detailed(x));
+    }
+    // max_missing: 5 / 2
+    for k in 1..=4 {
+        let s = a.update_missing_detailed();
+        assert_eq!(s, b.update_missing_detailed());
+        assert_eq!(s.stale, k > 2, "k = {}", k);
+    }
+}
+
+/// 格納しなかった欠けたサンプルの後も，外挿は時刻の差を使う
+#[cfg(feature = "extrapolation")]
+#[test]
+fn runtime_size_extrapolates_over_the_gaps() {
+    let (mut buf, mut scratch, mut gaps) = ([0.0; 7], [0.0; 7], [0.0; 7]);
+    let mut a = WindowMut::new(&mut buf, &mut scratch, &mut gaps, 0.0, 3.0).unwrap();
+    let mut b = Window::<f64, 7>::new(0.0, 3.0);
+    #[cfg(feature = "alloc")]
+    let mut c = hampel::DynWindow::new(7, 0.0, 3.0).unwrap();
+    for i in 0..60 {
+        // 1, 3, 5個の欠けたサンプルの後に外れ値
+        let missing = matches!(i, 20 | 30..=32 | 40..=44);
+        let x = (i as f64 * 0.2).sin() + if matches!(i, 21 | 33 | 45) { 5.0 } else { 0.0 };
+        let s = if missing { b.update_missing_detailed() } else { b.update_detailed(x) };
+        assert_eq!(if missing { a.update_missing_detailed() } else { a.update_detailed(x) }, s, "i = {}", i);
+        #[cfg(feature = "alloc")]
+        assert_eq!(if missing { c.update_missing_detailed() } else { c.update_detailed(x) }, s, "i = {}", i);
+    }
+}
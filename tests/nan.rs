@@ -0,0 +1,108 @@
+//! Decisions on NaN inputs and on windows containing NaN.
+
+use hampel::{NanPolicy, Replacement, Window};
+
+#[test]
+fn nan_input_is_outlier() {
+    let mut filter = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+    for x in [0.1, -0.1, 0.2, -0.2] {
+        filter.update(x);
+    }
+    let s = filter.update_detailed(f64::NAN);
+    assert!(s.is_outlier);
+    assert!(s.relative);
+    assert_eq!(s.value, 0.1);
+    assert_eq!(s.median, 0.1);
+}
+
+#[test]
+fn nan_input_is_outlier_f32() {
+    let mut filter = Window::<f32, 5>::with_replacement(0.0, 3.0, Replacement::Median);
+    for x in [0.1, -0.1, 0.2, -0.2] {
+        filter.update(x);
+    }
+    assert_eq!(filter.update(f32::NAN), 0.1);
+}
+
+#[test]
+fn nan_contaminated_window_flags_everything() {
+    let mut filter = Window::<f64, 5>::new(0.0, 3.0);
+    for _ in 0..3 {
+        filter.update(f64::NAN);
+    }
+    // The median of the window is NaN: no sample can be accepted
+    let s = filter.update_detailed(1.0);
+    assert!(s.median.is_nan());
+    assert!(s.is_outlier);
+    let s = filter.update_detailed(0.0);
+    assert!(s.is_outlier);
+
+    // Once the NaN have left the window, the samples are accepted again
+    for _ in 0..5 {
+        filter.update(0.5);
+    }
+    let s = filter.update_detailed(0.5);
+    assert!(!s.is_outlier);
+    assert_eq!(s.value, 0.5);
+}
+
+/// The filter before `NanPolicy` was added (median replacement)
+struct Plain<const N: usize> {
+    window: [f64; N],
+    oldest: usize,
+    coef: f64,
+}
+
+impl<const N: usize> Plain<N> {
+    fn new(init_val: f64, n_sigma: f64) -> Self {
+        Self { window: [init_val; N], oldest: 0, coef: 1.4826 * n_sigma }
+    }
+
+    fn update(&mut self, x: f64) -> f64 {
+        self.window[self.oldest] = x;
+        self.oldest = (self.oldest + 1) % N;
+        let mut work = self.window;
+        let w0 = Self::median(&mut work);
+        for w in work.iter_mut() {
+            *w = (*w - w0).abs();
+        }
+        let s0 = Self::median(&mut work);
+        if (x - w0).abs() <= self.coef * s0 { x } else { w0 }
+    }
+
+    fn median(work: &mut [f64; N]) -> f64 {
+        for i in 1..N {
+            let mut j = i;
+            while j > 0 && work[j - 1] > work[j] {
+                work.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        work[N / 2]
+    }
+}
+
+fn check_propagate<const N: usize>(input: &[f64]) {
+    let mut filter = Window::<f64, N>::builder()
+        .nan_policy(NanPolicy::Propagate)
+        .replacement(Replacement::Median)
+        .build();
+    let mut plain = Plain::<N>::new(0.0, 3.0);
+    for &x in input {
+        assert_eq!(filter.update(x).to_bits(), plain.update(x).to_bits(), "N = {}, x = {}", N, x);
+    }
+}
+
+#[test]
+fn propagate_matches_plain_filter() {
+    let nan = f64::NAN;
+    let input = [
+        1.0, 0.5, nan, 0.25, 3.0, 0.75, 0.5, 20.0, 0.25, nan, nan, 1.0, 0.5, 0.75, 0.25,
+        nan, 0.5, nan, 1.5, nan, 0.25, 0.5, 0.75, 1.0, 0.5, 0.25, 10.0, 0.5, 0.75, 1.0,
+    ];
+    check_propagate::<3>(&input);
+    check_propagate::<5>(&input);
+    check_propagate::<7>(&input);
+    check_propagate::<9>(&input);
+    check_propagate::<11>(&input);
+}
@@ -0,0 +1,166 @@
+//! Differential tests of the offline filters and the calibration, on long pseudo-random inputs.
+
+mod common;
+
+use common::{median_mad, Xorshift, SEED};
+use hampel::{filter_slice, EdgeMode};
+
+/// 同じ分布の新しい雑音で，外れ値と判定される割合がおよそ`target_fpr`になる
+#[cfg(feature = "alloc")]
+#[test]
+fn calibrated_window_meets_target_fpr() {
+    let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+    let clean: Vec<f64> = (0..50_000).map(|_| rng.gaussian()).collect();
+
+    for target_fpr in [0.001, 0.01, 0.05] {
+        let mut filter = hampel::Window::<f64, 31>::new_calibrated(&clean, target_fpr);
+        let n = 200_000;
+        for _ in 0..n {
+            filter.update(rng.gaussian());
+        }
+        let fpr = filter.stats().outliers_total as f64 / n as f64;
+        assert!(fpr > 0.7 * target_fpr && fpr < 1.3 * target_fpr, "{} {}", target_fpr, fpr);
+    }
+}
+
+/// ガウス雑音で較正した閾値は正規分布の分位数に近い
+#[cfg(feature = "alloc")]
+#[test]
+fn calibration_on_gaussian_noise() {
+    use hampel::calibrate;
+
+    let mut rng = Xorshift::new(SEED);
+    let clean: Vec<f64> = (0..100_000).map(|_| 5.0 + 0.1 * rng.gaussian()).collect();
+
+    let result = calibrate::<f64, 101>(&clean, 0.01);
+    assert_eq!(result.samples, 100_000 - 100);
+    assert!((result.n_sigma - 2.576).abs() < 0.1, "{}", result.n_sigma);
+    assert!(result.max_score > result.n_sigma);
+    assert!(result.mad_floor > 0.0 && result.mad_floor < 0.1 * 0.6745);
+
+    // 短いwindowは尺度の推定が粗く，大きな閾値が要る
+    assert!(calibrate::<f64, 9>(&clean, 0.01).n_sigma > result.n_sigma + 0.2);
+}
+
+/// チャンクの境界がどこにあっても，記録全体に`filter_slice`を使った結果と同じになる
+#[cfg(feature = "alloc")]
+#[test]
+fn chunked_filter_matches_filter_slice() {
+    use hampel::ChunkedFilter;
+
+    // 両端を含めてスパイクがある
+    let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+    let mut recording: Vec<f64> = (0..3000).map(|_| {
+        let (x, state) = rng.next_f64();
+        if state.is_multiple_of(40) { x * 30.0 } else { x }
+    }).collect();
+    recording[0] = 50.0;
+    recording[2999] = -50.0;
+
+    for edge in [EdgeMode::Shrink, EdgeMode::Mirror, EdgeMode::Nearest, EdgeMode::Skip] {
+        for half_window in [1, 4, 7] {
+            let mut expected = recording.clone();
+            filter_slice(&mut expected, half_window, 3.0, edge);
+
+            // windowより短いチャンク，数サンプルのチャンク，素数の長さのチャンク
+            for chunk_len in [1, 2, 3, 5, 8, 13, 997, 3000] {
+                let mut filter = ChunkedFilter::new(half_window, 3.0, edge);
+                let mut output = Vec::new();
+                for chunk in recording.chunks(chunk_len) {
+                    filter.push(chunk, &mut output);
+                }
+                filter.finish(&mut output);
+                assert_eq!(output, expected);
+            }
+
+            // 空のものを含む不規則な長さのチャンク
+            let mut filter = ChunkedFilter::new(half_window, 3.0, edge);
+            let mut output = Vec::new();
+            let mut rest = &recording[..];
+            for k in 0.. {
+                let (chunk, tail) = rest.split_at(rest.len().min(k * 7 % 23));
+                filter.push(chunk, &mut output);
+                rest = tail;
+                if rest.is_empty() {
+                    break;
+                }
+            }
+            filter.finish(&mut output);
+            assert_eq!(output, expected);
+        }
+    }
+}
+
+/// 並べ替えで求めた中央値とMADによる判定
+fn decide(window: &[f64], x: f64) -> f64 {
+    let (w0, s0) = median_mad(window);
+    if (x - w0).abs() > 1.4826f32 as f64 * 3.0 * s0 { w0 } else { x }
+}
+
+/// 5, 7, 9サンプルの中央値を求める選択ネットワークが並べ替えと同じ結果になる
+#[test]
+fn filter_slice_matches_sorting() {
+    for half_window in 2..=4 {
+        let n = 2 * half_window + 1;
+        // 0, 1, 2の値の全てのwindow
+        for code in 0..3usize.pow(n as u32) {
+            let window: Vec<f64> = (0..n).map(|k| (code / 3usize.pow(k as u32) % 3) as f64).collect();
+            let mut data = window.clone();
+            filter_slice(&mut data, half_window, 3.0, EdgeMode::Shrink);
+            assert_eq!(data[half_window], decide(&window, window[half_window]));
+        }
+
+        // スパイクを含む
+        let mut rng = Xorshift::new(SEED);
+        let src: Vec<f64> = (0..2000).map(|_| {
+            let (x, state) = rng.next_f64();
+            if state.is_multiple_of(20) { x * 50.0 } else { x }
+        }).collect();
+        let mut data = src.clone();
+        filter_slice(&mut data, half_window, 3.0, EdgeMode::Shrink);
+        for i in half_window..src.len() - half_window {
+            assert_eq!(data[i], decide(&src[i - half_window..=i + half_window], src[i]));
+        }
+    }
+}
+
+/// チャンクに分けて並列に処理しても`filter_slice`と同じ結果になる
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_matches_filter_slice() {
+    use hampel::par_filter_slice_chunked;
+
+    // スパイクと重複を含む
+    let mut rng = Xorshift::new(SEED);
+    let data: Vec<f64> = (0..5000)
+        .map(|_| {
+            let (x, state) = rng.next_f64();
+            match state % 100 {
+                0..=4 => x * 100.0,
+                5..=20 => (x * 4.0).round(),
+                _ => x,
+            }
+        })
+        .collect();
+
+    for edge in [EdgeMode::Shrink, EdgeMode::Mirror, EdgeMode::Nearest, EdgeMode::Skip] {
+        for half_window in [0, 1, 3, 20] {
+            let mut expected = data.clone();
+            filter_slice(&mut expected, half_window, 3.0, edge);
+            // 窓より短いチャンクと，様々な位置の境界
+            for chunk_len in [1, 7, 64, 999, 5000, 10_000] {
+                let mut a = data.clone();
+                par_filter_slice_chunked(&mut a, chunk_len, half_window, 3.0, edge);
+                assert_eq!(a, expected);
+            }
+            // 窓より短いスライス
+            for len in [1, 5, 30] {
+                let mut expected = data[..len].to_vec();
+                filter_slice(&mut expected, half_window, 3.0, edge);
+                let mut a = data[..len].to_vec();
+                par_filter_slice_chunked(&mut a, 2, half_window, 3.0, edge);
+                assert_eq!(a, expected);
+            }
+        }
+    }
+}
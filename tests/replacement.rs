@@ -0,0 +1,251 @@
+//! Replacements, centers, references, scale estimators and the handling of a zero MAD of `Window`, on small inputs.
+
+use hampel::{Center, ConfigError, NanPolicy, Reference, Replacement, ScaleEstimator, ThresholdMode, Window, ZeroMad};
+
+/// 連続する外れ値も直線の上の値で置き換える
+#[test]
+fn linear_extrapolation_follows_a_ramp() {
+    let ramp = |i: usize| 2.0 * i as f64 + 10.0;
+    let mut filter = Window::<f64, 9>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    filter.seed(&(0..9).map(ramp).collect::<Vec<_>>());
+    for i in 9..100 {
+        // 3つ連続する外れ値
+        let spike = if (50..53).contains(&i) { 1000.0 } else { 0.0 };
+        let s = filter.update_detailed(ramp(i) + spike);
+        assert_eq!(s.is_outlier, spike > 0.0);
+        assert!((s.value - ramp(i)).abs() < 1e-9);
+    }
+}
+
+/// 残りのサンプル0, 1, 3, 4（x = 0, 1, 2, 3）を通る最小二乗直線をx = 4で評価する
+#[test]
+fn linear_extrapolation_by_hand() {
+    let mut filter = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    filter.seed(&[9.0, 0.0, 1.0, 3.0, 4.0]);
+    let s = filter.update_detailed(100.0);
+    assert!(s.is_outlier);
+    assert!((s.value - 5.5).abs() < 1e-12);  // y = 1.4x - 0.1
+}
+
+/// 大きな共通のオフセットがあっても，`f32`のwindowが`f64`と同じ精度で置き換える
+#[test]
+fn linear_extrapolation_keeps_precision_with_an_offset() {
+    // パスカル単位の気圧：1e5付近のゆっくりしたドリフトと雑音，37サンプルごとの外れ値
+    let pressure = |i: usize| {
+        let noise = ((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) as f64 / (1u64 << 24) as f64 - 0.5;
+        let spike = if i % 37 == 20 { 500.0 } else { 0.0 };
+        101_325.0 + 0.02 * i as f64 + (i as f64 * 0.05).sin() + 0.1 * noise + spike
+    };
+    let mut narrow = Window::<f32, 31>::with_replacement(101_325.0, 3.0, Replacement::LinearExtrapolation);
+    let mut wide = Window::<f64, 31>::with_replacement(101_325.0, 3.0, Replacement::LinearExtrapolation);
+    for i in 0..5000 {
+        // f64のwindowにも同じf32のサンプルを与える
+        let x = pressure(i) as f32;
+        let s = narrow.update_detailed(x);
+        let t = wide.update_detailed(x as f64);
+        assert_eq!(s.is_outlier, t.is_outlier);
+        if i > 100 && i % 37 == 20 {
+            assert!(s.is_outlier);
+            assert!((s.value as f64 - t.value).abs() < 0.01);
+        }
+    }
+}
+
+/// 放物線の上の外れ値を放物線の上の値で置き換え，最小のwindowでは直線と同じになる
+#[test]
+fn quadratic_extrapolation_follows_a_parabola() {
+    let parabola = |i: usize| (i as f64 - 20.0).powi(2) - 3.0 * i as f64;
+    let mut quadratic = Window::<f64, 7>::with_replacement(0.0, 3.0, Replacement::QuadraticExtrapolation);
+    let mut linear = Window::<f64, 7>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    for i in 0..60 {
+        let spike = if i > 10 && i % 10 == 0 { 1000.0 } else { 0.0 };
+        let (q, l) = (quadratic.update_detailed(parabola(i) + spike), linear.update_detailed(parabola(i) + spike));
+        if spike > 0.0 {
+            assert!(q.is_outlier && l.is_outlier);
+            assert!((q.value - parabola(i)).abs() < 1e-9);
+            assert!((l.value - parabola(i)).abs() > 9.0);  // 直線では遅れる
+        }
+    }
+
+    let mut a = Window::<f64, 3>::with_replacement(0.0, 3.0, Replacement::QuadraticExtrapolation);
+    let mut b = Window::<f64, 3>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    for i in 0..100 {
+        let x = parabola(i) + if i % 7 == 0 { 100.0 } else { 0.0 };
+        assert_eq!(a.update_detailed(x), b.update_detailed(x));
+    }
+}
+
+/// 見逃した外れ値に最小二乗直線は引きずられるが，Theil–Sen推定量は引きずられない
+#[test]
+fn theil_sen_ignores_an_earlier_outlier() {
+    let ramp = |i: usize| 2.0 * i as f64 + 10.0;
+    let mut history: Vec<f64> = (0..9).map(ramp).collect();
+    history[2] += 30.0;
+
+    let mut theil_sen = Window::<f64, 9>::with_replacement(0.0, 3.0, Replacement::TheilSenExtrapolation);
+    let mut least_squares = Window::<f64, 9>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    theil_sen.seed(&history);
+    least_squares.seed(&history);
+
+    let (t, l) = (theil_sen.update_detailed(ramp(9) + 1000.0), least_squares.update_detailed(ramp(9) + 1000.0));
+    assert!(t.is_outlier && l.is_outlier);
+    assert!((t.value - ramp(9)).abs() < 1e-9);
+    assert!((l.value - ramp(9)).abs() > 4.0);
+}
+
+/// 外れ値を受け入れ範囲の端で置き換える
+#[test]
+fn clamp_by_hand() {
+    let mut filter = Window::<f64, 5>::with_replacement(0.0, 2.0, Replacement::Clamp);
+    filter.seed(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    let s = filter.update_detailed(50.0);
+    assert!(s.is_outlier);
+    assert_eq!((s.median, s.mad), (4.0, 1.0));
+    assert_eq!(s.value, 4.0 + f64::from(1.4826f32) * 2.0);  // `Window::builder`は1.4826を`f32`で使う
+}
+
+/// 外れ値と判定されなかった最後の出力を保持する
+#[test]
+fn hold_last_valid_holds_the_last_accepted_output() {
+    let ramp = |i: usize| 0.5 * i as f64;
+    let mut filter = Window::<f64, 7>::with_replacement(0.0, 3.0, Replacement::HoldLastValid);
+    filter.seed(&(0..7).map(ramp).collect::<Vec<_>>());
+    for i in 7..60 {
+        // 1つの外れ値と，3つ連続する外れ値
+        let spike = if i == 20 || (40..43).contains(&i) { 100.0 } else { 0.0 };
+        let s = filter.update_detailed(ramp(i) + spike);
+        assert_eq!(s.is_outlier, spike > 0.0);
+        match i {
+            20 => assert_eq!(s.value, ramp(19)),
+            40..=42 => assert_eq!(s.value, ramp(39)),
+            _ => assert_eq!(s.value, ramp(i)),
+        }
+    }
+
+    // まだ何も受け入れていなければ初期値を保持する
+    let mut filter = Window::<f64, 5>::builder()
+        .init_value(1.0)
+        .warm_up(true)
+        .nan_policy(NanPolicy::TreatAsOutlier)
+        .replacement(Replacement::HoldLastValid)
+        .build();
+    assert_eq!(filter.update(f64::INFINITY), 1.0);
+    assert_eq!(filter.update(f64::INFINITY), 1.0);
+}
+
+/// N = 5，0.25では両端から1つずつ除く
+#[test]
+fn trimmed_mean_by_hand() {
+    let mut filter = Window::<f64, 5>::builder().center(Center::TrimmedMean { trim_fraction: 0.25 }).build();
+    filter.seed(&[1.0, 2.0, 4.0, 100.0]);
+    assert_eq!(filter.update_detailed(6.0).median, 4.0);  // (2 + 4 + 6) / 3
+}
+
+#[test]
+fn quantile_by_hand() {
+    // [1, 2, 3, 4, 6]の位置0.8 * 4 = 3.2
+    let mut filter = Window::<f64, 5>::builder().center(Center::Quantile { p: 0.8 }).build();
+    filter.seed(&[4.0, 1.0, 3.0, 2.0]);
+    assert!((filter.update_detailed(6.0).median - 4.4).abs() < 1e-6);  // p = 0.8は`f32`に丸められる
+}
+
+/// 傾きの急なランプの先頭を中央値は外れ値とするが，予測値はしない
+#[test]
+fn prediction_follows_a_ramp() {
+    // 平坦な部分に続く1サンプルあたり1のランプと，その上の外れ値
+    let input: Vec<f64> = (0..150).map(|i| {
+        let ramp = if i < 50 { 0.0 } else { (i - 50) as f64 };
+        ramp + if i == 120 { 30.0 } else { 0.0 }
+    }).collect();
+    let build = |reference| {
+        Window::<f64, 15>::builder().reference(reference).replacement(Replacement::Median).mad_floor(0.7).build()
+    };
+    let (mut median, mut prediction) = (build(Reference::Median), build(Reference::Prediction));
+    let (mut by_median, mut by_prediction) = (Vec::new(), Vec::new());
+    for (i, &x) in input.iter().enumerate() {
+        let (m, p) = (median.update_detailed(x), prediction.update_detailed(x));
+        if m.is_outlier {
+            by_median.push(i);
+        }
+        if p.is_outlier {
+            by_prediction.push(i);
+        }
+        if i == 120 {
+            // 遅れる中央値ではなく，ランプの上の値で置き換える
+            assert_eq!(p.value, 70.0);
+            assert!(m.value < 65.0);
+        }
+    }
+    assert_eq!(by_median, [54, 55, 56, 57, 58, 120]);
+    assert_eq!(by_prediction, [120]);
+
+    let invalid = Window::<f64, 15>::builder().reference(Reference::Prediction).forgetting_factor(0.9).try_build();
+    assert_eq!(invalid.unwrap_err(), ConfigError::InvalidReference);
+}
+
+#[test]
+fn sn_by_hand() {
+    let mut filter = Window::<f64, 5>::builder().scale_estimator(ScaleEstimator::Sn).build();
+    filter.seed(&[1.0, 2.0, 3.0, 4.0]);
+    // x = [1, 2, 3, 4, 100]に対してhimed_j |x_i - x_j| = [2, 1, 1, 2, 97]で，その下側の中央値は2
+    let s = filter.update_detailed(100.0);
+    assert_eq!(s.mad, 2.0);
+    assert!(s.is_outlier);
+}
+
+/// 四分位数が`numpy.percentile`と同じになる
+#[test]
+fn iqr_by_hand() {
+    let mut filter = Window::<f64, 8>::builder().n_sigma(1.5).scale_estimator(ScaleEstimator::Iqr).build();
+    filter.seed(&[2.0, 4.0, 1.0, 3.0, 7.0, 5.0, 6.0]);
+    // numpy.percentile([1, 2, 3, 4, 5, 6, 7, 20], [25, 75]) == [2.75, 6.25]
+    let s = filter.update_detailed(20.0);
+    assert_eq!(s.mad, 6.25 - 2.75);
+    assert_eq!(s.median, 4.5);
+    assert!(s.is_outlier);  // 20 > 6.25 + 1.5 * 3.5
+
+    // N = 4: numpy.percentile([0, 4, 8, 12], [25, 75]) == [3, 9]
+    let mut small = Window::<f64, 4>::builder().scale_estimator(ScaleEstimator::Iqr).build();
+    small.seed(&[0.0, 4.0, 8.0]);
+    let s = small.update_detailed(12.0);
+    assert_eq!(s.mad, 6.0);
+    assert!(!s.is_outlier);
+}
+
+#[test]
+fn zero_mad_policies() {
+    // 入力を含む9サンプルのうち⌈9 / 2⌉ = 5個が等しいので，MADは0になる
+    let samples = [1.0, 1.0, 0.7, 1.0, 1.4, 1.0, 0.8, 1.0];
+    let decisions = |zero_mad, abs_threshold: Option<(f64, ThresholdMode)>| {
+        let mut builder = Window::<f64, 9>::builder().zero_mad(zero_mad);
+        if let Some((t, mode)) = abs_threshold {
+            builder = builder.abs_threshold(t).threshold_mode(mode);
+        }
+        let mut w = builder.build();
+        [1.0, 1.01, 1.3, 2.0].map(|x| {
+            w.seed(&samples);
+            let s = w.update_detailed(x);
+            assert!(s.zero_mad && s.mad == 0.0);
+            s.is_outlier
+        })
+    };
+
+    assert_eq!(decisions(ZeroMad::FlagEverything, None), [false, true, true, true]);
+    assert_eq!(decisions(ZeroMad::PassEverything, None), [false, false, false, false]);
+    // |x - 1| > 3 * 1.4826 * 0.05 = 0.22239
+    assert_eq!(decisions(ZeroMad::UseFloor(0.05), None), [false, false, true, true]);
+
+    // 絶対閾値は単独でも相対閾値と組み合わせても適用される
+    let either = Some((0.5, ThresholdMode::Either));
+    let both = Some((0.5, ThresholdMode::Both));
+    assert_eq!(decisions(ZeroMad::FlagEverything, both), [false, false, false, true]);
+    assert_eq!(decisions(ZeroMad::PassEverything, either), [false, false, false, true]);
+    assert_eq!(decisions(ZeroMad::PassEverything, both), [false, false, false, false]);
+    assert_eq!(decisions(ZeroMad::UseFloor(0.05), either), [false, false, true, true]);
+
+    // MADが正の間は使われない
+    let mut w = Window::<f64, 9>::builder().zero_mad(ZeroMad::PassEverything).build();
+    w.seed(&[1.0, 1.1, 0.7, 1.0, 1.4, 0.9, 0.8, 1.2]);
+    let s = w.update_detailed(9.0);
+    assert!(s.is_outlier && !s.zero_mad);
+}
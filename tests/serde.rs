@@ -0,0 +1,108 @@
+//! Validation of deserialized windows (`serde` feature).
+
+#![cfg(feature = "serde")]
+
+use hampel::{LargeWindow, Window};
+use serde_json::{json, Value};
+
+/// `value`の`path`のフィールドを`replacement`に置き換えた状態をデシリアライズする
+fn patched<W: serde::de::DeserializeOwned>(value: &Value, path: &[&str], replacement: Value) -> Result<W, serde_json::Error> {
+    let mut value = value.clone();
+    let mut field = &mut value;
+    for key in path {
+        field = &mut field[*key];
+    }
+    *field = replacement;
+    serde_json::from_value(value)
+}
+
+fn snapshot() -> Value {
+    let mut filter = Window::<f64, 5>::builder().confirmation(2, 3).build();
+    for x in [0.1, -0.1, 0.2, 5.0, -0.2, 0.1] {
+        filter.update(x);
+    }
+    serde_json::to_value(&filter).unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let value = snapshot();
+    assert!(value["config"].is_object() && value["state"].is_object());
+    assert!(value.get("core").is_none());
+    let mut a: Window<f64, 5> = serde_json::from_value(value.clone()).unwrap();
+    let mut b: Window<f64, 5> = serde_json::from_value(value).unwrap();
+    assert_eq!(a.update_detailed(3.0), b.update_detailed(3.0));
+}
+
+#[test]
+fn invalid_confirmation_is_rejected() {
+    let value = snapshot();
+    for confirmation in [json!([1, 9]), json!([0, 3]), json!([4, 3]), json!([0, 0])] {
+        assert!(patched::<Window<f64, 5>>(&value, &["config", "confirmation"], confirmation).is_err());
+    }
+    let mut filter: Window<f64, 5> = patched(&value, &["config", "confirmation"], json!([1, 8])).unwrap();
+    filter.update(1.0);
+}
+
+#[test]
+fn invalid_config_is_rejected() {
+    let value = snapshot();
+    let invalid = [
+        (["config", "n_sigma_low"], json!(-1.0)),
+        (["config", "n_sigma_high"], json!(null)),
+        (["config", "n_sigma_high"], json!(0.0)),
+        (["config", "scale"], json!(-1.4826)),
+        (["config", "abs_threshold"], json!(-0.5)),
+        (["config", "output_range"], json!([1.0, -1.0])),
+        (["config", "max_slew"], json!(-1.0)),
+        (["config", "soft_replacement"], json!(-1.0)),
+        (["config", "forgetting_factor"], json!(1.5)),
+        (["config", "hysteresis_coef"], json!(100.0)),
+        (["config", "suspect_coef"], json!([100.0, false])),
+        (["config", "difference_coef"], json!([-1.0, "Either"])),
+        (["config", "zero_mad"], json!({ "UseFloor": -1.0 })),
+        (["config", "center"], json!({ "Quantile": { "p": 1.5 } })),
+        (["state", "oldest"], json!(5)),
+    ];
+    for (path, replacement) in invalid {
+        let result = patched::<Window<f64, 5>>(&value, &path, replacement.clone());
+        assert!(result.is_err(), "{:?} = {}", path, replacement);
+    }
+}
+
+#[test]
+fn invalid_config_is_rejected_by_large_window() {
+    let filter = LargeWindow::<f64, 16>::new(0.0, 3.0);
+    let value = serde_json::to_value(&filter).unwrap();
+    assert!(patched::<LargeWindow<f64, 16>>(&value, &["config", "confirmation"], json!([1, 9])).is_err());
+    assert!(patched::<LargeWindow<f64, 16>>(&value, &["config", "confirmation"], json!([1, 2])).is_ok());
+}
+
+/// 欠けたサンプルの後の時刻の差も復元する
+#[cfg(feature = "alloc")]
+#[test]
+fn dyn_window_keeps_the_gaps() {
+    use hampel::{DynWindow, Replacement};
+
+    // DynWindowは置換方法を設定できないので，同じ形式のWindowの状態から作る
+    let mut b = Window::<f64, 5>::with_replacement(0.0, 3.0, Replacement::LinearExtrapolation);
+    let mut a: DynWindow<f64> = serde_json::from_value(serde_json::to_value(&b).unwrap()).unwrap();
+    for i in 0..10 {
+        a.update(i as f64);
+        b.update(i as f64);
+    }
+    a.update_missing();
+    b.update_missing();
+    a.update(11.0);
+    b.update(11.0);
+
+    let value = serde_json::to_value(&a).unwrap();
+    let mut restored: DynWindow<f64> = serde_json::from_value(value.clone()).unwrap();
+    assert!(restored == a);
+    assert!(patched::<DynWindow<f64>>(&value, &["gaps"], json!([1.0])).is_err());
+    for x in [12.0, 13.0, 30.0] {
+        let s = b.update_detailed(x);
+        assert_eq!(restored.update_detailed(x), s);
+        assert_eq!(a.update_detailed(x), s);
+    }
+}
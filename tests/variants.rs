@@ -0,0 +1,484 @@
+//! Differential tests of the other window types against `Window` and offline references, on long pseudo-random inputs.
+
+mod common;
+
+use std::collections::VecDeque;
+
+use common::{median, median_mad, Xorshift, SEED};
+use hampel::{
+    DualWindow, LargeWindow, MadEstimator, MedianWindow, MetricWindow, QuantileWindow, Replacement, Sample,
+    TagSource, TaggedWindow, WeightedWindow, Window,
+};
+
+/// 重複とNaNを含む入力
+fn with_nan(rng: &mut Xorshift) -> f64 {
+    let (x, state) = rng.next_f64();
+    match state % 1000 {
+        0 => f64::NAN,
+        1..=20 => x * 100.0,
+        21..=100 => (x * 4.0).round(),
+        _ => x,
+    }
+}
+
+/// 約10%の外れ値を含む入力
+fn with_outliers(rng: &mut Xorshift) -> f64 {
+    let (x, state) = rng.next_f64();
+    x + if state.is_multiple_of(10) { 5.0 } else { 0.0 }
+}
+
+/// NaN != NaNなので，ビット列で比較する
+fn assert_same_bits(a: &Sample<f64>, b: &Sample<f64>) {
+    assert_eq!(a.value.to_bits(), b.value.to_bits());
+    assert_eq!(a.median.to_bits(), b.median.to_bits());
+    assert_eq!(a.mad.to_bits(), b.mad.to_bits());
+    assert_eq!(a.is_outlier, b.is_outlier);
+}
+
+#[test]
+fn dual_window_of_equal_sizes_is_window() {
+    let mut a = DualWindow::<f64, 9, 9>::new(0.0, 3.0);
+    let mut b = Window::<f64, 9>::new(0.0, 3.0);
+
+    let mut rng = Xorshift::new(SEED);
+    for i in 0..5000 {
+        let x = with_nan(&mut rng);
+        if i == 2500 {
+            a.seed(&[x, 0.5, 0.25]);
+            b.seed(&[x, 0.5, 0.25]);
+        }
+        assert_same_bits(&a.update_detailed(x), &b.update_detailed(x));
+    }
+}
+
+#[test]
+fn dual_window_has_a_steadier_mad() {
+    let mut dual = DualWindow::<f64, 7, 63>::new(0.0, 3.0);
+    let mut short = Window::<f64, 7>::new(0.0, 3.0);
+
+    // 雑音のある正弦波
+    let mut rng = Xorshift::new(0x853c_49e6_748f_ea9b);
+    let mut mads = (Vec::new(), Vec::new());
+    for i in 0..3000 {
+        let noise = (rng.uniform() - 0.5) * 0.2;
+        let x = (i as f64 * 0.0005).sin() + noise;
+        let (d, s) = (dual.update_detailed(x), short.update_detailed(x));
+        if i >= 63 {
+            assert_eq!(d.median, s.median);
+            mads.0.push(d.mad);
+            mads.1.push(s.mad);
+        }
+    }
+
+    // MADの時間変化の相対的なばらつき
+    let spread = |m: &[f64]| {
+        let mean = m.iter().sum::<f64>() / m.len() as f64;
+        (m.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / m.len() as f64).sqrt() / mean
+    };
+    assert!(spread(&mads.0) < 0.5 * spread(&mads.1));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn dyn_window_is_window() {
+    use hampel::DynWindow;
+
+    let mut a = DynWindow::new(5, 0.0, 3.0).unwrap();
+    let mut b = Window::<f64, 5>::new(0.0, 3.0);
+
+    let mut rng = Xorshift::new(88172645463325252);
+    for _ in 0..10000 {
+        let (x, state) = rng.next_f64();
+        let x = if state.is_multiple_of(50) { x * 100.0 } else { x };
+        assert_eq!(a.update_detailed(x), b.update_detailed(x));
+    }
+}
+
+#[test]
+fn large_window_is_window() {
+    let mut a = LargeWindow::<f64, 512>::new(0.0, 3.0);
+    let mut b = Window::<f64, 512>::new(0.0, 3.0);
+
+    let mut rng = Xorshift::new(SEED);
+    for i in 0..5000 {
+        let x = with_nan(&mut rng);
+        if i == 2500 {
+            a.seed(&[x, 0.5, 0.25]);
+            b.seed(&[x, 0.5, 0.25]);
+        }
+        assert_same_bits(&a.update_detailed(x), &b.update_detailed(x));
+    }
+}
+
+#[cfg(feature = "fixed")]
+#[test]
+fn fixed_window_is_within_one_lsb_of_window() {
+    use fixed::types::I16F16;
+    use hampel::FixedWindow;
+
+    fn check<const N: usize>(levels: &[f64]) {
+        let n_sigma = I16F16::from_num(3);
+        let mut a = FixedWindow::<I16F16, N>::with_replacement(I16F16::ZERO, n_sigma, Replacement::Median);
+        let mut b = Window::<f64, N>::with_replacement(0.0, 3.0, Replacement::Median);
+        let lsb = I16F16::DELTA.to_num::<f64>();
+
+        // 各水準の周りの入力と，フルスケールの外れ値
+        let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+        let mut detected = 0;
+        for i in 0..20_000 {
+            let state = rng.next_u64();
+            let x = match state % 100 {
+                0 => I16F16::MIN,
+                1 => I16F16::MAX,
+                2 => I16F16::from_bits((state >> 32) as i32),
+                3..=10 => I16F16::from_num(0.5),
+                _ => {
+                    let level = levels[i / 1000 % levels.len()];
+                    let noise = (state >> 40) as f64 / (1u64 << 24) as f64 - 0.5;
+                    I16F16::saturating_from_num(level + noise * 20.0)
+                },
+            };
+            let (sa, sb) = (a.update_detailed(x), b.update_detailed(x.to_num()));
+
+            assert!((sa.median.to_num::<f64>() - sb.median).abs() < lsb);
+            assert!((sa.mad.to_num::<f64>() - sb.mad).abs() < lsb);
+            let threshold = 1.4826 * 3.0 * sb.mad;
+            let deviation = (x.to_num::<f64>() - sb.median).abs();
+            if (deviation - threshold).abs() > threshold * 1e-9 {
+                assert_eq!(sa.is_outlier, sb.is_outlier, "{}: {}", i, x);
+                assert_eq!(sa.side, sb.side);
+                assert!((sa.value.to_num::<f64>() - sb.value).abs() < lsb);
+            }
+            detected += sa.is_outlier as usize;
+        }
+        assert!(detected > 1000);
+    }
+
+    // 奇数と偶数のwindowサイズ，値域（±32768）の端の近く
+    check::<5>(&[0.0, 100.0, -2000.0]);
+    check::<8>(&[32760.0, -32760.0, 0.25]);
+    check::<101>(&[-32768.0, 32767.0]);
+}
+
+#[test]
+fn int_window_is_within_one_of_window() {
+    use hampel::IntWindow;
+
+    fn check<const N: usize>(levels: &[f64], n_sigma_num: u32, n_sigma_den: u32) {
+        let n_sigma = n_sigma_num as f64 / n_sigma_den as f64;
+        let mut a = IntWindow::<i16, N>::with_replacement(0, n_sigma_num, n_sigma_den, Replacement::Median);
+        let mut b = Window::<f64, N>::with_replacement(0.0, n_sigma, Replacement::Median);
+
+        // 各水準の周りの入力と，フルスケールの外れ値
+        let mut rng = Xorshift::new(SEED);
+        let mut detected = 0;
+        for i in 0..20_000 {
+            let state = rng.next_u64();
+            let x = match state % 100 {
+                0 => i16::MIN,
+                1 => i16::MAX,
+                2 => (state >> 48) as i16,
+                _ => {
+                    let level = levels[i / 1000 % levels.len()];
+                    let noise = (state >> 40) as f64 / (1u64 << 24) as f64 - 0.5;
+                    (level + noise * 40.0).round().clamp(-32768.0, 32767.0) as i16
+                },
+            };
+            let (sa, sb) = (a.update_detailed(x), b.update_detailed(x as f64));
+
+            assert!((sa.median as f64 - sb.median).abs() < 1.0);
+            assert!((sa.mad as f64 - sb.mad).abs() < 1.0);
+            let threshold = 1.4826 * n_sigma * sb.mad;
+            let deviation = (x as f64 - sb.median).abs();
+            if (deviation - threshold).abs() > threshold * 1e-9 {
+                assert_eq!(sa.is_outlier, sb.is_outlier, "{}: {}", i, x);
+                assert_eq!(sa.side, sb.side);
+                assert!((sa.value as f64 - sb.value).abs() < 1.0);
+            }
+            detected += sa.is_outlier as usize;
+        }
+        assert!(detected > 500);
+    }
+
+    // 奇数と偶数のwindowサイズ，値域（±32767）の端の近く
+    check::<5>(&[0.0, 1000.0, -2000.0], 3, 1);
+    check::<8>(&[32767.0, -32768.0, 0.0], 5, 2);
+    check::<101>(&[-32760.0, 32760.0], 3, 1);
+}
+
+#[test]
+fn mad_estimator_matches_sorting() {
+    fn check<const N: usize>() {
+        let mut estimator = MadEstimator::<f64, N>::new(0.0).warm_up(true);
+        let mut reference = VecDeque::new();
+
+        // 重複を含む
+        let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            let (x, state) = rng.next_f64();
+            let x = if state.is_multiple_of(4) { (x * 4.0).round() } else { x * 10.0 - 5.0 };
+            if reference.len() == N {
+                reference.pop_front();
+            }
+            reference.push_back(x);
+
+            // それまでに受け取った（高々N個の）サンプルのMAD
+            let (_, mad) = median_mad(reference.make_contiguous());
+
+            let sigma = estimator.update(x);
+            assert_eq!(estimator.mad(), mad);
+            assert_eq!(estimator.sigma(), sigma);
+            assert!((sigma - 1.4826 * mad).abs() <= 1e-6 * mad);
+        }
+    }
+
+    check::<1>();
+    check::<4>();
+    check::<7>();
+    check::<100>();
+}
+
+#[test]
+fn median_window_matches_sorting() {
+    fn check<const N: usize>() {
+        let mut filter = MedianWindow::<f64, N>::new(0.0);
+        let mut reference = VecDeque::from([0.0; N]);
+
+        // 重複を含む
+        let mut rng = Xorshift::new(SEED);
+        for _ in 0..5000 {
+            let (x, state) = rng.next_f64();
+            let x = if state.is_multiple_of(4) { (x * 4.0).round() } else { x * 100.0 - 50.0 };
+            reference.pop_front();
+            reference.push_back(x);
+
+            let expected = median(&mut reference.iter().copied().collect::<Vec<_>>());
+            assert_eq!(filter.update(x), expected);
+            assert_eq!(filter.median(), expected);
+        }
+    }
+
+    check::<1>();
+    check::<2>();
+    check::<5>();
+    check::<8>();
+    check::<101>();
+}
+
+/// 距離を`|a - b|`とすると，奇数のwindowのmedoidは中央値になる
+#[test]
+fn metric_window_with_absolute_difference_is_window() {
+    fn check<const N: usize>() {
+        let mut metric = MetricWindow::<f64, f64, N>::new(0.0, 3.0, |a, b| (a - b).abs());
+        let mut plain = Window::<f64, N>::with_replacement(0.0, 3.0, Replacement::Median);
+
+        let mut rng = Xorshift::new(SEED);
+        for _ in 0..5000 {
+            let x = with_outliers(&mut rng);
+            let s = plain.update_detailed(x);
+            assert_eq!(metric.update(x).to_bits(), s.value.to_bits());
+            assert_eq!(metric.is_last_outlier(), s.is_outlier);
+            assert_eq!((metric.medoid(), metric.scale()), (s.median, s.mad));
+        }
+    }
+
+    check::<3>();
+    check::<9>();
+    check::<31>();
+}
+
+#[cfg(feature = "ordered-float")]
+#[test]
+fn not_nan_window_is_window() {
+    use hampel::NotNanWindow;
+    use ordered_float::{NotNan, OrderedFloat};
+
+    fn check<const N: usize>() {
+        let mut a = NotNanWindow::<f64, N>::new(NotNan::new(0.0).unwrap(), 3.0);
+        let mut b = Window::<OrderedFloat<f64>, N>::new(OrderedFloat(0.0), OrderedFloat(3.0));
+        let mut reference = Window::<f64, N>::new(0.0, 3.0);
+
+        // スパイク，バースト，重複を含む
+        let mut rng = Xorshift::new(SEED);
+        let mut detected = 0;
+        for i in 0..20_000 {
+            let (x, state) = rng.next_f64();
+            let x = match state % 100 {
+                0..=2 => x * 100.0 + 10.0,
+                3..=10 => (x * 4.0).round(),
+                _ if i % 1000 < 5 => -50.0,
+                _ => x,
+            };
+            let s = reference.update_detailed(x);
+            let sa = a.update_detailed(NotNan::new(x).unwrap());
+            let sb = b.update_detailed(OrderedFloat(x));
+            assert_eq!(sa, s);
+            assert_eq!((sb.value.0, sb.median.0, sb.mad.0), (s.value, s.median, s.mad));
+            assert_eq!(sb.is_outlier, s.is_outlier);
+            detected += s.is_outlier as usize;
+        }
+        assert!(detected > 500);
+    }
+
+    check::<5>();
+    check::<8>();
+    check::<31>();
+}
+
+/// numpy.percentile(window, 100 * p) (method="linear")
+fn percentile(window: &VecDeque<f64>, p: f64) -> f64 {
+    let mut sorted: Vec<f64> = window.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let h = p * (sorted.len() - 1) as f64;
+    let (i, t) = (h.floor() as usize, h - h.floor());
+    if t == 0.0 {
+        return sorted[i];
+    }
+    let d = sorted[i + 1] - sorted[i];
+    if t >= 0.5 { sorted[i + 1] - d * (1.0 - t) } else { sorted[i] + d * t }
+}
+
+#[test]
+fn quantile_window_matches_percentile() {
+    fn check<const N: usize>(p: f32) {
+        let mut filter = QuantileWindow::<f64, N>::with_p(0.0, p);
+        let mut median = MedianWindow::<f64, N>::new(0.0);
+        let mut reference = VecDeque::from([0.0; N]);
+
+        // 重複を含む
+        let mut rng = Xorshift::new(SEED);
+        for _ in 0..3000 {
+            let (x, state) = rng.next_f64();
+            let x = if state.is_multiple_of(4) { (x * 4.0).round() } else { x * 100.0 - 50.0 };
+            reference.pop_front();
+            reference.push_back(x);
+
+            let q = filter.update(x);
+            let expected = percentile(&reference, f64::from(p));
+            assert!((q - expected).abs() <= 1e-12 * expected.abs().max(1.0));
+            if p == 0.5 {
+                assert_eq!(q, median.update(x));
+            }
+        }
+    }
+
+    for p in [0.5, 0.1, 0.25, 0.9, 0.999] {
+        check::<1>(p);
+        check::<2>(p);
+        check::<8>(p);
+        check::<101>(p);
+    }
+    // p * (N - 1)が整数になり，補間しない
+    check::<5>(0.25);
+    check::<9>(0.75);
+    // 小さいwindowでpが0や1に近い
+    check::<3>(0.001);
+    check::<3>(0.999);
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn simd_bank_is_a_bank_of_windows() {
+    use hampel::SimdBank;
+
+    fn check<const N: usize>() {
+        let mut a = SimdBank::<N, 16>::new(0.0, 3.0);
+        let mut b: [_; 16] =
+            core::array::from_fn(|_| Window::<f32, N>::with_replacement(0.0, 3.0, Replacement::Median));
+
+        // 重複，NaN，±無限大，負のゼロを含む
+        let mut rng = Xorshift::new(0x2545_f491_4f6c_dd1d ^ N as u64);
+        for _ in 0..5000 {
+            let x: [f32; 16] = core::array::from_fn(|_| {
+                let (x, state) = rng.next_f32();
+                match state % 1000 {
+                    0 => f32::NAN,
+                    1 => f32::INFINITY,
+                    2 => f32::NEG_INFINITY,
+                    3 => -0.0,
+                    4..=30 => x * 100.0,
+                    31..=100 => (x * 4.0).round(),
+                    _ => x,
+                }
+            });
+            let sa = a.update_detailed(x);
+            for (i, w) in b.iter_mut().enumerate() {
+                let sb = w.update_detailed(x[i]);
+                assert_eq!(sa[i].value.to_bits(), sb.value.to_bits());
+                assert_eq!(sa[i].median.to_bits(), sb.median.to_bits());
+                assert_eq!(sa[i].mad.to_bits(), sb.mad.to_bits());
+                assert_eq!(sa[i].is_outlier, sb.is_outlier);
+            }
+        }
+    }
+
+    check::<5>();
+    check::<7>();
+    check::<9>();
+    check::<11>();
+    check::<6>();  // スカラーの経路
+}
+
+#[test]
+fn tags_follow_the_replacements() {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Tag {
+        seq: usize,
+        timestamp_us: u64,
+    }
+
+    fn check<const N: usize>() -> usize {
+        let window = Window::<f64, N>::with_replacement(0.0, 3.0, Replacement::Median);
+        // 初期値はサンプル0のタグを持つ
+        let mut filter = TaggedWindow::from_window(window, Tag { seq: 0, timestamp_us: 0 });
+        let mut history = vec![0.0];
+        let mut synthesized = 0;
+
+        let mut rng = Xorshift::new(SEED);
+        for seq in 1..5000 {
+            let x = with_outliers(&mut rng);
+            let tag = Tag { seq, timestamp_us: 1000 * seq as u64 };
+            history.push(x);
+
+            let (y, source) = filter.update_tagged(x, tag);
+            match source {
+                TagSource::Input(t) => {
+                    assert_eq!(t, tag);
+                    assert_eq!(y, x);
+                },
+                TagSource::Window(t) => {
+                    // 返された値を持つwindowのサンプル
+                    assert!(t.seq < seq && (t.seq == 0 || t.seq + N > seq));
+                    assert_eq!(history[t.seq], y);
+                },
+                TagSource::Synthesized => synthesized += 1,
+            }
+        }
+        synthesized
+    }
+
+    // 奇数のwindowの中央値は常にいずれかのサンプルで，中央の2つの平均とは異なる
+    assert_eq!(check::<7>(), 0);
+    assert!(check::<8>() > 0);
+}
+
+#[test]
+fn equal_weights_are_window() {
+    fn check<const N: usize>() {
+        let mut weighted = WeightedWindow::<f64, N>::new(0.0, 3.0);
+        let mut plain = Window::<f64, N>::with_replacement(0.0, 3.0, Replacement::Median);
+
+        let mut rng = Xorshift::new(SEED);
+        for _ in 0..5000 {
+            let x = with_outliers(&mut rng);
+            let s = plain.update_detailed(x);
+            assert_eq!(weighted.update_weighted(x, 1.0).to_bits(), s.value.to_bits());
+            assert_eq!(weighted.is_last_outlier(), s.is_outlier);
+            assert_eq!((weighted.median(), weighted.mad()), (s.median, s.mad));
+        }
+    }
+
+    check::<5>();
+    check::<8>();
+    check::<31>();
+}